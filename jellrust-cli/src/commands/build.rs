@@ -1,48 +1,331 @@
-use anyhow::Result;
-use jellrust_core::{config::{Config, ConfigExt}, site::SiteBuilder};
-use std::path::PathBuf;
+use super::{resolve_destination, site_builder};
+use anyhow::{Context, Result};
+use jellrust_core::{
+    config::{Config, ConfigExt},
+    deps::CACHE_DIR,
+    notification::{notify_rebuild, RebuildOutcome},
+    site::BUILD_ID_FILE,
+};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Options for `jellrust build`
+pub struct BuildOptions {
+    pub drafts: bool,
+    pub watch: bool,
+    pub incremental: bool,
+    pub dry_run: bool,
+    pub diff: bool,
+    pub profile: bool,
+}
 
 pub async fn execute(
-    source: PathBuf,
-    destination: PathBuf,
-    drafts: bool,
-    watch: bool,
+    root: PathBuf,
+    destination: Option<PathBuf>,
+    options: BuildOptions,
 ) -> Result<()> {
-    tracing::info!("Building site from {} to {}", source.display(), destination.display());
-    
     // Load configuration
-    let config = Config::load(&source)?;
+    let config = Config::load(&root)?;
+    let destination = resolve_destination(&root, destination, &config);
+    
+    if options.dry_run {
+        return dry_run(root, destination, config, options.drafts, options.diff).await;
+    }
+    
+    let source = config.source_dir(&root);
+    tracing::info!("Building site from {} to {}", source.display(), destination.display());
     
     // Build the site
-    let mut builder = SiteBuilder::new(source.clone(), destination.clone(), config);
-    builder.set_include_drafts(drafts);
+    let mut builder = site_builder(&root, destination.clone(), config);
+    builder.set_include_drafts(options.drafts);
+    builder.set_incremental(options.incremental);
+    builder.set_profile(options.profile);
     
-    builder.build().await?;
+    let summary = builder.build().await?;
     
-    println!("✅ Site built successfully!");
+    println!("✅ Site built: {}", summary);
     println!("📁 Output: {}", destination.display());
     
-    if watch {
+    if options.profile {
+        println!("\n⏱️  Render profile:\n{}", builder.profile_report());
+    }
+    
+    if options.watch {
         println!("\n👀 Watching for changes... (Press Ctrl+C to stop)");
-        watch_and_rebuild(source, destination, drafts).await?;
+        watch_and_rebuild(root, source, destination, options.drafts, options.incremental).await?;
+    }
+    
+    Ok(())
+}
+
+/// Build into a scratch directory and report how the output would change
+async fn dry_run(
+    root: PathBuf,
+    destination: PathBuf,
+    config: Config,
+    drafts: bool,
+    show_diff: bool,
+) -> Result<()> {
+    let scratch = std::env::temp_dir().join(format!("jellrust-dry-run-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&scratch);
+    
+    let report = match build_scratch(&root, &destination, &scratch, config, drafts).await {
+        Ok(report) => report,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&scratch);
+            return Err(e);
+        }
+    };
+    
+    println!("🔍 Dry run: comparing with {}", destination.display());
+    for path in &report.added {
+        println!("   + {}", path.display());
+    }
+    for path in &report.removed {
+        println!("   - {}", path.display());
     }
+    for path in &report.changed {
+        println!("   ~ {}", path.display());
+        
+        if show_diff && path.extension().is_some_and(|ext| ext == "html") {
+            let old = fs::read_to_string(destination.join(path)).unwrap_or_default();
+            let new = fs::read_to_string(scratch.join(path)).unwrap_or_default();
+            for hunk in format_word_diff(&word_diff(&old, &new)) {
+                println!("       {}", hunk);
+            }
+        }
+    }
+    println!(
+        "📊 {} added, {} removed, {} changed, {} unchanged",
+        report.added.len(),
+        report.removed.len(),
+        report.changed.len(),
+        report.unchanged
+    );
+    println!("ℹ️  Nothing was written to {}", destination.display());
     
+    fs::remove_dir_all(&scratch).context("Failed to remove dry run output")?;
     Ok(())
 }
 
+/// Build into `scratch` and compare it with the output in `destination`
+///
+/// The destination is skipped as source, as it would be when building there.
+async fn build_scratch(
+    root: &Path,
+    destination: &Path,
+    scratch: &Path,
+    config: Config,
+    drafts: bool,
+) -> Result<TreeDiff> {
+    let mut builder = site_builder(root, scratch.to_path_buf(), config);
+    builder.exclude_dir(destination.to_path_buf());
+    builder.set_include_drafts(drafts);
+    builder.build().await?;
+    diff_trees(destination, scratch)
+}
+
+/// Differences between an existing output tree and a fresh build
+#[derive(Debug, Default)]
+struct TreeDiff {
+    added: Vec<PathBuf>,
+    removed: Vec<PathBuf>,
+    changed: Vec<PathBuf>,
+    unchanged: usize,
+}
+
+/// Relative paths of all files below `root` (the build id is ignored)
+fn output_files(root: &Path) -> BTreeSet<PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.path().strip_prefix(root).ok().map(Path::to_path_buf))
+        .filter(|p| p != Path::new(BUILD_ID_FILE))
+        .collect()
+}
+
+fn diff_trees(old_root: &Path, new_root: &Path) -> Result<TreeDiff> {
+    let old_files = output_files(old_root);
+    let new_files = output_files(new_root);
+    let mut diff = TreeDiff::default();
+    
+    for path in new_files.difference(&old_files) {
+        diff.added.push(path.clone());
+    }
+    for path in old_files.difference(&new_files) {
+        diff.removed.push(path.clone());
+    }
+    for path in old_files.intersection(&new_files) {
+        let old = fs::read(old_root.join(path))?;
+        let new = fs::read(new_root.join(path))?;
+        if old == new {
+            diff.unchanged += 1;
+        } else {
+            diff.changed.push(path.clone());
+        }
+    }
+    
+    Ok(diff)
+}
+
+#[derive(Debug, PartialEq)]
+enum WordChange<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Above this many LCS cells the changed region is reported wholesale
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Word-level diff of two documents (whitespace is not significant)
+fn word_diff<'a>(old: &'a str, new: &'a str) -> Vec<WordChange<'a>> {
+    let old: Vec<&str> = old.split_whitespace().collect();
+    let new: Vec<&str> = new.split_whitespace().collect();
+    
+    // Trim the common prefix and suffix before the quadratic part
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    
+    let mut changes: Vec<WordChange> = old[..prefix].iter().map(|w| WordChange::Same(w)).collect();
+    
+    if old_mid.len() * new_mid.len() > MAX_DIFF_CELLS {
+        changes.extend(old_mid.iter().map(|w| WordChange::Removed(w)));
+        changes.extend(new_mid.iter().map(|w| WordChange::Added(w)));
+    } else {
+        // lcs[i][j] = length of the LCS of old_mid[i..] and new_mid[j..]
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                changes.push(WordChange::Same(old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if j == m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+                changes.push(WordChange::Removed(old_mid[i]));
+                i += 1;
+            } else {
+                changes.push(WordChange::Added(new_mid[j]));
+                j += 1;
+            }
+        }
+    }
+    
+    changes.extend(old[old.len() - suffix..].iter().map(|w| WordChange::Same(w)));
+    changes
+}
+
+/// Words of unchanged context shown around each change
+const DIFF_CONTEXT_WORDS: usize = 4;
+
+/// Render changes as `[-removed-]{+added+}` hunks with a little context
+fn format_word_diff(changes: &[WordChange]) -> Vec<String> {
+    let changed: Vec<usize> = changes
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| !matches!(c, WordChange::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+    
+    // Group nearby changes into hunks of (start, end) word ranges
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(DIFF_CONTEXT_WORDS);
+        let end = (i + DIFF_CONTEXT_WORDS + 1).min(changes.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let mut hunk = Vec::new();
+            let mut k = start;
+            while k < end {
+                match changes[k] {
+                    WordChange::Same(word) => {
+                        hunk.push(word.to_string());
+                        k += 1;
+                    }
+                    _ => {
+                        // Collapse a run of removals/additions into one marker each
+                        let mut removed = Vec::new();
+                        let mut added = Vec::new();
+                        while k < end && !matches!(changes[k], WordChange::Same(_)) {
+                            match changes[k] {
+                                WordChange::Removed(word) => removed.push(word),
+                                WordChange::Added(word) => added.push(word),
+                                WordChange::Same(_) => unreachable!(),
+                            }
+                            k += 1;
+                        }
+                        if !removed.is_empty() {
+                            hunk.push(format!("[-{}-]", removed.join(" ")));
+                        }
+                        if !added.is_empty() {
+                            hunk.push(format!("{{+{}+}}", added.join(" ")));
+                        }
+                    }
+                }
+            }
+            
+            let mut line = hunk.join(" ");
+            if start > 0 {
+                line = format!("... {}", line);
+            }
+            if end < changes.len() {
+                line.push_str(" ...");
+            }
+            line
+        })
+        .collect()
+}
+
 async fn watch_and_rebuild(
+    root: PathBuf,
     source: PathBuf,
     destination: PathBuf,
     drafts: bool,
+    incremental: bool,
 ) -> Result<()> {
     use notify::{RecursiveMode, Watcher};
     use tokio::sync::mpsc;
     
     let (tx, mut rx) = mpsc::channel(100);
     
-    let mut watcher = notify::recommended_watcher(move |res| {
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
         if let Ok(event) = res {
-            let _ = tx.blocking_send(event);
+            // Ignore writes to the build cache made by the build itself
+            let is_cache_event = event
+                .paths
+                .iter()
+                .all(|path| path.components().any(|c| c.as_os_str() == CACHE_DIR));
+            if !is_cache_event {
+                let _ = tx.blocking_send(event);
+            }
         }
     })?;
     
@@ -52,15 +335,23 @@ async fn watch_and_rebuild(
     while let Some(_event) = rx.recv().await {
         tracing::info!("Change detected, rebuilding...");
         
-        match Config::load(&source) {
+        match Config::load(&root) {
             Ok(config) => {
-                let mut builder = SiteBuilder::new(source.clone(), destination.clone(), config);
+                let mut builder = site_builder(&root, destination.clone(), config.clone());
                 builder.set_include_drafts(drafts);
+                builder.set_incremental(incremental);
                 
-                match builder.build().await {
-                    Ok(_) => println!("✅ Site rebuilt successfully!"),
-                    Err(e) => eprintln!("❌ Build failed: {}", e),
-                }
+                let outcome = match builder.build().await {
+                    Ok(summary) => {
+                        println!("✅ Site rebuilt: {}", summary);
+                        RebuildOutcome::Success { duration: summary.duration }
+                    }
+                    Err(e) => {
+                        eprintln!("❌ Build failed: {}", e);
+                        RebuildOutcome::Failure { error: e.to_string() }
+                    }
+                };
+                notify_rebuild(&config, &outcome);
             }
             Err(e) => eprintln!("❌ Failed to load config: {}", e),
         }
@@ -69,3 +360,74 @@ async fn watch_and_rebuild(
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+
+    #[test]
+    fn test_word_diff() {
+        let changes = word_diff("<p>Hello old world</p>", "<p>Hello new   world</p> !");
+        assert_eq!(
+            changes,
+            vec![
+                WordChange::Same("<p>Hello"),
+                WordChange::Removed("old"),
+                WordChange::Added("new"),
+                WordChange::Same("world</p>"),
+                WordChange::Added("!"),
+            ]
+        );
+        assert!(word_diff("same words", "same\nwords").iter().all(|c| matches!(c, WordChange::Same(_))));
+    }
+
+    #[test]
+    fn test_format_word_diff_hunks() {
+        let old = "a b c d e f g h i j k l m n o p";
+        let new = "a B c d e f g h i j k l m n o P";
+        let hunks = format_word_diff(&word_diff(old, new));
+        assert_eq!(hunks, vec!["a [-b-] {+B+} c d e f ...", "... l m n o [-p-] {+P+}"]);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_skips_destination() {
+        let root = temp_site("dry-run");
+        fs::write(root.join("_config.yml"), "destination: public\n").unwrap();
+        fs::write(root.join("index.md"), "# Home").unwrap();
+        fs::create_dir_all(root.join("public")).unwrap();
+        fs::write(root.join("public/index.html"), "old home").unwrap();
+        fs::write(root.join("public/gone.html"), "x").unwrap();
+
+        let config = Config::load(&root).unwrap();
+        let destination = resolve_destination(&root, None, &config);
+        let report = build_scratch(&root, &destination, &root.join("scratch"), config, false)
+            .await
+            .unwrap();
+        assert!(report.added.is_empty(), "{:?}", report.added);
+        assert_eq!(report.removed, vec![PathBuf::from("gone.html")]);
+        assert_eq!(report.changed, vec![PathBuf::from("index.html")]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_diff_trees() {
+        let root = temp_site("tree-diff");
+        let (old, new) = (root.join("old"), root.join("new"));
+        fs::create_dir_all(old.join("blog")).unwrap();
+        fs::create_dir_all(new.join("blog")).unwrap();
+        fs::write(old.join("index.html"), "same").unwrap();
+        fs::write(new.join("index.html"), "same").unwrap();
+        fs::write(old.join("blog/post.html"), "before").unwrap();
+        fs::write(new.join("blog/post.html"), "after").unwrap();
+        fs::write(old.join("gone.html"), "x").unwrap();
+        fs::write(new.join("about.html"), "x").unwrap();
+        fs::write(new.join(BUILD_ID_FILE), "1").unwrap();
+
+        let diff = diff_trees(&old, &new).unwrap();
+        assert_eq!(diff.added, vec![PathBuf::from("about.html")]);
+        assert_eq!(diff.removed, vec![PathBuf::from("gone.html")]);
+        assert_eq!(diff.changed, vec![PathBuf::from("blog/post.html")]);
+        assert_eq!(diff.unchanged, 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
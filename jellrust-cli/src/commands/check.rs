@@ -0,0 +1,43 @@
+use super::{resolve_destination, site_builder};
+use anyhow::{bail, Result};
+use jellrust_core::config::{Config, ConfigExt};
+use jellrust_core::links::{collect_links, dead_external_links, dead_internal_links, DeadLink};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Build the site, then look for links in the output that lead nowhere
+pub async fn execute(root: PathBuf, destination: Option<PathBuf>, external_links: bool) -> Result<()> {
+    let config = Config::load(&root)?;
+    let destination = resolve_destination(&root, destination, &config);
+    let source = config.source_dir(&root);
+    let baseurl = config.baseurl.clone();
+    let link_check = config.link_check.clone();
+
+    let mut builder = site_builder(&root, destination.clone(), config);
+    builder.build().await?;
+
+    println!("🔍 Checking links in {}...", destination.display());
+    let links = collect_links(&destination)?;
+    let mut dead = dead_internal_links(&destination, &baseurl, &links);
+    if external_links {
+        dead.extend(dead_external_links(&source, &link_check, &links)?);
+    }
+
+    if dead.is_empty() {
+        println!("✅ No dead links among {} link(s)", links.len());
+        return Ok(());
+    }
+
+    let mut by_page: BTreeMap<&str, Vec<&DeadLink>> = BTreeMap::new();
+    for link in &dead {
+        by_page.entry(&link.page).or_default().push(link);
+    }
+    for (page, links) in &by_page {
+        println!("\n❌ {}", page);
+        for link in links {
+            println!("     {} ({})", link.url, link.reason);
+        }
+    }
+    println!();
+    bail!("Found {} dead link(s) on {} page(s)", dead.len(), by_page.len())
+}
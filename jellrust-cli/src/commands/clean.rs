@@ -1,22 +1,41 @@
-use anyhow::{Context, Result};
+use super::resolve_destination;
+use anyhow::{bail, Context, Result};
+use jellrust_core::config::{Config, ConfigExt};
+use jellrust_core::lock::BuildLock;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
-pub fn execute(source: PathBuf) -> Result<()> {
-    let site_dir = source.join("_site");
+pub fn execute(root: PathBuf, destination: Option<PathBuf>) -> Result<()> {
+    let config = Config::load(&root)?;
+    let site_dir = resolve_destination(&root, destination, &config);
+    let source = config.source_dir(&root);
     
     if !site_dir.exists() {
-        println!("✅ Nothing to clean - _site directory doesn't exist");
+        println!("✅ Nothing to clean - {} doesn't exist", site_dir.display());
         return Ok(());
     }
     
+    // Never delete the site itself because of a misconfigured destination
+    let site_dir_canonical = site_dir.canonicalize()?;
+    for dir in [&root, &source] {
+        if dir.canonicalize().is_ok_and(|dir| dir.starts_with(&site_dir_canonical)) {
+            bail!(
+                "Refusing to remove {}: it contains the site source",
+                site_dir.display()
+            );
+        }
+    }
+    
+    // Don't pull the destination out from under a running build
+    let _lock = BuildLock::acquire(&source, Duration::from_secs(config.build_lock_timeout))?;
+    
     tracing::info!("Removing {}", site_dir.display());
     
     fs::remove_dir_all(&site_dir)
-        .context("Failed to remove _site directory")?;
+        .with_context(|| format!("Failed to remove {}", site_dir.display()))?;
     
-    println!("✅ Successfully removed _site directory");
+    println!("✅ Successfully removed {}", site_dir.display());
     
     Ok(())
 }
-
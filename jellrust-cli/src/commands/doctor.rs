@@ -1,29 +1,81 @@
+use super::meta::{content_files, split_front_matter};
+use super::resolve_destination;
 use anyhow::Result;
-use std::path::PathBuf;
+use chrono::Utc;
+use jellrust_core::config::{Config, ConfigExt};
+use jellrust_core::site::parse_date;
+use jellrust_core::theme::theme_dir;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-pub fn execute(source: PathBuf) -> Result<()> {
+pub fn execute(root: PathBuf, destination: Option<PathBuf>) -> Result<()> {
     println!("🔍 Running JellRust Doctor...\n");
     
     let mut issues = 0;
     let mut warnings = 0;
     
     // Check if _config.yml exists
-    if !source.join("_config.yml").exists() {
+    if !root.join("_config.yml").exists() {
         println!("❌ Missing _config.yml");
         issues += 1;
     } else {
         println!("✅ Found _config.yml");
     }
     
+    let config = match Config::load(&root) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("❌ Invalid _config.yml: {}", e);
+            issues += 1;
+            Config::default()
+        }
+    };
+    
+    // Check the content directory
+    let source = config.source_dir(&root);
+    if !source.is_dir() {
+        println!("❌ Source directory {} doesn't exist", source.display());
+        issues += 1;
+    } else if source != root {
+        println!("✅ Source directory: {}", source.display());
+    }
+    
+    // Check the output directory
+    let destination = resolve_destination(&root, destination, &config);
+    let contains_source = match (destination.canonicalize(), source.canonicalize()) {
+        (Ok(destination), Ok(source)) => source.starts_with(destination),
+        _ => false,
+    };
+    if contains_source {
+        println!("❌ Destination {} contains the source directory", destination.display());
+        issues += 1;
+    } else {
+        println!("✅ Output directory: {}", destination.display());
+    }
+    
+    // Check the theme, which may provide layouts and assets
+    let theme = theme_dir(&source, &config);
+    match (&config.theme, &theme) {
+        (_, Some(theme)) => println!("✅ Theme: {}", theme.display()),
+        (Some(name), None) => {
+            println!("❌ Theme {:?} not found", name);
+            issues += 1;
+        }
+        (None, None) => {}
+    }
+    let exists = |path: &str| {
+        source.join(path).exists() || theme.as_ref().is_some_and(|theme| theme.join(path).exists())
+    };
+    
     // Check for _layouts directory
-    if !source.join("_layouts").exists() {
+    if !exists("_layouts") {
         println!("⚠️  Missing _layouts directory");
         warnings += 1;
     } else {
         println!("✅ Found _layouts directory");
         
         // Check for default layout
-        if !source.join("_layouts/default.html").exists() {
+        if !exists("_layouts/default.html") {
             println!("⚠️  No default.html layout found");
             warnings += 1;
         }
@@ -50,13 +102,24 @@ pub fn execute(source: PathBuf) -> Result<()> {
     }
     
     // Check for assets
-    if source.join("assets").exists() {
+    if exists("assets") {
         println!("✅ Found assets directory");
     } else {
         println!("⚠️  No assets directory found");
         warnings += 1;
     }
     
+    // Check for posts past their `expires:` or `sitemap_expires:` date
+    let expired = expired_content(&source, &destination, &config);
+    if !expired.is_empty() {
+        println!("⚠️  {} expired post(s), still built but no longer listed:", expired.len());
+        for (path, key, date) in &expired {
+            let path = path.strip_prefix(&source).unwrap_or(path);
+            println!("     {} ({}: {})", path.display(), key, date);
+        }
+        warnings += 1;
+    }
+    
     // Summary
     println!("\n─────────────────────────");
     if issues == 0 && warnings == 0 {
@@ -73,3 +136,38 @@ pub fn execute(source: PathBuf) -> Result<()> {
     Ok(())
 }
 
+
+/// Posts whose `expires:` or `sitemap_expires:` date has passed, with that key and date
+fn expired_content(source: &Path, destination: &Path, config: &Config) -> Vec<(PathBuf, &'static str, String)> {
+    let now = Utc::now();
+    let mut expired = Vec::new();
+
+    for path in content_files(source, destination, config) {
+        let in_posts = path
+            .components()
+            .any(|c| matches!(c.as_os_str().to_str(), Some("_posts" | "_drafts")));
+        if !in_posts {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Some((yaml, _)) = split_front_matter(&content) else {
+            continue;
+        };
+        let Ok(serde_yaml::Value::Mapping(front_matter)) = serde_yaml::from_str(yaml) else {
+            continue;
+        };
+
+        let passed = ["expires", "sitemap_expires"].into_iter().find_map(|key| {
+            let date = front_matter.get(key)?.as_str()?;
+            (parse_date(date)? <= now).then(|| (key, date.to_string()))
+        });
+        if let Some((key, date)) = passed {
+            expired.push((path, key, date));
+        }
+    }
+
+    expired.sort();
+    expired
+}
@@ -0,0 +1,429 @@
+use super::resolve_destination;
+use anyhow::{bail, Context, Result};
+use jellrust_core::config::{Config, ConfigExt};
+use jellrust_markdown::has_front_matter;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// `key:` starting a line, the key plain or quoted
+static TOP_LEVEL_KEY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^("[^"]*"|'[^']*'|[^\s#'"\-][^:#]*?)[ \t]*:(?:[ \t]|$)"#).unwrap());
+
+/// Options for `jellrust meta migrate`
+pub struct MigrateOptions {
+    pub rename: Vec<String>,
+    pub set: Vec<String>,
+    pub filter: Option<String>,
+    pub dry_run: bool,
+}
+
+/// A parsed `--where` condition
+#[derive(Debug, PartialEq)]
+enum Condition {
+    Exists(String),
+    Equals(String, String),
+    NotEquals(String, String),
+    Contains(String, String),
+}
+
+pub fn migrate(root: PathBuf, options: MigrateOptions) -> Result<()> {
+    let renames = parse_pairs(&options.rename, "--rename")?;
+    let sets = parse_pairs(&options.set, "--set")?;
+    let condition = options.filter.as_deref().map(parse_condition).transpose()?;
+
+    if renames.is_empty() && sets.is_empty() {
+        bail!("Nothing to do: pass at least one --rename or --set");
+    }
+
+    let config = Config::load(&root)?;
+    let source = config.source_dir(&root);
+    let destination = resolve_destination(&root, None, &config);
+    let mut changed = 0;
+
+    for path in content_files(&source, &destination, &config) {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        let Some((yaml, _)) = split_front_matter(&content) else {
+            if has_front_matter(&content) {
+                tracing::warn!("Skipping {}: only YAML front matter can be migrated", path.display());
+            }
+            continue;
+        };
+
+        let mut front_matter: Mapping = match serde_yaml::from_str(yaml) {
+            Ok(Value::Mapping(m)) => m,
+            Ok(_) => Mapping::new(),
+            Err(e) => {
+                tracing::warn!("Skipping {}: invalid front matter: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        if let Some(condition) = &condition
+            && !matches_condition(&front_matter, condition)
+        {
+            continue;
+        }
+
+        let before = front_matter.clone();
+        apply_changes(&mut front_matter, &renames, &sets);
+        if front_matter == before {
+            continue;
+        }
+
+        // Edited as text to keep comments, key order and quoting; files the
+        // edit would get wrong are left alone
+        let new_yaml = edit_front_matter(yaml, &renames, &sets);
+        if serde_yaml::from_str::<Mapping>(&new_yaml).ok() != Some(front_matter) {
+            tracing::warn!("Skipping {}: its front matter can't be edited in place", path.display());
+            continue;
+        }
+        let rel_path = path.strip_prefix(&source).unwrap_or(&path);
+
+        println!("📝 {}", rel_path.display());
+        print_diff(yaml, &new_yaml);
+        changed += 1;
+
+        if !options.dry_run {
+            fs::write(&path, replace_front_matter(&content, &new_yaml))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+    }
+
+    if options.dry_run {
+        println!("\n🔍 Dry run: {} file(s) would be changed", changed);
+    } else {
+        println!("\n✅ Migrated front matter in {} file(s)", changed);
+    }
+
+    Ok(())
+}
+
+/// Parse `key=value` arguments
+fn parse_pairs(args: &[String], flag: &str) -> Result<Vec<(String, String)>> {
+    args.iter()
+        .map(|arg| match arg.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            }
+            _ => bail!("Invalid {} argument '{}', expected key=value", flag, arg),
+        })
+        .collect()
+}
+
+/// Parse a `--where` expression such as `tags contains "rust"`
+fn parse_condition(expr: &str) -> Result<Condition> {
+    let expr = expr.trim();
+    let unquote = |s: &str| s.trim().trim_matches(['"', '\'']).to_string();
+
+    if let Some((key, value)) = expr.split_once(" contains ") {
+        return Ok(Condition::Contains(key.trim().to_string(), unquote(value)));
+    }
+    if let Some((key, value)) = expr.split_once("!=") {
+        return Ok(Condition::NotEquals(key.trim().to_string(), unquote(value)));
+    }
+    if let Some((key, value)) = expr.split_once("==") {
+        return Ok(Condition::Equals(key.trim().to_string(), unquote(value)));
+    }
+    if !expr.is_empty() && !expr.contains(char::is_whitespace) {
+        return Ok(Condition::Exists(expr.to_string()));
+    }
+
+    bail!("Unsupported --where expression '{}'", expr)
+}
+
+/// Check whether front matter satisfies a condition
+fn matches_condition(front_matter: &Mapping, condition: &Condition) -> bool {
+    let get = |key: &str| front_matter.get(Value::String(key.to_string()));
+
+    match condition {
+        Condition::Exists(key) => get(key).is_some(),
+        Condition::Equals(key, expected) => get(key).map(scalar_to_string).as_deref() == Some(expected),
+        Condition::NotEquals(key, expected) => get(key).map(scalar_to_string).as_deref() != Some(expected),
+        Condition::Contains(key, needle) => match get(key) {
+            Some(Value::Sequence(items)) => items.iter().any(|v| scalar_to_string(v) == *needle),
+            Some(Value::String(s)) => s.contains(needle.as_str()),
+            _ => false,
+        },
+    }
+}
+
+/// Render a scalar YAML value as a plain string
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Null => String::new(),
+        other => serde_yaml::to_string(other).unwrap_or_default().trim().to_string(),
+    }
+}
+
+/// Apply renames (keeping key position) and sets to the front matter
+fn apply_changes(front_matter: &mut Mapping, renames: &[(String, String)], sets: &[(String, String)]) {
+    for (old_key, new_key) in renames {
+        let old = Value::String(old_key.clone());
+        if !front_matter.contains_key(&old) {
+            continue;
+        }
+
+        *front_matter = front_matter
+            .iter()
+            .map(|(k, v)| {
+                if *k == old {
+                    (Value::String(new_key.clone()), v.clone())
+                } else {
+                    (k.clone(), v.clone())
+                }
+            })
+            .collect();
+    }
+
+    for (key, raw) in sets {
+        let value = serde_yaml::from_str(raw).unwrap_or_else(|_| Value::String(raw.clone()));
+        front_matter.insert(Value::String(key.clone()), value);
+    }
+}
+
+/// Split a file into raw front matter YAML and the untouched body
+pub(super) fn split_front_matter(content: &str) -> Option<(&str, &str)> {
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+    let end = rest.find("\n---")?;
+    let yaml = &rest[..end + 1];
+    let after = &rest[end + 4..];
+    let body = after
+        .strip_prefix("\r\n")
+        .or_else(|| after.strip_prefix('\n'))
+        .unwrap_or(after);
+    Some((yaml, body))
+}
+
+/// `content` with its front matter YAML swapped for `yaml`, keeping the
+/// fences and body byte for byte
+fn replace_front_matter(content: &str, yaml: &str) -> String {
+    let start = if content.starts_with("---\r\n") { 5 } else { 4 };
+    let old_len = split_front_matter(content).map_or(0, |(old, _)| old.len());
+    format!("{}{}{}", &content[..start], yaml, &content[start + old_len..])
+}
+
+/// Key of a top-level `key:` line of front matter YAML, and where the key
+/// as written ends
+fn top_level_key(line: &str) -> Option<(String, usize)> {
+    let key = TOP_LEVEL_KEY.captures(line)?.get(1)?;
+    Some((key.as_str().trim_matches(['"', '\'']).to_string(), key.end()))
+}
+
+/// Lines `start..end` of each top-level entry: its key line, and the
+/// indented or `- ` lines (with blank lines in between) holding its value
+fn entries(lines: &[String]) -> Vec<(String, usize, usize)> {
+    let mut entries = Vec::new();
+    for (start, line) in lines.iter().enumerate() {
+        let Some((key, _)) = top_level_key(line) else {
+            continue;
+        };
+        let mut end = start + 1;
+        let mut next = end;
+        while next < lines.len() {
+            let line = &lines[next];
+            if line.trim().is_empty() {
+                next += 1;
+                continue;
+            }
+            if !(line.starts_with([' ', '\t']) || line.starts_with("- ") || line == "-") {
+                break;
+            }
+            next += 1;
+            end = next;
+        }
+        entries.push((key, start, end));
+    }
+    entries
+}
+
+/// `key: value` lines for a value given on the command line
+fn entry_lines(key: &str, raw: &str) -> Vec<String> {
+    let value: Value = serde_yaml::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()));
+    let yaml = serde_yaml::to_string(&value).unwrap_or_default();
+    match &value {
+        Value::Sequence(items) if !items.is_empty() => nested_lines(key, &yaml),
+        Value::Mapping(map) if !map.is_empty() => nested_lines(key, &yaml),
+        _ => vec![format!("{}: {}", key, yaml.trim_end())],
+    }
+}
+
+fn nested_lines(key: &str, yaml: &str) -> Vec<String> {
+    std::iter::once(format!("{}:", key))
+        .chain(yaml.lines().map(|line| format!("  {}", line)))
+        .collect()
+}
+
+/// Apply renames and sets to front matter YAML as text
+///
+/// Renamed keys keep their line, and set keys replace their entry or are
+/// appended; everything else, comments included, is left as written.
+fn edit_front_matter(yaml: &str, renames: &[(String, String)], sets: &[(String, String)]) -> String {
+    let newline = if yaml.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = yaml.lines().map(String::from).collect();
+
+    for (old_key, new_key) in renames {
+        for line in lines.iter_mut() {
+            if let Some((key, end)) = top_level_key(line)
+                && key == *old_key
+            {
+                *line = format!("{}{}", new_key, &line[end..]);
+            }
+        }
+    }
+
+    for (key, raw) in sets {
+        let replacement = entry_lines(key, raw);
+        match entries(&lines).into_iter().find(|(existing, _, _)| existing == key) {
+            Some((_, start, end)) => {
+                lines.splice(start..end, replacement);
+            }
+            None => lines.extend(replacement),
+        }
+    }
+
+    lines.iter().map(|line| format!("{}{}", line, newline)).collect()
+}
+
+/// Print a line-based diff of two YAML documents
+fn print_diff(old: &str, new: &str) {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    for line in &old_lines {
+        if !new_lines.contains(line) {
+            println!("   - {}", line);
+        }
+    }
+    for line in &new_lines {
+        if !old_lines.contains(line) {
+            println!("   + {}", line);
+        }
+    }
+}
+
+/// All content files (Markdown and HTML) in the source tree, outside the
+/// build output
+pub(super) fn content_files(source: &Path, destination: &Path, config: &Config) -> Vec<PathBuf> {
+    WalkDir::new(source)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            !matches!(name.as_ref(), "node_modules" | ".git") && e.path() != destination
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .filter(|p| !config.is_excluded(p))
+        .filter(|p| {
+            matches!(
+                p.extension().and_then(|s| s.to_str()),
+                Some("md") | Some("markdown") | Some("html")
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+
+    fn mapping(yaml: &str) -> Mapping {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        assert_eq!(
+            parse_condition(r#"tags contains "rust""#).unwrap(),
+            Condition::Contains("tags".into(), "rust".into())
+        );
+        assert_eq!(
+            parse_condition("layout == post").unwrap(),
+            Condition::Equals("layout".into(), "post".into())
+        );
+        assert_eq!(parse_condition("image").unwrap(), Condition::Exists("image".into()));
+        assert!(parse_condition("what is this").is_err());
+    }
+
+    #[test]
+    fn test_matches_condition() {
+        let fm = mapping("tags: [rust, web]\nlayout: post\n");
+        assert!(matches_condition(&fm, &Condition::Contains("tags".into(), "rust".into())));
+        assert!(!matches_condition(&fm, &Condition::Contains("tags".into(), "go".into())));
+        assert!(matches_condition(&fm, &Condition::NotEquals("layout".into(), "page".into())));
+    }
+
+    #[test]
+    fn test_apply_changes_keeps_position() {
+        let mut fm = mapping("title: Hi\nauthor_name: Bob\ndate: 2024-01-01\n");
+        apply_changes(
+            &mut fm,
+            &[("author_name".into(), "author".into())],
+            &[("layout".into(), "post".into()), ("comments".into(), "true".into())],
+        );
+
+        let yaml = serde_yaml::to_string(&fm).unwrap();
+        assert_eq!(yaml, "title: Hi\nauthor: Bob\ndate: 2024-01-01\nlayout: post\ncomments: true\n");
+    }
+
+    #[test]
+    fn test_split_front_matter_keeps_body() {
+        let (yaml, body) = split_front_matter("---\ntitle: Hi\n---\n\n# Body\n").unwrap();
+        assert_eq!(yaml, "title: Hi\n");
+        assert_eq!(body, "\n# Body\n");
+        assert!(split_front_matter("# No front matter").is_none());
+    }
+    #[test]
+    fn test_edit_front_matter_keeps_formatting() {
+        let yaml = "# Post settings\ntitle: \"Hi: there\"\nauthor_name: 'Bob' # legacy\ntags:\n  - a\n  - b\ndate: 2024-01-01\n";
+        let edited = edit_front_matter(
+            yaml,
+            &[("author_name".into(), "author".into())],
+            &[("tags".into(), "[x]".into()), ("layout".into(), "post".into())],
+        );
+        assert_eq!(
+            edited,
+            "# Post settings\ntitle: \"Hi: there\"\nauthor: 'Bob' # legacy\ntags:\n  - x\ndate: 2024-01-01\nlayout: post\n"
+        );
+    }
+
+    #[test]
+    fn test_migrate_skips_other_formats_and_destination() {
+        let root = temp_site("migrate");
+        fs::write(root.join("_config.yml"), "destination: public\n").unwrap();
+        fs::create_dir_all(root.join("public")).unwrap();
+        let toml = "+++\ntitle = \"B\"\n+++\nBody\n";
+        let built = "---\ntitle: C\n---\n";
+        fs::write(root.join("a.md"), "---\ntitle: A # first\n---\nBody\n").unwrap();
+        fs::write(root.join("b.md"), toml).unwrap();
+        fs::write(root.join("public/c.md"), built).unwrap();
+
+        let options = MigrateOptions {
+            rename: Vec::new(),
+            set: vec!["layout=post".into()],
+            filter: None,
+            dry_run: false,
+        };
+        migrate(root.clone(), options).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(root.join("a.md")).unwrap(),
+            "---\ntitle: A # first\nlayout: post\n---\nBody\n"
+        );
+        assert_eq!(fs::read_to_string(root.join("b.md")).unwrap(), toml);
+        assert_eq!(fs::read_to_string(root.join("public/c.md")).unwrap(), built);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
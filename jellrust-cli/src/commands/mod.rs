@@ -3,4 +3,21 @@ pub mod build;
 pub mod serve;
 pub mod clean;
 pub mod doctor;
+pub mod check;
 
+pub mod meta;
+
+use jellrust_core::{config::Config, site::SiteBuilder};
+use std::path::{Path, PathBuf};
+
+/// Output directory: the CLI flag wins over `destination` in _config.yml
+pub fn resolve_destination(root: &Path, destination: Option<PathBuf>, config: &Config) -> PathBuf {
+    destination.unwrap_or_else(|| config.destination_dir(root))
+}
+
+/// Builder for the site whose _config.yml is in `root`, honoring its `source` key
+pub fn site_builder(root: &Path, destination: PathBuf, config: Config) -> SiteBuilder {
+    let mut builder = SiteBuilder::new(config.source_dir(root), destination, config);
+    builder.set_config_file(root.join("_config.yml"));
+    builder
+}
@@ -1,6 +1,6 @@
 use anyhow::{Context, Result};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn execute(name: String, path: Option<PathBuf>) -> Result<()> {
     let site_path = path.unwrap_or_else(|| PathBuf::from(&name));
@@ -24,9 +24,10 @@ pub fn execute(name: String, path: Option<PathBuf>) -> Result<()> {
     Ok(())
 }
 
-fn create_directory_structure(base: &PathBuf) -> Result<()> {
+fn create_directory_structure(base: &Path) -> Result<()> {
     let dirs = [
         "_layouts",
+        "_includes",
         "_posts",
         "_drafts",
         "_data",
@@ -45,7 +46,7 @@ fn create_directory_structure(base: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-fn create_default_files(base: &PathBuf, site_name: &str) -> Result<()> {
+fn create_default_files(base: &Path, site_name: &str) -> Result<()> {
     // _config.yml
     let config = format!(
 r#"# Site settings
@@ -114,35 +115,45 @@ This is the about page. Edit `about.md` to tell people about yourself!
     <meta charset="UTF-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
     <title>{{ page.title }} | {{ site.title }}</title>
-    <link rel="stylesheet" href="/assets/css/style.css">
+    <link rel="stylesheet" href="{{ '/assets/css/style.css' | relative_url }}">
 </head>
 <body>
-    <header class="site-header">
-        <div class="container">
-            <h1 class="site-title">
-                <a href="/">{{ site.title }}</a>
-            </h1>
-            <nav class="site-nav">
-                <a href="/">Home</a>
-                <a href="/about/">About</a>
-            </nav>
-        </div>
-    </header>
+    {% include header.html %}
 
     <main class="container">
         {{ content }}
     </main>
 
-    <footer class="site-footer">
-        <div class="container">
-            <p>&copy; {{ site.title }}. Built with JellRust.</p>
-        </div>
-    </footer>
+    {% include footer.html %}
 </body>
 </html>
 "#;
     fs::write(base.join("_layouts/default.html"), default_layout)?;
     
+    // _includes/header.html
+    let header = r#"<header class="site-header">
+    <div class="container">
+        <h1 class="site-title">
+            <a href="{{ '/' | relative_url }}">{{ site.title }}</a>
+        </h1>
+        <nav class="site-nav">
+            <a href="{{ '/' | relative_url }}">Home</a>
+            <a href="{{ '/about/' | relative_url }}">About</a>
+        </nav>
+    </div>
+</header>
+"#;
+    fs::write(base.join("_includes/header.html"), header)?;
+    
+    // _includes/footer.html
+    let footer = r#"<footer class="site-footer">
+    <div class="container">
+        <p>&copy; {{ site.title }}. Built with JellRust.</p>
+    </div>
+</footer>
+"#;
+    fs::write(base.join("_includes/footer.html"), footer)?;
+    
     // _layouts/post.html
     let post_layout = r#"---
 layout: default
@@ -352,6 +363,25 @@ code {
     font-size: 0.9rem;
 }
 
+/* Admonitions */
+.admonition {
+    border-left: 4px solid var(--primary-color);
+    background-color: var(--gray-light);
+    border-radius: 4px;
+    padding: 0.5rem 1rem;
+    margin: 1rem 0;
+}
+
+.admonition-title {
+    font-weight: bold;
+    margin-bottom: 0.5rem;
+}
+
+.admonition.tip { border-left-color: #27ae60; }
+.admonition.warning { border-left-color: #f39c12; }
+.admonition.danger,
+.admonition.caution { border-left-color: #c0392b; }
+
 /* Footer */
 .site-footer {
     background-color: var(--gray-light);
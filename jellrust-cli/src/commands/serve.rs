@@ -1,40 +1,58 @@
+use super::{resolve_destination, site_builder};
 use anyhow::Result;
-use jellrust_core::{config::{Config, ConfigExt}, site::SiteBuilder};
+use jellrust_core::config::{Config, ConfigExt};
 use jellrust_server::DevServer;
 use std::path::PathBuf;
 
+/// Options for `jellrust serve`; unset ones fall back to `serve:` in _config.yml
+pub struct ServeOptions {
+    pub port: Option<u16>,
+    pub host: Option<String>,
+    pub open: bool,
+    pub no_livereload: bool,
+    pub drafts: bool,
+    pub incremental: bool,
+}
+
 pub async fn execute(
-    source: PathBuf,
-    port: u16,
-    host: String,
-    open: bool,
-    drafts: bool,
+    root: PathBuf,
+    destination: Option<PathBuf>,
+    options: ServeOptions,
 ) -> Result<()> {
     tracing::info!("Starting development server...");
     
     // Load configuration
-    let config = Config::load(&source)?;
+    let config = Config::load(&root)?;
+    let port = options.port.unwrap_or(config.serve.port);
+    let host = options.host.unwrap_or_else(|| config.serve.host.clone());
+    let open = options.open || config.serve.open;
+    let livereload = !options.no_livereload && config.serve.livereload;
+    let ServeOptions { drafts, incremental, .. } = options;
     
     // Build the site first
-    let destination = source.join("_site");
-    let mut builder = SiteBuilder::new(source.clone(), destination.clone(), config.clone());
+    let destination = resolve_destination(&root, destination, &config);
+    let source = config.source_dir(&root);
+    let mut builder = site_builder(&root, destination.clone(), config.clone());
     builder.set_include_drafts(drafts);
-    builder.build().await?;
+    builder.set_incremental(incremental);
+    let summary = builder.build().await?;
     
-    println!("✅ Initial build complete!");
+    println!("✅ Initial build complete: {}", summary);
     
     // Start the dev server
-    let server = DevServer::new(source, destination, config, port, host.clone(), drafts);
+    let mut server = DevServer::new(source, destination, config, port, host.clone(), drafts);
+    server.set_incremental(incremental);
+    server.set_livereload(livereload);
     
     let url = format!("http://{}:{}", host, port);
     println!("\n🚀 Server running at {}", url);
     println!("👀 Watching for changes...");
     println!("   Press Ctrl+C to stop\n");
     
-    if open {
-        if let Err(e) = open::that(&url) {
-            tracing::warn!("Failed to open browser: {}", e);
-        }
+    if open
+        && let Err(e) = open::that(&url)
+    {
+        tracing::warn!("Failed to open browser: {}", e);
     }
     
     server.run().await?;
@@ -3,6 +3,8 @@ use std::path::PathBuf;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod commands;
+#[cfg(test)]
+mod testing;
 
 /// JellRust - A blazingly fast static site generator written in Rust
 #[derive(Parser)]
@@ -26,51 +28,119 @@ enum Commands {
     
     /// Build the site
     Build {
-        /// Source directory
+        /// Site directory (where _config.yml lives)
         #[arg(short, long, default_value = ".")]
         source: PathBuf,
-        /// Destination directory
-        #[arg(short, long, default_value = "_site")]
-        destination: PathBuf,
+        /// Destination directory (defaults to `destination` in _config.yml)
+        #[arg(short, long)]
+        destination: Option<PathBuf>,
         /// Include draft posts
         #[arg(long)]
         drafts: bool,
         /// Watch for changes and rebuild
         #[arg(short, long)]
         watch: bool,
+        /// Only re-render content whose source, layouts or includes changed
+        #[arg(long)]
+        incremental: bool,
+        /// Build into a scratch directory and summarize changes to the destination
+        #[arg(long, conflicts_with_all = ["watch", "incremental"])]
+        dry_run: bool,
+        /// With --dry-run, show word-level diffs of changed HTML files
+        #[arg(long, requires = "dry_run")]
+        diff: bool,
+        /// Report the time spent rendering each layout and include
+        #[arg(long)]
+        profile: bool,
     },
     
     /// Serve the site locally with live reload
     Serve {
-        /// Source directory
+        /// Site directory (where _config.yml lives)
         #[arg(short, long, default_value = ".")]
         source: PathBuf,
-        /// Port to serve on
-        #[arg(short, long, default_value = "4000")]
-        port: u16,
-        /// Host to bind to
-        #[arg(long, default_value = "127.0.0.1")]
-        host: String,
-        /// Open browser automatically
+        /// Destination directory (defaults to `destination` in _config.yml)
+        #[arg(short, long)]
+        destination: Option<PathBuf>,
+        /// Port to serve on (defaults to `serve.port`, else 4000)
+        #[arg(short, long)]
+        port: Option<u16>,
+        /// Host to bind to (defaults to `serve.host`, else 127.0.0.1)
+        #[arg(long)]
+        host: Option<String>,
+        /// Open browser automatically (also with `serve.open: true`)
         #[arg(short, long)]
         open: bool,
+        /// Don't reload open pages after rebuilds (also with `serve.livereload: false`)
+        #[arg(long)]
+        no_livereload: bool,
         /// Include draft posts
         #[arg(long)]
         drafts: bool,
+        /// Only re-render content that changed, reusing the last build across restarts
+        #[arg(long)]
+        incremental: bool,
     },
     
-    /// Clean the site (remove _site directory)
+    /// Clean the site (remove the destination directory)
     Clean {
-        /// Source directory
+        /// Site directory (where _config.yml lives)
         #[arg(short, long, default_value = ".")]
         source: PathBuf,
+        /// Destination directory (defaults to `destination` in _config.yml)
+        #[arg(short, long)]
+        destination: Option<PathBuf>,
     },
     
     /// Doctor - Check your site for common issues
     Doctor {
-        /// Source directory
+        /// Site directory (where _config.yml lives)
+        #[arg(short, long, default_value = ".")]
+        source: PathBuf,
+        /// Destination directory (defaults to `destination` in _config.yml)
+        #[arg(short, long)]
+        destination: Option<PathBuf>,
+    },
+    
+    /// Build the site and report links that lead nowhere
+    Check {
+        /// Site directory (where _config.yml lives)
+        #[arg(short, long, default_value = ".")]
+        source: PathBuf,
+        /// Destination directory (defaults to `destination` in _config.yml)
+        #[arg(short, long)]
+        destination: Option<PathBuf>,
+        /// Also request outbound http(s) links, as configured under `link_check:`
+        #[arg(long)]
+        external_links: bool,
+    },
+    
+    /// Manage front matter across the content tree
+    Meta {
+        #[command(subcommand)]
+        command: MetaCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum MetaCommands {
+    /// Rename or set front matter keys in bulk
+    Migrate {
+        /// Site directory (where _config.yml lives)
         #[arg(short, long, default_value = ".")]
         source: PathBuf,
+        /// Rename a key (old_key=new_key), can be repeated
+        #[arg(long, value_name = "OLD=NEW")]
+        rename: Vec<String>,
+        /// Set a key to a value (key=value), can be repeated
+        #[arg(long, value_name = "KEY=VALUE")]
+        set: Vec<String>,
+        /// Only touch files matching a condition (e.g. 'tags contains "rust"')
+        #[arg(long = "where", value_name = "EXPR")]
+        filter: Option<String>,
+        /// Show the changes without writing any files
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
@@ -83,6 +153,7 @@ async fn main() -> anyhow::Result<()> {
                 .unwrap_or_else(|_| "jellrust=info".into()),
         )
         .with(tracing_subscriber::fmt::layer())
+        .with(jellrust_core::summary::WarningCounter)
         .init();
 
     let cli = Cli::parse();
@@ -96,24 +167,71 @@ async fn main() -> anyhow::Result<()> {
             destination,
             drafts,
             watch,
+            incremental,
+            dry_run,
+            diff,
+            profile,
         } => {
-            commands::build::execute(source, destination, drafts, watch).await?;
+            let options = commands::build::BuildOptions {
+                drafts,
+                watch,
+                incremental,
+                dry_run,
+                diff,
+                profile,
+            };
+            commands::build::execute(source, destination, options).await?;
         }
         Commands::Serve {
             source,
+            destination,
             port,
             host,
             open,
+            no_livereload,
             drafts,
+            incremental,
         } => {
-            commands::serve::execute(source, port, host, open, drafts).await?;
+            let options = commands::serve::ServeOptions {
+                port,
+                host,
+                open,
+                no_livereload,
+                drafts,
+                incremental,
+            };
+            commands::serve::execute(source, destination, options).await?;
+        }
+        Commands::Clean { source, destination } => {
+            commands::clean::execute(source, destination)?;
         }
-        Commands::Clean { source } => {
-            commands::clean::execute(source)?;
+        Commands::Doctor { source, destination } => {
+            commands::doctor::execute(source, destination)?;
         }
-        Commands::Doctor { source } => {
-            commands::doctor::execute(source)?;
+        Commands::Check {
+            source,
+            destination,
+            external_links,
+        } => {
+            commands::check::execute(source, destination, external_links).await?;
         }
+        Commands::Meta { command } => match command {
+            MetaCommands::Migrate {
+                source,
+                rename,
+                set,
+                filter,
+                dry_run,
+            } => {
+                let options = commands::meta::MigrateOptions {
+                    rename,
+                    set,
+                    filter,
+                    dry_run,
+                };
+                commands::meta::migrate(source, options)?;
+            }
+        },
     }
 
     Ok(())
@@ -0,0 +1,217 @@
+use crate::config::Config;
+use crate::content::{Page, Post, Site};
+use crate::data::DATA_DIR;
+use crate::discovery::{atom_feed, FEED_FILE};
+use crate::error::Result;
+use jellrust_template::TemplateEngine;
+use jellrust_types::Paginator;
+use std::fs;
+use std::path::Path;
+
+/// Key of the author data in `site.data` (`_data/authors.yml`)
+pub const AUTHORS_DATA: &str = "authors";
+
+/// Add a page listing the posts of each author in `_data/authors.yml`
+///
+/// Pages are only generated once the site or its theme has the
+/// `authors.layout` layout.
+/// The author's data is available as `page.author` (plus `page.author.slug`)
+/// and their posts as `paginator.posts`, split over several pages when
+/// `authors.paginate` is set.
+pub fn generate_author_pages(site: &mut Site, config: &Config, source: &Path, engine: &TemplateEngine) {
+    let Some(serde_yaml::Value::Mapping(authors)) = site.data.get(AUTHORS_DATA) else {
+        return;
+    };
+    let layout = &config.authors.layout;
+    if !engine.has_layout(layout) {
+        tracing::debug!("No {} layout, skipping author pages", layout);
+        return;
+    }
+
+    let data_path = source.join(DATA_DIR).join(format!("{}.yml", AUTHORS_DATA));
+    let mut pages = Vec::new();
+    for (slug, data) in authors {
+        let Some(slug) = slug.as_str() else {
+            continue;
+        };
+        let name = data.get("name").and_then(|name| name.as_str());
+        let posts: Vec<usize> = (0..site.posts.len())
+            .filter(|&i| {
+                let post = &site.posts[i];
+                !post.is_expired(site.time) && is_author_of(post, slug, name)
+            })
+            .collect();
+
+        let mut author = match data {
+            serde_yaml::Value::Mapping(data) => data.clone(),
+            _ => serde_yaml::Mapping::new(),
+        };
+        author.insert("slug".into(), slug.into());
+
+        let mut template = Page::new(data_path.clone());
+        template.author_page = Some(slug.to_string());
+        template.front_matter.layout = Some(layout.clone());
+        template.front_matter.title = Some(name.unwrap_or(slug).to_string());
+        template
+            .front_matter
+            .custom
+            .insert("author".to_string(), serde_yaml::Value::Mapping(author));
+
+        let base = author_url(config, slug);
+        pages.extend(paginated(&template, &base, &posts, config.authors.paginate));
+    }
+
+    site.pages.extend(pages);
+}
+
+/// Write each author's Atom feed next to their page, when `authors.feed` is set
+pub fn write_author_feeds(site: &Site, config: &Config, destination: &Path) -> Result<()> {
+    if !config.authors.feed {
+        return Ok(());
+    }
+
+    for page in &site.pages {
+        let Some(slug) = page.author_page.as_deref() else {
+            continue;
+        };
+        if page.paginator.as_ref().is_some_and(|paginator| paginator.page != 1) {
+            continue;
+        }
+
+        let base = author_url(config, slug);
+        let author = page.front_matter.custom.get("author");
+        let name = author.and_then(|author| author.get("name")).and_then(|name| name.as_str());
+        let title = format!("{} - {}", config.title, name.unwrap_or(slug));
+        let path = format!("{}{}", base, FEED_FILE);
+        let xml = atom_feed(site, config, &path, &base, &title, |post| is_author_of(post, slug, name));
+
+        let dir = destination.join(base.trim_start_matches('/'));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(FEED_FILE), xml)?;
+    }
+    Ok(())
+}
+
+/// Whether a post's `author` or `authors` names the author, by key or by name
+fn is_author_of(post: &Post, slug: &str, name: Option<&str>) -> bool {
+    let matches = |author: &str| author == slug || Some(author) == name;
+    post.front_matter.author.as_deref().is_some_and(matches)
+        || match post.front_matter.custom.get("authors") {
+            Some(serde_yaml::Value::Sequence(authors)) => {
+                authors.iter().filter_map(|a| a.as_str()).any(matches)
+            }
+            Some(serde_yaml::Value::String(author)) => matches(author),
+            _ => false,
+        }
+}
+
+/// Public URL of an author's first page, always a directory (`/authors/jane/`)
+fn author_url(config: &Config, slug: &str) -> String {
+    let url = config.authors.permalink.replace(":author", slug);
+    format!("/{}/", url.trim_matches('/')).replace("//", "/")
+}
+
+/// Copies of `template` listing `posts`, `per_page` at a time (all at once for 0)
+///
+/// Page 1 is at `base`, the others at `<base>page<N>/`.
+fn paginated(template: &Page, base: &str, posts: &[usize], per_page: usize) -> Vec<Page> {
+    let per_page = if per_page == 0 { posts.len().max(1) } else { per_page };
+    let total_posts = posts.len();
+    let total_pages = total_posts.div_ceil(per_page).max(1);
+    let page_path = |num: usize| {
+        if num == 1 {
+            base.to_string()
+        } else {
+            format!("{}page{}/", base, num)
+        }
+    };
+
+    (1..=total_pages)
+        .map(|num| {
+            let mut page = template.clone();
+            page.url = format!("{}index.html", page_path(num));
+            page.paginator = Some(Paginator {
+                page: num,
+                per_page,
+                posts: posts[(num - 1) * per_page..(num * per_page).min(total_posts)].to_vec(),
+                total_posts,
+                total_pages,
+                previous_page: (num > 1).then(|| num - 1),
+                previous_page_path: (num > 1).then(|| page_path(num - 1)),
+                next_page: (num < total_pages).then(|| num + 1),
+                next_page_path: (num < total_pages).then(|| page_path(num + 1)),
+            });
+            page
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+    use std::path::PathBuf;
+
+    fn site() -> Site {
+        let mut site = Site::new();
+        site.data.insert(
+            AUTHORS_DATA.to_string(),
+            serde_yaml::from_str("jane:\n  name: Jane Doe\n  bio: Writes things\njohn:\n  name: John").unwrap(),
+        );
+        let post = |name: &str, front_matter: &str| {
+            let mut post = Post::new(PathBuf::from(format!("_posts/2024-01-15-{}.md", name)));
+            post.url = format!("/2024/{}.html", name);
+            post.front_matter = serde_yaml::from_str(front_matter).unwrap();
+            post
+        };
+        site.posts = vec![
+            post("one", "author: jane"),
+            post("two", "authors: [John, Jane Doe]"),
+            post("three", "author: jane"),
+            post("four", "title: Anonymous"),
+        ];
+        site
+    }
+
+    #[test]
+    fn test_author_pages() {
+        let dir = temp_site("authors");
+        let mut config: Config = serde_yaml::from_str("authors:\n  paginate: 2").unwrap();
+
+        let mut site = site();
+        generate_author_pages(&mut site, &config, &dir, &TemplateEngine::new(dir.clone()));
+        assert!(site.pages.is_empty(), "no pages without the layout");
+
+        fs::create_dir_all(dir.join("_layouts")).unwrap();
+        fs::write(dir.join("_layouts/author.html"), "{{ content }}").unwrap();
+        generate_author_pages(&mut site, &config, &dir, &TemplateEngine::new(dir.clone()));
+
+        let urls: Vec<&str> = site.pages.iter().map(|p| p.url.as_str()).collect();
+        assert_eq!(urls, ["/authors/jane/index.html", "/authors/jane/page2/index.html", "/authors/john/index.html"]);
+        let jane = &site.pages[0];
+        assert_eq!(jane.author_page.as_deref(), Some("jane"));
+        assert_eq!(jane.front_matter.title.as_deref(), Some("Jane Doe"));
+        assert_eq!(jane.front_matter.custom["author"]["slug"], "jane");
+        assert_eq!(jane.front_matter.custom["author"]["bio"], "Writes things");
+        let paginator = jane.paginator.as_ref().unwrap();
+        assert_eq!(paginator.posts, [0, 1]);
+        assert_eq!(paginator.next_page_path.as_deref(), Some("/authors/jane/page2/"));
+        assert_eq!(site.pages[1].paginator.as_ref().unwrap().posts, [2]);
+        assert_eq!(site.pages[2].paginator.as_ref().unwrap().posts, [1]);
+
+        config.authors.feed = true;
+        // A page of the site's own with an `author` isn't an author page
+        let mut own = site.pages[0].clone();
+        own.author_page = None;
+        own.front_matter.custom.insert("author".to_string(), serde_yaml::from_str("slug: ghost").unwrap());
+        site.pages.push(own);
+        let output = dir.join("_site");
+        write_author_feeds(&site, &config, &output).unwrap();
+        let feed = fs::read_to_string(output.join("authors/john/feed.xml")).unwrap();
+        assert!(feed.contains("/2024/two.html"));
+        assert!(!feed.contains("/2024/one.html"));
+        assert!(!output.join("authors/jane/page2/feed.xml").exists());
+        assert!(!output.join("authors/ghost/feed.xml").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+use crate::error::{Error, Result};
+use jellrust_types::paths::to_url_path;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Extension of bundle manifests (`assets/js/main.bundle`)
+pub const BUNDLE_EXTENSION: &str = "bundle";
+
+/// Files of a bundle manifest concatenated into one fingerprinted asset
+///
+/// A manifest lists one file per line, relative to the manifest's directory
+/// or, with a leading `/`, to the site source. Blank lines and lines starting
+/// with `#` are ignored. All files must share an extension, which the output
+/// takes: `assets/js/main.bundle` becomes `assets/js/main-<hash>.js`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    /// Manifest path relative to the source, e.g. `assets/js/main.bundle`
+    pub manifest: String,
+    /// Output path relative to the destination
+    pub output: String,
+    pub content: String,
+}
+
+impl Bundle {
+    /// Read a manifest below `root` and concatenate the files it lists
+    pub fn load(root: &Path, manifest: &Path) -> Result<Self> {
+        let relative = manifest.strip_prefix(root).unwrap_or(manifest);
+        let dir = manifest.parent().unwrap_or(root);
+        let listing = fs::read_to_string(manifest)?;
+
+        let mut extension: Option<String> = None;
+        let mut content = String::new();
+        for line in listing.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let path = match line.strip_prefix('/') {
+                Some(path) => root.join(path),
+                None => dir.join(line),
+            };
+
+            let file_extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+            match &extension {
+                None => extension = Some(file_extension.to_string()),
+                Some(ext) if ext != file_extension => {
+                    return Err(Error::Other(format!(
+                        "Bundle {} mixes .{} and .{} files",
+                        relative.display(),
+                        ext,
+                        file_extension
+                    )));
+                }
+                Some(_) => {}
+            }
+
+            let file = fs::read_to_string(&path).map_err(|_| {
+                Error::FileNotFound(format!("{} (listed in {})", line, relative.display()))
+            })?;
+            content.push_str(&file);
+            // Keep a file without a trailing `;` from running into the next
+            content.push_str(if file_extension == "js" { "\n;\n" } else { "\n" });
+        }
+
+        let Some(extension) = extension else {
+            return Err(Error::Other(format!("Bundle {} lists no files", relative.display())));
+        };
+        let stem = relative.file_stem().and_then(|s| s.to_str()).unwrap_or("bundle");
+        let output = relative.with_file_name(format!("{}-{}.{}", stem, fingerprint(&content), extension));
+
+        Ok(Self {
+            manifest: to_url_path(relative),
+            output: to_url_path(&output),
+            content,
+        })
+    }
+
+    /// Public URL of the output, for `site.bundles`
+    pub fn url(&self) -> String {
+        format!("/{}", self.output)
+    }
+}
+
+/// Load the manifests in the `assets` directories of `roots`
+///
+/// Roots are given from lowest to highest precedence (theme, then site), so
+/// a site manifest replaces a theme manifest at the same path.
+pub fn collect_bundles(roots: &[&Path]) -> Result<Vec<Bundle>> {
+    let mut bundles = BTreeMap::new();
+    for root in roots {
+        for manifest in manifests(&root.join("assets")) {
+            let bundle = Bundle::load(root, &manifest)?;
+            bundles.insert(bundle.manifest.clone(), bundle);
+        }
+    }
+    Ok(bundles.into_values().collect())
+}
+
+/// Whether a file is a bundle manifest, which is not copied as-is
+pub fn is_manifest(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some(BUNDLE_EXTENSION)
+}
+
+fn manifests(dir: &Path) -> Vec<PathBuf> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_manifest(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// First 8 hex digits of the 64-bit FNV-1a hash, stable across builds and platforms
+pub(crate) fn fingerprint(content: impl AsRef<[u8]>) -> String {
+    let hash = content.as_ref().iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)[..8].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+
+    #[test]
+    fn test_collect_bundles() {
+        let dir = temp_site("bundle");
+        let (theme, site) = (dir.join("theme"), dir.join("site"));
+        fs::create_dir_all(site.join("assets/js/vendor")).unwrap();
+        fs::create_dir_all(theme.join("assets/css")).unwrap();
+        fs::write(site.join("assets/js/vendor/lib.js"), "var lib = 1").unwrap();
+        fs::write(site.join("assets/js/app.js"), "lib++;").unwrap();
+        fs::write(site.join("assets/js/main.bundle"), "# scripts\nvendor/lib.js\n\n/assets/js/app.js\n").unwrap();
+        fs::write(theme.join("assets/css/base.css"), "body {}").unwrap();
+        fs::write(theme.join("assets/css/all.bundle"), "base.css").unwrap();
+
+        let bundles = collect_bundles(&[&theme, &site]).unwrap();
+        assert_eq!(bundles.len(), 2);
+        assert_eq!(bundles[0].manifest, "assets/css/all.bundle");
+        assert_eq!(bundles[1].content, "var lib = 1\n;\nlib++;\n;\n");
+        assert!(bundles[1].url().starts_with("/assets/js/main-"));
+        assert!(bundles[1].url().ends_with(".js"));
+        assert_eq!(bundles[1].output, Bundle::load(&site, &site.join("assets/js/main.bundle")).unwrap().output);
+
+        fs::write(site.join("assets/js/mixed.bundle"), "app.js\n/theme.css").unwrap();
+        let error = collect_bundles(&[&site]).unwrap_err().to_string();
+        assert!(error.contains("mixes .js and .css"), "{}", error);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
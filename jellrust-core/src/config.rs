@@ -5,6 +5,17 @@ use std::path::Path;
 // Re-export Config from jellrust-types
 pub use jellrust_types::Config;
 
+/// Build environment from `JELLRUST_ENV` (or Jekyll's `JEKYLL_ENV`), `development` by default
+///
+/// Comment widgets are only output in `production`.
+pub fn environment() -> String {
+    std::env::var("JELLRUST_ENV")
+        .or_else(|_| std::env::var("JEKYLL_ENV"))
+        .ok()
+        .filter(|env| !env.trim().is_empty())
+        .unwrap_or_else(|| "development".to_string())
+}
+
 /// Extension trait for loading Config
 pub trait ConfigExt {
     fn load<P: AsRef<Path>>(source_dir: P) -> Result<Self>
@@ -45,6 +56,26 @@ mod tests {
         assert_eq!(config.paginate, 10);
     }
     
+    #[test]
+    fn test_destination_dir() {
+        let config = Config::default();
+        assert_eq!(config.destination_dir(Path::new("site")), Path::new("site/_site"));
+        
+        let config: Config = serde_yaml::from_str("destination: public").unwrap();
+        assert_eq!(config.destination_dir(Path::new("site")), Path::new("site/public"));
+        assert_eq!(config.destination_dir(Path::new(".")), Path::new("./public"));
+    }
+    
+    #[test]
+    fn test_source_dir() {
+        let config = Config::default();
+        assert_eq!(config.source_dir(Path::new("repo")), Path::new("repo"));
+        
+        let config: Config = serde_yaml::from_str("source: site\ndestination: public").unwrap();
+        assert_eq!(config.source_dir(Path::new("repo")), Path::new("repo/site"));
+        assert_eq!(config.destination_dir(Path::new("repo")), Path::new("repo/public"));
+    }
+    
     #[test]
     fn test_exclusion() {
         let config = Config::default();
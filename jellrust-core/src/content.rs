@@ -1,5 +1,5 @@
 // Re-export types from jellrust-types
-pub use jellrust_types::{FrontMatter, Page, Post, Site};
+pub use jellrust_types::{FrontMatter, Page, Post, Site, StaticFile};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,81 @@
+use super::Converter;
+use crate::error::{Error, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// AsciiDoc converter that shells out to `asciidoctor`
+pub struct AsciidocConverter {
+    command: String,
+}
+
+impl AsciidocConverter {
+    pub fn new() -> Self {
+        Self::with_command("asciidoctor")
+    }
+
+    /// Use a specific `asciidoctor` executable
+    pub fn with_command(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+}
+
+impl Default for AsciidocConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Converter for AsciidocConverter {
+    fn name(&self) -> &str {
+        "asciidoc"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["adoc", "asciidoc"]
+    }
+
+    fn convert(&self, content: &str) -> Result<String> {
+        // Embedded output (no header/footer), read from stdin, write to stdout
+        let mut child = Command::new(&self.command)
+            .args(["--no-header-footer", "--out-file", "-", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                Error::Other(format!(
+                    "AsciiDoc support requires `{}` on the PATH: {}",
+                    self.command, e
+                ))
+            })?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(content.as_bytes())?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(Error::Other(format!(
+                "{} failed: {}",
+                self.command,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_command_is_reported() {
+        let converter = AsciidocConverter::with_command("jellrust-missing-asciidoctor");
+        let err = converter.convert("= Title").unwrap_err();
+        assert!(err.to_string().contains("jellrust-missing-asciidoctor"));
+    }
+}
@@ -0,0 +1,248 @@
+use super::Converter;
+use crate::content::FrontMatter;
+use crate::error::{Error, Result};
+use jellrust_markdown::{HighlightOptions, MarkdownProcessor};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value as Json;
+
+/// ANSI color codes that Jupyter leaves in tracebacks
+static ANSI_ESCAPE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap());
+
+/// Jupyter notebook (`.ipynb`) converter
+///
+/// Markdown cells are rendered as Markdown, code cells as highlighted code
+/// blocks, and stored outputs (text, HTML, images) are embedded statically.
+/// Front matter is read from the notebook's `metadata.front_matter` object.
+pub struct JupyterConverter {
+    processor: MarkdownProcessor,
+}
+
+impl JupyterConverter {
+    pub fn new() -> Self {
+        Self::with_highlight(HighlightOptions::default())
+    }
+
+    /// Converter highlighting code cells with the given settings
+    pub fn with_highlight(highlight: HighlightOptions) -> Self {
+        Self::with_processor(MarkdownProcessor::new().with_highlight(highlight))
+    }
+
+    /// Converter rendering Markdown cells and code with a configured processor
+    pub fn with_processor(processor: MarkdownProcessor) -> Self {
+        Self { processor }
+    }
+}
+
+impl Default for JupyterConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Converter for JupyterConverter {
+    fn name(&self) -> &str {
+        "jupyter"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ipynb"]
+    }
+
+    fn front_matter(&self, content: &str) -> Result<Option<FrontMatter>> {
+        let notebook = parse_notebook(content)?;
+        let front_matter = notebook
+            .pointer("/metadata/front_matter")
+            .cloned()
+            .unwrap_or_else(|| Json::Object(Default::default()));
+
+        serde_json::from_value(front_matter)
+            .map(Some)
+            .map_err(|e| Error::Yaml(format!("Invalid notebook front matter: {}", e)))
+    }
+
+    fn convert(&self, content: &str) -> Result<String> {
+        let notebook = parse_notebook(content)?;
+        let markdown = notebook_to_markdown(&notebook);
+        self.processor
+            .render(&markdown)
+            .map_err(|e| Error::Markdown(e.to_string()))
+    }
+}
+
+fn parse_notebook(content: &str) -> Result<Json> {
+    serde_json::from_str(content).map_err(|e| Error::Other(format!("Invalid notebook: {}", e)))
+}
+
+/// Notebook text fields are either a string or a list of lines
+fn join_source(value: Option<&Json>) -> String {
+    match value {
+        Some(Json::String(text)) => text.clone(),
+        Some(Json::Array(lines)) => lines.iter().filter_map(|l| l.as_str()).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Fenced code block whose fence can't collide with the content
+fn fenced(code: &str, lang: &str) -> String {
+    let longest_run = code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{fence}{lang}\n{}\n{fence}\n\n", code.trim_end_matches('\n'))
+}
+
+/// Render a notebook as Markdown with embedded HTML for rich outputs
+fn notebook_to_markdown(notebook: &Json) -> String {
+    let language = notebook
+        .pointer("/metadata/language_info/name")
+        .or_else(|| notebook.pointer("/metadata/kernelspec/language"))
+        .and_then(|l| l.as_str())
+        .unwrap_or("python");
+
+    let mut markdown = String::new();
+    let cells = notebook.get("cells").and_then(|c| c.as_array());
+    for cell in cells.into_iter().flatten() {
+        let source = join_source(cell.get("source"));
+        match cell.get("cell_type").and_then(|t| t.as_str()) {
+            Some("markdown") => {
+                markdown.push_str(source.trim_end());
+                markdown.push_str("\n\n");
+            }
+            Some("code") => {
+                if !source.trim().is_empty() {
+                    markdown.push_str(&fenced(&source, language));
+                }
+                let outputs = cell.get("outputs").and_then(|o| o.as_array());
+                for output in outputs.into_iter().flatten() {
+                    markdown.push_str(&render_output(output));
+                }
+            }
+            // Raw cells are passed through untouched
+            Some("raw") => {
+                markdown.push_str(&source);
+                markdown.push_str("\n\n");
+            }
+            _ => {}
+        }
+    }
+    markdown
+}
+
+/// Render one stored cell output
+fn render_output(output: &Json) -> String {
+    match output.get("output_type").and_then(|t| t.as_str()) {
+        Some("stream") => fenced(&join_source(output.get("text")), "text"),
+        Some("error") => {
+            let traceback = join_lines(output.get("traceback"));
+            fenced(&ANSI_ESCAPE.replace_all(&traceback, ""), "text")
+        }
+        Some("execute_result") | Some("display_data") => match output.get("data") {
+            Some(data) => render_rich_output(data),
+            None => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// Tracebacks are a list of lines without trailing newlines
+fn join_lines(value: Option<&Json>) -> String {
+    match value {
+        Some(Json::Array(lines)) => lines
+            .iter()
+            .filter_map(|l| l.as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => join_source(other),
+    }
+}
+
+/// Pick the richest static representation of a display output
+fn render_rich_output(data: &Json) -> String {
+    for mime in ["image/png", "image/jpeg", "image/gif"] {
+        if let Some(image) = data.get(mime) {
+            let encoded: String = join_source(Some(image)).split_whitespace().collect();
+            return format!(
+                "<div class=\"jupyter-output\"><img src=\"data:{};base64,{}\" alt=\"\"></div>\n\n",
+                mime, encoded
+            );
+        }
+    }
+
+    for mime in ["image/svg+xml", "text/html"] {
+        if data.get(mime).is_some() {
+            let html = join_source(data.get(mime));
+            return format!("<div class=\"jupyter-output\">\n{}\n</div>\n\n", html.trim());
+        }
+    }
+
+    if data.get("text/markdown").is_some() {
+        return format!("{}\n\n", join_source(data.get("text/markdown")).trim_end());
+    }
+
+    match data.get("text/plain") {
+        Some(text) => fenced(&join_source(Some(text)), "text"),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+        "metadata": {
+            "front_matter": { "title": "Plotting", "tags": ["data"] },
+            "language_info": { "name": "python" }
+        },
+        "cells": [
+            { "cell_type": "markdown", "source": ["# Intro\n", "Some *text*."] },
+            {
+                "cell_type": "code",
+                "source": "print('hi')",
+                "outputs": [
+                    { "output_type": "stream", "name": "stdout", "text": ["hi\n"] },
+                    { "output_type": "display_data", "data": { "image/png": "iVBORw0KGgo=\n", "text/plain": ["<Figure>"] } },
+                    { "output_type": "error", "ename": "E", "evalue": "", "traceback": ["\u001b[0;31mValueError\u001b[0m: boom"] }
+                ]
+            }
+        ]
+    }"##;
+
+    #[test]
+    fn test_notebook_front_matter() {
+        let front_matter = JupyterConverter::new().front_matter(NOTEBOOK).unwrap().unwrap();
+        assert_eq!(front_matter.title.as_deref(), Some("Plotting"));
+        assert_eq!(front_matter.tags, vec!["data"]);
+        assert!(front_matter.published);
+
+        let empty = JupyterConverter::new().front_matter(r#"{"cells": []}"#).unwrap().unwrap();
+        assert!(empty.title.is_none());
+        assert!(empty.published);
+    }
+
+    #[test]
+    fn test_notebook_cells_and_outputs() {
+        let markdown = notebook_to_markdown(&parse_notebook(NOTEBOOK).unwrap());
+
+        assert!(markdown.starts_with("# Intro\nSome *text*.\n\n"));
+        assert!(markdown.contains("```python\nprint('hi')\n```"));
+        assert!(markdown.contains("```text\nhi\n```"));
+        assert!(markdown.contains(r#"<img src="data:image/png;base64,iVBORw0KGgo=" alt="">"#));
+        assert!(!markdown.contains("<Figure>"));
+        assert!(markdown.contains("ValueError: boom"));
+        assert!(!markdown.contains('\u{1b}'));
+    }
+
+    #[test]
+    fn test_fence_longer_than_content_backticks() {
+        assert_eq!(fenced("a ```` b", ""), "`````\na ```` b\n`````\n\n");
+    }
+
+    #[test]
+    fn test_invalid_notebook() {
+        assert!(JupyterConverter::new().convert("not json").is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::content::FrontMatter;
+use crate::error::{Error, Result};
+use jellrust_markdown::{HighlightOptions, MarkdownProcessor};
+use std::path::Path;
+
+#[cfg(feature = "asciidoc")]
+mod asciidoc;
+mod jupyter;
+
+#[cfg(feature = "asciidoc")]
+pub use asciidoc::AsciidocConverter;
+pub use jupyter::JupyterConverter;
+
+/// Turns the body of a source file (front matter already stripped) into HTML
+pub trait Converter: Send + Sync {
+    /// Name shown in logs
+    fn name(&self) -> &str;
+
+    /// Source file extensions handled by this converter, without the dot
+    fn extensions(&self) -> &[&str];
+
+    /// Front matter carried inside the document itself, for formats that
+    /// can't start with a YAML block (e.g. notebook metadata)
+    ///
+    /// When this returns `Some`, the document isn't parsed for a front matter
+    /// block and is converted whole.
+    fn front_matter(&self, _content: &str) -> Result<Option<FrontMatter>> {
+        Ok(None)
+    }
+
+    /// Convert the document body to HTML
+    fn convert(&self, content: &str) -> Result<String>;
+}
+
+/// Markdown converter backed by pulldown-cmark
+pub struct MarkdownConverter {
+    processor: MarkdownProcessor,
+}
+
+impl MarkdownConverter {
+    pub fn new() -> Self {
+        Self::with_highlight(HighlightOptions::default())
+    }
+
+    /// Converter highlighting code blocks with the given settings
+    pub fn with_highlight(highlight: HighlightOptions) -> Self {
+        Self::with_processor(MarkdownProcessor::new().with_highlight(highlight))
+    }
+
+    /// Converter rendering with a configured processor
+    pub fn with_processor(processor: MarkdownProcessor) -> Self {
+        Self { processor }
+    }
+}
+
+impl Default for MarkdownConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Converter for MarkdownConverter {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["md", "markdown"]
+    }
+
+    fn convert(&self, content: &str) -> Result<String> {
+        self.processor
+            .render(content)
+            .map_err(|e| Error::Markdown(e.to_string()))
+    }
+}
+
+/// Plain HTML, passed through unchanged
+pub struct HtmlConverter;
+
+impl Converter for HtmlConverter {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["html", "htm"]
+    }
+
+    fn convert(&self, content: &str) -> Result<String> {
+        Ok(content.to_string())
+    }
+}
+
+/// Maps source file extensions to the converter that renders them
+pub struct ConverterRegistry {
+    converters: Vec<Box<dyn Converter>>,
+}
+
+impl ConverterRegistry {
+    /// Registry without any converters
+    pub fn empty() -> Self {
+        Self {
+            converters: Vec::new(),
+        }
+    }
+
+    /// Registry with the built-in converters (Markdown, HTML, Jupyter and,
+    /// when the `asciidoc` feature is enabled, AsciiDoc)
+    pub fn new() -> Self {
+        Self::with_markdown(MarkdownProcessor::new())
+    }
+
+    /// Built-in converters, rendering Markdown (also in notebooks) with the
+    /// given processor
+    pub fn with_markdown(processor: MarkdownProcessor) -> Self {
+        let mut registry = Self::empty();
+        registry.register(MarkdownConverter::with_processor(processor.clone()));
+        registry.register(HtmlConverter);
+        registry.register(JupyterConverter::with_processor(processor));
+        #[cfg(feature = "asciidoc")]
+        registry.register(AsciidocConverter::new());
+        registry
+    }
+
+    /// Add a converter; it takes precedence over earlier ones for shared extensions
+    pub fn register<C: Converter + 'static>(&mut self, converter: C) {
+        tracing::debug!(
+            "Registered converter '{}' for: {}",
+            converter.name(),
+            converter.extensions().join(", ")
+        );
+        self.converters.push(Box::new(converter));
+    }
+
+    /// Find the converter for a source file, based on its extension
+    pub fn for_path(&self, path: &Path) -> Option<&dyn Converter> {
+        let ext = path.extension()?.to_str()?;
+        self.converters
+            .iter()
+            .rev()
+            .find(|c| c.extensions().iter().any(|e| e.eq_ignore_ascii_case(ext)))
+            .map(|c| c.as_ref())
+    }
+}
+
+impl Default for ConverterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ShoutConverter;
+
+    impl Converter for ShoutConverter {
+        fn name(&self) -> &str {
+            "shout"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["txt", "html"]
+        }
+
+        fn convert(&self, content: &str) -> Result<String> {
+            Ok(content.to_uppercase())
+        }
+    }
+
+    #[test]
+    fn test_builtin_converters() {
+        let registry = ConverterRegistry::new();
+
+        assert_eq!(registry.for_path(Path::new("a/post.md")).unwrap().name(), "markdown");
+        assert_eq!(registry.for_path(Path::new("About.MARKDOWN")).unwrap().name(), "markdown");
+        assert_eq!(registry.for_path(Path::new("index.html")).unwrap().name(), "html");
+        assert!(registry.for_path(Path::new("style.css")).is_none());
+        assert!(registry.for_path(Path::new("README")).is_none());
+    }
+
+    #[test]
+    fn test_registered_converter_takes_precedence() {
+        let mut registry = ConverterRegistry::new();
+        registry.register(ShoutConverter);
+
+        let converter = registry.for_path(Path::new("index.html")).unwrap();
+        assert_eq!(converter.convert("<p>hi</p>").unwrap(), "<P>HI</P>");
+        assert_eq!(registry.for_path(Path::new("notes.txt")).unwrap().name(), "shout");
+        assert_eq!(registry.for_path(Path::new("post.md")).unwrap().name(), "markdown");
+    }
+}
@@ -0,0 +1,72 @@
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Directory (inside the source) holding data files
+pub const DATA_DIR: &str = "_data";
+
+/// Load the YAML and JSON files of a data directory, keyed by file stem
+///
+/// Subdirectories become nested maps, so `_data/team/members.yml` is
+/// available as `site.data.team.members`.
+pub fn load_data(dir: &Path) -> Result<HashMap<String, serde_yaml::Value>> {
+    let mut data = HashMap::new();
+    if !dir.is_dir() {
+        return Ok(data);
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if stem.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            let nested = load_data(&path)?
+                .into_iter()
+                .map(|(key, value)| (serde_yaml::Value::String(key), value))
+                .collect();
+            data.insert(stem.to_string(), serde_yaml::Value::Mapping(nested));
+            continue;
+        }
+
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if !matches!(extension, "yml" | "yaml" | "json") {
+            continue;
+        }
+
+        // JSON is a subset of YAML, so one parser covers both
+        let content = fs::read_to_string(&path)?;
+        let value = serde_yaml::from_str(&content)
+            .map_err(|e| Error::Yaml(format!("{}: {}", path.display(), e)))?;
+        data.insert(stem.to_string(), value);
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+
+    #[test]
+    fn test_load_data_nested() {
+        let dir = temp_site("data");
+        fs::create_dir_all(dir.join("team")).unwrap();
+        fs::write(dir.join("nav.yml"), "- title: Home\n  url: /\n").unwrap();
+        fs::write(dir.join("team/members.json"), r#"[{"name": "Alice"}]"#).unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let data = load_data(&dir).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data["nav"][0]["title"].as_str(), Some("Home"));
+        assert_eq!(data["team"]["members"][0]["name"].as_str(), Some("Alice"));
+        assert!(load_data(&dir.join("missing")).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,267 @@
+use crate::bundle::fingerprint;
+use crate::config::{environment, Config};
+use crate::error::Result;
+use jellrust_types::paths::to_url_path;
+use jellrust_types::Post;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directory (inside the source) holding build caches
+pub const CACHE_DIR: &str = ".jellrust-cache";
+
+/// File storing the dependency graph
+const GRAPH_FILE: &str = "dependencies.json";
+
+/// Which layouts and includes each document used during its last render
+///
+/// Documents and dependencies are stored as paths relative to the source.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    /// Time of the build that produced this graph (milliseconds since epoch)
+    built_at: u64,
+
+    /// Configuration, destination and jellrust build the graph is valid for,
+    /// see [`cache_fingerprint`]
+    #[serde(default)]
+    fingerprint: String,
+
+    /// What documents listing posts saw of them, see [`posts_fingerprint`]
+    #[serde(default)]
+    posts: String,
+
+    /// Document path -> layouts/includes it used
+    documents: BTreeMap<String, Vec<String>>,
+}
+
+impl DependencyGraph {
+    /// Create an empty graph stamped with the current time
+    ///
+    /// Create it when a build starts, so files edited mid-build count as
+    /// changed on the next build.
+    pub fn new() -> Self {
+        Self {
+            built_at: now_millis(),
+            fingerprint: String::new(),
+            posts: String::new(),
+            documents: BTreeMap::new(),
+        }
+    }
+
+    /// Stamp the graph with a [`cache_fingerprint`]
+    pub fn with_fingerprint(mut self, fingerprint: String) -> Self {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    /// Fingerprint the graph was stamped with
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Record the [`posts_fingerprint`] of the site being built
+    pub fn set_posts(&mut self, fingerprint: String) {
+        self.posts = fingerprint;
+    }
+
+    /// Posts fingerprint the graph was built with
+    pub fn posts(&self) -> &str {
+        &self.posts
+    }
+
+    /// Load the graph from the previous build, if any
+    pub fn load(source: &Path) -> Option<Self> {
+        let content = fs::read_to_string(graph_path(source)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Persist the graph
+    pub fn save(&self, source: &Path) -> Result<()> {
+        let path = graph_path(source);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| crate::Error::Other(format!("Failed to serialize dependencies: {}", e)))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Record the dependencies of a document
+    pub fn record(&mut self, document: &Path, dependencies: Vec<PathBuf>) {
+        self.documents.insert(
+            to_url_path(document),
+            dependencies.iter().map(|d| to_url_path(d)).collect(),
+        );
+    }
+
+    /// Recorded dependencies of a document
+    pub fn dependencies_of(&self, document: &Path) -> Vec<PathBuf> {
+        self.documents
+            .get(&to_url_path(document))
+            .map(|deps| deps.iter().map(PathBuf::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// Documents that used the given layout or include
+    pub fn dependents_of(&self, dependency: &Path) -> Vec<&str> {
+        let key = to_url_path(dependency);
+        self.documents
+            .iter()
+            .filter(|(_, deps)| deps.contains(&key))
+            .map(|(doc, _)| doc.as_str())
+            .collect()
+    }
+
+    /// Whether a document must be re-rendered
+    ///
+    /// A document is stale when it is unknown to the graph, or when it or any
+    /// recorded dependency changed (or vanished) since the graph was built.
+    pub fn is_stale(&self, source: &Path, document: &Path) -> bool {
+        let Some(deps) = self.documents.get(&to_url_path(document)) else {
+            return true;
+        };
+
+        std::iter::once(to_url_path(document))
+            .chain(deps.iter().cloned())
+            .any(|path| self.changed_since_build(&source.join(path)))
+    }
+
+    /// Whether a file was modified after this graph was built
+    pub fn changed_since_build(&self, path: &Path) -> bool {
+        match fs::metadata(path).and_then(|m| m.modified()) {
+            Ok(modified) => to_millis(modified) >= self.built_at,
+            Err(_) => true,
+        }
+    }
+}
+
+/// Identifies what a dependency graph was built with
+///
+/// A graph saved by a build with another configuration, environment,
+/// destination or jellrust binary can't vouch for the outputs, so it is
+/// thrown away.
+pub fn cache_fingerprint(config: &Config, destination: &Path) -> String {
+    let config = serde_json::to_value(config).unwrap_or_default();
+    fingerprint(format!(
+        "{}\n{}\n{}\n{}",
+        binary_version(),
+        environment(),
+        to_url_path(destination),
+        config
+    ))
+}
+
+/// Identifies what other documents can read of the posts
+///
+/// Any page may loop over `site.posts` and any post links its neighbours, so
+/// adding, removing or moving a post, or editing its front matter or excerpt,
+/// affects every document. Edits further down a post's body only affect the
+/// post itself.
+pub fn posts_fingerprint(posts: &[Post]) -> String {
+    let summary: Vec<_> = posts
+        .iter()
+        .map(|post| {
+            serde_json::json!([
+                to_url_path(&post.path),
+                post.url,
+                post.date,
+                post.front_matter,
+                post.excerpt,
+            ])
+        })
+        .collect();
+    fingerprint(serde_json::Value::Array(summary).to_string())
+}
+
+/// Version of the running jellrust, including when its executable was built
+fn binary_version() -> String {
+    let built = std::env::current_exe()
+        .and_then(fs::metadata)
+        .and_then(|m| m.modified())
+        .map(to_millis)
+        .unwrap_or(0);
+    format!("{}+{}", env!("CARGO_PKG_VERSION"), built)
+}
+
+fn graph_path(source: &Path) -> PathBuf {
+    source.join(CACHE_DIR).join(GRAPH_FILE)
+}
+
+fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn now_millis() -> u64 {
+    to_millis(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+
+    #[test]
+    fn test_dependents_of() {
+        let mut graph = DependencyGraph::new();
+        graph.record(
+            Path::new("index.md"),
+            vec![PathBuf::from("_layouts/default.html"), PathBuf::from("_includes/header.html")],
+        );
+        graph.record(Path::new("about.md"), vec![PathBuf::from("_layouts/default.html")]);
+
+        assert_eq!(graph.dependents_of(Path::new("_includes/header.html")), vec!["index.md"]);
+        assert_eq!(
+            graph.dependents_of(Path::new("_layouts/default.html")),
+            vec!["about.md", "index.md"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_document_is_stale() {
+        let graph = DependencyGraph::default();
+        assert!(graph.is_stale(Path::new("."), Path::new("new.md")));
+    }
+
+    #[test]
+    fn test_cache_fingerprint() {
+        let config = Config::default();
+        let fingerprint = cache_fingerprint(&config, Path::new("_site"));
+        assert_eq!(fingerprint, cache_fingerprint(&config, Path::new("_site")));
+        assert_ne!(fingerprint, cache_fingerprint(&config, Path::new("public")));
+
+        let mut changed = config.clone();
+        changed.title = "Another title".to_string();
+        assert_ne!(fingerprint, cache_fingerprint(&changed, Path::new("_site")));
+
+        let graph = DependencyGraph::new().with_fingerprint(fingerprint.clone());
+        let saved: DependencyGraph = serde_json::from_str(&serde_json::to_string(&graph).unwrap()).unwrap();
+        assert_eq!(saved.fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn test_is_stale_tracks_dependency_changes() {
+        let dir = temp_site("deps");
+        fs::create_dir_all(dir.join("_includes")).unwrap();
+        fs::write(dir.join("index.md"), "x").unwrap();
+        fs::write(dir.join("about.md"), "x").unwrap();
+        fs::write(dir.join("_includes/header.html"), "x").unwrap();
+
+        let mut graph = DependencyGraph::new();
+        graph.record(Path::new("index.md"), vec![PathBuf::from("_includes/header.html")]);
+        graph.record(Path::new("about.md"), vec![]);
+        graph.built_at = now_millis() + 60_000;
+        assert!(!graph.is_stale(&dir, Path::new("index.md")));
+
+        graph.built_at = 0;
+        fs::write(dir.join("_includes/header.html"), "y").unwrap();
+        assert!(graph.is_stale(&dir, Path::new("index.md")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+use crate::config::Config;
+use crate::content::{FrontMatter, Post, Site};
+use crate::error::Result;
+use jellrust_template::filters::{absolute_url, xml_escape};
+use std::fs;
+use std::path::Path;
+
+/// Sitemap written when the `sitemap` plugin is enabled
+pub const SITEMAP_FILE: &str = "sitemap.xml";
+
+/// Atom feed written when the `feed` plugin is enabled
+pub const FEED_FILE: &str = "feed.xml";
+
+/// Number of posts in the feed
+const FEED_LIMIT: usize = 10;
+
+/// Whether a plugin is listed in `plugins`, with or without Jekyll's `jekyll-` prefix
+pub fn plugin_enabled(config: &Config, name: &str) -> bool {
+    config
+        .plugins
+        .iter()
+        .any(|plugin| plugin.strip_prefix("jekyll-").unwrap_or(plugin) == name)
+}
+
+/// Write sitemap.xml and feed.xml for the plugins that are enabled
+pub fn write_discovery_files(site: &Site, config: &Config, destination: &Path) -> Result<()> {
+    if plugin_enabled(config, "sitemap") {
+        fs::write(destination.join(SITEMAP_FILE), sitemap(site, config))?;
+    }
+    if plugin_enabled(config, "feed") {
+        fs::write(destination.join(FEED_FILE), feed(site, config))?;
+    }
+    Ok(())
+}
+
+/// Whether a document belongs in a discovery file
+///
+/// `key: false` in front matter opts a document out, as does a URL matching
+/// one of the configured glob patterns (e.g. `/thanks.html`, `/page*/**`).
+fn is_listed(front_matter: &FrontMatter, key: &str, url: &str, exclude: &[String]) -> bool {
+    let opted_out = front_matter.custom.get(key).and_then(|v| v.as_bool()) == Some(false);
+    !opted_out
+        && !exclude.iter().any(|pattern| {
+            glob::Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(url))
+        })
+}
+
+/// Posts that appear in discovery files (drafts never do)
+fn published_posts(site: &Site) -> impl Iterator<Item = &Post> {
+    site.posts.iter().filter(|post| !post.draft)
+}
+
+/// Render sitemap.xml for all published HTML pages and posts
+pub fn sitemap(site: &Site, config: &Config) -> String {
+    let exclude = &config.sitemap.exclude;
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+
+    for post in published_posts(site).filter(|post| !post.is_sitemap_expired(site.time)) {
+        let url = public_url(&post.url);
+        if is_listed(&post.front_matter, "sitemap", &url, exclude) {
+            xml.push_str(&format!(
+                "  <url>\n    <loc>{}</loc>\n    <lastmod>{}</lastmod>\n  </url>\n",
+                xml_escape(&absolute_url(&config.url, &config.baseurl, &url)),
+                post.date.to_rfc3339()
+            ));
+        }
+    }
+
+    for page in &site.pages {
+        let url = public_url(&page.url);
+        let is_html = url.ends_with('/') || url.ends_with(".html");
+        if is_html && is_listed(&page.front_matter, "sitemap", &url, exclude) {
+            xml.push_str(&format!(
+                "  <url>\n    <loc>{}</loc>\n  </url>\n",
+                xml_escape(&absolute_url(&config.url, &config.baseurl, &url))
+            ));
+        }
+    }
+
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// Render an Atom feed of the latest published posts
+pub fn feed(site: &Site, config: &Config) -> String {
+    atom_feed(site, config, FEED_FILE, "/", &config.title, |_| true)
+}
+
+/// Indices into `site.posts` of the latest published posts that `include` accepts
+///
+/// Expired posts and those excluded through `feed:` settings are left out.
+pub fn feed_posts(site: &Site, config: &Config, include: impl Fn(&Post) -> bool) -> Vec<usize> {
+    let exclude = &config.feed.exclude;
+    site.posts
+        .iter()
+        .enumerate()
+        .filter(|(_, post)| !post.draft && !post.is_expired(site.time))
+        .filter(|(_, post)| is_listed(&post.front_matter, "feed", &public_url(&post.url), exclude))
+        .filter(|(_, post)| include(post))
+        .map(|(index, _)| index)
+        .take(FEED_LIMIT)
+        .collect()
+}
+
+/// Render an Atom feed at `path` of the latest published posts that `include` accepts
+///
+/// `alternate` is the URL of the HTML page the feed mirrors.
+pub(crate) fn atom_feed(
+    site: &Site,
+    config: &Config,
+    path: &str,
+    alternate: &str,
+    title: &str,
+    include: impl Fn(&Post) -> bool,
+) -> String {
+    let link = |url: &str| xml_escape(&absolute_url(&config.url, &config.baseurl, url));
+    let posts: Vec<&Post> = feed_posts(site, config, include).into_iter().map(|index| &site.posts[index]).collect();
+    let updated = posts
+        .iter()
+        .map(|post| post.date)
+        .max()
+        .unwrap_or_else(chrono::Utc::now);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <title>{}</title>\n", xml_escape(title)));
+    if !config.description.is_empty() {
+        xml.push_str(&format!("  <subtitle>{}</subtitle>\n", xml_escape(&config.description)));
+    }
+    xml.push_str(&format!("  <link href=\"{}\" rel=\"self\" type=\"application/atom+xml\"/>\n", link(path)));
+    xml.push_str(&format!("  <link href=\"{}\" rel=\"alternate\" type=\"text/html\"/>\n", link(alternate)));
+    xml.push_str(&format!("  <id>{}</id>\n", link(path)));
+    xml.push_str(&format!("  <updated>{}</updated>\n", updated.to_rfc3339()));
+
+    for post in posts {
+        let url = link(&public_url(&post.url));
+        let title = post.front_matter.title.as_deref().unwrap_or(&post.url);
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", xml_escape(title)));
+        xml.push_str(&format!("    <link href=\"{}\" rel=\"alternate\" type=\"text/html\"/>\n", url));
+        xml.push_str(&format!("    <id>{}</id>\n", url));
+        xml.push_str(&format!("    <published>{}</published>\n", post.date.to_rfc3339()));
+        xml.push_str(&format!("    <updated>{}</updated>\n", post.date.to_rfc3339()));
+        if let Some(author) = &post.front_matter.author {
+            xml.push_str(&format!("    <author><name>{}</name></author>\n", xml_escape(author)));
+        }
+        xml.push_str(&format!("    <summary type=\"html\">{}</summary>\n", xml_escape(&post.excerpt)));
+        // Posts carry no HTML until rendered in `low_memory` mode
+        if !post.html.is_empty() {
+            xml.push_str(&format!("    <content type=\"html\">{}</content>\n", xml_escape(&post.html)));
+        }
+        xml.push_str("  </entry>\n");
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// Public form of a generated URL: rooted, without a trailing `index.html`
+pub(crate) fn public_url(url: &str) -> String {
+    let url = format!("/{}", url.trim_start_matches('/'));
+    match url.strip_suffix("index.html") {
+        Some(dir) if dir.ends_with('/') => dir.to_string(),
+        _ => url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::{Page, Post};
+    use std::path::PathBuf;
+
+    fn site() -> Site {
+        let post = |name: &str, front_matter: &str| {
+            let mut post = Post::new(PathBuf::from(format!("_posts/2024-01-15-{}.md", name)));
+            post.url = format!("/2024/{}.html", name);
+            post.front_matter = serde_yaml::from_str(front_matter).unwrap();
+            post
+        };
+        let page = |url: &str, front_matter: &str| {
+            let mut page = Page::new(PathBuf::from("page.md"));
+            page.url = url.to_string();
+            page.front_matter = serde_yaml::from_str(front_matter).unwrap();
+            page
+        };
+
+        let mut site = Site::new();
+        site.posts = vec![
+            post("hello", "title: Hello & welcome"),
+            post("secret", "feed: false"),
+            post("expired", "title: Old news"),
+            post("unlisted", "title: Still current"),
+        ];
+        site.posts[2].expires = Some(site.time);
+        site.posts[3].sitemap_expires = Some(site.time);
+        site.pages = vec![
+            page("index.html", "title: Home"),
+            page("thanks.html", "title: Thanks"),
+            page("hidden.html", "sitemap: false"),
+            page("page2/index.html", "title: Archive"),
+        ];
+        site
+    }
+
+    fn config() -> Config {
+        serde_yaml::from_str(
+            "url: https://example.com\n\
+             plugins: [jekyll-sitemap, feed]\n\
+             sitemap:\n  exclude: [\"/thanks.html\", \"/page*/\"]\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plugin_enabled() {
+        let config = config();
+        assert!(plugin_enabled(&config, "sitemap"));
+        assert!(plugin_enabled(&config, "feed"));
+        assert!(!plugin_enabled(&config, "seo"));
+    }
+
+    #[test]
+    fn test_sitemap_exclusions() {
+        let xml = sitemap(&site(), &config());
+        assert!(xml.contains("<loc>https://example.com/2024/hello.html</loc>"));
+        assert!(xml.contains("<loc>https://example.com/2024/secret.html</loc>"));
+        assert!(xml.contains("<loc>https://example.com/</loc>"));
+        assert!(!xml.contains("thanks"));
+        assert!(!xml.contains("hidden"));
+        assert!(!xml.contains("page2"));
+        assert!(!xml.contains("expired"));
+        assert!(!xml.contains("unlisted"));
+    }
+
+    #[test]
+    fn test_feed_exclusions() {
+        let xml = feed(&site(), &config());
+        assert!(xml.contains("<title>Hello &amp; welcome</title>"));
+        assert!(!xml.contains("secret"));
+        assert!(!xml.contains("Old news"));
+        assert!(xml.contains("Still current"));
+        assert_eq!(feed_posts(&site(), &config(), |_| true), vec![0, 3]);
+    }
+
+    #[test]
+    fn test_feed_content() {
+        let mut site = site();
+        site.posts[0].html = "<p>Hi</p>".to_string();
+        site.posts[3].excerpt = "Current".to_string();
+
+        let xml = feed(&site, &config());
+        assert!(xml.contains("<content type=\"html\">&lt;p&gt;Hi&lt;/p&gt;</content>"));
+        assert_eq!(xml.matches("<content").count(), 1);
+        assert!(xml.contains("<summary type=\"html\">Current</summary>"));
+    }
+
+    #[test]
+    fn test_public_url() {
+        assert_eq!(public_url("about.html"), "/about.html");
+        assert_eq!(public_url("/docs/index.html"), "/docs/");
+        assert_eq!(public_url("index.html"), "/");
+        assert_eq!(public_url("/2024/01/15/hello/"), "/2024/01/15/hello/");
+        assert_eq!(public_url("/blogindex.html"), "/blogindex.html");
+    }
+}
@@ -34,13 +34,14 @@ impl From<serde_yaml::Error> for Error {
 
 impl From<anyhow::Error> for Error {
     fn from(err: anyhow::Error) -> Self {
-        Error::Other(err.to_string())
+        // Keep the context chain, e.g. the liquid error behind a template error
+        Error::Other(format!("{:#}", err))
     }
 }
 
 impl From<walkdir::Error> for Error {
     fn from(err: walkdir::Error) -> Self {
-        Error::Io(std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))
+        Error::Io(std::io::Error::other(err.to_string()))
     }
 }
 
@@ -0,0 +1,98 @@
+use crate::content::FrontMatter;
+use jellrust_template::filters::{close_html, first_paragraph};
+pub use jellrust_template::filters::{strip_html, truncate_html, truncate_text};
+use jellrust_types::{Config, ExcerptConfig, ExcerptFormat};
+
+/// Characters kept of content without a paragraph, when `excerpt.length` is 0
+const FALLBACK_LENGTH: usize = 200;
+
+/// Excerpt of rendered content, shaped by the `excerpt:` settings
+///
+/// It is everything before `separator` when the content has it, else the
+/// first paragraph. Feeds, taxonomy listings and the `description` fallback
+/// are all derived from it, so they agree with what `post.excerpt` shows.
+pub fn extract_excerpt(html: &str, config: &ExcerptConfig, separator: Option<&str>) -> String {
+    let separated = separator
+        .and_then(|separator| html.find(separator))
+        .map(|end| close_html(html[..end].trim_end()));
+    let (excerpt, length) = match (&separated, first_paragraph(html)) {
+        (Some(separated), _) => (separated.as_str(), config.length),
+        (None, Some(paragraph)) => (paragraph, config.length),
+        (None, None) if config.length > 0 => (html, config.length),
+        (None, None) => (html, FALLBACK_LENGTH),
+    };
+
+    match (config.format, length) {
+        (ExcerptFormat::Html, 0) => excerpt.to_string(),
+        (ExcerptFormat::Html, length) => truncate_html(excerpt, length),
+        (ExcerptFormat::Plain, 0) => strip_html(excerpt),
+        (ExcerptFormat::Plain, length) => truncate_text(&strip_html(excerpt), length),
+    }
+}
+
+/// `excerpt_separator` of a document's front matter, else of the site
+///
+/// An empty separator in front matter turns the site's off.
+pub fn excerpt_separator<'a>(front_matter: &'a FrontMatter, config: &'a Config) -> Option<&'a str> {
+    front_matter
+        .custom
+        .get("excerpt_separator")
+        .and_then(|v| v.as_str())
+        .or(config.excerpt_separator.as_deref())
+        .filter(|separator| !separator.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(yaml: &str) -> ExcerptConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_extract_excerpt() {
+        let html = "<h1>T</h1><p>Read <a href=\"/x\">the <em>whole</em> story</a> here.</p><p>More</p>";
+        assert_eq!(
+            extract_excerpt(html, &ExcerptConfig::default(), None),
+            "Read <a href=\"/x\">the <em>whole</em> story</a> here."
+        );
+        assert_eq!(
+            extract_excerpt(html, &config("length: 14"), None),
+            "Read <a href=\"/x\">the <em>whole...</em></a>"
+        );
+        assert_eq!(
+            extract_excerpt(html, &config("format: plain"), None),
+            "Read the whole story here."
+        );
+        assert_eq!(
+            extract_excerpt(html, &config("format: plain\nlength: 16"), None),
+            "Read the whole..."
+        );
+    }
+
+    #[test]
+    fn test_excerpt_separator() {
+        let html = "<p>One</p>\n<p>Two <em>and <!--more--> three</em></p>\n<p>Four</p>";
+        let excerpt = ExcerptConfig::default();
+        assert_eq!(
+            extract_excerpt(html, &excerpt, Some("<!--more-->")),
+            "<p>One</p>\n<p>Two <em>and</em></p>"
+        );
+        assert_eq!(extract_excerpt(html, &config("format: plain"), Some("<!--more-->")), "One Two and");
+        assert_eq!(extract_excerpt(html, &excerpt, Some("<!--cut-->")), "One");
+
+        let mut site: Config = serde_yaml::from_str("excerpt_separator: <!--more-->").unwrap();
+        let mut front_matter = FrontMatter::default();
+        assert_eq!(excerpt_separator(&front_matter, &site), Some("<!--more-->"));
+        front_matter
+            .custom
+            .insert("excerpt_separator".into(), serde_yaml::Value::String("<!--cut-->".into()));
+        assert_eq!(excerpt_separator(&front_matter, &site), Some("<!--cut-->"));
+        front_matter.custom.insert("excerpt_separator".into(), serde_yaml::Value::String(String::new()));
+        assert_eq!(excerpt_separator(&front_matter, &site), None);
+        site.excerpt_separator = None;
+        front_matter.custom.clear();
+        assert_eq!(excerpt_separator(&front_matter, &site), None);
+    }
+}
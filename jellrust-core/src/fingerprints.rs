@@ -0,0 +1,73 @@
+use crate::bundle::fingerprint;
+use crate::deps::CACHE_DIR;
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use jellrust_types::StaticFile;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File in the cache directory the asset hashes are kept in
+const CACHE_FILE: &str = "fingerprints.json";
+
+/// Content hash of an asset as of its modification time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    modified: DateTime<Utc>,
+    hash: String,
+}
+
+/// Content hashes of the static files `fingerprint:` adds to asset URLs
+///
+/// Hashes are kept in `.jellrust-cache/fingerprints.json` between builds,
+/// so an asset is only read again once it's modified.
+#[derive(Debug, Default)]
+pub struct FingerprintCache {
+    path: PathBuf,
+    previous: HashMap<String, Entry>,
+    current: HashMap<String, Entry>,
+}
+
+impl FingerprintCache {
+    /// Hashes recorded by the previous build of the site in `source`
+    pub fn load(source: &Path) -> Self {
+        let path = source.join(CACHE_DIR).join(CACHE_FILE);
+        let previous = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            previous,
+            current: HashMap::new(),
+        }
+    }
+
+    /// Hash of an asset copied to `output`, and the one the previous build
+    /// recorded when it differs
+    pub fn hash(&mut self, file: &StaticFile, output: &Path) -> Result<(String, Option<String>)> {
+        let previous = self.previous.get(&file.path);
+        let hash = match previous {
+            Some(entry) if entry.modified == file.modified_time => entry.hash.clone(),
+            _ => fingerprint(fs::read(output)?),
+        };
+        let replaced = previous.map(|entry| entry.hash.clone()).filter(|old| *old != hash);
+
+        let entry = Entry {
+            modified: file.modified_time,
+            hash: hash.clone(),
+        };
+        self.current.insert(file.path.clone(), entry);
+        Ok((hash, replaced))
+    }
+
+    /// Keep the hashes of this build's assets for the next one
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(&self.current).unwrap_or_default())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use crate::config::Config;
+use crate::content::{FrontMatter, Site};
+use crate::discovery::public_url;
+use crate::error::Result;
+use jellrust_types::HeaderFile;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Netlify's header rules file
+pub const NETLIFY_HEADERS_FILE: &str = "_headers";
+
+/// Vercel's project configuration file
+pub const VERCEL_CONFIG_FILE: &str = "vercel.json";
+
+/// Response headers declared in front matter, keyed by public URL
+type HeaderRules = BTreeMap<String, Vec<(String, String)>>;
+
+/// Write the header files configured in `header_files` from `headers:` front matter
+///
+/// ```yaml
+/// headers:
+///   Content-Security-Policy: "default-src 'self'"
+///   Cache-Control: max-age=3600
+/// ```
+pub fn write_header_files(site: &Site, config: &Config, destination: &Path) -> Result<()> {
+    let rules = header_rules(site);
+    if rules.is_empty() {
+        return Ok(());
+    }
+    if config.header_files.is_empty() {
+        tracing::warn!(
+            "{} documents declare `headers:` but `header_files` is empty; no header file written",
+            rules.len()
+        );
+        return Ok(());
+    }
+
+    for format in &config.header_files {
+        match format {
+            HeaderFile::Netlify => fs::write(destination.join(NETLIFY_HEADERS_FILE), netlify_headers(&rules))?,
+            HeaderFile::Vercel => fs::write(destination.join(VERCEL_CONFIG_FILE), vercel_config(&rules))?,
+        }
+    }
+    Ok(())
+}
+
+/// Collect the `headers:` of every post and page
+fn header_rules(site: &Site) -> HeaderRules {
+    let documents = site
+        .posts
+        .iter()
+        .map(|post| (&post.url, &post.front_matter))
+        .chain(site.pages.iter().map(|page| (&page.url, &page.front_matter)));
+
+    let mut rules = HeaderRules::new();
+    for (url, front_matter) in documents {
+        let headers = declared_headers(front_matter);
+        if !headers.is_empty() {
+            rules.entry(public_url(url)).or_default().extend(headers);
+        }
+    }
+    rules
+}
+
+/// `headers:` of one document, with scalar values turned into strings
+fn declared_headers(front_matter: &FrontMatter) -> Vec<(String, String)> {
+    let Some(serde_yaml::Value::Mapping(headers)) = front_matter.custom.get("headers") else {
+        return Vec::new();
+    };
+
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = match value {
+                serde_yaml::Value::String(value) => value.clone(),
+                serde_yaml::Value::Number(value) => value.to_string(),
+                serde_yaml::Value::Bool(value) => value.to_string(),
+                _ => return None,
+            };
+            Some((name.as_str()?.to_string(), value))
+        })
+        .collect()
+}
+
+fn netlify_headers(rules: &HeaderRules) -> String {
+    let mut file = String::new();
+    for (url, headers) in rules {
+        file.push_str(url);
+        file.push('\n');
+        for (name, value) in headers {
+            file.push_str(&format!("  {}: {}\n", name, value));
+        }
+    }
+    file
+}
+
+fn vercel_config(rules: &HeaderRules) -> String {
+    let headers: Vec<serde_json::Value> = rules
+        .iter()
+        .map(|(url, headers)| {
+            let headers: Vec<serde_json::Value> = headers
+                .iter()
+                .map(|(name, value)| serde_json::json!({ "key": name, "value": value }))
+                .collect();
+            serde_json::json!({ "source": url, "headers": headers })
+        })
+        .collect();
+
+    let mut file = serde_json::to_string_pretty(&serde_json::json!({ "headers": headers }))
+        .unwrap_or_default();
+    file.push('\n');
+    file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::{Page, Post};
+    use std::path::PathBuf;
+
+    fn site() -> Site {
+        let mut site = Site::new();
+        let mut post = Post::new(PathBuf::from("_posts/2024-01-15-hello.md"));
+        post.url = "/2024/hello.html".to_string();
+        post.front_matter = serde_yaml::from_str("headers:\n  Cache-Control: max-age=60").unwrap();
+        site.posts.push(post);
+
+        let mut page = Page::new(PathBuf::from("about.md"));
+        page.url = "about/index.html".to_string();
+        page.front_matter =
+            serde_yaml::from_str("headers:\n  Content-Security-Policy: \"default-src 'self'\"\n  X-Count: 3").unwrap();
+        site.pages.push(page);
+        site.pages.push(Page::new(PathBuf::from("plain.md")));
+        site
+    }
+
+    #[test]
+    fn test_netlify_headers() {
+        assert_eq!(
+            netlify_headers(&header_rules(&site())),
+            "/2024/hello.html\n  Cache-Control: max-age=60\n\
+             /about/\n  Content-Security-Policy: default-src 'self'\n  X-Count: 3\n"
+        );
+    }
+
+    #[test]
+    fn test_vercel_config() {
+        let config: serde_json::Value = serde_json::from_str(&vercel_config(&header_rules(&site()))).unwrap();
+        assert_eq!(config["headers"][1]["source"], "/about/");
+        assert_eq!(config["headers"][1]["headers"][0]["key"], "Content-Security-Policy");
+        assert_eq!(config["headers"][0]["headers"][0]["value"], "max-age=60");
+    }
+}
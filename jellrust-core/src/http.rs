@@ -0,0 +1,12 @@
+use once_cell::sync::Lazy;
+
+/// HTTP client shared by link checks, `vendor:` downloads and webhooks
+///
+/// It follows redirects and verifies certificates against the bundled
+/// Mozilla roots, so no system tools are needed. Callers set their own
+/// timeouts per request.
+pub(crate) static AGENT: Lazy<ureq::Agent> = Lazy::new(|| {
+    ureq::AgentBuilder::new()
+        .user_agent(concat!("jellrust/", env!("CARGO_PKG_VERSION")))
+        .build()
+});
@@ -0,0 +1,125 @@
+use jellrust_types::ImagesConfig;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+/// An `<img>` tag
+static IMG_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<img\b[^>]*>").unwrap());
+
+static SRC: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?i)\ssrc\s*=\s*["']([^"']+)["']"#).unwrap());
+static LOADING: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\sloading\s*=").unwrap());
+static DIMENSION: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\s(?:width|height)\s*=").unwrap());
+
+/// Add the attributes enabled in `images:` to the `<img>` tags of an HTML output
+///
+/// Attributes a tag already has are kept. Dimensions come from the image in
+/// `destination`: a root-relative `src` is looked up there without
+/// `baseurl`, a relative one next to `output`. Remote images get none.
+pub fn add_image_attributes(
+    html: &str,
+    config: &ImagesConfig,
+    baseurl: &str,
+    destination: &Path,
+    output: &Path,
+) -> String {
+    if !config.lazy && !config.dimensions {
+        return html.to_string();
+    }
+
+    IMG_TAG
+        .replace_all(html, |caps: &regex::Captures| {
+            let tag = &caps[0];
+            let mut attributes = String::new();
+            if config.lazy && !LOADING.is_match(tag) {
+                attributes.push_str(" loading=\"lazy\"");
+            }
+            if config.dimensions && !DIMENSION.is_match(tag) {
+                let size = SRC
+                    .captures(tag)
+                    .and_then(|src| local_image(&src[1], baseurl, destination, output))
+                    .and_then(|path| imagesize::size(path).ok());
+                if let Some(size) = size {
+                    attributes.push_str(&format!(" width=\"{}\" height=\"{}\"", size.width, size.height));
+                }
+            }
+            insert_attributes(tag, &attributes)
+        })
+        .into_owned()
+}
+
+/// `<img src="a">` + ` b="c"` -> `<img src="a" b="c">`, keeping a self-closing `/>`
+fn insert_attributes(tag: &str, attributes: &str) -> String {
+    let body = tag.trim_end_matches('>');
+    match body.strip_suffix('/') {
+        Some(body) => format!("{}{} />", body.trim_end(), attributes),
+        None => format!("{}{}>", body.trim_end(), attributes),
+    }
+}
+
+/// File in `destination` an image `src` refers to
+fn local_image(src: &str, baseurl: &str, destination: &Path, output: &Path) -> Option<PathBuf> {
+    let src = src.split(['?', '#']).next()?.trim();
+    if src.is_empty() || src.starts_with("//") || src.contains(':') {
+        return None;
+    }
+
+    let path = match src.strip_prefix('/') {
+        Some(rooted) => {
+            let baseurl = baseurl.trim_matches('/');
+            let rooted = match rooted.strip_prefix(baseurl) {
+                Some(rest) if !baseurl.is_empty() && (rest.is_empty() || rest.starts_with('/')) => rest,
+                _ => rooted,
+            };
+            destination.join(rooted.trim_start_matches('/'))
+        }
+        None => output.parent()?.join(src),
+    };
+    path.is_file().then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+    use std::fs;
+
+    /// Header of a 3x2 PNG, all `imagesize` reads
+    const PNG: &[u8] = &[
+        0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0, 0, 0, 13, b'I', b'H', b'D', b'R', 0, 0, 0, 3, 0, 0,
+        0, 2, 8, 6, 0, 0, 0,
+    ];
+
+    #[test]
+    fn test_add_image_attributes() {
+        let dir = temp_site("images");
+        fs::create_dir_all(dir.join("assets")).unwrap();
+        fs::create_dir_all(dir.join("blog")).unwrap();
+        fs::write(dir.join("assets/a.png"), PNG).unwrap();
+        fs::write(dir.join("blog/b.png"), PNG).unwrap();
+
+        let config = ImagesConfig {
+            lazy: true,
+            dimensions: true,
+        };
+        let html = concat!(
+            "<img src=\"/docs/assets/a.png?v=1\" alt=\"a\" />",
+            "<IMG SRC='b.png'>",
+            "<img src=\"/missing.png\" loading=\"eager\">",
+            "<img src=\"https://example.com/c.png\" width=\"10\">",
+        );
+        let output = add_image_attributes(html, &config, "/docs", &dir, &dir.join("blog/index.html"));
+        assert_eq!(
+            output,
+            concat!(
+                "<img src=\"/docs/assets/a.png?v=1\" alt=\"a\" loading=\"lazy\" width=\"3\" height=\"2\" />",
+                "<IMG SRC='b.png' loading=\"lazy\" width=\"3\" height=\"2\">",
+                "<img src=\"/missing.png\" loading=\"eager\">",
+                "<img src=\"https://example.com/c.png\" width=\"10\" loading=\"lazy\">",
+            )
+        );
+
+        let off = ImagesConfig::default();
+        assert_eq!(add_image_attributes(html, &off, "", &dir, &dir.join("index.html")), html);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,33 @@
 pub mod config;
 pub mod site;
 pub mod content;
+pub mod converter;
+pub mod authors;
+pub mod bundle;
+pub mod data;
+pub mod discovery;
+pub mod deps;
 pub mod error;
+pub mod excerpt;
+pub mod fingerprints;
+pub mod headers;
+mod http;
+pub mod images;
+pub mod links;
+pub mod lock;
+pub mod notification;
+pub mod output;
+pub mod pagination;
+pub mod summary;
+pub mod taxonomy;
+pub mod theme;
+pub mod vendor;
+pub mod wikilinks;
+
+#[cfg(test)]
+mod testing;
+
+pub use jellrust_types::paths;
 
 pub use error::{Error, Result};
 
@@ -0,0 +1,338 @@
+use crate::deps::CACHE_DIR;
+use crate::error::{Error, Result};
+use crate::http::AGENT;
+use jellrust_types::LinkCheckConfig;
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+/// File in the cache directory remembering when external links last worked
+const CACHE_FILE: &str = "links.json";
+
+/// Wait before the first retry, doubled for every further one
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// `href` and `src` attribute values
+static LINK_ATTRIBUTE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\s(?:href|src)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+/// URL -> URL paths of the pages linking to it
+pub type Links = BTreeMap<String, BTreeSet<String>>;
+
+/// A link that doesn't lead anywhere
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DeadLink {
+    /// URL path of the page with the link
+    pub page: String,
+    pub url: String,
+    /// What went wrong, e.g. `HTTP 404` or `no such file`
+    pub reason: String,
+}
+
+/// Links in the `href` and `src` attributes of the built HTML pages
+pub fn collect_links(destination: &Path) -> Result<Links> {
+    let mut links = Links::new();
+    for entry in WalkDir::new(destination) {
+        let entry = entry?;
+        let is_html = entry.path().extension().is_some_and(|ext| ext == "html" || ext == "htm");
+        if !entry.file_type().is_file() || !is_html {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(destination) else {
+            continue;
+        };
+        let segments: Vec<_> = relative.iter().map(|segment| segment.to_string_lossy()).collect();
+        let page = format!("/{}", segments.join("/"));
+        let html = fs::read_to_string(entry.path())?;
+        for caps in LINK_ATTRIBUTE.captures_iter(&html) {
+            let url = caps.get(1).or_else(|| caps.get(2)).map_or("", |m| m.as_str()).trim();
+            if !url.is_empty() {
+                links.entry(url.replace("&amp;", "&")).or_default().insert(page.clone());
+            }
+        }
+    }
+    Ok(links)
+}
+
+/// Whether a link points to another site
+pub fn is_external(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("//")
+}
+
+/// Links within the site whose target isn't in `destination`
+///
+/// Root-relative links have `baseurl` stripped; relative ones are resolved
+/// against the page's directory. A link to a directory needs its
+/// `index.html`, and extensionless links may drop `.html`.
+pub fn dead_internal_links(destination: &Path, baseurl: &str, links: &Links) -> Vec<DeadLink> {
+    let baseurl = baseurl.trim_end_matches('/');
+    let mut dead = Vec::new();
+    for (url, pages) in links {
+        let path = url.split(['#', '?']).next().unwrap_or_default();
+        if path.is_empty() || is_external(url) || has_scheme(path) {
+            continue;
+        }
+        for page in pages {
+            let target = if path.starts_with('/') {
+                path.strip_prefix(baseurl).filter(|rest| rest.is_empty() || rest.starts_with('/')).unwrap_or(path).to_string()
+            } else {
+                let dir = &page[..page.rfind('/').map_or(0, |end| end + 1)];
+                format!("{}{}", dir, path)
+            };
+            if !target_exists(destination, &percent_decode(&target)) {
+                dead.push(DeadLink {
+                    page: page.clone(),
+                    url: url.clone(),
+                    reason: "no such file".to_string(),
+                });
+            }
+        }
+    }
+    dead.sort();
+    dead
+}
+
+/// Whether a URL path from the site root names a built file
+fn target_exists(destination: &Path, path: &str) -> bool {
+    // Resolve `.` and `..`, refusing to climb out of the site
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.pop().is_none() {
+                    return false;
+                }
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let file: PathBuf = segments.iter().fold(destination.to_path_buf(), |file, segment| file.join(segment));
+    file.is_file()
+        || file.join("index.html").is_file()
+        || (!path.ends_with('/')
+            && segments.last().is_some_and(|name| file.with_file_name(format!("{}.html", name)).is_file()))
+}
+
+/// Whether a link has a scheme such as `mailto:` or `data:`
+fn has_scheme(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty() && !scheme.contains('/') && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Decode `%XX` escapes, leaving malformed ones as they are
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%').then(|| text.get(i + 1..i + 3)).flatten();
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Check the external links over HTTP
+///
+/// Up to `link_check.concurrency` links are checked at once. Links matching
+/// `link_check.allow` are skipped, as are links that worked within the last
+/// `link_check.cache_hours`, remembered in `.jellrust-cache/links.json`.
+pub fn dead_external_links(source: &Path, config: &LinkCheckConfig, links: &Links) -> Result<Vec<DeadLink>> {
+    let cache_path = cache_path(source);
+    let mut cache: HashMap<String, u64> = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let fresh = |url: &str| cache.get(url).is_some_and(|checked| now.saturating_sub(*checked) < config.cache_hours * 3600);
+
+    let allow: Vec<glob::Pattern> = config.allow.iter().filter_map(|pattern| glob::Pattern::new(pattern).ok()).collect();
+    let urls: Vec<&String> = links
+        .keys()
+        .filter(|url| is_external(url))
+        .filter(|url| !allow.iter().any(|pattern| pattern.matches(url)))
+        .filter(|url| !fresh(url))
+        .collect();
+    tracing::info!("Checking {} external link(s)", urls.len());
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.concurrency.max(1))
+        .build()
+        .map_err(|e| Error::Other(format!("Failed to start link checker: {}", e)))?;
+    let results: Vec<(&String, std::result::Result<(), String>)> =
+        pool.install(|| urls.par_iter().map(|url| (*url, check_url(url, config))).collect());
+
+    let mut dead = Vec::new();
+    for (url, result) in results {
+        match result {
+            Ok(()) => {
+                cache.insert(url.clone(), now);
+            }
+            Err(reason) => {
+                cache.remove(url);
+                dead.extend(links[url].iter().map(|page| DeadLink {
+                    page: page.clone(),
+                    url: url.clone(),
+                    reason: reason.clone(),
+                }));
+            }
+        }
+    }
+
+    if let Some(parent) = cache_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&cache_path, serde_json::to_string(&cache).unwrap_or_default())?;
+
+    dead.sort();
+    Ok(dead)
+}
+
+/// What a request says about a link
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Alive,
+    Dead(String),
+    /// Possibly temporary, worth another try
+    Retry(String),
+}
+
+/// Check a link, retrying temporary failures with exponential backoff
+fn check_url(url: &str, config: &LinkCheckConfig) -> std::result::Result<(), String> {
+    let mut attempt = 0;
+    loop {
+        // Some servers refuse HEAD requests but serve the page
+        let outcome = match request(url, true, config.timeout) {
+            Outcome::Dead(reason) if reason == "HTTP 405" || reason == "HTTP 501" => request(url, false, config.timeout),
+            outcome => outcome,
+        };
+        match outcome {
+            Outcome::Alive => return Ok(()),
+            Outcome::Dead(reason) => return Err(reason),
+            Outcome::Retry(reason) if attempt >= config.retries => return Err(reason),
+            Outcome::Retry(reason) => {
+                tracing::debug!("Retrying {} after {}", url, reason);
+                std::thread::sleep(RETRY_DELAY * 2u32.pow(attempt));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// One `HEAD` or `GET` request, following redirects
+fn request(url: &str, head: bool, timeout: u64) -> Outcome {
+    let url = match url.strip_prefix("//") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    };
+    let request = if head {
+        AGENT.head(&url)
+    } else {
+        AGENT.get(&url).set("Range", "bytes=0-0")
+    };
+
+    match request.timeout(Duration::from_secs(timeout)).call() {
+        Ok(response) => classify(response.status()),
+        Err(ureq::Error::Status(code, _)) => classify(code),
+        // The host doesn't resolve, which retrying won't fix
+        Err(ureq::Error::Transport(e)) if e.kind() == ureq::ErrorKind::Dns => Outcome::Dead(e.to_string()),
+        Err(e) => Outcome::Retry(e.to_string()),
+    }
+}
+
+/// Outcome of a final HTTP status code
+fn classify(code: u16) -> Outcome {
+    match code {
+        200..=399 => Outcome::Alive,
+        0 => Outcome::Retry("no response".to_string()),
+        429 | 500..=599 => Outcome::Retry(format!("HTTP {}", code)),
+        code => Outcome::Dead(format!("HTTP {}", code)),
+    }
+}
+
+/// File the results of external link checks are cached in
+fn cache_path(source: &Path) -> PathBuf {
+    source.join(CACHE_DIR).join(CACHE_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{http_server, temp_site};
+
+    #[test]
+    fn test_collect_and_check_internal_links() {
+        let dir = temp_site("links");
+        fs::create_dir_all(dir.join("docs/guide")).unwrap();
+        fs::write(dir.join("docs/guide/index.html"), "").unwrap();
+        fs::write(dir.join("docs/my page.html"), "").unwrap();
+        fs::write(
+            dir.join("docs/intro.html"),
+            concat!(
+                "<a href=\"/blog/docs/guide/\">ok</a><a href='my%20page.html#top'>ok</a>",
+                "<a href=\"/blog/docs/my page\">ok</a><a href=\"#top\">ok</a><a href=\"mailto:a@b.c\">ok</a>",
+                "<img src=\"../img/missing.png\"><a href=\"https://example.com/?a=1&amp;b=2\">x</a>",
+                "<a href=\"/blog/docs/gone.html\">gone</a>",
+            ),
+        )
+        .unwrap();
+
+        let links = collect_links(&dir).unwrap();
+        assert_eq!(links["https://example.com/?a=1&b=2"], BTreeSet::from(["/docs/intro.html".to_string()]));
+
+        let dead = dead_internal_links(&dir, "/blog", &links);
+        let urls: Vec<&str> = dead.iter().map(|link| link.url.as_str()).collect();
+        assert_eq!(urls, ["../img/missing.png", "/blog/docs/gone.html"]);
+        assert_eq!(dead[0].page, "/docs/intro.html");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_request() {
+        let url = http_server(vec!["", ""]);
+        assert_eq!(request(&url, true, 5), Outcome::Alive);
+        assert_eq!(request(&url, false, 5), Outcome::Alive);
+        assert!(matches!(request("http://jellrust.invalid/", true, 5), Outcome::Dead(_)));
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(200), Outcome::Alive);
+        assert_eq!(classify(301), Outcome::Alive);
+        assert_eq!(classify(404), Outcome::Dead("HTTP 404".to_string()));
+        assert_eq!(classify(429), Outcome::Retry("HTTP 429".to_string()));
+        assert_eq!(classify(503), Outcome::Retry("HTTP 503".to_string()));
+    }
+
+    #[test]
+    fn test_allowed_and_cached_links_are_skipped() {
+        let dir = temp_site("links-cache");
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        fs::create_dir_all(dir.join(CACHE_DIR)).unwrap();
+        fs::write(cache_path(&dir), format!("{{\"https://cached.invalid/\": {}}}", now)).unwrap();
+
+        let mut links = Links::new();
+        for url in ["https://cached.invalid/", "https://twitter.com/someone", "/local.html"] {
+            links.entry(url.to_string()).or_default().insert("/index.html".to_string());
+        }
+        let config: LinkCheckConfig = serde_yaml::from_str("allow: ['https://twitter.com/*']").unwrap();
+        assert!(dead_external_links(&dir, &config, &links).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
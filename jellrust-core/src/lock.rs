@@ -0,0 +1,139 @@
+use crate::deps::CACHE_DIR;
+use crate::error::{Error, Result};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// File (inside the cache directory) guarding builds of a site
+const LOCK_FILE: &str = "build.lock";
+
+/// How often a waiting build retries the lock
+const RETRY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Exclusive lock on a site's destination and caches, released on drop
+///
+/// Backed by an OS file lock, so a crashed process never leaves a stale lock
+/// behind. The file holds the owner's process id for error messages.
+#[derive(Debug)]
+pub struct BuildLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl BuildLock {
+    /// Lock the site at `source`, waiting up to `timeout` for another process
+    pub fn acquire(source: &Path, timeout: Duration) -> Result<Self> {
+        let path = source.join(CACHE_DIR).join(LOCK_FILE);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let started = Instant::now();
+        let mut announced = false;
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+
+            let owner = lock_owner(&mut file);
+            if started.elapsed() >= timeout {
+                return Err(Error::Other(format!(
+                    "Another jellrust process{} is building this site (lock: {}); \
+                     wait for it to finish or stop it, then try again",
+                    owner, path.display()
+                )));
+            }
+            if !announced {
+                tracing::warn!("Another jellrust process{} is building this site, waiting...", owner);
+                announced = true;
+            }
+            thread::sleep(RETRY_INTERVAL);
+        }
+
+        file.set_len(0)?;
+        file.rewind()?;
+        writeln!(file, "{}", std::process::id())?;
+
+        Ok(Self { file, path })
+    }
+
+    /// Like [`acquire`](Self::acquire), but waits on a blocking thread so
+    /// the async runtime stays free meanwhile
+    pub async fn acquire_async(source: &Path, timeout: Duration) -> Result<Self> {
+        let source = source.to_path_buf();
+        tokio::task::spawn_blocking(move || Self::acquire(&source, timeout))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// Location of the lock file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for BuildLock {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+        let _ = self.file.unlock();
+    }
+}
+
+/// ` (pid N)` when the lock file names its owner, otherwise empty
+fn lock_owner(file: &mut File) -> String {
+    let mut content = String::new();
+    if file.rewind().is_err() || file.read_to_string(&mut content).is_err() {
+        return String::new();
+    }
+
+    match content.trim().parse::<u32>() {
+        Ok(pid) => format!(" (pid {})", pid),
+        Err(_) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+
+    #[test]
+    fn test_second_lock_times_out_until_released() {
+        let dir = temp_site("lock");
+
+        let lock = BuildLock::acquire(&dir, Duration::ZERO).unwrap();
+        assert!(lock.path().ends_with("build.lock"));
+
+        let err = BuildLock::acquire(&dir, Duration::ZERO).unwrap_err();
+        assert!(err.to_string().contains(&format!("pid {}", std::process::id())));
+
+        drop(lock);
+        BuildLock::acquire(&dir, Duration::ZERO).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[tokio::test]
+    async fn test_acquire_async_leaves_runtime_free() {
+        let dir = temp_site("lock-async");
+
+        // The single-threaded test runtime must get to release the lock
+        let lock = BuildLock::acquire(&dir, Duration::ZERO).unwrap();
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            drop(lock);
+        });
+        BuildLock::acquire_async(&dir, Duration::from_secs(5)).await.unwrap();
+        release.await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use crate::config::Config;
+use crate::http::AGENT;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// Title shown on desktop notifications
+const NOTIFICATION_TITLE: &str = "JellRust";
+
+/// Time the webhook may take to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Result of a watch or serve rebuild
+#[derive(Debug)]
+pub enum RebuildOutcome {
+    Success { duration: Duration },
+    Failure { error: String },
+}
+
+impl RebuildOutcome {
+    /// One-line human readable summary
+    pub fn message(&self, site: &str) -> String {
+        match self {
+            Self::Success { duration } => {
+                format!("{} rebuilt in {} ms", site, duration.as_millis())
+            }
+            Self::Failure { error } => {
+                let first_line = error.lines().next().unwrap_or_default();
+                format!("{} failed to rebuild: {}", site, first_line)
+            }
+        }
+    }
+
+    /// JSON body posted to the webhook
+    pub fn webhook_payload(&self, site: &str) -> serde_json::Value {
+        match self {
+            Self::Success { duration } => serde_json::json!({
+                "site": site,
+                "status": "success",
+                "duration_ms": duration.as_millis() as u64,
+                "message": self.message(site),
+            }),
+            Self::Failure { error } => serde_json::json!({
+                "site": site,
+                "status": "failure",
+                "error": error,
+                "message": self.message(site),
+            }),
+        }
+    }
+}
+
+/// Send the notifications enabled under `notifications:` in _config.yml
+///
+/// Notifications are delivered in the background; failures are only logged.
+pub fn notify_rebuild(config: &Config, outcome: &RebuildOutcome) {
+    let settings = &config.notifications;
+    if !settings.desktop && settings.webhook.is_none() {
+        return;
+    }
+
+    let message = outcome.message(&config.title);
+    let desktop = settings.desktop.then(|| desktop_command(&message)).flatten();
+    let webhook = settings
+        .webhook
+        .clone()
+        .map(|url| (url, outcome.webhook_payload(&config.title)));
+
+    thread::spawn(move || {
+        if let Some(command) = desktop {
+            run(command);
+        }
+        if let Some((url, payload)) = webhook {
+            post_webhook(&url, &payload);
+        }
+    });
+}
+
+/// Platform command showing a desktop notification, if there is one
+fn desktop_command(message: &str) -> Option<Command> {
+    if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(message),
+            applescript_string(NOTIFICATION_TITLE)
+        );
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        Some(command)
+    } else if cfg!(unix) {
+        let mut command = Command::new("notify-send");
+        command.args([NOTIFICATION_TITLE, message]);
+        Some(command)
+    } else {
+        tracing::warn!("Desktop notifications are not supported on this platform");
+        None
+    }
+}
+
+/// Post `payload` as JSON to `url`, logging instead of failing
+fn post_webhook(url: &str, payload: &serde_json::Value) {
+    let result = AGENT
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .set("Content-Type", "application/json")
+        .send_string(&payload.to_string());
+    if let Err(e) = result {
+        tracing::warn!("Failed to post build notification to {}: {}", url, e);
+    }
+}
+
+/// Quote a string literal for AppleScript
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Run a notification command, logging instead of failing
+fn run(mut command: Command) {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => tracing::warn!(
+            "Notification command `{}` failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => tracing::warn!("Failed to run notification command `{}`: {}", program, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_message_and_payload() {
+        let success = RebuildOutcome::Success {
+            duration: Duration::from_millis(1250),
+        };
+        assert_eq!(success.message("Blog"), "Blog rebuilt in 1250 ms");
+        assert_eq!(success.webhook_payload("Blog")["duration_ms"], 1250);
+
+        let failure = RebuildOutcome::Failure {
+            error: "Template error: bad tag\nat line 3".to_string(),
+        };
+        assert_eq!(failure.message("Blog"), "Blog failed to rebuild: Template error: bad tag");
+        assert_eq!(failure.webhook_payload("Blog")["status"], "failure");
+    }
+
+    #[test]
+    fn test_applescript_string() {
+        assert_eq!(applescript_string(r#"say "hi" \ bye"#), r#""say \"hi\" \\ bye""#);
+    }
+}
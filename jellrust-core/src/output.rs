@@ -0,0 +1,345 @@
+use jellrust_types::OutputStyle;
+
+/// Indentation used by `output: pretty_html`
+const INDENT: &str = "  ";
+
+/// Elements whose content is kept byte for byte
+const RAW_ELEMENTS: &[&str] = &["pre", "textarea", "script", "style"];
+
+/// Elements that hold other blocks: their children go on indented lines
+const CONTAINER_ELEMENTS: &[&str] = &[
+    "html", "head", "body", "div", "section", "article", "aside", "nav", "header", "footer",
+    "main", "ul", "ol", "dl", "table", "thead", "tbody", "tfoot", "tr", "form", "fieldset",
+    "figure", "blockquote", "details", "select", "video", "audio", "picture", "noscript",
+];
+
+/// Elements that start a line but keep their (inline) content on it
+const LINE_ELEMENTS: &[&str] = &[
+    "title", "p", "h1", "h2", "h3", "h4", "h5", "h6", "li", "dt", "dd", "td", "th", "caption",
+    "figcaption", "summary", "option", "legend", "address",
+];
+
+/// Void elements that go on a line of their own
+const VOID_LINE_ELEMENTS: &[&str] = &["meta", "link", "base", "hr", "source", "track"];
+
+/// Reformat a generated HTML document according to the `output` setting
+pub fn format_html(html: &str, style: OutputStyle) -> String {
+    match style {
+        OutputStyle::Off => html.to_string(),
+        OutputStyle::PrettyHtml => pretty(html),
+        OutputStyle::Minified => minify(html),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Text(&'a str),
+    Open { name: String, markup: &'a str },
+    Close { name: String, markup: &'a str },
+    /// Comments, doctypes and other `<!...>` markup
+    Special(&'a str),
+    /// Content of a raw element, up to its closing tag
+    Raw(&'a str),
+}
+
+impl Token<'_> {
+    fn kind(&self) -> Kind {
+        match self {
+            Token::Open { name, .. } | Token::Close { name, .. } => kind_of(name),
+            Token::Special(_) => Kind::Container,
+            Token::Text(_) | Token::Raw(_) => Kind::Inline,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Container,
+    Line,
+    VoidLine,
+    Inline,
+}
+
+fn kind_of(name: &str) -> Kind {
+    if CONTAINER_ELEMENTS.contains(&name) {
+        Kind::Container
+    } else if LINE_ELEMENTS.contains(&name) || RAW_ELEMENTS.contains(&name) {
+        Kind::Line
+    } else if VOID_LINE_ELEMENTS.contains(&name) {
+        Kind::VoidLine
+    } else {
+        Kind::Inline
+    }
+}
+
+/// Split HTML into tags and text
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(Token::Text(rest));
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+            rest = &rest[start..];
+        }
+
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|i| i + 3)
+        } else {
+            tag_end(rest)
+        };
+        let Some(end) = end.filter(|_| is_markup_start(rest)) else {
+            // A stray `<` is just text
+            tokens.push(Token::Text(&rest[..1]));
+            rest = &rest[1..];
+            continue;
+        };
+
+        let markup = &rest[..end];
+        rest = &rest[end..];
+        if markup.starts_with("<!") || markup.starts_with("<?") {
+            tokens.push(Token::Special(markup));
+        } else if let Some(name) = markup.strip_prefix("</") {
+            tokens.push(Token::Close {
+                name: tag_name(name),
+                markup,
+            });
+        } else {
+            let name = tag_name(&markup[1..]);
+            let self_closing = markup.ends_with("/>");
+            let raw = RAW_ELEMENTS.contains(&name.as_str()) && !self_closing;
+            tokens.push(Token::Open {
+                name: name.clone(),
+                markup,
+            });
+
+            // Keep everything up to the matching close tag untouched
+            if raw {
+                let close = format!("</{}", name);
+                let content_end = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+                if content_end > 0 {
+                    tokens.push(Token::Raw(&rest[..content_end]));
+                }
+                rest = &rest[content_end..];
+            }
+        }
+    }
+
+    tokens
+}
+
+fn is_markup_start(text: &str) -> bool {
+    let mut chars = text.chars().skip(1);
+    match chars.next() {
+        Some('/') => chars.next().is_some_and(|c| c.is_ascii_alphabetic()),
+        Some(c) => c.is_ascii_alphabetic() || c == '!' || c == '?',
+        None => false,
+    }
+}
+
+/// Index just past the `>` ending the tag at the start of `text`, skipping quoted values
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn tag_name(markup: &str) -> String {
+    markup
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == ':')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Collapse runs of whitespace into single spaces
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                collapsed.push(' ');
+            }
+            in_space = true;
+        } else {
+            collapsed.push(c);
+            in_space = false;
+        }
+    }
+    collapsed
+}
+
+/// Put block elements on their own, indented lines
+///
+/// Inline markup and text stay together on one line, so the whitespace
+/// the browser renders is unchanged.
+fn pretty(html: &str) -> String {
+    struct Writer {
+        output: String,
+        line: String,
+        depth: usize,
+    }
+
+    impl Writer {
+        fn flush(&mut self) {
+            let line = self.line.trim();
+            if !line.is_empty() {
+                self.output.push_str(&INDENT.repeat(self.depth));
+                self.output.push_str(line);
+                self.output.push('\n');
+            }
+            self.line.clear();
+        }
+
+        fn push_line(&mut self, markup: &str) {
+            self.flush();
+            self.line.push_str(markup);
+            self.flush();
+        }
+    }
+
+    let mut writer = Writer {
+        output: String::with_capacity(html.len()),
+        line: String::new(),
+        depth: 0,
+    };
+
+    for token in tokenize(html) {
+        match (&token, token.kind()) {
+            (Token::Text(text), _) => writer.line.push_str(&collapse_whitespace(text)),
+            (Token::Raw(content), _) => {
+                // Emitted as-is; the line (opening tag) is flushed with the close tag
+                writer.line.push_str(content);
+            }
+            (Token::Special(markup), _) => writer.push_line(markup),
+            (Token::Open { markup, .. }, Kind::Container) => {
+                writer.push_line(markup);
+                writer.depth += 1;
+            }
+            (Token::Open { markup, .. }, Kind::Line) => {
+                writer.flush();
+                writer.line.push_str(markup);
+                writer.depth += 1;
+            }
+            (Token::Open { markup, .. }, Kind::VoidLine) => writer.push_line(markup),
+            (Token::Close { markup, .. }, Kind::Container) => {
+                writer.flush();
+                writer.depth = writer.depth.saturating_sub(1);
+                writer.push_line(markup);
+            }
+            (Token::Close { markup, .. }, Kind::Line) => {
+                let on_own_line = writer.line.trim().is_empty();
+                writer.depth = writer.depth.saturating_sub(1);
+                if on_own_line {
+                    writer.push_line(markup);
+                } else {
+                    // The line was opened one level deeper
+                    writer.line.push_str(markup);
+                    writer.flush();
+                }
+            }
+            (Token::Open { markup, .. } | Token::Close { markup, .. }, _) => {
+                writer.line.push_str(markup);
+            }
+        }
+    }
+    writer.flush();
+
+    writer.output
+}
+
+/// Drop comments and the whitespace around blocks, collapse the rest
+///
+/// Conditional comments (`<!--[if IE]>`) are kept.
+fn minify(html: &str) -> String {
+    let tokens = tokenize(html);
+    let mut output = String::with_capacity(html.len());
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Text(text) => {
+                let text = collapse_whitespace(text);
+                let next_to_block = |token: Option<&Token>| {
+                    token.is_none_or(|token| token.kind() != Kind::Inline)
+                };
+                let text = if next_to_block(i.checked_sub(1).and_then(|i| tokens.get(i))) {
+                    text.trim_start().to_string()
+                } else {
+                    text
+                };
+                let text = if next_to_block(tokens.get(i + 1)) {
+                    text.trim_end()
+                } else {
+                    &text
+                };
+                output.push_str(text);
+            }
+            Token::Special(markup) if markup.starts_with("<!--") && !markup.starts_with("<!--[if") => {}
+            Token::Special(markup) | Token::Raw(markup) => output.push_str(markup),
+            Token::Open { markup, .. } | Token::Close { markup, .. } => output.push_str(markup),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "<!DOCTYPE html>\n<html><head>\n    <meta charset=\"utf-8\"><title>Hi</title>\n\
+        <!-- analytics --></head>\n<body>  <div class=\"a>b\"><p>Hello   <em>big</em> world</p>\n\n\n\
+        <pre>  keep\n    this</pre><ul><li>One</li><li>Two <a href=\"/\">home</a></li></ul></div>\
+        <script>if (a < b) {}</script></body></html>";
+
+    #[test]
+    fn test_pretty_html() {
+        assert_eq!(
+            format_html(PAGE, OutputStyle::PrettyHtml),
+            "<!DOCTYPE html>\n\
+             <html>\n\
+             \x20 <head>\n\
+             \x20   <meta charset=\"utf-8\">\n\
+             \x20   <title>Hi</title>\n\
+             \x20   <!-- analytics -->\n\
+             \x20 </head>\n\
+             \x20 <body>\n\
+             \x20   <div class=\"a>b\">\n\
+             \x20     <p>Hello <em>big</em> world</p>\n\
+             \x20     <pre>  keep\n    this</pre>\n\
+             \x20     <ul>\n\
+             \x20       <li>One</li>\n\
+             \x20       <li>Two <a href=\"/\">home</a></li>\n\
+             \x20     </ul>\n\
+             \x20   </div>\n\
+             \x20   <script>if (a < b) {}</script>\n\
+             \x20 </body>\n\
+             </html>\n"
+        );
+    }
+
+    #[test]
+    fn test_minified() {
+        assert_eq!(
+            format_html(PAGE, OutputStyle::Minified),
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Hi</title></head>\
+             <body><div class=\"a>b\"><p>Hello <em>big</em> world</p><pre>  keep\n    this</pre>\
+             <ul><li>One</li><li>Two <a href=\"/\">home</a></li></ul></div>\
+             <script>if (a < b) {}</script></body></html>"
+        );
+        assert_eq!(format_html(PAGE, OutputStyle::Off), PAGE);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::config::Config;
+use crate::content::Site;
+use crate::discovery::{plugin_enabled, public_url};
+use jellrust_types::Paginator;
+
+/// Split `site.posts` over the index page and copies of it at `paginate_path`
+///
+/// Like jekyll-paginate, this needs the `paginate` plugin and only applies
+/// to the `index` page in the directory of `paginate_path` (`/blog/` for
+/// `/blog/page:num/`). Page 1 stays at the index page's own URL.
+pub fn paginate(site: &mut Site, config: &Config) {
+    if !plugin_enabled(config, "paginate") || config.paginate == 0 {
+        return;
+    }
+    let Some(base) = pagination_base(&config.paginate_path) else {
+        tracing::warn!("paginate_path {:?} has no :num placeholder", config.paginate_path);
+        return;
+    };
+    let Some(index) = site.pages.iter().position(|page| public_url(&page.url) == base) else {
+        tracing::warn!("No index page at {} to paginate", base);
+        return;
+    };
+
+    // Expired posts keep their pages but drop out of the index
+    let listed: Vec<usize> = (0..site.posts.len())
+        .filter(|&i| !site.posts[i].is_expired(site.time))
+        .collect();
+    let per_page = config.paginate;
+    let total_posts = listed.len();
+    let total_pages = total_posts.div_ceil(per_page).max(1);
+    let page_path = |num: usize| {
+        if num == 1 {
+            base.clone()
+        } else {
+            let path = config.paginate_path.replace(":num", &num.to_string());
+            format!("/{}", path.trim_start_matches('/'))
+        }
+    };
+
+    let template = site.pages[index].clone();
+    let pages: Vec<_> = (1..=total_pages)
+        .map(|num| {
+            let mut page = template.clone();
+            if num > 1 {
+                let path = page_path(num);
+                page.url = if path.ends_with('/') {
+                    format!("{}index.html", path)
+                } else {
+                    path
+                };
+            }
+            page.paginator = Some(Paginator {
+                page: num,
+                per_page,
+                posts: listed[(num - 1) * per_page..(num * per_page).min(total_posts)].to_vec(),
+                total_posts,
+                total_pages,
+                previous_page: (num > 1).then(|| num - 1),
+                previous_page_path: (num > 1).then(|| page_path(num - 1)),
+                next_page: (num < total_pages).then(|| num + 1),
+                next_page_path: (num < total_pages).then(|| page_path(num + 1)),
+            });
+            page
+        })
+        .collect();
+
+    site.pages.splice(index..=index, pages);
+}
+
+/// Public URL of the page being paginated: the directory of `paginate_path`
+fn pagination_base(paginate_path: &str) -> Option<String> {
+    let path = paginate_path.trim_end_matches('/');
+    let (dir, last) = path.rsplit_once('/').unwrap_or(("", path));
+    if !last.contains(":num") {
+        return None;
+    }
+    Some(format!("/{}/", dir.trim_matches('/')).replace("//", "/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::{Page, Post};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_pagination_base() {
+        assert_eq!(pagination_base("/blog/page:num/").as_deref(), Some("/blog/"));
+        assert_eq!(pagination_base("page:num").as_deref(), Some("/"));
+        assert_eq!(pagination_base("/page/:num").as_deref(), Some("/page/"));
+        assert_eq!(pagination_base("/blog/"), None);
+    }
+
+    #[test]
+    fn test_paginate() {
+        let mut site = Site::new();
+        for name in ["about.html", "blog/index.html"] {
+            let mut page = Page::new(PathBuf::from(name));
+            page.url = name.to_string();
+            site.pages.push(page);
+        }
+        site.posts = (0..6).map(|i| Post::new(PathBuf::from(format!("{}.md", i)))).collect();
+        site.posts[3].expires = Some(site.time);
+        let mut config: Config =
+            serde_yaml::from_str("plugins: [jekyll-paginate]\npaginate: 2\npaginate_path: /blog/page:num/").unwrap();
+
+        paginate(&mut site, &config);
+        let urls: Vec<&str> = site.pages.iter().map(|p| p.url.as_str()).collect();
+        assert_eq!(urls, ["about.html", "blog/index.html", "/blog/page2/index.html", "/blog/page3/index.html"]);
+        assert!(site.pages[0].paginator.is_none());
+
+        let first = site.pages[1].paginator.as_ref().unwrap();
+        assert_eq!(first.posts, [0, 1]);
+        assert_eq!(first.previous_page_path, None);
+        assert_eq!(first.next_page_path.as_deref(), Some("/blog/page2/"));
+
+        let second = site.pages[2].paginator.as_ref().unwrap();
+        assert_eq!(second.previous_page_path.as_deref(), Some("/blog/"));
+        assert_eq!(second.posts, [2, 4]);
+        assert_eq!(site.pages[3].paginator.as_ref().unwrap().posts, [5]);
+
+        config.plugins.clear();
+        let mut unpaginated = Site::new();
+        unpaginated.pages = vec![Page::new(PathBuf::from("index.html"))];
+        paginate(&mut unpaginated, &config);
+        assert!(unpaginated.pages[0].paginator.is_none());
+    }
+}
@@ -1,33 +1,97 @@
-use crate::config::Config;
-use crate::content::{Page, Post, Site};
+use crate::authors::{generate_author_pages, write_author_feeds};
+use crate::bundle::{collect_bundles, is_manifest};
+use crate::config::{environment, Config};
+use crate::content::{FrontMatter, Page, Post, Site, StaticFile};
+use crate::converter::{Converter, ConverterRegistry};
+use crate::data::{load_data, DATA_DIR};
+use crate::discovery::{feed_posts, public_url, write_discovery_files, FEED_FILE};
+use crate::headers::write_header_files;
+use crate::images::add_image_attributes;
+use crate::deps::{cache_fingerprint, posts_fingerprint, DependencyGraph, CACHE_DIR};
 use crate::error::Result;
-use jellrust_markdown::MarkdownProcessor;
+use crate::excerpt::{excerpt_separator, extract_excerpt, strip_html, truncate_text};
+use crate::fingerprints::FingerprintCache;
+use crate::lock::BuildLock;
+use crate::output::format_html;
+use crate::pagination::paginate;
+use crate::summary::{warnings_logged, BuildSummary};
+use crate::paths;
+use crate::taxonomy::{normalize_post_taxonomies, tag_cloud, write_taxonomy_json, Taxonomy};
+use crate::theme::theme_dir;
+use crate::vendor::vendor_assets;
+use crate::wikilinks::WikiIndex;
+use jellrust_markdown::highlight::stylesheet;
+use jellrust_markdown::external::host;
+use jellrust_markdown::{has_front_matter, restore_liquid_raw, strip_bom, HighlightMode, MarkdownProcessor};
+use jellrust_template::context::ContextProvider;
+use jellrust_template::filters::toc_entries;
+use jellrust_template::liquid_core::{ParseBlock, ParseFilter, ParseTag};
+use jellrust_template::profile::ProfileReport;
 use jellrust_template::TemplateEngine;
+use jellrust_types::Fingerprint;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 
+/// File written into the destination after every successful build
+pub const BUILD_ID_FILE: &str = ".build-id";
+
 pub struct SiteBuilder {
     source: PathBuf,
     destination: PathBuf,
     config: Config,
     include_drafts: bool,
+    incremental: bool,
+    config_file: PathBuf,
     markdown_processor: MarkdownProcessor,
+    converters: ConverterRegistry,
     template_engine: TemplateEngine,
+    /// Directory of the configured `theme`
+    theme_dir: Option<PathBuf>,
+    /// Directories in the source that aren't content, besides the destination
+    excluded_dirs: Vec<PathBuf>,
+    previous_graph: Option<DependencyGraph>,
+    dependency_graph: DependencyGraph,
+    /// Targets of `[[...]]` links, filled once posts and pages are processed
+    wiki_index: WikiIndex,
+    /// Includes used by the Liquid in post bodies, recorded along with the
+    /// post's layouts once it's rendered
+    body_dependencies: HashMap<PathBuf, Vec<PathBuf>>,
 }
 
 impl SiteBuilder {
     pub fn new(source: PathBuf, destination: PathBuf, config: Config) -> Self {
         let markdown_processor = MarkdownProcessor::new();
-        let template_engine = TemplateEngine::new(source.clone());
+        let theme_dir = theme_dir(&source, &config);
+        let mut template_engine = TemplateEngine::new(source.clone());
+        template_engine.set_theme(theme_dir.clone());
+        template_engine.set_liquid_options(config.liquid.clone());
+        template_engine.set_seo_options(config.seo.clone());
+        template_engine.set_comments_options(config.comments.clone(), environment() == "production");
+        template_engine.set_template_language(config.template_engine);
+        let converters = ConverterRegistry::with_markdown(markdown_processor_for(&config));
         
         Self {
+            config_file: source.join("_config.yml"),
             source,
             destination,
             config,
             include_drafts: false,
+            incremental: false,
             markdown_processor,
+            converters,
             template_engine,
+            theme_dir,
+            excluded_dirs: Vec::new(),
+            previous_graph: None,
+            dependency_graph: DependencyGraph::new(),
+            wiki_index: WikiIndex::default(),
+            body_dependencies: HashMap::new(),
         }
     }
     
@@ -35,15 +99,87 @@ impl SiteBuilder {
         self.include_drafts = include;
     }
     
+    /// Skip a directory of the source when looking for pages, e.g. the real
+    /// output directory while building somewhere else
+    pub fn exclude_dir(&mut self, dir: PathBuf) {
+        self.excluded_dirs.push(dir);
+    }
+    
+    /// Location of _config.yml when it lives outside the source directory
+    pub fn set_config_file(&mut self, path: PathBuf) {
+        self.config_file = path;
+    }
+    
+    /// Register a converter for additional source formats (or to replace a built-in one)
+    pub fn register_converter<C: Converter + 'static>(&mut self, converter: C) {
+        self.converters.register(converter);
+    }
+    
+    /// Register a custom Liquid filter (or replace a built-in one)
+    pub fn register_filter<F: ParseFilter + 'static>(&mut self, filter: F) {
+        self.template_engine.register_filter(filter);
+    }
+    
+    /// Register a custom Liquid tag, e.g. a `{% youtube id %}` shortcode
+    pub fn register_tag<T: ParseTag + 'static>(&mut self, tag: T) {
+        self.template_engine.register_tag(tag);
+    }
+    
+    /// Register a custom Liquid block (`{% name %}...{% endname %}`)
+    pub fn register_block<B: ParseBlock + 'static>(&mut self, block: B) {
+        self.template_engine.register_block(block);
+    }
+    
+    /// Register extra Liquid variables, e.g. a `git` object or per-document values
+    pub fn register_context<P: ContextProvider + 'static>(&mut self, provider: P) {
+        self.template_engine.register_context(provider);
+    }
+    
+    /// Only re-render documents whose source, layouts or includes changed
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
+    
+    /// Time every layout and include, see [`profile_report`](Self::profile_report)
+    pub fn set_profile(&mut self, profile: bool) {
+        self.template_engine.set_profiling(profile);
+    }
+    
+    /// Render time per layout and include, collected when profiling
+    pub fn profile_report(&self) -> ProfileReport {
+        self.template_engine.profile_report()
+    }
+    
     /// Build the entire site
-    pub async fn build(&mut self) -> Result<()> {
+    pub async fn build(&mut self) -> Result<BuildSummary> {
         tracing::info!("Starting site build...");
+        let started = Instant::now();
+        let warnings = warnings_logged();
+        
+        // Keep concurrent builds of this site from clobbering each other
+        let _lock = BuildLock::acquire_async(
+            &self.source,
+            Duration::from_secs(self.config.build_lock_timeout),
+        )
+        .await?;
         
         // Create destination directory
         fs::create_dir_all(&self.destination)?;
         
+        // Cached includes and data may have changed since the last build
+        self.template_engine.clear_include_cache();
+        self.template_engine.clear_site();
+        
+        // Load the previous dependency graph for incremental builds
+        self.dependency_graph = DependencyGraph::new()
+            .with_fingerprint(cache_fingerprint(&self.config, &self.destination));
+        if self.incremental {
+            self.previous_graph = self.load_previous_graph();
+        }
+        
         // Collect all content
         let mut site = Site::new();
+        site.data = load_data(&self.source.join(DATA_DIR))?;
         
         // Process posts
         let posts_dir = self.source.join("_posts");
@@ -58,28 +194,77 @@ impl SiteBuilder {
             if drafts_dir.exists() {
                 tracing::info!("Processing drafts...");
                 let mut drafts = self.process_posts(&drafts_dir)?;
+                for draft in &mut drafts {
+                    draft.draft = true;
+                }
                 site.posts.append(&mut drafts);
             }
         }
         
-        // Sort posts by date (newest first)
-        site.posts.sort_by(|a, b| b.date.cmp(&a.date));
+        // Sort posts by date (newest first), pinned posts on top
+        sort_posts(&mut site.posts, self.config.pinned_first);
+        link_neighbours(&mut site.posts, site.time);
+        normalize_post_taxonomies(&mut site.posts, &self.config.taxonomy);
+        site.tag_cloud = tag_cloud(&site.posts, site.time, &self.config.taxonomy);
+        self.template_engine.set_post_urls(post_urls(&site.posts));
+        
+        // Post listings and neighbour links read the whole post set
+        self.dependency_graph.set_posts(posts_fingerprint(&site.posts));
+        if self.previous_graph.as_ref().is_some_and(|previous| previous.posts() != self.dependency_graph.posts()) {
+            tracing::info!("Posts changed, rebuilding everything");
+            self.previous_graph = None;
+        }
         
         // Process pages
         tracing::info!("Processing pages...");
         site.pages = self.process_pages()?;
+        self.template_engine.set_document_urls(self.document_urls(&site));
+        self.template_engine.check_related(&site)?;
+        if self.config.wikilinks {
+            self.resolve_wikilinks(&mut site);
+        }
+        paginate(&mut site, &self.config);
+        site.feed_posts = feed_posts(&site, &self.config, |_| true);
+        generate_author_pages(&mut site, &self.config, &self.source, &self.template_engine);
         
         // Copy static files
         tracing::info!("Copying static files...");
-        self.copy_static_files()?;
+        self.copy_static_files(&mut site)?;
+        site.vendor = vendor_assets(&self.config.vendor, &self.source, &self.destination)?;
+        self.write_highlight_stylesheet()?;
+        self.write_bundles(&mut site)?;
+        self.template_engine.set_asset_urls(self.asset_urls(&site)?);
+        
+        // Site variables are built once for all documents, and again once
+        // post bodies are rendered so listings show them
+        self.template_engine.set_site(&site, &self.config);
+        self.render_post_bodies(&mut site)?;
+        self.template_engine.set_site(&site, &self.config);
         
         // Render all content
         tracing::info!("Rendering content...");
         self.render_posts(&site).await?;
         self.render_pages(&site).await?;
+        write_discovery_files(&site, &self.config, &self.destination)?;
+        self.write_custom_feed(&site)?;
+        write_author_feeds(&site, &self.config, &self.destination)?;
+        write_taxonomy_json(&site, &self.config, &self.destination)?;
+        write_header_files(&site, &self.config, &self.destination)?;
+
+        if self.incremental {
+            self.dependency_graph.save(&self.source)?;
+        }
+
+        self.write_build_id()?;
 
         tracing::info!("Build complete!");
-        Ok(())
+        Ok(BuildSummary {
+            posts: site.posts.len(),
+            pages: site.pages.len(),
+            static_files: site.static_files.len(),
+            warnings: warnings_logged() - warnings,
+            duration: started.elapsed(),
+        })
     }
     
     /// Process all posts in a directory
@@ -94,15 +279,13 @@ impl SiteBuilder {
                 continue;
             }
             
-            let ext = path.extension().and_then(|s| s.to_str());
-            if !matches!(ext, Some("md") | Some("markdown")) {
+            let Some(converter) = self.converters.for_path(&path) else {
                 continue;
-            }
+            };
             
             tracing::debug!("Processing post: {}", path.display());
             
-            let content = fs::read_to_string(&path)?;
-            let (front_matter, body) = self.markdown_processor.parse_front_matter(&content)?;
+            let (front_matter, body) = self.read_document(&path, converter)?;
             
             // Skip unpublished posts
             if !front_matter.published {
@@ -112,21 +295,38 @@ impl SiteBuilder {
             
             let mut post = Post::new(path.clone());
             post.front_matter = front_matter;
-            post.content = body.to_string();
+            post.content = body;
             
-            // Parse date from filename
-            if let Some(date) = post.parse_date_from_filename() {
+            // A front matter date carries the time of day, and wins over the filename
+            let date = post.front_matter.date.as_deref().and_then(parse_date);
+            if let Some(date) = date.or_else(|| post.parse_date_from_filename()) {
                 post.date = date;
             }
             
+            post.pinned = front_matter_flag(&post.front_matter, "pinned");
+            post.featured = front_matter_flag(&post.front_matter, "featured");
+            post.expires = front_matter_date(&post.front_matter, "expires");
+            post.sitemap_expires = front_matter_date(&post.front_matter, "sitemap_expires");
+            if let Some(slug) = post.front_matter.custom.get("slug").and_then(|v| v.as_str()) {
+                post.slug = slug.trim().to_string();
+            }
+            
             // Generate URL
             post.url = self.generate_post_url(&post);
+            post.id = post_id(&post.url, &post.slug);
+            check_portable_url(&post.path, &post.url);
             
-            // Render markdown to HTML
-            post.html = self.markdown_processor.render(&post.content)?;
+            // Convert to HTML, raw blocks staying masked until the Liquid
+            // pass in `render_post_bodies`
+            post.html = converter.convert(&post.content)?;
+            let html = restore_liquid_raw(&post.html);
+            self.extract_post_fields(&mut post, &html);
             
-            // Extract excerpt
-            post.excerpt = self.extract_excerpt(&post.html);
+            // Converted again when rendered, see `reload_html`
+            if self.config.low_memory {
+                post.content = String::new();
+                post.html = String::new();
+            }
             
             posts.push(post);
         }
@@ -134,14 +334,76 @@ impl SiteBuilder {
         Ok(posts)
     }
     
+    /// Excerpt, headings and social meta fallbacks of a post's HTML
+    fn extract_post_fields(&self, post: &mut Post, html: &str) {
+        let separator = excerpt_separator(&post.front_matter, &self.config);
+        post.excerpt = extract_excerpt(html, &self.config.excerpt, separator);
+        post.toc = toc_entries(html);
+        
+        if self.config.extract_image {
+            post.image = extract_first_image(html);
+        }
+        if self.config.description_length > 0 {
+            post.description = extract_description(&post.excerpt, self.config.description_length);
+        }
+    }
+    
+    /// Render the Liquid in post bodies, once every URL they can link to is known
+    ///
+    /// Listings and feeds then show the rendered bodies, and excerpts and
+    /// headings are taken again from them.
+    fn render_post_bodies(&mut self, site: &mut Site) -> Result<()> {
+        self.body_dependencies.clear();
+        for i in 0..site.posts.len() {
+            let post = &site.posts[i];
+            let rendered = has_liquid(&post.html);
+            let html = self.render_post_body(&post.html, post, site)?;
+            
+            let post = &mut site.posts[i];
+            if rendered {
+                let dependencies = self.template_engine.take_dependencies();
+                self.body_dependencies.insert(post.path.clone(), dependencies);
+                self.extract_post_fields(post, &html);
+            }
+            post.html = html;
+        }
+        Ok(())
+    }
+    
+    /// A post's HTML with its Liquid rendered and raw blocks restored
+    fn render_post_body(&self, html: &str, post: &Post, site: &Site) -> Result<String> {
+        let html = if has_liquid(html) {
+            self.template_engine.render_post_content(html, post, site, &self.config)?
+        } else {
+            html.to_string()
+        };
+        Ok(restore_liquid_raw(&html))
+    }
+    
     /// Process all pages (non-post content)
     fn process_pages(&mut self) -> Result<Vec<Page>> {
         let mut pages = Vec::new();
         
+        // The destination, theme and excluded directories may live anywhere
+        // inside the source
+        let skipped: Vec<PathBuf> = [Some(&self.destination), self.theme_dir.as_ref()]
+            .into_iter()
+            .flatten()
+            .chain(&self.excluded_dirs)
+            .filter_map(|dir| dir.canonicalize().ok())
+            .collect();
+        let is_skipped = |entry: &walkdir::DirEntry| {
+            entry.file_type().is_dir()
+                && entry
+                    .path()
+                    .canonicalize()
+                    .is_ok_and(|path| skipped.contains(&path))
+        };
+        
         for entry in WalkDir::new(&self.source)
             .follow_links(true)
             .into_iter()
-            .filter_entry(|e| !self.is_special_directory(e.path()))
+            .filter_entry(|e| !self.is_special_directory(e.path()) && !is_skipped(e))
         {
             let entry = entry?;
             let path = entry.path();
@@ -155,10 +417,9 @@ impl SiteBuilder {
                 continue;
             }
             
-            let ext = path.extension().and_then(|s| s.to_str());
-            if !matches!(ext, Some("md") | Some("markdown") | Some("html")) {
+            let Some(converter) = self.converters.for_path(path) else {
                 continue;
-            }
+            };
             
             // Skip posts directories
             if path.starts_with(self.source.join("_posts"))
@@ -169,21 +430,27 @@ impl SiteBuilder {
             
             tracing::debug!("Processing page: {}", path.display());
             
-            let content = fs::read_to_string(path)?;
-            let (front_matter, body) = self.markdown_processor.parse_front_matter(&content)?;
+            let (front_matter, body) = self.read_document(path, converter)?;
             
             let mut page = Page::new(path.to_path_buf());
             page.front_matter = front_matter;
-            page.content = body.to_string();
+            page.content = body;
 
             // Generate URL
             page.url = self.generate_page_url(&page);
+            check_portable_url(&page.path, &page.url);
+
+            page.date = page.front_matter.date.as_deref().and_then(parse_date);
 
             // Render content
-            if matches!(ext, Some("md") | Some("markdown")) {
-                page.html = self.markdown_processor.render(&page.content)?;
-            } else {
-                page.html = page.content.clone();
+            page.html = converter.convert(&page.content)?;
+            let separator = excerpt_separator(&page.front_matter, &self.config);
+            page.excerpt = extract_excerpt(&page.html, &self.config.excerpt, separator);
+            page.toc = toc_entries(&page.html);
+            
+            if self.config.low_memory {
+                page.content = String::new();
+                page.html = String::new();
             }
             
             pages.push(page);
@@ -192,12 +459,120 @@ impl SiteBuilder {
         Ok(pages)
     }
     
+    /// Render a `feed.xml` with front matter in the source over the generated feed
+    ///
+    /// The template sees the generated feed's entries as `site.feed.posts`
+    /// and gets no layout unless its front matter names one.
+    fn write_custom_feed(&self, site: &Site) -> Result<()> {
+        let path = self.source.join(FEED_FILE);
+        if !path.is_file() || self.config.is_excluded(&path) {
+            return Ok(());
+        }
+        let content = self.read_source(&path)?;
+        if !has_front_matter(&content) {
+            return Ok(());
+        }
+        
+        let (front_matter, body) = self.markdown_processor.parse_front_matter(&content)?;
+        let mut page = Page::new(path);
+        page.front_matter = front_matter;
+        page.url = FEED_FILE.to_string();
+        page.html = self.template_engine.render_page_content(body, &page, site, &self.config)?;
+        let xml = if page.front_matter.layout.is_some() {
+            self.template_engine.render_page(&page, site, &self.config)?
+        } else {
+            page.html
+        };
+        fs::write(self.destination.join(FEED_FILE), xml)?;
+        Ok(())
+    }
+    
+    /// Front matter and body of a source document
+    ///
+    /// Formats carrying their own front matter (notebooks are JSON objects)
+    /// aren't looked at for a YAML, TOML or JSON block.
+    fn read_document(&self, path: &Path, converter: &dyn Converter) -> Result<(FrontMatter, String)> {
+        let content = self.read_source(path)?;
+        if let Some(embedded) = converter.front_matter(&content)? {
+            return Ok((embedded, content));
+        }
+        let (front_matter, body) = self.markdown_processor.parse_front_matter(&content)?;
+        Ok((front_matter, body.to_string()))
+    }
+    
+    /// Convert a document again right before rendering it, in `low_memory` mode
+    ///
+    /// Generated pages without a source document of their own keep their HTML.
+    fn reload_html(&self, path: &Path) -> Result<Option<String>> {
+        if !self.config.low_memory || !path.is_file() {
+            return Ok(None);
+        }
+        let Some(converter) = self.converters.for_path(path) else {
+            return Ok(None);
+        };
+        let (_, body) = self.read_document(path, converter)?;
+        let html = converter.convert(&body)?;
+        Ok(Some(self.wiki_index.resolve(&html, Some(&self.relative_source_path(path)))))
+    }
+    
+    /// Point `[[...]]` links in posts and pages at the documents they name
+    fn resolve_wikilinks(&mut self, site: &mut Site) {
+        self.wiki_index = WikiIndex::new(site, &self.config.baseurl);
+        let documents = site
+            .posts
+            .iter_mut()
+            .map(|post| (&post.path, &mut post.html, &mut post.excerpt))
+            .chain(site.pages.iter_mut().map(|page| (&page.path, &mut page.html, &mut page.excerpt)));
+        for (path, html, excerpt) in documents {
+            let source = self.relative_source_path(path);
+            *html = self.wiki_index.resolve(html, Some(&source));
+            *excerpt = self.wiki_index.resolve(excerpt, None);
+        }
+    }
+    
+    /// Read a page or post, dropping its byte order mark if configured
+    fn read_source(&self, path: &Path) -> Result<String> {
+        let content = fs::read_to_string(path)?;
+        if self.config.strip_bom && content.starts_with('\u{feff}') {
+            return Ok(strip_bom(&content).to_string());
+        }
+        Ok(content)
+    }
+    
+    /// Output URLs of posts, pages and assets keyed by source path, for `{% link %}`
+    fn document_urls(&self, site: &Site) -> HashMap<String, String> {
+        let documents = site
+            .posts
+            .iter()
+            .map(|post| (&post.path, &post.url))
+            .chain(site.pages.iter().map(|page| (&page.path, &page.url)));
+        
+        let mut urls: HashMap<String, String> = documents
+            .map(|(path, url)| (self.source_key(path), public_url(url)))
+            .collect();
+        
+        // Assets are copied verbatim, so their URL is their path
+        for entry in WalkDir::new(self.source.join("assets")).into_iter().filter_map(|e| e.ok()) {
+            if entry.file_type().is_file() {
+                let key = self.source_key(entry.path());
+                urls.insert(key.clone(), format!("/{}", key));
+            }
+        }
+        
+        urls
+    }
+    
+    /// Path relative to the source with `/` separators
+    fn source_key(&self, path: &Path) -> String {
+        paths::relative_to(path, &self.source).unwrap_or_else(|| paths::to_url_path(path))
+    }
+    
     /// Check if a path is a special Jekyll directory
     fn is_special_directory(&self, path: &Path) -> bool {
         if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
             matches!(
                 name,
-                "_site" | "_layouts" | "_includes" | "_data" | "node_modules" | ".git"
+                "_site" | "_layouts" | "_includes" | "_data" | "node_modules" | ".git" | CACHE_DIR
             )
         } else {
             false
@@ -216,18 +591,22 @@ impl SiteBuilder {
         url = url.replace(":month", &post.date.format("%m").to_string());
         url = url.replace(":day", &post.date.format("%d").to_string());
         
-        // Extract title from filename
-        if let Some(filename) = post.path.file_stem().and_then(|s| s.to_str()) {
-            // Remove date prefix (YYYY-MM-DD-)
-            let title = filename
-                .split('-')
-                .skip(3)
-                .collect::<Vec<_>>()
-                .join("-");
-            url = url.replace(":title", &title);
+        if url.contains(":categories") {
+            let taxonomy = Taxonomy::new(&self.config.taxonomy);
+            let mut slugs: Vec<String> = Vec::new();
+            for category in &post.front_matter.categories {
+                let slug = taxonomy.slug(category);
+                if !slug.is_empty() && !slugs.contains(&slug) {
+                    slugs.push(slug);
+                }
+            }
+            url = url.replace(":categories", &slugs.join("/"));
+            while url.contains("//") {
+                url = url.replace("//", "/");
+            }
         }
         
-        url
+        url.replace(":title", &post.slug)
     }
     
     /// Generate URL for a page
@@ -240,41 +619,109 @@ impl SiteBuilder {
             return permalink.clone();
         }
         
-        let rel_path = page
-            .path
-            .strip_prefix(&self.source)
-            .unwrap_or(&page.path);
+        let rel_path = self.relative_source_path(&page.path);
+        let url = paths::to_url_path(&rel_path.with_extension("html"));
+        url.trim_start_matches('/').to_string()
+    }
+    
+    /// Copy static files (CSS, JS, images, etc.)
+    fn copy_static_files(&self, site: &mut Site) -> Result<()> {
+        // Theme assets first, so the site's own files replace them
+        let dest_assets = self.destination.join("assets");
+        for dir in self.theme_dir.iter().chain([&self.source]) {
+            let assets_dir = dir.join("assets");
+            if assets_dir.exists() {
+                self.copy_directory(&assets_dir, &dest_assets)?;
+            }
+        }
         
-        let url = rel_path.with_extension("html");
+        let roots: Vec<&Path> = self.theme_dir.iter().chain([&self.source]).map(PathBuf::as_path).collect();
+        site.static_files = static_files(&roots);
+        Ok(())
+    }
+    
+    /// Concatenate asset bundles and record their fingerprinted URLs
+    fn write_bundles(&mut self, site: &mut Site) -> Result<()> {
+        let roots: Vec<&Path> = self.theme_dir.iter().chain([&self.source]).map(PathBuf::as_path).collect();
+        let bundles = collect_bundles(&roots)?;
         
-        // Convert to string and make it web-friendly
-        url.to_string_lossy()
-            .replace("\\", "/")
-            .trim_start_matches('/')
-            .to_string()
+        // A new fingerprint changes the pages linking to the bundle
+        let changed = bundles.iter().any(|bundle| !self.destination.join(&bundle.output).exists());
+        if changed && self.previous_graph.take().is_some() {
+            tracing::info!("Asset bundles changed, rebuilding everything");
+        }
+        
+        for bundle in &bundles {
+            let path = self.destination.join(&bundle.output);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, &bundle.content)?;
+            tracing::debug!("Bundled {} -> {}", bundle.manifest, path.display());
+        }
+        site.bundles = bundles
+            .iter()
+            .map(|bundle| (bundle.manifest.clone(), bundle.url()))
+            .collect();
+        Ok(())
     }
     
-    /// Extract excerpt from HTML content
-    fn extract_excerpt(&self, html: &str) -> String {
-        // Simple excerpt: first paragraph or first 200 characters
-        if let Some(start) = html.find("<p>") {
-            if let Some(end) = html[start..].find("</p>") {
-                let excerpt = &html[start + 3..start + end];
-                return excerpt.to_string();
+    /// URLs of the copied assets and bundles for `asset_url`, keyed by source path
+    ///
+    /// With `fingerprint: filename`, each asset also gets a copy with its
+    /// content hash in the name, which its URL points to; the copy for the
+    /// asset's previous content is removed. Hashes are cached between builds,
+    /// see [`FingerprintCache`].
+    fn asset_urls(&self, site: &Site) -> Result<HashMap<String, String>> {
+        let mut urls: HashMap<String, String> = site.bundles.clone();
+        if self.config.fingerprint == Fingerprint::Off {
+            for file in &site.static_files {
+                urls.insert(file.path.trim_start_matches('/').to_string(), file.path.clone());
             }
+            return Ok(urls);
         }
         
-        html.chars().take(200).collect::<String>() + "..."
+        let mut hashes = FingerprintCache::load(&self.source);
+        for file in &site.static_files {
+            let path = file.path.trim_start_matches('/');
+            let output = self.destination.join(path);
+            let (hash, replaced) = hashes.hash(file, &output)?;
+            let url = if self.config.fingerprint == Fingerprint::Filename {
+                let hashed_name = |hash: &str| format!("{}-{}{}", file.basename, hash, file.extname);
+                let name = hashed_name(&hash);
+                let hashed = output.with_file_name(&name);
+                if !hashed.exists() {
+                    fs::copy(&output, &hashed)?;
+                }
+                let stale = replaced.map(|replaced| output.with_file_name(hashed_name(&replaced)));
+                if let Some(stale) = stale.filter(|stale| stale.exists()) {
+                    fs::remove_file(stale)?;
+                }
+                format!("{}{}", &file.path[..file.path.len() - file.name.len()], name)
+            } else {
+                format!("{}?v={}", file.path, hash)
+            };
+            urls.insert(path.to_string(), url);
+        }
+        hashes.save()?;
+        Ok(urls)
     }
     
-    /// Copy static files (CSS, JS, images, etc.)
-    fn copy_static_files(&self) -> Result<()> {
-        let assets_dir = self.source.join("assets");
-        if assets_dir.exists() {
-            let dest_assets = self.destination.join("assets");
-            self.copy_directory(&assets_dir, &dest_assets)?;
+    /// Write the stylesheet for class-based highlighting, unless the site has its own
+    fn write_highlight_stylesheet(&self) -> Result<()> {
+        let highlight = &self.config.highlight;
+        if highlight.options.mode == HighlightMode::Inline
+            || self.source.join(&highlight.stylesheet).exists()
+        {
+            return Ok(());
         }
         
+        let path = self.destination.join(&highlight.stylesheet);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, stylesheet(&highlight.options))?;
+        tracing::debug!("Wrote highlighting stylesheet: {}", path.display());
         Ok(())
     }
     
@@ -290,6 +737,9 @@ impl SiteBuilder {
             
             if path.is_dir() {
                 self.copy_directory(&path, &dest_path)?;
+            } else if is_manifest(&path) {
+                // Bundle manifests are written out by `write_bundles`
+                continue;
             } else {
                 fs::copy(&path, &dest_path)?;
                 tracing::debug!("Copied: {} -> {}", path.display(), dest_path.display());
@@ -299,20 +749,117 @@ impl SiteBuilder {
         Ok(())
     }
     
+    /// Write a unique, increasing build identifier into the destination
+    ///
+    /// Lets cache purgers and the live-reload client tell builds apart.
+    fn write_build_id(&self) -> Result<()> {
+        let build_id = chrono::Utc::now().timestamp_millis().to_string();
+        fs::write(self.destination.join(BUILD_ID_FILE), format!("{}\n", build_id))?;
+        tracing::debug!("Build id: {}", build_id);
+        Ok(())
+    }
+    
+    /// Load the previous dependency graph unless the config changed since
+    ///
+    /// The graph outlives the process, so `serve --incremental` picks up
+    /// where the last run left off.
+    fn load_previous_graph(&self) -> Option<DependencyGraph> {
+        let graph = DependencyGraph::load(&self.source)?;
+        if graph.fingerprint() != self.dependency_graph.fingerprint() {
+            tracing::info!("Build cache is from another configuration or jellrust version, rebuilding everything");
+            return None;
+        }
+        
+        let config_path = &self.config_file;
+        
+        if config_path.exists() && graph.changed_since_build(config_path) {
+            tracing::info!("Configuration changed, rebuilding everything");
+            return None;
+        }
+        
+        // Any document may read `site.data`, so data changes affect them all
+        let data_changed = WalkDir::new(self.source.join(DATA_DIR))
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_type().is_file() && graph.changed_since_build(entry.path()));
+        if data_changed {
+            tracing::info!("Data files changed, rebuilding everything");
+            return None;
+        }
+        
+        Some(graph)
+    }
+    
+    /// Path of a source document relative to the site source
+    fn relative_source_path(&self, path: &Path) -> PathBuf {
+        paths::relative_to(path, &self.source)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+    
+    /// Whether an incremental build can keep a document's existing output
+    ///
+    /// Kept documents carry their recorded dependencies into the new graph.
+    fn is_up_to_date(&mut self, source_path: &Path, output_path: &Path) -> bool {
+        let Some(previous) = &self.previous_graph else {
+            return false;
+        };
+        
+        let document = self.relative_source_path(source_path);
+        if !output_path.exists() || previous.is_stale(&self.source, &document) {
+            return false;
+        }
+        
+        let dependencies = previous.dependencies_of(&document);
+        self.dependency_graph.record(&document, dependencies);
+        true
+    }
+    
+    /// Store the layouts and includes used by the document just rendered
+    fn record_dependencies(&mut self, source_path: &Path) {
+        let mut dependencies = self.template_engine.take_dependencies();
+        dependencies.extend(self.body_dependencies.remove(source_path).unwrap_or_default());
+        let document = self.relative_source_path(source_path);
+        self.dependency_graph.record(&document, dependencies);
+    }
+    
     /// Render all posts with their layouts
     async fn render_posts(&mut self, site: &Site) -> Result<()> {
         for post in &site.posts {
             let output_path = self.destination.join(post.url.trim_start_matches('/'));
 
+            if self.is_up_to_date(&post.path, &output_path) {
+                tracing::debug!("Skipping unchanged post: {}", output_path.display());
+                continue;
+            }
+
             // Ensure parent directory exists
             if let Some(parent) = output_path.parent() {
                 fs::create_dir_all(parent)?;
             }
 
+            let reloaded;
+            let post = match self.reload_html(&post.path)? {
+                Some(html) => {
+                    reloaded = Post {
+                        html: self.render_post_body(&html, post, site)?,
+                        ..post.clone()
+                    };
+                    &reloaded
+                }
+                None => post,
+            };
+
             // Render with template
-            let html = self.template_engine.render_post(post, site, &self.config)?;
+            let mut html = self.template_engine.render_post(post, site, &self.config)?;
+
+            // Keep draft previews out of search engines
+            if post.draft {
+                html = mark_as_draft(&html, self.config.draft_banner);
+            }
 
-            fs::write(&output_path, html)?;
+            fs::write(&output_path, self.postprocess_html(html, &output_path))?;
+            self.record_dependencies(&post.path);
             tracing::debug!("Rendered post: {}", output_path.display());
         }
 
@@ -324,31 +871,676 @@ impl SiteBuilder {
         for page in &site.pages {
             let output_path = self.destination.join(page.url.trim_start_matches('/'));
 
+            if self.is_up_to_date(&page.path, &output_path) {
+                tracing::debug!("Skipping unchanged page: {}", output_path.display());
+                continue;
+            }
+
             // Ensure parent directory exists
             if let Some(parent) = output_path.parent() {
                 fs::create_dir_all(parent)?;
             }
 
+            let html = match self.reload_html(&page.path)? {
+                Some(html) => html,
+                None => page.html.clone(),
+            };
+            
             // Check if the page content contains Liquid templates
-            let processed_content = if page.html.contains("{{") || page.html.contains("{%") {
+            let processed_content = if has_liquid(&html) {
                 // Re-process through Liquid templating with full site data
-                self.template_engine.render_page_content(&page.html, page, site, &self.config)?
+                self.template_engine.render_page_content(&html, page, site, &self.config)?
             } else {
-                page.html.clone()
+                html
             };
 
             // Create a temporary page with the processed content for layout rendering
             let mut processed_page = page.clone();
-            processed_page.html = processed_content;
+            processed_page.html = restore_liquid_raw(&processed_content);
 
             // Render with template
             let html = self.template_engine.render_page(&processed_page, site, &self.config)?;
 
-            fs::write(&output_path, html)?;
+            fs::write(&output_path, self.postprocess_html(html, &output_path))?;
+            self.record_dependencies(&page.path);
             tracing::debug!("Rendered page: {}", output_path.display());
         }
 
         Ok(())
     }
+
+    /// Apply the configured fix-ups to a rendered document bound for an HTML file
+    fn postprocess_html(&self, mut html: String, output_path: &Path) -> String {
+        if !is_html_output(output_path) {
+            return html;
+        }
+        if self.config.charset_meta {
+            html = ensure_charset_meta(&html);
+        }
+        if self.config.rewrite_baseurl {
+            html = prefix_baseurl(&html, &self.config.baseurl);
+        }
+        html = add_image_attributes(
+            &html,
+            &self.config.images,
+            &self.config.baseurl,
+            &self.destination,
+            output_path,
+        );
+        format_html(&html, self.config.output)
+    }
+}
+
+
+/// Matches the `src` attribute of the first `<img>` tag
+static IMG_SRC: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<img\b[^>]*?\bsrc\s*=\s*["']([^"']+)["']"#).unwrap()
+});
+
+/// Matches an opening `<head>` tag
+static HEAD_OPEN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<head(\s[^>]*)?>").unwrap());
+
+/// Matches a `<meta charset>` or `http-equiv` content-type declaration
+static CHARSET_DECLARATION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)<meta\b[^>]*\bcharset\s*=").unwrap());
+
+/// Matches a URL attribute whose value starts with `/`
+static ROOT_RELATIVE_LINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(\b(?:href|src|action|poster)\s*=\s*["'])(/[^"']*)"#).unwrap()
+});
+
+/// Markdown processor with the site's extensions and highlighting, math,
+/// link, figure, wikilink and admonition settings
+///
+/// Links to the site's own `url` never count as external.
+fn markdown_processor_for(config: &Config) -> MarkdownProcessor {
+    let mut external_links = config.external_links.clone();
+    external_links.allow.extend(host(&config.url));
+    MarkdownProcessor::new()
+        .with_extensions(config.markdown_ext)
+        .with_highlight(config.highlight.options.clone())
+        .with_math(config.math)
+        .with_external_links(external_links)
+        .with_figures(config.figures)
+        .with_wikilinks(config.wikilinks)
+        .with_admonitions(config.admonitions)
+}
+
+/// Charset declaration added to HTML outputs
+const CHARSET_META: &str = r#"<meta charset="utf-8">"#;
+
+/// Whether converted content has Liquid markup left to render
+fn has_liquid(html: &str) -> bool {
+    html.contains("{{") || html.contains("{%")
+}
+
+/// Whether an output file is an HTML document
+fn is_html_output(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("html") | Some("htm")
+    )
+}
+
+/// Declare UTF-8 as the first element of `<head>` unless a charset is already declared
+///
+/// Documents without a `<head>` are left alone.
+fn ensure_charset_meta(html: &str) -> String {
+    let Some(head) = HEAD_OPEN.find(html) else {
+        return html.to_string();
+    };
+    if CHARSET_DECLARATION.is_match(html) {
+        return html.to_string();
+    }
+
+    let mut result = html.to_string();
+    result.insert_str(head.end(), CHARSET_META);
+    result
+}
+
+/// Put `baseurl` in front of root-relative links
+///
+/// Protocol-relative URLs (`//cdn...`) and links that already start with
+/// the base URL are left alone.
+fn prefix_baseurl(html: &str, baseurl: &str) -> String {
+    let baseurl = baseurl.trim_end_matches('/');
+    if baseurl.is_empty() {
+        return html.to_string();
+    }
+
+    ROOT_RELATIVE_LINK
+        .replace_all(html, |caps: &regex::Captures| {
+            let url = &caps[2];
+            let prefixed = url == baseurl
+                || url.strip_prefix(baseurl).is_some_and(|rest| rest.starts_with(['/', '?', '#']));
+            if url.starts_with("//") || prefixed {
+                caps[0].to_string()
+            } else {
+                format!("{}{}{}", &caps[1], baseurl, url)
+            }
+        })
+        .into_owned()
+}
+
+/// Parse a front matter date such as `2024-01-15` or `2024-01-15 10:30:00 +0100`
+///
+/// Dates without an offset are taken as UTC.
+pub fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    let date = date.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+        return Some(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = DateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S %z") {
+        return Some(date.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(date, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .ok()
+                .and_then(|day| day.and_hms_opt(0, 0, 0))
+        })
+        .map(|date| date.and_utc())
+}
+
+/// Find the source of the first image in rendered HTML
+fn extract_first_image(html: &str) -> Option<String> {
+    IMG_SRC.captures(html).map(|caps| caps[1].to_string())
+}
+
+/// Build a plain-text description of at most `max_len` characters
+///
+/// Truncation happens on a word boundary and is marked with an ellipsis.
+fn extract_description(html: &str, max_len: usize) -> Option<String> {
+    let text = strip_html(html);
+    (!text.is_empty()).then(|| truncate_text(&text, max_len))
+}
+
+/// Files `copy_static_files` copies from the `assets` directories of `roots`
+///
+/// Later roots replace files of earlier ones, as when copying. Sorted by path.
+fn static_files(roots: &[&Path]) -> Vec<StaticFile> {
+    let mut files = BTreeMap::new();
+    for root in roots {
+        let entries = WalkDir::new(root.join("assets")).follow_links(true).into_iter();
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if !entry.file_type().is_file() || is_manifest(entry.path()) {
+                continue;
+            }
+            let Some(path) = paths::relative_to(entry.path(), root) else {
+                continue;
+            };
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (basename, extname) = match name.rfind('.') {
+                Some(dot) if dot > 0 => (name[..dot].to_string(), name[dot..].to_string()),
+                _ => (name.clone(), String::new()),
+            };
+            let modified_time = entry
+                .metadata()
+                .ok()
+                .and_then(|metadata| metadata.modified().ok())
+                .map_or_else(Utc::now, DateTime::<Utc>::from);
+            let path = format!("/{}", path);
+            files.insert(path.clone(), StaticFile { path, name, basename, extname, modified_time });
+        }
+    }
+    files.into_values().collect()
+}
+
+/// Sort posts newest first, optionally moving pinned posts ahead of the rest
+fn sort_posts(posts: &mut [Post], pinned_first: bool) {
+    posts.sort_by(Post::newest_first);
+    if pinned_first {
+        // Stable sort keeps date order within pinned and unpinned posts
+        posts.sort_by_key(|p| !p.pinned);
+    }
+}
+
+/// Point each listed post at its older and newer neighbours, for `page.previous` and `page.next`
+///
+/// Follows date order even when pinned posts are sorted first.
+fn link_neighbours(posts: &mut [Post], now: DateTime<Utc>) {
+    let mut listed: Vec<usize> = (0..posts.len()).filter(|&i| !posts[i].is_expired(now)).collect();
+    listed.sort_by(|&a, &b| posts[a].newest_first(&posts[b]));
+
+    for post in posts.iter_mut() {
+        post.previous = None;
+        post.next = None;
+    }
+    for (position, &index) in listed.iter().enumerate() {
+        posts[index].next = position.checked_sub(1).map(|newer| listed[newer]);
+        posts[index].previous = listed.get(position + 1).copied();
+    }
+}
+
+/// Jekyll's `post.id`: the directory of the URL followed by the slug
+fn post_id(url: &str, slug: &str) -> String {
+    let path = url.trim_end_matches('/');
+    let dir = path[..path.rfind('/').unwrap_or(0)].trim_start_matches('/');
+    if dir.is_empty() {
+        format!("/{}", slug)
+    } else {
+        format!("/{}/{}", dir, slug)
+    }
+}
+
+/// Warn about output paths Windows can't create, so sites stay portable
+fn check_portable_url(source: &Path, url: &str) {
+    if let Some(name) = paths::reserved_name(url) {
+        tracing::warn!(
+            "{}: {} can't be written on Windows, where {:?} is a reserved name",
+            source.display(),
+            url,
+            name
+        );
+    }
+}
+
+/// Post URLs keyed by file name without extension, for `{% post_url %}`
+fn post_urls(posts: &[Post]) -> HashMap<String, String> {
+    posts
+        .iter()
+        .filter_map(|post| {
+            let name = post.path.file_stem()?.to_str()?;
+            Some((name.to_string(), post.url.clone()))
+        })
+        .collect()
+}
+
+/// Read a boolean flag such as `pinned: true` from custom front matter
+fn front_matter_flag(front_matter: &FrontMatter, key: &str) -> bool {
+    front_matter
+        .custom
+        .get(key)
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Read a date such as `expires: 2024-12-31` from custom front matter
+fn front_matter_date(front_matter: &FrontMatter, key: &str) -> Option<DateTime<Utc>> {
+    let value = front_matter.custom.get(key)?.as_str()?;
+    let date = parse_date(value);
+    if date.is_none() {
+        tracing::warn!("Ignoring invalid `{}:` date {:?}", key, value);
+    }
+    date
 }
 
+/// Meta tag injected into draft outputs
+const NOINDEX_META: &str = r#"<meta name="robots" content="noindex, nofollow">"#;
+
+/// Banner injected into draft outputs when `draft_banner` is enabled
+const DRAFT_BANNER: &str = r#"<div class="jellrust-draft-banner" style="background:#f39c12;color:#fff;padding:0.5rem;text-align:center;">Draft preview &mdash; not published</div>"#;
+
+/// Inject a `noindex` meta tag (and optionally a banner) into a rendered draft
+fn mark_as_draft(html: &str, with_banner: bool) -> String {
+    let mut result = html.to_string();
+
+    // Inject meta before </head> if present, otherwise prepend
+    match result.find("</head>") {
+        Some(pos) => result.insert_str(pos, NOINDEX_META),
+        None => result.insert_str(0, NOINDEX_META),
+    }
+
+    if with_banner {
+        // Inject banner right after the opening <body> tag if present
+        let body_end = result
+            .find("<body")
+            .and_then(|start| result[start..].find('>').map(|end| start + end + 1));
+        match body_end {
+            Some(pos) => result.insert_str(pos, DRAFT_BANNER),
+            None => result.push_str(DRAFT_BANNER),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_mark_as_draft_injects_noindex() {
+        let html = "<html><head><title>T</title></head><body><p>Hi</p></body></html>";
+        let result = mark_as_draft(html, false);
+
+        let meta_pos = result.find(NOINDEX_META).unwrap();
+        assert!(meta_pos < result.find("</head>").unwrap());
+        assert!(!result.contains("jellrust-draft-banner"));
+    }
+
+    #[test]
+    fn test_mark_as_draft_with_banner() {
+        let html = "<html><head></head><body class=\"x\"><p>Hi</p></body></html>";
+        let result = mark_as_draft(html, true);
+
+        let banner_pos = result.find("jellrust-draft-banner").unwrap();
+        assert!(banner_pos > result.find("<body class=\"x\">").unwrap());
+        assert!(banner_pos < result.find("<p>Hi</p>").unwrap());
+    }
+
+    #[test]
+    fn test_extract_first_image() {
+        let html = r#"<p>Intro</p><p><img alt="x" src="/img/a.png"> <img src="/img/b.png"></p>"#;
+        assert_eq!(extract_first_image(html), Some("/img/a.png".to_string()));
+        assert_eq!(extract_first_image("<p>No images</p>"), None);
+    }
+
+    #[test]
+    fn test_extract_description() {
+        let html = "Hello <em>wonderful</em>\n world of static sites";
+        assert_eq!(
+            extract_description(html, 100),
+            Some("Hello wonderful world of static sites".to_string())
+        );
+        assert_eq!(
+            extract_description(html, 18),
+            Some("Hello wonderful...".to_string())
+        );
+        assert_eq!(extract_description("<br>", 10), None);
+    }
+
+    #[test]
+    fn test_sort_posts_pinned_first() {
+        let post = |name: &str, day: u32, pinned: bool| {
+            let mut post = Post::new(PathBuf::from(name));
+            post.date = Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap();
+            post.pinned = pinned;
+            post
+        };
+        let mut posts = vec![
+            post("old-pinned", 1, true),
+            post("newest", 3, false),
+            post("middle", 2, false),
+        ];
+
+        sort_posts(&mut posts, true);
+        let order: Vec<_> = posts.iter().map(|p| p.path.to_str().unwrap()).collect();
+        assert_eq!(order, ["old-pinned", "newest", "middle"]);
+
+        sort_posts(&mut posts, false);
+        let order: Vec<_> = posts.iter().map(|p| p.path.to_str().unwrap()).collect();
+        assert_eq!(order, ["newest", "middle", "old-pinned"]);
+    }
+
+    #[test]
+    fn test_sort_posts_by_time_then_slug() {
+        let post = |name: &str, hour: u32, pinned: bool| {
+            let mut post = Post::new(PathBuf::from(format!("_posts/2024-01-15-{}.md", name)));
+            post.date = Utc.with_ymd_and_hms(2024, 1, 15, hour, 0, 0).unwrap();
+            post.pinned = pinned;
+            post
+        };
+        let mut posts = vec![
+            post("beta", 9, false),
+            post("evening", 18, false),
+            post("alpha", 9, false),
+            post("pinned", 6, true),
+        ];
+
+        sort_posts(&mut posts, true);
+        let order: Vec<String> = posts.iter().map(|post| post.slug.clone()).collect();
+        assert_eq!(order, ["pinned", "evening", "alpha", "beta"]);
+
+        // Neighbours follow date order, not pinning
+        link_neighbours(&mut posts, Utc::now());
+        let slug = |index: Option<usize>| index.map(|i| posts[i].slug.clone());
+        assert_eq!(slug(posts[0].next), Some("beta".to_string()));
+        assert_eq!(slug(posts[0].previous), None);
+        assert_eq!(slug(posts[1].next), None);
+        assert_eq!(slug(posts[2].previous), Some("beta".to_string()));
+        assert_eq!(slug(posts[3].previous), Some("pinned".to_string()));
+    }
+
+    #[test]
+    fn test_ensure_charset_meta() {
+        let html = "<html><HEAD lang=\"en\"><title>T</title></HEAD><body><header></header></body></html>";
+        assert_eq!(
+            ensure_charset_meta(html),
+            "<html><HEAD lang=\"en\"><meta charset=\"utf-8\"><title>T</title></HEAD><body><header></header></body></html>"
+        );
+
+        let declared = r#"<head><meta http-equiv="Content-Type" content="text/html; charset=utf-8"></head>"#;
+        assert_eq!(ensure_charset_meta(declared), declared);
+        assert_eq!(ensure_charset_meta("<header>Hi</header>"), "<header>Hi</header>");
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let expected = |s: &str| Some(s.parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(parse_date("2024-01-15"), expected("2024-01-15T00:00:00Z"));
+        assert_eq!(parse_date("2024-01-15 10:30"), expected("2024-01-15T10:30:00Z"));
+        assert_eq!(parse_date("2024-01-15 10:30:00 +0100"), expected("2024-01-15T09:30:00Z"));
+        assert_eq!(parse_date("2024-01-15T10:30:00+02:00"), expected("2024-01-15T08:30:00Z"));
+        assert_eq!(parse_date("soon"), None);
+    }
+    
+    #[test]
+    fn test_prefix_baseurl() {
+        let html = r#"<a href="/about/">A</a><img SRC='/img/a.png'><a href="/blog/x.html">X</a>
+<script src="//cdn.example.com/x.js"></script><a href="https://example.com/">E</a><a href="/blogroll/">R</a>"#;
+        assert_eq!(
+            prefix_baseurl(html, "/blog/"),
+            r#"<a href="/blog/about/">A</a><img SRC='/blog/img/a.png'><a href="/blog/x.html">X</a>
+<script src="//cdn.example.com/x.js"></script><a href="https://example.com/">E</a><a href="/blog/blogroll/">R</a>"#
+        );
+        assert_eq!(prefix_baseurl(html, ""), html);
+    }
+    
+    #[test]
+    fn test_post_id() {
+        assert_eq!(post_id("/2024/01/15/hello.html", "hello"), "/2024/01/15/hello");
+        assert_eq!(post_id("/blog/hello-world/", "hello-world"), "/blog/hello-world");
+        assert_eq!(post_id("/renamed.html", "hello"), "/hello");
+        assert_eq!(post_id("notes/x.html", "x"), "/notes/x");
+    }
+    
+    #[test]
+    fn test_mark_as_draft_without_head() {
+        let result = mark_as_draft("<p>Hi</p>", false);
+        assert!(result.starts_with(NOINDEX_META));
+    }
+
+    #[tokio::test]
+    async fn test_build_notebook_page() {
+        let dir = temp_site("notebook");
+        let notebook = r##"{
+            "metadata": { "front_matter": { "title": "Plotting" } },
+            "cells": [{ "cell_type": "markdown", "source": ["# Intro\n", "{{ page.title }}"] }]
+        }"##;
+        fs::write(dir.join("plot.ipynb"), notebook).unwrap();
+
+        let mut builder = SiteBuilder::new(dir.clone(), dir.join("_site"), Config::default());
+        builder.build().await.unwrap();
+        let html = fs::read_to_string(dir.join("_site/plot.html")).unwrap();
+        assert!(html.contains("<h1 id=\"intro\">Intro</h1>"));
+        assert!(html.contains("Plotting"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_low_memory_build() {
+        let dir = temp_site("low-memory");
+        fs::create_dir_all(dir.join("_posts")).unwrap();
+        fs::write(dir.join("about.md"), "# About\n\nAll about **us**").unwrap();
+        fs::write(
+            dir.join("_posts/2024-01-01-hello.md"),
+            "---\ntitle: Hello\n---\nFirst paragraph\n\nSecond *paragraph*",
+        )
+        .unwrap();
+
+        let config = Config {
+            url: "https://example.com".to_string(),
+            permalink: "/:year/:title.html".to_string(),
+            plugins: vec!["jekyll-feed".to_string()],
+            low_memory: true,
+            ..Config::default()
+        };
+        let mut builder = SiteBuilder::new(dir.clone(), dir.join("_site"), config);
+        builder.build().await.unwrap();
+
+        let page = fs::read_to_string(dir.join("_site/about.html")).unwrap();
+        assert!(page.contains("All about <strong>us</strong>"));
+        let post = fs::read_to_string(dir.join("_site/2024/hello.html")).unwrap();
+        assert!(post.contains("Second <em>paragraph</em>"));
+
+        let feed = fs::read_to_string(dir.join("_site").join(FEED_FILE)).unwrap();
+        assert!(feed.contains("<summary type=\"html\">First paragraph</summary>"));
+        assert!(!feed.contains("<content"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_post_url_in_post_body() {
+        let dir = temp_site("post-url-body");
+        fs::create_dir_all(dir.join("_posts")).unwrap();
+        fs::write(dir.join("_posts/2024-01-01-hello.md"), "---\ntitle: Hello\n---\nHi").unwrap();
+        fs::write(
+            dir.join("_posts/2024-02-01-sequel.md"),
+            "---\ntitle: Sequel\n---\nSee <a href=\"{% post_url 2024-01-01-hello %}\">the first one</a>, \
+            {% raw %}`{{ kept }}`{% endraw %}",
+        )
+        .unwrap();
+        fs::write(dir.join("index.md"), "{% for post in site.posts %}{{ post.excerpt }}{% endfor %}").unwrap();
+
+        let config = Config {
+            permalink: "/:year/:title.html".to_string(),
+            ..Config::default()
+        };
+        let mut builder = SiteBuilder::new(dir.clone(), dir.join("_site"), config.clone());
+        builder.build().await.unwrap();
+        let post = fs::read_to_string(dir.join("_site/2024/sequel.html")).unwrap();
+        assert!(post.contains("<a href=\"/2024/hello.html\">the first one</a>, <code>{{ kept }}</code>"));
+        let index = fs::read_to_string(dir.join("_site/index.html")).unwrap();
+        assert!(index.contains("<a href=\"/2024/hello.html\">"));
+
+        fs::remove_file(dir.join("_posts/2024-01-01-hello.md")).unwrap();
+        let mut builder = SiteBuilder::new(dir.clone(), dir.join("_site"), config);
+        let error = builder.build().await.unwrap_err();
+        assert!(format!("{:?}", error).contains("2024-01-01-hello"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_link_in_post_body() {
+        let dir = temp_site("link-body");
+        fs::create_dir_all(dir.join("_posts")).unwrap();
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        fs::write(dir.join("docs/intro.md"), "---\npermalink: /guide/\n---\nIntro").unwrap();
+        fs::write(
+            dir.join("_posts/2024-01-01-hello.md"),
+            "---\ntitle: Hello\n---\nRead <a href=\"{% link docs/intro.md %}\">the guide</a>",
+        )
+        .unwrap();
+
+        // Low memory posts are converted again, and get their Liquid pass, when rendered
+        for low_memory in [false, true] {
+            let config = Config {
+                permalink: "/:year/:title.html".to_string(),
+                low_memory,
+                ..Config::default()
+            };
+            let mut builder = SiteBuilder::new(dir.clone(), dir.join("_site"), config);
+            builder.build().await.unwrap();
+            let post = fs::read_to_string(dir.join("_site/2024/hello.html")).unwrap();
+            assert!(post.contains("Read <a href=\"/guide/\">the guide</a>"));
+        }
+
+        fs::remove_file(dir.join("docs/intro.md")).unwrap();
+        let config = Config {
+            permalink: "/:year/:title.html".to_string(),
+            ..Config::default()
+        };
+        let mut builder = SiteBuilder::new(dir.clone(), dir.join("_site"), config);
+        let error = builder.build().await.unwrap_err();
+        assert!(format!("{:?}", error).contains("docs/intro.md"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_incremental_build_rerenders_post_listings() {
+        let dir = temp_site("incremental-posts");
+        fs::create_dir_all(dir.join("_posts")).unwrap();
+        fs::write(
+            dir.join("index.md"),
+            "{% for post in site.posts %}[{{ post.title }}]{% endfor %}",
+        )
+        .unwrap();
+        fs::write(dir.join("_posts/2024-01-01-first.md"), "---\ntitle: First\n---\nHi").unwrap();
+
+        let config = Config {
+            permalink: "/:year/:title.html".to_string(),
+            ..Config::default()
+        };
+        let build = |dir: &PathBuf| {
+            let mut builder = SiteBuilder::new(dir.clone(), dir.join("_site"), config.clone());
+            builder.set_incremental(true);
+            builder
+        };
+        build(&dir).build().await.unwrap();
+        fs::write(dir.join("_posts/2024-02-01-second.md"), "---\ntitle: Second\n---\nHi").unwrap();
+        build(&dir).build().await.unwrap();
+
+        let html = fs::read_to_string(dir.join("_site/index.html")).unwrap();
+        assert!(html.contains("[Second][First]"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_filename_fingerprint_replaces_stale_copies() {
+        let dir = temp_site("fingerprint");
+        fs::create_dir_all(dir.join("assets")).unwrap();
+        fs::write(dir.join("assets/app.js"), "one()").unwrap();
+        fs::write(dir.join("index.html"), "---\n---\n{{ 'assets/app.js' | asset_url }}").unwrap();
+
+        let config = Config {
+            fingerprint: Fingerprint::Filename,
+            ..Config::default()
+        };
+        let build = || SiteBuilder::new(dir.clone(), dir.join("_site"), config.clone());
+        let hashed = |content: &str| format!("app-{}.js", crate::bundle::fingerprint(content));
+        build().build().await.unwrap();
+        assert!(dir.join("_site/assets").join(hashed("one()")).exists());
+        assert!(dir.join(CACHE_DIR).join("fingerprints.json").exists());
+
+        // Let the modification time move on
+        std::thread::sleep(Duration::from_millis(10));
+        fs::write(dir.join("assets/app.js"), "two()").unwrap();
+        build().build().await.unwrap();
+        assert!(!dir.join("_site/assets").join(hashed("one()")).exists());
+        assert!(dir.join("_site/assets").join(hashed("two()")).exists());
+        let index = fs::read_to_string(dir.join("_site/index.html")).unwrap();
+        assert!(index.contains(&format!("/assets/{}", hashed("two()"))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_static_files() {
+        let dir = temp_site("static");
+        fs::create_dir_all(dir.join("theme/assets/img")).unwrap();
+        fs::create_dir_all(dir.join("site/assets/img")).unwrap();
+        fs::create_dir_all(dir.join("site/assets/files")).unwrap();
+        fs::write(dir.join("theme/assets/img/logo.png"), "theme").unwrap();
+        fs::write(dir.join("theme/assets/main.bundle"), "css/a.css").unwrap();
+        fs::write(dir.join("site/assets/img/logo.png"), "site").unwrap();
+        fs::write(dir.join("site/assets/files/README"), "read me").unwrap();
+
+        let files = static_files(&[&dir.join("theme"), &dir.join("site")]);
+        let summary: Vec<(&str, &str, &str, &str)> = files
+            .iter()
+            .map(|file| (file.path.as_str(), file.name.as_str(), file.basename.as_str(), file.extname.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("/assets/files/README", "README", "README", ""),
+                ("/assets/img/logo.png", "logo.png", "logo", ".png"),
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Warnings logged since the process started, counted by [`WarningCounter`]
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// Tracing layer counting the warnings that get logged, for [`BuildSummary::warnings`]
+///
+/// Without it installed, summaries report no warnings.
+pub struct WarningCounter;
+
+impl<S: Subscriber> Layer<S> for WarningCounter {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() == Level::WARN {
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Warnings counted so far
+pub fn warnings_logged() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+/// What a build produced, printed once it's done
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuildSummary {
+    pub posts: usize,
+    /// Pages including generated ones, such as pagination and author pages
+    pub pages: usize,
+    pub static_files: usize,
+    /// Warnings logged during the build
+    pub warnings: usize,
+    pub duration: Duration,
+}
+
+impl fmt::Display for BuildSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {}, {}, {}, took {} ms",
+            count(self.posts, "post"),
+            count(self.pages, "page"),
+            count(self.static_files, "static file"),
+            count(self.warnings, "warning"),
+            self.duration.as_millis()
+        )
+    }
+}
+
+/// `1 post`, `2 posts`
+fn count(n: usize, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_line() {
+        let summary = BuildSummary {
+            posts: 12,
+            pages: 1,
+            static_files: 0,
+            warnings: 2,
+            duration: Duration::from_millis(412),
+        };
+        assert_eq!(summary.to_string(), "12 posts, 1 page, 0 static files, 2 warnings, took 412 ms");
+    }
+}
@@ -0,0 +1,291 @@
+use crate::config::Config;
+use crate::content::{Post, Site};
+use crate::discovery::public_url;
+use crate::error::Result;
+use jellrust_template::filters::{slugify, SlugifyMode};
+use chrono::{DateTime, Utc};
+use jellrust_types::{CloudTag, TaxonomyConfig};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Directory of the tag endpoints written with `taxonomy.json`
+pub const TAGS_DIR: &str = "tags";
+
+/// Directory of the category endpoints written with `taxonomy.json`
+pub const CATEGORIES_DIR: &str = "categories";
+
+/// Merges spellings of the same tag or category
+///
+/// Terms are compared by slug, so `JavaScript`, `javascript` and
+/// `Java Script` only differ in display. Aliases from `taxonomy.aliases`
+/// point further spellings (`js`) at a canonical term (`javascript`). A
+/// merged term is displayed as its alias target, or else as the first
+/// spelling seen.
+#[derive(Debug, Default)]
+pub struct Taxonomy {
+    /// Slug of an alias -> canonical term
+    aliases: HashMap<String, String>,
+    /// Slug of a term -> slug override from `taxonomy.slugs`
+    slugs: HashMap<String, String>,
+    /// Slug of a term -> display name
+    names: HashMap<String, String>,
+}
+
+impl Taxonomy {
+    pub fn new(config: &TaxonomyConfig) -> Self {
+        let aliases = config
+            .aliases
+            .iter()
+            .map(|(alias, canonical)| (term_key(alias), canonical.trim().to_string()))
+            .collect();
+        let slugs = config
+            .slugs
+            .iter()
+            .map(|(term, slug)| (term_key(term), slug.trim().to_string()))
+            .collect();
+
+        Self {
+            aliases,
+            slugs,
+            names: HashMap::new(),
+        }
+    }
+
+    /// Display name of a term
+    pub fn normalize(&mut self, term: &str) -> String {
+        let term = term.trim();
+        let key = term_key(term);
+        let (key, spelling) = match self.aliases.get(&key) {
+            Some(canonical) => (term_key(canonical), canonical.clone()),
+            None => (key, term.to_string()),
+        };
+
+        self.names.entry(key).or_insert(spelling).clone()
+    }
+
+    /// Normalize a list of terms, dropping the duplicates merging creates
+    pub fn normalize_all(&mut self, terms: &[String]) -> Vec<String> {
+        let mut normalized: Vec<String> = Vec::with_capacity(terms.len());
+        for term in terms {
+            let term = self.normalize(term);
+            if !term.is_empty() && !normalized.contains(&term) {
+                normalized.push(term);
+            }
+        }
+        normalized
+    }
+
+    /// URL slug of a term, honoring `taxonomy.slugs`
+    pub fn slug(&self, term: &str) -> String {
+        let key = term_key(term);
+        let key = match self.aliases.get(&key) {
+            Some(canonical) => term_key(canonical),
+            None => key,
+        };
+
+        match self.slugs.get(&key) {
+            Some(slug) => slug.clone(),
+            None => slugify(&key, SlugifyMode::Default, false),
+        }
+    }
+}
+
+/// Case-insensitive identity of a term; keeps punctuation so `c` and `c++` stay apart
+fn term_key(term: &str) -> String {
+    slugify(term, SlugifyMode::Pretty, false)
+}
+
+/// Normalize the tags and categories of every post
+pub fn normalize_post_taxonomies(posts: &mut [Post], config: &TaxonomyConfig) {
+    let mut tags = Taxonomy::new(config);
+    let mut categories = Taxonomy::new(config);
+
+    for post in posts {
+        post.front_matter.tags = tags.normalize_all(&post.front_matter.tags);
+        post.front_matter.categories = categories.normalize_all(&post.front_matter.categories);
+    }
+}
+
+/// Tags of the listed posts with their counts and weights, for `site.tag_cloud`
+///
+/// Tags are sorted by slug. Weights grow with the logarithm of the count,
+/// so a few very common tags don't squash all others into the lowest bucket.
+pub fn tag_cloud(posts: &[Post], now: DateTime<Utc>, config: &TaxonomyConfig) -> Vec<CloudTag> {
+    let taxonomy = Taxonomy::new(config);
+    let mut counts: BTreeMap<String, (String, usize)> = BTreeMap::new();
+    for post in posts.iter().filter(|post| !post.is_expired(now)) {
+        for tag in &post.front_matter.tags {
+            counts.entry(taxonomy.slug(tag)).or_insert_with(|| (tag.clone(), 0)).1 += 1;
+        }
+    }
+
+    let min = counts.values().map(|(_, count)| *count).min().unwrap_or_default();
+    let max = counts.values().map(|(_, count)| *count).max().unwrap_or_default();
+    counts
+        .into_iter()
+        .map(|(slug, (tag, count))| CloudTag {
+            weight: weight(count, min, max),
+            tag,
+            slug,
+            count,
+        })
+        .collect()
+}
+
+/// Bucket of `count` between the least and most used tags' counts
+fn weight(count: usize, min: usize, max: usize) -> usize {
+    if max <= min {
+        return 1;
+    }
+    let spread = (max as f64).ln() - (min as f64).ln();
+    let position = ((count as f64).ln() - (min as f64).ln()) / spread;
+    1 + (position * (CloudTag::WEIGHTS - 1) as f64).round() as usize
+}
+
+/// Write a JSON list of posts for every tag and category, when `taxonomy.json` is set
+///
+/// Lets client-side widgets fetch e.g. `/tags/rust.json` for "more like
+/// this" lists. Drafts and expired posts are left out.
+pub fn write_taxonomy_json(site: &Site, config: &Config, destination: &Path) -> Result<()> {
+    if !config.taxonomy.json {
+        return Ok(());
+    }
+
+    let taxonomy = Taxonomy::new(&config.taxonomy);
+    let posts: Vec<&Post> = site
+        .posts
+        .iter()
+        .filter(|post| !post.draft && !post.is_expired(site.time))
+        .collect();
+
+    write_listings(&destination.join(TAGS_DIR), &posts, config, &taxonomy, |post| &post.front_matter.tags)?;
+    write_listings(&destination.join(CATEGORIES_DIR), &posts, config, &taxonomy, |post| {
+        &post.front_matter.categories
+    })
+}
+
+/// Write `<dir>/<slug>.json` for every term `terms` returns for the posts
+fn write_listings(
+    dir: &Path,
+    posts: &[&Post],
+    config: &Config,
+    taxonomy: &Taxonomy,
+    terms: impl Fn(&Post) -> &Vec<String>,
+) -> Result<()> {
+    let mut listings: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
+    for post in posts {
+        for term in terms(post) {
+            listings
+                .entry(taxonomy.slug(term))
+                .or_default()
+                .push(post_summary(post, config));
+        }
+    }
+
+    for (slug, posts) in listings {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(format!("{}.json", slug)), serde_json::Value::Array(posts).to_string())?;
+    }
+    Ok(())
+}
+
+/// What a JSON endpoint lists about a post
+fn post_summary(post: &Post, config: &Config) -> serde_json::Value {
+    serde_json::json!({
+        "title": post.front_matter.title,
+        "url": format!("{}{}", config.baseurl.trim_end_matches('/'), public_url(&post.url)),
+        "date": post.date.to_rfc3339(),
+        "excerpt": post.excerpt,
+        "tags": post.front_matter.tags,
+        "categories": post.front_matter.categories,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+
+    fn config() -> TaxonomyConfig {
+        serde_yaml::from_str(
+            "aliases:\n  js: JavaScript\n  ECMAScript: javascript\nslugs:\n  c++: cpp\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_normalize_merges_case_and_aliases() {
+        let mut taxonomy = Taxonomy::new(&config());
+        let terms = ["Rust", "rust", "JS", "javascript", "Ecmascript", "C", "c++"].map(String::from);
+
+        assert_eq!(taxonomy.normalize_all(&terms), ["Rust", "JavaScript", "C", "c++"]);
+        assert_eq!(taxonomy.normalize("Machine Learning"), "Machine Learning");
+        assert_eq!(taxonomy.normalize("machine-learning"), "Machine Learning");
+    }
+
+    #[test]
+    fn test_slug() {
+        let taxonomy = Taxonomy::new(&config());
+        assert_eq!(taxonomy.slug("Machine Learning"), "machine-learning");
+        assert_eq!(taxonomy.slug("js"), "javascript");
+        assert_eq!(taxonomy.slug("C++"), "cpp");
+    }
+
+    #[test]
+    fn test_tag_cloud() {
+        let post = |tags: &str| {
+            let mut post = Post::new(std::path::PathBuf::from("_posts/2024-01-15-post.md"));
+            post.front_matter = serde_yaml::from_str(&format!("tags: [{}]", tags)).unwrap();
+            post
+        };
+        let mut posts: Vec<Post> = (0..8).map(|_| post("Rust")).collect();
+        posts.extend([post("Go, Web"), post("Go"), post("C++")]);
+        normalize_post_taxonomies(&mut posts, &config());
+
+        let cloud = tag_cloud(&posts, Utc::now(), &config());
+        let weights: Vec<(&str, &str, usize, usize)> = cloud
+            .iter()
+            .map(|tag| (tag.tag.as_str(), tag.slug.as_str(), tag.count, tag.weight))
+            .collect();
+        assert_eq!(
+            weights,
+            [("C++", "cpp", 1, 1), ("Go", "go", 2, 2), ("Rust", "rust", 8, 5), ("Web", "web", 1, 1)]
+        );
+        assert_eq!(weight(3, 3, 3), 1);
+    }
+
+    #[test]
+    fn test_write_taxonomy_json() {
+        let post = |name: &str, front_matter: &str| {
+            let mut post = Post::new(std::path::PathBuf::from(format!("_posts/2024-01-15-{}.md", name)));
+            post.url = format!("/2024/{}.html", name);
+            post.front_matter = serde_yaml::from_str(front_matter).unwrap();
+            post
+        };
+        let mut site = Site::new();
+        site.posts = vec![
+            post("one", "title: One\ntags: [Rust, C++]\ncategories: [Notes]"),
+            post("two", "title: Two\ntags: [rust]"),
+            post("draft", "tags: [Rust]"),
+        ];
+        site.posts[2].draft = true;
+
+        let dir = temp_site("taxonomy");
+        let mut config: Config =
+            serde_yaml::from_str("baseurl: /blog\ntaxonomy:\n  slugs:\n    c++: cpp").unwrap();
+        write_taxonomy_json(&site, &config, &dir).unwrap();
+        assert!(fs::read_dir(&dir).unwrap().next().is_none());
+
+        config.taxonomy.json = true;
+        write_taxonomy_json(&site, &config, &dir).unwrap();
+        let rust: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("tags/rust.json")).unwrap()).unwrap();
+        assert_eq!(rust.as_array().unwrap().len(), 2);
+        assert_eq!(rust[0]["url"], "/blog/2024/one.html");
+        assert_eq!(rust[1]["title"], "Two");
+        assert!(dir.join("tags/cpp.json").exists());
+        assert!(dir.join("categories/notes.json").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+use crate::config::Config;
+use crate::deps::CACHE_DIR;
+use std::path::{Path, PathBuf};
+
+/// Directory inside the cache holding downloaded themes
+pub const THEMES_DIR: &str = "themes";
+
+/// Directory of the configured `theme`
+///
+/// `theme` is a path (relative to the site source) or the name of a theme
+/// downloaded into `.jellrust-cache/themes/<name>`. A theme that can't be
+/// found is reported and ignored.
+pub fn theme_dir(source: &Path, config: &Config) -> Option<PathBuf> {
+    let theme = config.theme.as_deref()?.trim();
+    if theme.is_empty() {
+        return None;
+    }
+
+    let found = [source.join(theme), source.join(CACHE_DIR).join(THEMES_DIR).join(theme)]
+        .into_iter()
+        .find(|dir| dir.is_dir());
+    if found.is_none() {
+        tracing::warn!("Theme {:?} not found in the site or {}/{}", theme, CACHE_DIR, THEMES_DIR);
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+    use std::fs;
+
+    #[test]
+    fn test_theme_dir() {
+        let dir = temp_site("theme");
+        fs::create_dir_all(dir.join("themes/local")).unwrap();
+        fs::create_dir_all(dir.join(CACHE_DIR).join(THEMES_DIR).join("cached")).unwrap();
+
+        let config = |theme: &str| Config {
+            theme: Some(theme.to_string()),
+            ..Config::default()
+        };
+        assert_eq!(theme_dir(&dir, &config("themes/local")), Some(dir.join("themes/local")));
+        assert_eq!(
+            theme_dir(&dir, &config("cached")),
+            Some(dir.join(CACHE_DIR).join(THEMES_DIR).join("cached"))
+        );
+        assert_eq!(theme_dir(&dir, &config("missing")), None);
+        assert_eq!(theme_dir(&dir, &Config::default()), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
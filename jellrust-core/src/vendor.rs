@@ -0,0 +1,246 @@
+use crate::deps::CACHE_DIR;
+use crate::error::{Error, Result};
+use crate::http::AGENT;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use jellrust_types::{VendorAsset, VendoredAsset};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+/// Directory of the destination `vendor:` assets are written to
+pub const VENDOR_DIR: &str = "assets/vendor";
+
+/// Directory of the cache holding downloaded assets
+const CACHE_SUBDIR: &str = "vendor";
+
+/// Time a single download may take
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Serve the `vendor:` assets from `assets/vendor/` in the destination
+///
+/// Assets are downloaded once and kept in `.jellrust-cache/vendor/`,
+/// so later builds work offline. An asset that doesn't match its `integrity`
+/// fails the build rather than being served.
+pub fn vendor_assets(
+    assets: &[VendorAsset],
+    source: &Path,
+    destination: &Path,
+) -> Result<HashMap<String, VendoredAsset>> {
+    let mut vendored = HashMap::new();
+    for asset in assets {
+        let path = asset_path(asset)?;
+        let cached = source.join(CACHE_DIR).join(CACHE_SUBDIR).join(&path);
+
+        let content = match fs::read(&cached) {
+            Ok(content) if matches_integrity(asset, &content)? => content,
+            _ => {
+                let content = download(&asset.url, &cached)?;
+                if !matches_integrity(asset, &content)? {
+                    let _ = fs::remove_file(&cached);
+                    return Err(Error::Other(format!(
+                        "Integrity check failed for {}: expected {}, got {}",
+                        asset.url,
+                        asset.integrity.as_deref().unwrap_or_default(),
+                        integrity(strongest_algorithm(asset)?, &content)
+                    )));
+                }
+                content
+            }
+        };
+
+        let output = destination.join(VENDOR_DIR).join(&path);
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&output, &content)?;
+        tracing::debug!("Vendored {} -> {}", asset.url, output.display());
+
+        let asset = VendoredAsset {
+            url: format!("/{}/{}", VENDOR_DIR, path),
+            integrity: integrity(Algorithm::Sha256, &content),
+        };
+        vendored.insert(path, asset);
+    }
+    Ok(vendored)
+}
+
+/// Path of an asset under `assets/vendor/`
+fn asset_path(asset: &VendorAsset) -> Result<String> {
+    let path = match &asset.path {
+        Some(path) => path.trim_matches('/').to_string(),
+        None => {
+            let url = asset.url.split(['?', '#']).next().unwrap_or_default();
+            url.rsplit('/').next().unwrap_or_default().to_string()
+        }
+    };
+
+    let portable = path.split('/').all(|segment| !matches!(segment, "" | "." | ".."));
+    if !portable || path.contains('\\') {
+        return Err(Error::Config(format!(
+            "vendor asset {} needs a `path` of the form dir/file.ext, not {:?}",
+            asset.url, path
+        )));
+    }
+    Ok(path)
+}
+
+/// Download `url` to `path`, returning the content
+fn download(url: &str, path: &Path) -> Result<Vec<u8>> {
+    tracing::info!("Downloading {}", url);
+    let response = AGENT
+        .get(url)
+        .timeout(DOWNLOAD_TIMEOUT)
+        .call()
+        .map_err(|e| Error::Other(format!("Failed to download {}: {}", url, e)))?;
+    let mut content = Vec::new();
+    response.into_reader().read_to_end(&mut content)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Written next to the cached file first, so an interrupted write isn't taken for a download
+    let partial = path.with_file_name(format!(
+        "{}.part",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    fs::write(&partial, &content)?;
+    fs::rename(&partial, path)?;
+    Ok(content)
+}
+
+/// Subresource Integrity hash algorithms, weakest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Algorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    /// Algorithm of a hash such as `sha384-...`
+    fn of(hash: &str) -> Option<Self> {
+        match hash.split_once('-')?.0 {
+            "sha256" => Some(Self::Sha256),
+            "sha384" => Some(Self::Sha384),
+            "sha512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    fn digest(self, content: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha256 => Sha256::digest(content).to_vec(),
+            Self::Sha384 => Sha384::digest(content).to_vec(),
+            Self::Sha512 => Sha512::digest(content).to_vec(),
+        }
+    }
+}
+
+/// Whether `content` matches the asset's `integrity`, if it has one
+///
+/// Like browsers, only hashes of the strongest algorithm listed count, and
+/// any one of those may match.
+fn matches_integrity(asset: &VendorAsset, content: &[u8]) -> Result<bool> {
+    let Some(expected) = &asset.integrity else {
+        return Ok(true);
+    };
+
+    let actual = integrity(strongest_algorithm(asset)?, content);
+    Ok(expected
+        .split_whitespace()
+        .any(|hash| hash.split('?').next() == Some(actual.as_str())))
+}
+
+/// Strongest algorithm among the asset's `integrity` hashes
+fn strongest_algorithm(asset: &VendorAsset) -> Result<Algorithm> {
+    asset
+        .integrity
+        .iter()
+        .flat_map(|expected| expected.split_whitespace())
+        .filter_map(Algorithm::of)
+        .max()
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "vendor asset {} has no sha256, sha384 or sha512 integrity hash",
+                asset.url
+            ))
+        })
+}
+
+/// Subresource Integrity hash of `content`
+fn integrity(algorithm: Algorithm, content: &[u8]) -> String {
+    format!("{}-{}", algorithm.name(), STANDARD.encode(algorithm.digest(content)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{http_server, temp_site};
+
+    fn asset(url: &str, path: Option<&str>, integrity: Option<&str>) -> VendorAsset {
+        VendorAsset {
+            url: url.to_string(),
+            path: path.map(String::from),
+            integrity: integrity.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_integrity() {
+        assert_eq!(integrity(Algorithm::Sha256, b"abc"), "sha256-ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=");
+        assert_eq!(
+            integrity(Algorithm::Sha384, b"abc"),
+            "sha384-ywB1P0WjXou1oD1pmsZQBycsMqsO3tFjGotgWkP/W+2AhgcroefMI1i67KE0yCWn"
+        );
+
+        let sha256 = "sha256-ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=";
+        let sha512 = integrity(Algorithm::Sha512, b"abc");
+        let check = |expected: &str| matches_integrity(&asset("x", None, Some(expected)), b"abc");
+        assert!(check(&format!("{} {}", sha256, sha512)).unwrap());
+        // A matching weaker hash doesn't vouch for content failing the stronger one
+        assert!(!check(&format!("{} sha384-AAAA", sha256)).unwrap());
+        assert!(check("md5-AAAA").is_err());
+    }
+
+    #[test]
+    fn test_asset_path() {
+        let path = |url, path| asset_path(&asset(url, path, None));
+        assert_eq!(path("https://cdn.example.com/lib/katex.min.js?v=1", None).unwrap(), "katex.min.js");
+        assert_eq!(path("https://cdn.example.com/a.css", Some("/katex/a.css")).unwrap(), "katex/a.css");
+        assert!(path("https://cdn.example.com/", None).is_err());
+        assert!(path("https://cdn.example.com/a.css", Some("../a.css")).is_err());
+    }
+
+    #[test]
+    fn test_vendor_assets() {
+        let dir = temp_site("vendor");
+        let url = format!("{}/lib.js", http_server(vec!["abc", "tampered"]));
+        let (source, destination) = (dir.join("site"), dir.join("site/_site"));
+
+        let good = asset(&url, Some("lib/lib.js"), Some("md5-xyz sha256-ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="));
+        let vendored = vendor_assets(&[good], &source, &destination).unwrap();
+        assert_eq!(vendored["lib/lib.js"].url, "/assets/vendor/lib/lib.js");
+        assert_eq!(fs::read_to_string(destination.join("assets/vendor/lib/lib.js")).unwrap(), "abc");
+
+        // Served from the cache once downloaded
+        assert!(vendor_assets(&[asset(&url, Some("lib/lib.js"), None)], &source, &destination).is_ok());
+
+        let bad = asset(&url, Some("lib/lib.js"), Some("sha256-AAAA"));
+        let error = vendor_assets(&[bad], &source, &destination).unwrap_err();
+        assert!(error.to_string().contains("Integrity check failed"));
+        assert!(!source.join(CACHE_DIR).join("vendor/lib/lib.js").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
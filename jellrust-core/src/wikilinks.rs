@@ -0,0 +1,147 @@
+use crate::content::Site;
+use crate::discovery::public_url;
+use jellrust_markdown::wikilink::WIKILINK_CLASS;
+use jellrust_template::filters::relative_url;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A link emitted for `[[Target]]`, capturing its `href` and target
+static WIKILINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r#"<a href="([^"]*)" class="{}" data-wikilink="([^"]*)">"#,
+        WIKILINK_CLASS
+    ))
+    .unwrap()
+});
+
+/// URLs of posts and pages keyed by what `[[...]]` may name them by: title,
+/// slug or file name
+#[derive(Debug, Default)]
+pub struct WikiIndex {
+    urls: HashMap<String, String>,
+}
+
+impl WikiIndex {
+    /// Index the site's posts, newest first, then its pages
+    ///
+    /// When two documents share a name, the first one indexed wins.
+    pub fn new(site: &Site, baseurl: &str) -> Self {
+        let mut urls = HashMap::new();
+        let mut add = |name: &str, url: &str| {
+            let key = wiki_key(name);
+            if !key.is_empty() {
+                urls.entry(key).or_insert_with(|| relative_url(baseurl, &public_url(url)));
+            }
+        };
+        for post in &site.posts {
+            if let Some(title) = &post.front_matter.title {
+                add(title, &post.url);
+            }
+            add(&post.slug, &post.url);
+        }
+        for page in &site.pages {
+            if let Some(title) = &page.front_matter.title {
+                add(title, &page.url);
+            }
+            let stem = page.path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+            if stem != "index" {
+                add(stem, &page.url);
+            }
+        }
+        Self { urls }
+    }
+
+    /// Point the wikilinks of rendered HTML at the documents they name
+    ///
+    /// Links to nothing get a `wikilink-missing` class; with a `source`, it
+    /// is warned about once per unknown name.
+    pub fn resolve(&self, html: &str, source: Option<&Path>) -> String {
+        if !html.contains(WIKILINK_CLASS) {
+            return html.to_string();
+        }
+        let mut warned: Vec<String> = Vec::new();
+        WIKILINK
+            .replace_all(html, |caps: &regex::Captures| {
+                let (fragment, target) = (&caps[1], &caps[2]);
+                if target.is_empty() {
+                    return caps[0].to_string();
+                }
+                match self.urls.get(&wiki_key(&unescape(target))) {
+                    Some(url) => format!(
+                        r#"<a href="{}{}" class="{}" data-wikilink="{}">"#,
+                        url, fragment, WIKILINK_CLASS, target
+                    ),
+                    None => {
+                        if let Some(source) = source.filter(|_| !warned.iter().any(|name| name == target)) {
+                            tracing::warn!("{}: no page or post named [[{}]]", source.display(), unescape(target));
+                            warned.push(target.to_string());
+                        }
+                        format!(
+                            r#"<a href="{}" class="{} wikilink-missing" data-wikilink="{}">"#,
+                            fragment, WIKILINK_CLASS, target
+                        )
+                    }
+                }
+            })
+            .into_owned()
+    }
+}
+
+/// `Getting_Started`, `getting-started` and `Getting  started` -> `getting started`
+fn wiki_key(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&quot;", "\"").replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::{Page, Post};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_resolve_wikilinks() {
+        let mut site = Site::new();
+        let mut post = Post::new(PathBuf::from("_posts/2024-01-15-getting-started.md"));
+        post.url = "/2024/01/15/getting-started.html".to_string();
+        post.front_matter.title = Some("Hello & Welcome".to_string());
+        site.posts.push(post);
+        let mut page = Page::new(PathBuf::from("garden/Rust_Notes.md"));
+        page.url = "/garden/rust-notes/index.html".to_string();
+        site.pages.push(page);
+
+        let index = WikiIndex::new(&site, "/blog");
+        let link = |href: &str, target: &str| {
+            format!(r#"<a href="{}" class="wikilink" data-wikilink="{}">x</a>"#, href, target)
+        };
+        let html = [
+            link("", "Getting Started"),
+            link("#setup", "rust notes"),
+            link("", "hello &amp; welcome"),
+            link("#top", ""),
+            link("", "Nowhere"),
+        ]
+        .concat();
+
+        assert_eq!(
+            index.resolve(&html, Some(Path::new("index.md"))),
+            [
+                link("/blog/2024/01/15/getting-started.html", "Getting Started"),
+                link("/blog/garden/rust-notes/#setup", "rust notes"),
+                link("/blog/2024/01/15/getting-started.html", "hello &amp; welcome"),
+                link("#top", ""),
+                r#"<a href="" class="wikilink wikilink-missing" data-wikilink="Nowhere">x</a>"#.to_string(),
+            ]
+            .concat()
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use once_cell::sync::Lazy;
+use pulldown_cmark::{html, CowStr, Event, Tag, TagEnd};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// `[!note] Optional title` on the rendered first line of a blockquote;
+/// Obsidian's fold markers (`+`/`-`) are accepted and ignored
+static BLOCKQUOTE_MARKER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)^\s*\[!([A-Za-z][\w-]*)\][+-]?\s*(.*)$").unwrap());
+
+/// `:::warning Optional title` opening a fenced admonition
+static FENCE_OPEN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*:::+\s*([A-Za-z][\w-]*)\s*(.*)$").unwrap());
+static FENCE_CLOSE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*:::+\s*$").unwrap());
+static CODE_FENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s{0,3}(```+|~~~+)").unwrap());
+
+/// Which callout syntax is rendered as `<div class="admonition note">`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdmonitionSyntax {
+    #[default]
+    Off,
+    /// `> [!note] Title`, as on GitHub and in Obsidian
+    Blockquote,
+    /// `:::note Title` ... `:::`
+    Fenced,
+    Both,
+}
+
+impl AdmonitionSyntax {
+    fn blockquote(self) -> bool {
+        matches!(self, Self::Blockquote | Self::Both)
+    }
+
+    fn fenced(self) -> bool {
+        matches!(self, Self::Fenced | Self::Both)
+    }
+}
+
+/// Opening markup of an admonition, given its title as HTML; the title
+/// defaults to the capitalized kind
+fn open_div(kind: &str, title: &str) -> String {
+    let kind = kind.to_ascii_lowercase();
+    let title = match title.trim() {
+        "" => {
+            let mut chars = kind.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+        title => title.to_string(),
+    };
+    format!(
+        "<div class=\"admonition {}\">\n<p class=\"admonition-title\">{}</p>\n",
+        kind, title
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Replace `:::kind` fences with admonition `<div>`s before parsing
+///
+/// Blank lines around the markup let the content in between still parse as
+/// Markdown. Fences inside code blocks are left alone; unclosed admonitions
+/// end with the document.
+pub(crate) fn render_fenced(markdown: &str, syntax: AdmonitionSyntax) -> String {
+    if !syntax.fenced() || !markdown.contains(":::") {
+        return markdown.to_string();
+    }
+
+    let mut output = String::with_capacity(markdown.len());
+    let mut code_fence: Option<String> = None;
+    let mut open = 0;
+    for line in markdown.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        if let Some(fence) = &code_fence {
+            if text.trim_start().starts_with(fence.as_str()) {
+                code_fence = None;
+            }
+        } else if let Some(caps) = CODE_FENCE.captures(text) {
+            code_fence = Some(caps[1].to_string());
+        } else if let Some(caps) = FENCE_OPEN.captures(text) {
+            output.push_str(&format!("\n{}\n", open_div(&caps[1], &escape(&caps[2]))));
+            open += 1;
+            continue;
+        } else if open > 0 && FENCE_CLOSE.is_match(text) {
+            output.push_str("\n</div>\n\n");
+            open -= 1;
+            continue;
+        }
+        output.push_str(line);
+    }
+    for _ in 0..open {
+        output.push_str("\n\n</div>\n");
+    }
+    output
+}
+
+/// Turn blockquotes starting with `[!kind]` into admonition `<div>`s
+pub(crate) fn render_blockquotes(
+    events: Vec<Event<'_>>,
+    syntax: AdmonitionSyntax,
+) -> Vec<Event<'_>> {
+    if !syntax.blockquote() {
+        return events;
+    }
+
+    let mut output = Vec::with_capacity(events.len());
+    // Whether each open blockquote became an admonition
+    let mut quotes: Vec<bool> = Vec::new();
+    let mut i = 0;
+    while i < events.len() {
+        match &events[i] {
+            Event::Start(Tag::BlockQuote(_)) => {
+                if let Some((html, consumed)) = admonition_start(&events[i..]) {
+                    output.push(Event::Html(CowStr::Boxed(html.into_boxed_str())));
+                    quotes.push(true);
+                    i += consumed;
+                    continue;
+                }
+                quotes.push(false);
+            }
+            Event::End(TagEnd::BlockQuote(_)) if quotes.pop() == Some(true) => {
+                output.push(Event::Html(CowStr::Borrowed("</div>\n")));
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        output.push(events[i].clone());
+        i += 1;
+    }
+    output
+}
+
+/// Admonition markup for a blockquote at the start of `events`, and how many
+/// events it replaces: the quote's start and its `[!kind]` line
+///
+/// The line is matched once rendered, as pulldown-cmark splits text at
+/// brackets and the title may hold inline markup.
+fn admonition_start(events: &[Event<'_>]) -> Option<(String, usize)> {
+    let [
+        Event::Start(Tag::BlockQuote(_)),
+        Event::Start(Tag::Paragraph),
+        rest @ ..,
+    ] = events
+    else {
+        return None;
+    };
+    if !matches!(rest.first(), Some(Event::Text(_))) {
+        return None;
+    }
+    let end = rest.iter().position(|event| {
+        matches!(
+            event,
+            Event::SoftBreak | Event::HardBreak | Event::End(TagEnd::Paragraph)
+        )
+    })?;
+    let mut line = String::new();
+    html::push_html(&mut line, rest[..end].iter().cloned());
+    let caps = BLOCKQUOTE_MARKER.captures(&line)?;
+    let html = open_div(&caps[1], caps[2].trim());
+
+    // The rest of the paragraph stays, unless the marker line was all of it
+    match rest[end] {
+        Event::End(TagEnd::Paragraph) => Some((html, end + 3)),
+        _ => Some((format!("{}<p>", html), end + 3)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MarkdownProcessor;
+
+    #[test]
+    fn test_blockquote_admonitions() {
+        let processor = MarkdownProcessor::new().with_admonitions(AdmonitionSyntax::Blockquote);
+        let html = processor
+            .render("> [!warning] Mind *the* gap\n> Text **here**\n> more\n\n> [!tip]\n>\n> Body\n\n> Plain quote")
+            .unwrap();
+
+        assert!(html.contains(
+            "<div class=\"admonition warning\">\n<p class=\"admonition-title\">Mind <em>the</em> gap</p>\n<p>Text <strong>here</strong>\nmore</p>\n</div>"
+        ));
+        assert!(html.contains("<div class=\"admonition tip\">\n<p class=\"admonition-title\">Tip</p>\n<p>Body</p>\n</div>"));
+        assert!(html.contains("<blockquote>\n<p>Plain quote</p>\n</blockquote>"));
+    }
+
+    #[test]
+    fn test_fenced_admonitions() {
+        let processor = MarkdownProcessor::new().with_admonitions(AdmonitionSyntax::Fenced);
+        let html = processor
+            .render(":::note\nSome *text*\n:::\n\n```\n:::danger\n```\n\n> [!tip]")
+            .unwrap();
+
+        assert!(html.contains(
+            "<div class=\"admonition note\">\n<p class=\"admonition-title\">Note</p>\n<p>Some <em>text</em></p>\n</div>"
+        ));
+        assert_eq!(html.matches("<div class=\"admonition").count(), 1);
+        assert!(html.contains(":::danger"));
+        assert!(html.contains("<blockquote>"));
+    }
+
+    #[test]
+    fn test_admonitions_off() {
+        let html = MarkdownProcessor::new()
+            .render(":::note\nx\n:::\n\n> [!tip]")
+            .unwrap();
+        assert!(!html.contains("admonition"));
+    }
+}
@@ -0,0 +1,84 @@
+use pulldown_cmark::Options;
+use serde::{Deserialize, Serialize};
+
+/// Markdown syntax extensions, set with `markdown_ext:` in _config.yml
+///
+/// Everything but heading attributes is on unless turned off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownExtensions {
+    /// `| a | b |` tables
+    pub tables: bool,
+
+    /// `[^1]` footnotes
+    pub footnotes: bool,
+
+    /// `~~struck~~` text
+    pub strikethrough: bool,
+
+    /// `- [x]` task list items
+    pub tasklists: bool,
+
+    /// `Term` / `: definition` lists
+    pub definition_lists: bool,
+
+    /// Curly quotes, en and em dashes and ellipses
+    pub smart_punctuation: bool,
+
+    /// `# Heading {#id .class}`
+    pub heading_attributes: bool,
+}
+
+impl Default for MarkdownExtensions {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            footnotes: true,
+            strikethrough: true,
+            tasklists: true,
+            definition_lists: true,
+            smart_punctuation: true,
+            heading_attributes: false,
+        }
+    }
+}
+
+impl MarkdownExtensions {
+    /// The pulldown-cmark options enabling these extensions
+    pub fn options(&self) -> Options {
+        let mut options = Options::empty();
+        options.set(Options::ENABLE_TABLES, self.tables);
+        options.set(Options::ENABLE_FOOTNOTES, self.footnotes);
+        options.set(Options::ENABLE_STRIKETHROUGH, self.strikethrough);
+        options.set(Options::ENABLE_TASKLISTS, self.tasklists);
+        options.set(Options::ENABLE_DEFINITION_LIST, self.definition_lists);
+        options.set(Options::ENABLE_SMART_PUNCTUATION, self.smart_punctuation);
+        options.set(Options::ENABLE_HEADING_ATTRIBUTES, self.heading_attributes);
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MarkdownProcessor;
+
+    #[test]
+    fn test_extensions() {
+        let extensions: MarkdownExtensions =
+            serde_yaml::from_str("tables: false\nsmart_punctuation: false\nheading_attributes: true").unwrap();
+        assert!(extensions.footnotes);
+
+        let processor = MarkdownProcessor::new().with_extensions(extensions);
+        let html = processor
+            .render("# Title {#top}\n\n\"Quoted\" -- ~~gone~~\n\n| a |\n|---|\n| b |")
+            .unwrap();
+
+        assert!(html.contains("<h1 id=\"top\">Title</h1>"));
+        assert!(html.contains("<p>\"Quoted\" -- <del>gone</del></p>"));
+        assert!(!html.contains("<table>"));
+
+        let html = MarkdownProcessor::new().render("\"Quoted\" -- x").unwrap();
+        assert!(html.contains("\u{201c}Quoted\u{201d} \u{2013} x"));
+    }
+}
@@ -0,0 +1,156 @@
+use pulldown_cmark::{html, CowStr, Event, LinkType, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+/// `external_links:` settings: attributes added to links pointing to other sites
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalLinks {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// `target` attribute, `_blank` by default; empty to leave it out
+    #[serde(default = "default_target")]
+    pub target: String,
+
+    /// `rel` attribute, `noopener noreferrer` by default; empty to leave it out
+    #[serde(default = "default_rel")]
+    pub rel: String,
+
+    /// Domains whose links are left alone, subdomains included
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+fn default_target() -> String {
+    "_blank".to_string()
+}
+
+fn default_rel() -> String {
+    "noopener noreferrer".to_string()
+}
+
+impl Default for ExternalLinks {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: default_target(),
+            rel: default_rel(),
+            allow: Vec::new(),
+        }
+    }
+}
+
+impl ExternalLinks {
+    /// Whether `url` points to a site outside the allowlist
+    pub fn is_external(&self, url: &str) -> bool {
+        let Some(host) = host(url) else {
+            return false;
+        };
+        !self.allow.iter().any(|domain| {
+            let domain = domain.trim().trim_start_matches("*.").to_ascii_lowercase();
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+    }
+
+    /// ` target="_blank" rel="..."`, as configured
+    fn attributes(&self) -> String {
+        let mut attributes = String::new();
+        for (name, value) in [("target", &self.target), ("rel", &self.rel)] {
+            if !value.trim().is_empty() {
+                attributes.push_str(&format!(" {}=\"{}\"", name, value.trim().replace('"', "&quot;")));
+            }
+        }
+        attributes
+    }
+}
+
+/// Lowercased host of an absolute `http(s)://` or protocol-relative URL
+pub fn host(url: &str) -> Option<String> {
+    let lower = url.trim().to_ascii_lowercase();
+    let rest = ["http://", "https://", "//"]
+        .iter()
+        .find_map(|prefix| lower.strip_prefix(prefix))?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// Give Markdown links to other sites the configured `target` and `rel`
+pub(crate) fn mark_external_links(events: &mut [Event<'_>], options: &ExternalLinks) {
+    if !options.enabled {
+        return;
+    }
+    let attributes = options.attributes();
+    let mut in_external = false;
+    for event in events.iter_mut() {
+        match event {
+            Event::Start(Tag::Link { link_type, dest_url, .. })
+                if *link_type != LinkType::Email && options.is_external(dest_url) =>
+            {
+                // Let pulldown-cmark escape the `href` and `title`
+                let mut tag = String::new();
+                html::push_html(&mut tag, std::iter::once(event.clone()));
+                let tag = format!("{}{}>", tag.trim_end_matches('>'), attributes);
+                *event = Event::InlineHtml(CowStr::Boxed(tag.into_boxed_str()));
+                in_external = true;
+            }
+            Event::End(TagEnd::Link) if in_external => {
+                *event = Event::InlineHtml(CowStr::Borrowed("</a>"));
+                in_external = false;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MarkdownProcessor;
+
+    use super::*;
+
+    #[test]
+    fn test_host() {
+        assert_eq!(host("https://User@Example.com:8080/a?b").as_deref(), Some("example.com"));
+        assert_eq!(host("//cdn.example.com").as_deref(), Some("cdn.example.com"));
+        assert_eq!(host("/about/"), None);
+        assert_eq!(host("mailto:a@example.com"), None);
+    }
+
+    #[test]
+    fn test_external_links() {
+        let options = ExternalLinks {
+            enabled: true,
+            allow: vec!["example.com".to_string()],
+            ..ExternalLinks::default()
+        };
+        let processor = MarkdownProcessor::new().with_external_links(options);
+        let html = processor
+            .render(concat!(
+                "[Rust](https://rust-lang.org \"The *Rust* site\") ",
+                "[docs](https://docs.example.com/) [home](/) <https://crates.io>",
+            ))
+            .unwrap();
+
+        assert!(html.contains(
+            r#"<a href="https://rust-lang.org" title="The *Rust* site" target="_blank" rel="noopener noreferrer">Rust</a>"#
+        ));
+        assert!(html.contains(r#"<a href="https://docs.example.com/">docs</a>"#));
+        assert!(html.contains(r#"<a href="/">home</a>"#));
+        assert!(html.contains(r#"<a href="https://crates.io" target="_blank" rel="noopener noreferrer">"#));
+    }
+
+    #[test]
+    fn test_external_links_rel_only() {
+        let options = ExternalLinks {
+            enabled: true,
+            target: String::new(),
+            rel: "nofollow".to_string(),
+            allow: Vec::new(),
+        };
+        let html = MarkdownProcessor::new()
+            .with_external_links(options)
+            .render("[a](http://a.org)")
+            .unwrap();
+        assert_eq!(html, "<p><a href=\"http://a.org\" rel=\"nofollow\">a</a></p>\n");
+    }
+}
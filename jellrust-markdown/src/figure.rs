@@ -0,0 +1,87 @@
+use pulldown_cmark::{html, CowStr, Event, Tag, TagEnd};
+
+/// Turn paragraphs holding nothing but an image with a title into
+/// `<figure><img ...><figcaption>title</figcaption></figure>`
+///
+/// Images inside running text stay `<img>` elements, as a figure can't be
+/// part of a paragraph.
+pub(crate) fn wrap_figures(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut i = 0;
+    while i < events.len() {
+        match figure(&events[i..]) {
+            Some((html, consumed)) => {
+                output.push(Event::Html(CowStr::Boxed(html.into_boxed_str())));
+                i += consumed;
+            }
+            None => {
+                output.push(events[i].clone());
+                i += 1;
+            }
+        }
+    }
+    output
+}
+
+/// Figure markup for a paragraph at the start of `events`, and the number of
+/// events it replaces
+fn figure(events: &[Event<'_>]) -> Option<(String, usize)> {
+    let [Event::Start(Tag::Paragraph), Event::Start(Tag::Image { link_type, dest_url, title, id }), rest @ ..] =
+        events
+    else {
+        return None;
+    };
+    if title.trim().is_empty() {
+        return None;
+    }
+    let end = rest.iter().position(|event| matches!(event, Event::End(TagEnd::Image)))?;
+    if !matches!(rest.get(end + 1), Some(Event::End(TagEnd::Paragraph))) {
+        return None;
+    }
+
+    // Render the image without its title, which becomes the caption
+    let image = Event::Start(Tag::Image {
+        link_type: *link_type,
+        dest_url: dest_url.clone(),
+        title: CowStr::Borrowed(""),
+        id: id.clone(),
+    });
+    let mut img = String::new();
+    html::push_html(
+        &mut img,
+        std::iter::once(image).chain(rest[..=end].iter().cloned()),
+    );
+
+    let html = format!(
+        "<figure>{}<figcaption>{}</figcaption></figure>\n",
+        img,
+        title.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+    );
+    // Paragraph start, image start, the image's events and both ends
+    Some((html, end + 4))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MarkdownProcessor;
+
+    #[test]
+    fn test_figures() {
+        let processor = MarkdownProcessor::new().with_figures(true);
+        let html = processor
+            .render("![A *cat*](/cat.jpg \"Our cat & dog\")\n\nText ![b](/b.png \"B\")\n\n![c](/c.png)")
+            .unwrap();
+
+        assert!(html.starts_with(
+            "<figure><img src=\"/cat.jpg\" alt=\"A cat\" /><figcaption>Our cat &amp; dog</figcaption></figure>\n"
+        ));
+        assert!(html.contains("<p>Text <img src=\"/b.png\" alt=\"b\" title=\"B\" /></p>"));
+        assert!(html.contains("<p><img src=\"/c.png\" alt=\"c\" /></p>"));
+    }
+
+    #[test]
+    fn test_figures_off() {
+        let html = MarkdownProcessor::new().render("![a](/a.png \"A\")").unwrap();
+        assert_eq!(html, "<p><img src=\"/a.png\" alt=\"a\" title=\"A\" /></p>\n");
+    }
+}
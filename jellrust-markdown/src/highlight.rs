@@ -0,0 +1,436 @@
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+use regex::Regex;
+use std::ops::RangeInclusive;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::{
+    append_highlighted_html_for_styled_line, css_for_theme_with_class_style, start_highlighted_html_snippet,
+    ClassStyle, ClassedHTMLGenerator, IncludeBackground,
+};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+pub(crate) static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+pub(crate) static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+/// Opening or closing `<span>` tag in highlighted HTML
+static SPAN_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<span[^>]*>|</span>").unwrap());
+
+/// Threads reserved for highlighting, so large code blocks don't hold up
+/// whatever else the caller runs on the global rayon pool
+static POOL: Lazy<ThreadPool> = Lazy::new(|| {
+    ThreadPoolBuilder::new()
+        .thread_name(|i| format!("jellrust-highlight-{}", i))
+        .build()
+        .expect("failed to start highlighting threads")
+});
+
+/// Theme used when neither the site nor the fence picks one
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Prefix of the CSS classes emitted in class mode
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// How highlighted code is colored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightMode {
+    /// Inline `style` attributes
+    #[default]
+    Inline,
+    /// CSS classes, styled by the stylesheet from [`stylesheet`]
+    Classes,
+    /// Rouge's short token classes (`<span class="k">`) in Jekyll's markup,
+    /// so Rouge and Pygments stylesheets apply; per-fence themes are ignored
+    Rouge,
+}
+
+/// Syntax highlighting settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighlightOptions {
+    /// Default theme, overridable per fence with ```` ```rust theme=github ````
+    #[serde(default = "default_theme")]
+    pub theme: String,
+
+    #[serde(default)]
+    pub mode: HighlightMode,
+}
+
+fn default_theme() -> String {
+    DEFAULT_THEME.to_string()
+}
+
+impl Default for HighlightOptions {
+    fn default() -> Self {
+        Self {
+            theme: default_theme(),
+            mode: HighlightMode::default(),
+        }
+    }
+}
+
+/// `key=value` attribute of a fence info string; values may be quoted
+static FENCE_ATTRIBUTE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"([\w-]+)=(?:"([^"]*)"|'([^']*)'|(\S+))"#).unwrap());
+
+/// Language and attributes of a fenced code block
+/// (```` ```rust title="main.rs" hl_lines="2-4" theme=github ````)
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct FenceInfo<'a> {
+    pub lang: &'a str,
+    pub theme: Option<&'a str>,
+    /// Caption shown above the code, usually a file name
+    pub title: Option<&'a str>,
+    /// Lines to emphasize, numbered from 1
+    pub hl_lines: Vec<RangeInclusive<usize>>,
+}
+
+impl<'a> FenceInfo<'a> {
+    pub fn parse(info: &'a str) -> Self {
+        let info = info.trim();
+        let lang = info.split_whitespace().next().filter(|word| !word.contains('=')).unwrap_or_default();
+        let mut fence = Self {
+            lang,
+            ..Self::default()
+        };
+        for caps in FENCE_ATTRIBUTE.captures_iter(&info[lang.len()..]) {
+            let value = caps.get(2).or(caps.get(3)).or(caps.get(4)).map_or("", |m| m.as_str());
+            match &caps[1] {
+                "theme" => fence.theme = Some(value),
+                "title" => fence.title = Some(value),
+                "hl_lines" => fence.hl_lines = parse_line_ranges(value),
+                _ => {}
+            }
+        }
+        fence
+    }
+}
+
+/// `2-4 7` or `2-4,7` -> `[2..=4, 7..=7]`; unparsable parts are skipped
+fn parse_line_ranges(value: &str) -> Vec<RangeInclusive<usize>> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|part| match part.split_once('-') {
+            Some((start, end)) => Some(start.trim().parse().ok()?..=end.trim().parse().ok()?),
+            None => part.parse().ok().map(|line| line..=line),
+        })
+        .collect()
+}
+
+/// Highlight fenced code blocks, given as `(code, info string)`, in parallel
+///
+/// Results are in input order; `None` means the block could not be highlighted.
+pub(crate) fn highlight_blocks(blocks: &[(String, String)], options: &HighlightOptions) -> Vec<Option<String>> {
+    match blocks {
+        [] => Vec::new(),
+        [(code, info)] => vec![highlight(code, info, options)],
+        _ => POOL.install(|| {
+            blocks
+                .par_iter()
+                .map(|(code, info)| highlight(code, info, options))
+                .collect()
+        }),
+    }
+}
+
+/// Highlight one code block
+pub(crate) fn highlight(code: &str, info: &str, options: &HighlightOptions) -> Option<String> {
+    let fence = FenceInfo::parse(info);
+    let syntax = SYNTAX_SET
+        .find_syntax_by_token(fence.lang)
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+
+    let html = match options.mode {
+        HighlightMode::Inline => {
+            let theme = fence
+                .theme
+                .and_then(find_theme)
+                .or_else(|| find_theme(&options.theme))
+                .unwrap_or_else(|| &THEME_SET.themes[DEFAULT_THEME]);
+            let mut highlighter = HighlightLines::new(syntax, theme);
+            let (pre, background) = start_highlighted_html_snippet(theme);
+            let mut body = String::new();
+            for line in LinesWithEndings::from(code) {
+                let regions = highlighter.highlight_line(line, &SYNTAX_SET).ok()?;
+                append_highlighted_html_for_styled_line(&regions, IncludeBackground::IfDifferent(background), &mut body)
+                    .ok()?;
+            }
+            // Without a stylesheet, emphasized lines take the theme's own color
+            let style = theme.settings.line_highlight.map(|c| {
+                format!(" style=\"background-color:#{:02x}{:02x}{:02x};\"", c.r, c.g, c.b)
+            });
+            let body = mark_lines(&body, &fence.hl_lines, style.as_deref().unwrap_or_default());
+            format!("{}{}</pre>\n", pre, body)
+        }
+        HighlightMode::Classes => {
+            let mut generator =
+                ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, CLASS_STYLE);
+            for line in LinesWithEndings::from(code) {
+                generator.parse_html_for_line_which_includes_newline(line).ok()?;
+            }
+
+            // The stylesheet colors overriding themes through a scoping class
+            let theme_class = fence
+                .theme
+                .and_then(theme_name)
+                .map(|name| format!(" highlight-{}", theme_slug(name)))
+                .unwrap_or_default();
+            let lang_class = if fence.lang.is_empty() {
+                String::new()
+            } else {
+                format!(" class=\"language-{}\"", fence.lang)
+            };
+            format!(
+                "<pre class=\"highlight{}\"><code{}>{}</code></pre>\n",
+                theme_class,
+                lang_class,
+                mark_lines(&generator.finalize(), &fence.hl_lines, "")
+            )
+        }
+        HighlightMode::Rouge => {
+            let body = crate::rouge::highlight(code, syntax)?;
+            let lang = if fence.lang.is_empty() { "plaintext" } else { fence.lang };
+            format!(
+                "<div class=\"language-{} highlighter-rouge\"><div class=\"highlight\"><pre class=\"highlight\"><code>{}</code></pre></div></div>\n",
+                lang,
+                mark_lines(&body, &fence.hl_lines, "")
+            )
+        }
+    };
+
+    Some(match fence.title {
+        Some(title) => format!(
+            "<figure class=\"code-block\"><figcaption class=\"code-title\">{}</figcaption>\n{}</figure>\n",
+            escape_html(title),
+            html
+        ),
+        None => html,
+    })
+}
+
+/// Wrap the given lines of highlighted code in `<span class="hll">`, the
+/// class Pygments and Rouge stylesheets give emphasized lines
+///
+/// Spans left open at the end of a line are closed there and reopened on the
+/// next one, so every line can be wrapped on its own.
+fn mark_lines(html: &str, lines: &[RangeInclusive<usize>], style: &str) -> String {
+    if lines.is_empty() {
+        return html.to_string();
+    }
+
+    let mut output = String::with_capacity(html.len());
+    // Opening tags of the spans still open
+    let mut open: Vec<&str> = Vec::new();
+    for (i, line) in html.split_inclusive('\n').enumerate() {
+        let marked = lines.iter().any(|range| range.contains(&(i + 1)));
+        let (text, newline) = match line.strip_suffix('\n') {
+            Some(text) => (text, "\n"),
+            None => (line, ""),
+        };
+
+        if marked {
+            output.push_str(&format!("<span class=\"hll\"{}>", style));
+        }
+        output.extend(open.iter().copied());
+        output.push_str(text);
+        for tag in SPAN_TAG.find_iter(text).map(|m| m.as_str()) {
+            if tag == "</span>" {
+                open.pop();
+            } else {
+                open.push(tag);
+            }
+        }
+        output.push_str(&"</span>".repeat(open.len()));
+        output.push_str(newline);
+        if marked {
+            output.push_str("</span>");
+        }
+    }
+    output
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Name of the bundled theme a user-supplied name refers to
+///
+/// Matching ignores case and punctuation and accepts partial names, so
+/// `github` finds `InspiredGitHub` and `solarized-light` finds
+/// `Solarized (light)`.
+fn theme_name(name: &str) -> Option<&'static str> {
+    let wanted = theme_slug(name);
+    let names = || THEME_SET.themes.keys().map(String::as_str);
+    let found = names()
+        .find(|candidate| theme_slug(candidate) == wanted)
+        .or_else(|| names().find(|candidate| theme_slug(candidate).contains(&wanted)));
+    if found.is_none() {
+        tracing::warn!("Unknown highlighting theme {:?}, using the default", name);
+    }
+    found
+}
+
+pub(crate) fn find_theme(name: &str) -> Option<&'static Theme> {
+    theme_name(name).map(|name| &THEME_SET.themes[name])
+}
+
+/// `Solarized (light)` -> `solarized-light`
+fn theme_slug(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Stylesheet for the class modes
+///
+/// In `classes` mode, the configured theme styles every `pre.highlight`;
+/// each other bundled theme is scoped to the `highlight-<theme>` class that
+/// per-fence overrides add. In `rouge` mode, it colors Rouge's token classes.
+pub fn stylesheet(options: &HighlightOptions) -> String {
+    if options.mode == HighlightMode::Rouge {
+        return crate::rouge::stylesheet(&options.theme);
+    }
+    let default = theme_name(&options.theme).unwrap_or(DEFAULT_THEME);
+    let mut css = scoped_css(default, ".highlight");
+    for name in THEME_SET.themes.keys().filter(|name| name.as_str() != default) {
+        css.push_str(&scoped_css(name, &format!(".highlight-{}", theme_slug(name))));
+    }
+    css
+}
+
+/// CSS of a bundled theme with every selector nested under `scope`
+///
+/// The theme's `.hl-code` rule (background and base color) applies to the
+/// scoping element itself.
+fn scoped_css(name: &str, scope: &str) -> String {
+    let css = css_for_theme_with_class_style(&THEME_SET.themes[name], CLASS_STYLE)
+        .unwrap_or_default();
+    let mut scoped = format!("/* {} */\n", name);
+    for line in css.lines() {
+        match line.strip_suffix('{') {
+            Some(selectors) if !line.starts_with(char::is_whitespace) => {
+                let selectors: Vec<String> = selectors
+                    .split(',')
+                    .map(|selector| match selector.trim() {
+                        ".hl-code" => scope.to_string(),
+                        selector => format!("{} {}", scope, selector),
+                    })
+                    .collect();
+                scoped.push_str(&format!("{} {{\n", selectors.join(", ")));
+            }
+            _ => {
+                scoped.push_str(line);
+                scoped.push('\n');
+            }
+        }
+    }
+    scoped.push_str(&line_highlight_css(&THEME_SET.themes[name], scope));
+    scoped
+}
+
+/// Rule coloring `hl_lines` emphasized lines under `scope` with the theme's
+/// line highlight color, if it has one
+pub(crate) fn line_highlight_css(theme: &Theme, scope: &str) -> String {
+    theme
+        .settings
+        .line_highlight
+        .map(|c| format!("{} .hll {{ background-color: #{:02x}{:02x}{:02x}; }}\n", scope, c.r, c.g, c.b))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fence_info() {
+        assert_eq!(
+            FenceInfo::parse("rust theme=github"),
+            FenceInfo {
+                lang: "rust",
+                theme: Some("github"),
+                ..FenceInfo::default()
+            }
+        );
+        assert_eq!(
+            FenceInfo::parse(r#"rust title="src/main file.rs" hl_lines="2-4 7""#),
+            FenceInfo {
+                lang: "rust",
+                title: Some("src/main file.rs"),
+                hl_lines: vec![2..=4, 7..=7],
+                ..FenceInfo::default()
+            }
+        );
+        assert_eq!(FenceInfo::parse("title=notes.txt").title, Some("notes.txt"));
+        assert_eq!(FenceInfo::parse("title=notes.txt").lang, "");
+        assert_eq!(parse_line_ranges("1,3-x,5"), vec![1..=1, 5..=5]);
+        assert_eq!(FenceInfo::parse("").lang, "");
+        assert_eq!(theme_name("github"), Some("InspiredGitHub"));
+        assert_eq!(theme_name("solarized-light"), Some("Solarized (light)"));
+        assert_eq!(theme_name("nope"), None);
+    }
+
+    #[test]
+    fn test_highlight_modes() {
+        let blocks = vec![
+            ("fn main() {}\n".to_string(), "rust".to_string()),
+            ("fn main() {}\n".to_string(), "rust theme=github".to_string()),
+        ];
+
+        let inline = highlight_blocks(&blocks, &HighlightOptions::default());
+        assert!(inline[0].as_ref().unwrap().contains("style=\""));
+        assert_ne!(inline[0], inline[1]);
+
+        let options = HighlightOptions {
+            mode: HighlightMode::Classes,
+            ..HighlightOptions::default()
+        };
+        let classed = highlight_blocks(&blocks, &options);
+        assert!(classed[0].as_ref().unwrap().starts_with("<pre class=\"highlight\"><code class=\"language-rust\">"));
+        assert!(classed[0].as_ref().unwrap().contains("hl-"));
+        assert!(classed[1].as_ref().unwrap().starts_with("<pre class=\"highlight highlight-inspiredgithub\">"));
+
+        let rouge = HighlightOptions {
+            mode: HighlightMode::Rouge,
+            ..HighlightOptions::default()
+        };
+        let html = highlight("x\n", "", &rouge).unwrap();
+        assert!(html.starts_with(
+            "<div class=\"language-plaintext highlighter-rouge\"><div class=\"highlight\"><pre class=\"highlight\"><code>"
+        ));
+
+        let css = stylesheet(&options);
+        assert!(css.contains("\n.highlight {"));
+        assert!(css.contains(".highlight .hl-"));
+        assert!(css.contains(".highlight-inspiredgithub .hl-"));
+        assert!(css.contains(".highlight .hll {"));
+    }
+
+    #[test]
+    fn test_title_and_highlighted_lines() {
+        let code = "/* a\nb */\nlet x = 1;\n";
+        for mode in [HighlightMode::Inline, HighlightMode::Classes, HighlightMode::Rouge] {
+            let options = HighlightOptions {
+                mode,
+                ..HighlightOptions::default()
+            };
+            let html = highlight(code, r#"rust title="a <b>.rs" hl_lines="2""#, &options).unwrap();
+            assert!(html.starts_with(
+                "<figure class=\"code-block\"><figcaption class=\"code-title\">a &lt;b&gt;.rs</figcaption>\n"
+            ));
+
+            // Only the second line is wrapped, and its spans are balanced
+            let start = html.find("<span class=\"hll\"").unwrap();
+            let end = html[start..].find("\n</span>").unwrap() + start;
+            let line = &html[start..end];
+            assert_eq!(html.matches("class=\"hll\"").count(), 1);
+            assert!(line.contains("*/") && !line.contains("let"));
+            assert_eq!(line.matches("<span").count(), line.matches("</span>").count() + 1);
+        }
+    }
+}
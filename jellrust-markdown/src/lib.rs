@@ -2,13 +2,25 @@ use anyhow::{Context, Result};
 use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use syntect::highlighting::ThemeSet;
-use syntect::html::highlighted_html_for_string;
-use syntect::parsing::SyntaxSet;
-use once_cell::sync::Lazy;
 
-static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
-static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+pub mod admonition;
+mod extensions;
+pub mod external;
+mod figure;
+pub mod highlight;
+pub mod liquid;
+pub mod math;
+mod rouge;
+pub mod smart;
+pub mod wikilink;
+
+pub use admonition::AdmonitionSyntax;
+pub use extensions::MarkdownExtensions;
+pub use external::ExternalLinks;
+pub use highlight::{HighlightMode, HighlightOptions};
+pub use liquid::restore_liquid_raw;
+pub use math::MathMode;
+pub use smart::smartify;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct FrontMatter {
@@ -48,106 +60,245 @@ fn default_true() -> bool {
     true
 }
 
+/// Whether a document opens with front matter [`MarkdownProcessor::parse_front_matter`]
+/// understands
+pub fn has_front_matter(content: &str) -> bool {
+    let trimmed = content.trim_start();
+    trimmed.starts_with("---") || trimmed.starts_with("+++") || starts_with_json_object(trimmed)
+}
+
+/// Text between an opening `fence` and the next line starting with it, and
+/// the body after that
+fn fenced<'a>(content: &'a str, fence: &str) -> Option<(&'a str, &'a str)> {
+    let rest = content.strip_prefix(fence)?;
+    let end_pos = rest.find(&format!("\n{}", fence))?;
+    Some((&rest[..end_pos], rest[end_pos + 1 + fence.len()..].trim_start()))
+}
+
+/// `{` followed by a key or `}`, which `{{ ... }}` and `{% ... %}` never are
+fn starts_with_json_object(content: &str) -> bool {
+    content
+        .strip_prefix('{')
+        .map(|rest| rest.trim_start().starts_with(['"', '}']))
+        .unwrap_or(false)
+}
+
+/// TOML front matter as the YAML values `FrontMatter` keeps; dates become
+/// strings, as unquoted YAML dates do
+fn toml_to_yaml(value: toml::Value) -> serde_yaml::Value {
+    match value {
+        toml::Value::String(s) => serde_yaml::Value::String(s),
+        toml::Value::Integer(i) => serde_yaml::Value::from(i),
+        toml::Value::Float(f) => serde_yaml::Value::from(f),
+        toml::Value::Boolean(b) => serde_yaml::Value::Bool(b),
+        toml::Value::Datetime(d) => serde_yaml::Value::String(d.to_string()),
+        toml::Value::Array(items) => serde_yaml::Value::Sequence(items.into_iter().map(toml_to_yaml).collect()),
+        toml::Value::Table(table) => serde_yaml::Value::Mapping(
+            table
+                .into_iter()
+                .map(|(key, value)| (serde_yaml::Value::String(key), toml_to_yaml(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Remove a leading UTF-8 byte order mark
+///
+/// Editors on Windows like to add one, and it hides the opening `---`.
+pub fn strip_bom(content: &str) -> &str {
+    content.strip_prefix('\u{feff}').unwrap_or(content)
+}
+
+#[derive(Clone)]
 pub struct MarkdownProcessor {
-    options: Options,
+    extensions: MarkdownExtensions,
+    highlight: HighlightOptions,
+    math: MathMode,
+    external_links: ExternalLinks,
+    figures: bool,
+    wikilinks: bool,
+    admonitions: AdmonitionSyntax,
 }
 
 impl MarkdownProcessor {
     pub fn new() -> Self {
-        let mut options = Options::empty();
-        options.insert(Options::ENABLE_STRIKETHROUGH);
-        options.insert(Options::ENABLE_TABLES);
-        options.insert(Options::ENABLE_FOOTNOTES);
-        options.insert(Options::ENABLE_TASKLISTS);
-        options.insert(Options::ENABLE_SMART_PUNCTUATION);
-        
-        Self { options }
+        Self {
+            extensions: MarkdownExtensions::default(),
+            highlight: HighlightOptions::default(),
+            math: MathMode::default(),
+            external_links: ExternalLinks::default(),
+            figures: false,
+            wikilinks: false,
+            admonitions: AdmonitionSyntax::default(),
+        }
+    }
+    
+    /// Processor highlighting code blocks with the given theme and mode
+    pub fn with_highlight(mut self, highlight: HighlightOptions) -> Self {
+        self.highlight = highlight;
+        self
+    }
+    
+    /// Processor parsing only the given syntax extensions
+    pub fn with_extensions(mut self, extensions: MarkdownExtensions) -> Self {
+        self.extensions = extensions;
+        self
+    }
+    
+    /// Processor rendering `$...$` and `$$...$$` math in the given mode
+    pub fn with_math(mut self, math: MathMode) -> Self {
+        self.math = math;
+        self
+    }
+    
+    /// Processor adding `target`/`rel` to links to other sites, if enabled
+    pub fn with_external_links(mut self, external_links: ExternalLinks) -> Self {
+        self.external_links = external_links;
+        self
+    }
+    
+    /// Processor turning standalone images with a title into figures
+    pub fn with_figures(mut self, figures: bool) -> Self {
+        self.figures = figures;
+        self
+    }
+    
+    /// Processor turning `[[Target]]` into links to be resolved by title or
+    /// slug, see [`wikilink`]
+    pub fn with_wikilinks(mut self, wikilinks: bool) -> Self {
+        self.wikilinks = wikilinks;
+        self
+    }
+    
+    /// Processor rendering callouts written in the given syntax as
+    /// `<div class="admonition kind">`
+    pub fn with_admonitions(mut self, admonitions: AdmonitionSyntax) -> Self {
+        self.admonitions = admonitions;
+        self
     }
     
     /// Parse front matter and content from a markdown file
+    ///
+    /// Front matter is YAML between `---` lines, TOML between `+++` lines or
+    /// a JSON object opening the file.
     pub fn parse_front_matter<'a>(&self, content: &'a str) -> Result<(FrontMatter, &'a str)> {
         let trimmed = content.trim();
         
-        // Check if content starts with ---
-        if !trimmed.starts_with("---") {
-            return Ok((FrontMatter::default(), content));
-        }
-        
-        // Find the ending ---
-        let rest = &trimmed[3..];
-        if let Some(end_pos) = rest.find("\n---") {
-            let yaml_content = &rest[..end_pos];
-            let body = &rest[end_pos + 4..].trim_start();
-            
-            // Parse YAML front matter
+        if let Some((yaml_content, body)) = fenced(trimmed, "---") {
             let front_matter: FrontMatter = serde_yaml::from_str(yaml_content)
                 .context("Failed to parse YAML front matter")?;
-            
-            Ok((front_matter, body))
-        } else {
-            // No closing ---, treat entire content as body
-            Ok((FrontMatter::default(), content))
+            return Ok((front_matter, body));
         }
+        
+        if let Some((toml_content, body)) = fenced(trimmed, "+++") {
+            let table: toml::Table = toml::from_str(toml_content)
+                .context("Failed to parse TOML front matter")?;
+            let front_matter: FrontMatter = serde_yaml::from_value(toml_to_yaml(toml::Value::Table(table)))
+                .context("Failed to parse TOML front matter")?;
+            return Ok((front_matter, body));
+        }
+        
+        if starts_with_json_object(trimmed) {
+            let mut stream = serde_json::Deserializer::from_str(trimmed).into_iter::<FrontMatter>();
+            let front_matter = stream
+                .next()
+                .context("Missing JSON front matter")?
+                .context("Failed to parse JSON front matter")?;
+            let body = trimmed[stream.byte_offset()..].trim_start();
+            return Ok((front_matter, body));
+        }
+        
+        // No front matter, or no closing fence: treat entire content as body
+        Ok((FrontMatter::default(), content))
     }
     
     /// Render Markdown to HTML
+    ///
+    /// Liquid `{% raw %}` regions come out masked; call [`restore_liquid_raw`]
+    /// once any Liquid pass over the HTML has run.
     pub fn render(&self, markdown: &str) -> Result<String> {
-        let parser = Parser::new_ext(markdown, self.options);
+        let markdown = liquid::protect_liquid(markdown);
+        let markdown = admonition::render_fenced(&markdown, self.admonitions);
+        let mut options = self.extensions.options();
+        options.set(Options::ENABLE_MATH, self.math != MathMode::Off);
+        let parser = Parser::new_ext(&markdown, options);
         let mut html_output = String::new();
         
         // Process events for syntax highlighting
-        let events = self.add_syntax_highlighting(parser);
+        let mut events = self.add_syntax_highlighting(parser);
+        add_heading_ids(&mut events);
+        math::render_math(&mut events, self.math);
+        external::mark_external_links(&mut events, &self.external_links);
+        if self.figures {
+            events = figure::wrap_figures(events);
+        }
+        if self.wikilinks {
+            events = wikilink::render_wikilinks(events);
+        }
+        events = admonition::render_blockquotes(events, self.admonitions);
         
         html::push_html(&mut html_output, events.into_iter());
         
-        Ok(html_output)
+        Ok(liquid::unmask_liquid_markup(&html_output))
     }
     
     /// Add syntax highlighting to code blocks
+    ///
+    /// Fenced blocks are collected first and highlighted together on the
+    /// highlighting thread pool.
     fn add_syntax_highlighting<'a>(
         &self,
         parser: Parser<'a>,
     ) -> Vec<Event<'a>> {
-        let mut events = Vec::new();
+        /// An event, or the place of the next fenced code block
+        enum Slot<'a> {
+            Event(Event<'a>),
+            Code,
+        }
+        
+        let mut slots = Vec::new();
+        let mut blocks: Vec<(String, String)> = Vec::new();
         let mut in_code_block = false;
-        let mut code_block_lang = String::new();
-        let mut code_block_content = String::new();
         
         for event in parser {
             match event {
-                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) => {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
                     in_code_block = true;
-                    code_block_lang = lang.to_string();
-                    code_block_content.clear();
+                    slots.push(Slot::Code);
+                    blocks.push((String::new(), info.to_string()));
                 }
-                Event::End(TagEnd::CodeBlock) => {
-                    if in_code_block {
-                        in_code_block = false;
-                        
-                        // Highlight the code
-                        if let Some(highlighted) = self.highlight_code(&code_block_content, &code_block_lang) {
-                            events.push(Event::Html(CowStr::Boxed(highlighted.into_boxed_str())));
-                        } else {
-                            // Fallback to plain code block - use owned string
-                            let lang_owned = CowStr::Boxed(code_block_lang.clone().into_boxed_str());
-                            events.push(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang_owned))));
-                            events.push(Event::Text(CowStr::Boxed(code_block_content.clone().into_boxed_str())));
-                            events.push(Event::End(TagEnd::CodeBlock));
-                        }
-                    } else {
-                        events.push(event);
-                    }
+                Event::End(TagEnd::CodeBlock) if in_code_block => {
+                    in_code_block = false;
                 }
-                Event::Text(text) => {
-                    if in_code_block {
-                        code_block_content.push_str(&text);
-                    } else {
-                        events.push(Event::Text(text));
+                Event::Text(text) if in_code_block => {
+                    if let Some((code, _)) = blocks.last_mut() {
+                        code.push_str(&text);
                     }
                 }
                 _ => {
                     if !in_code_block {
-                        events.push(event);
+                        slots.push(Slot::Event(event));
+                    }
+                }
+            }
+        }
+        
+        let mut highlighted = highlight::highlight_blocks(&blocks, &self.highlight).into_iter();
+        let mut blocks = blocks.into_iter();
+        let mut events = Vec::with_capacity(slots.len());
+        for slot in slots {
+            match slot {
+                Slot::Event(event) => events.push(event),
+                Slot::Code => {
+                    let (code, info) = blocks.next().unwrap_or_default();
+                    if let Some(Some(html)) = highlighted.next() {
+                        events.push(Event::Html(CowStr::Boxed(html.into_boxed_str())));
+                    } else {
+                        // Fallback to plain code block - use owned strings
+                        let info = CowStr::Boxed(info.into_boxed_str());
+                        events.push(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))));
+                        events.push(Event::Text(CowStr::Boxed(code.into_boxed_str())));
+                        events.push(Event::End(TagEnd::CodeBlock));
                     }
                 }
             }
@@ -155,16 +306,59 @@ impl MarkdownProcessor {
         
         events
     }
-    
-    /// Highlight code using syntect
-    fn highlight_code(&self, code: &str, lang: &str) -> Option<String> {
-        let syntax = SYNTAX_SET
-            .find_syntax_by_token(lang)
-            .or_else(|| Some(SYNTAX_SET.find_syntax_plain_text()))?;
-        
-        let theme = &THEME_SET.themes["base16-ocean.dark"];
-        
-        highlighted_html_for_string(code, &SYNTAX_SET, syntax, theme).ok()
+}
+
+/// Give headings an `id` derived from their text, as kramdown does
+///
+/// Lets `#anchor` links and the `toc` filter point at sections. Repeated
+/// headings get `-1`, `-2`, ... appended.
+fn add_heading_ids(events: &mut [Event<'_>]) {
+    let mut used: HashMap<String, usize> = HashMap::new();
+    let mut heading: Option<(usize, String)> = None;
+
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(Tag::Heading { id: None, .. }) => heading = Some((i, String::new())),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, title)) = &mut heading {
+                    title.push_str(text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let Some((start, title)) = heading.take() else {
+                    continue;
+                };
+                let mut id = heading_id(&title);
+                let count = used.entry(id.clone()).or_insert(0);
+                if *count > 0 {
+                    id = format!("{}-{}", id, count);
+                }
+                *count += 1;
+                if let Event::Start(Tag::Heading { id: slot, .. }) = &mut events[start] {
+                    *slot = Some(CowStr::Boxed(id.into_boxed_str()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `id` for a heading: lowercase words joined by `-`
+fn heading_id(title: &str) -> String {
+    let id: String = title
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            c if c.is_whitespace() => Some('-'),
+            _ => None,
+        })
+        .collect();
+    let id = id.trim_matches('-');
+    if id.is_empty() {
+        "section".to_string()
+    } else {
+        id.to_string()
     }
 }
 
@@ -201,10 +395,97 @@ This is content."#;
         let processor = MarkdownProcessor::new();
         let html = processor.render("# Hello\n\nThis is **bold**.").unwrap();
         
-        assert!(html.contains("<h1>"));
+        assert!(html.contains("<h1 id=\"hello\">"));
         assert!(html.contains("<strong>bold</strong>"));
     }
     
+    #[test]
+    fn test_heading_ids() {
+        let processor = MarkdownProcessor::new();
+        let html = processor
+            .render("## Getting `cargo` started!\n\n## Notes\n\n## Notes\n\n### ???")
+            .unwrap();
+        
+        assert!(html.contains("<h2 id=\"getting-cargo-started\">"));
+        assert!(html.contains("<h2 id=\"notes\">"));
+        assert!(html.contains("<h2 id=\"notes-1\">"));
+        assert!(html.contains("<h3 id=\"section\">"));
+    }
+    
+    #[test]
+    fn test_definition_list() {
+        let processor = MarkdownProcessor::new();
+        let html = processor
+            .render("Apple\n: A *fruit*\n\nOrange\n: A color\n: Also a fruit")
+            .unwrap();
+        
+        assert!(html.contains("<dl>"));
+        assert!(html.contains("<dt>Apple</dt>"));
+        assert!(html.contains("<dd>A <em>fruit</em></dd>"));
+        assert!(html.contains("<dd>Also a fruit</dd>"));
+    }
+    
+    #[test]
+    fn test_render_preserves_raw_in_code_block() {
+        let processor = MarkdownProcessor::new();
+        let html = processor
+            .render("{% raw %}\n```liquid\n{{ page.title }}\n```\n{% endraw %}")
+            .unwrap();
+        
+        assert!(!html.contains("{{"));
+        assert!(restore_liquid_raw(&html).contains("page"));
+        assert!(!restore_liquid_raw(&html).contains('\u{E000}'));
+    }
+    
+    #[test]
+    fn test_render_keeps_liquid_tag_arguments() {
+        let processor = MarkdownProcessor::new();
+        let html = processor
+            .render("{% include card.html title=\"Foo\" %} {% if a > b %}{{ my_var }}{% endif %}")
+            .unwrap();
+        
+        assert!(html.contains(r#"{% include card.html title="Foo" %}"#));
+        assert!(html.contains("{% if a > b %}{{ my_var }}{% endif %}"));
+    }
+    
+    #[test]
+    fn test_strip_bom() {
+        let content = "\u{feff}---\ntitle: Bom\n---\nBody";
+        let processor = MarkdownProcessor::new();
+        let (front_matter, body) = processor.parse_front_matter(strip_bom(content)).unwrap();
+        
+        assert_eq!(front_matter.title, Some("Bom".to_string()));
+        assert_eq!(body, "Body");
+        assert_eq!(strip_bom("plain"), "plain");
+    }
+    
+    #[test]
+    fn test_toml_and_json_front_matter() {
+        let processor = MarkdownProcessor::new();
+        
+        let toml = "+++\ntitle = \"Toml\"\ndate = 2024-01-01\ntags = [\"a\", \"b\"]\n[extra]\nkey = 1\n+++\n\n# Body";
+        let (front_matter, body) = processor.parse_front_matter(toml).unwrap();
+        assert_eq!(front_matter.title, Some("Toml".to_string()));
+        assert_eq!(front_matter.date, Some("2024-01-01".to_string()));
+        assert_eq!(front_matter.tags, vec!["a", "b"]);
+        assert_eq!(front_matter.custom["extra"]["key"], serde_yaml::Value::from(1));
+        assert_eq!(body, "# Body");
+        
+        let json = "{\n  \"title\": \"Json\",\n  \"published\": false,\n  \"toc\": true\n}\n\n# Body";
+        let (front_matter, body) = processor.parse_front_matter(json).unwrap();
+        assert_eq!(front_matter.title, Some("Json".to_string()));
+        assert!(!front_matter.published);
+        assert_eq!(front_matter.custom["toc"], serde_yaml::Value::Bool(true));
+        assert_eq!(body, "# Body");
+        
+        let liquid = "{% include header.html %}\n{{ page.title }}";
+        let (front_matter, body) = processor.parse_front_matter(liquid).unwrap();
+        assert_eq!(front_matter.title, None);
+        assert_eq!(body, liquid);
+        assert!(!has_front_matter(liquid));
+        assert!(processor.parse_front_matter("+++\ntitle = \n+++\n").is_err());
+    }
+    
     #[test]
     fn test_no_front_matter() {
         let content = "# Just content\n\nNo front matter here.";
@@ -0,0 +1,169 @@
+use once_cell::sync::Lazy;
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::Regex;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Matches `{% raw %}...{% endraw %}` blocks (with optional whitespace control)
+static RAW_BLOCK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)\{%-?\s*raw\s*-?%\}(.*?)\{%-?\s*endraw\s*-?%\}").unwrap()
+});
+
+/// Matches `{% comment %}...{% endcomment %}` blocks
+static COMMENT_BLOCK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)\{%-?\s*comment\s*-?%\}.*?\{%-?\s*endcomment\s*-?%\}").unwrap()
+});
+
+/// Matches Liquid output (`{{ ... }}`) and tag (`{% ... %}`) markup on one
+/// line, so a stray `{{` can't swallow the paragraphs up to the next `}}`
+static LIQUID_MARKUP: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{\{[^\n]*?\}\}|\{%[^\n]*?%\}").unwrap()
+});
+
+/// Characters inside Liquid markup that Markdown would otherwise rewrite
+/// (smart quotes, HTML escaping, emphasis), with their stand-ins.
+const MARKUP_SENTINELS: [(char, char); 6] = [
+    ('"', '\u{E010}'),
+    ('\'', '\u{E011}'),
+    ('<', '\u{E012}'),
+    ('>', '\u{E013}'),
+    ('_', '\u{E014}'),
+    ('*', '\u{E015}'),
+];
+
+/// Private-use characters standing in for Liquid delimiters inside raw blocks.
+///
+/// Single code points survive Markdown rendering and syntax highlighting even
+/// when the highlighter splits the surrounding text into separate spans.
+const SENTINELS: [(&str, char); 4] = [
+    ("{{", '\u{E000}'),
+    ("}}", '\u{E001}'),
+    ("{%", '\u{E002}'),
+    ("%}", '\u{E003}'),
+];
+
+/// Prepare Markdown source so Liquid markup survives rendering
+///
+/// Comment blocks are dropped, and the Liquid delimiters inside raw blocks are
+/// masked so neither the Markdown pass nor a later Liquid pass touches them.
+/// Call [`restore_liquid_raw`] on the final HTML to unmask them.
+///
+/// Characters inside the remaining Liquid markup are shielded from Markdown
+/// and must be unmasked with [`unmask_liquid_markup`] after rendering. Markup
+/// in code spans and blocks is left for Markdown to escape.
+pub fn protect_liquid(markdown: &str) -> Cow<'_, str> {
+    if !markdown.contains("{%") && !markdown.contains("{{") {
+        return Cow::Borrowed(markdown);
+    }
+
+    let without_comments = COMMENT_BLOCK.replace_all(markdown, "");
+    let protected = RAW_BLOCK.replace_all(&without_comments, |caps: &regex::Captures| {
+        let mut inner = caps[1].to_string();
+        for (delimiter, sentinel) in SENTINELS {
+            inner = inner.replace(delimiter, &sentinel.to_string());
+        }
+        inner
+    });
+    let code = code_ranges(&protected);
+    let shielded = LIQUID_MARKUP.replace_all(&protected, |caps: &regex::Captures| {
+        let start = caps.get(0).map_or(0, |m| m.start());
+        if code.iter().any(|range| range.contains(&start)) {
+            return caps[0].to_string();
+        }
+        caps[0]
+            .chars()
+            .map(|c| {
+                MARKUP_SENTINELS
+                    .iter()
+                    .find(|(original, _)| *original == c)
+                    .map_or(c, |(_, sentinel)| *sentinel)
+            })
+            .collect::<String>()
+    });
+
+    Cow::Owned(shielded.into_owned())
+}
+
+/// Byte ranges of code spans and blocks, whose Liquid markup Markdown
+/// escapes like any other code
+fn code_ranges(markdown: &str) -> Vec<Range<usize>> {
+    Parser::new(markdown)
+        .into_offset_iter()
+        .filter(|(event, _)| matches!(event, Event::Code(_) | Event::Start(Tag::CodeBlock(_))))
+        .map(|(_, range)| range)
+        .collect()
+}
+
+/// Undo the shielding of characters inside Liquid markup
+pub fn unmask_liquid_markup(html: &str) -> String {
+    html.chars()
+        .map(|c| {
+            MARKUP_SENTINELS
+                .iter()
+                .find(|(_, sentinel)| *sentinel == c)
+                .map_or(c, |(original, _)| *original)
+        })
+        .collect()
+}
+
+/// Turn masked raw-block delimiters back into literal Liquid syntax
+pub fn restore_liquid_raw(html: &str) -> String {
+    let mut result = html.to_string();
+    for (delimiter, sentinel) in SENTINELS {
+        result = result.replace(sentinel, delimiter);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MarkdownProcessor;
+
+    #[test]
+    fn test_protect_masks_raw_blocks() {
+        let protected = protect_liquid("a {% raw %}{{ page.title }}{% endraw %} b");
+        assert!(!protected.contains("{{"));
+        assert!(!protected.contains("raw"));
+        assert_eq!(restore_liquid_raw(&protected), "a {{ page.title }} b");
+    }
+
+    #[test]
+    fn test_protect_drops_comments() {
+        let protected = protect_liquid("a{% comment %}\n\nhidden\n\n{%- endcomment -%}b");
+        assert_eq!(protected, "ab");
+    }
+
+    #[test]
+    fn test_protect_shields_liquid_markup() {
+        let content = "{% if page.title %}{{ page.my_title | default: \"x\" }}{% endif %}";
+        let protected = protect_liquid(content);
+        assert!(!protected.contains('"'));
+        assert!(!protected.contains('_'));
+        assert_eq!(unmask_liquid_markup(&protected), content);
+    }
+
+    #[test]
+    fn test_protect_leaves_plain_markdown() {
+        let content = "Some *emphasis* and \"quotes\"";
+        assert_eq!(protect_liquid(content), content);
+    }
+
+    #[test]
+    fn test_shielding_stays_on_one_line() {
+        let html = MarkdownProcessor::new().render("A stray {{ here\n\n_emphasis_ }}").unwrap();
+        assert!(html.contains("<em>emphasis</em>"));
+    }
+
+    #[test]
+    fn test_code_is_not_shielded() {
+        let processor = MarkdownProcessor::new();
+        let html = processor.render("```\n{{ x | default: \"<b>\" }}\n```").unwrap();
+        assert!(html.contains("&lt;b&gt;"));
+        assert!(!html.contains("<b>"));
+
+        let html = processor.render("Inline `{{ \"<i>\" }}` and {{ \"<i>\" }}").unwrap();
+        assert!(html.contains("&lt;i&gt;"));
+        assert!(html.contains("and {{ \"<i>\" }}"));
+    }
+}
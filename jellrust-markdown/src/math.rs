@@ -0,0 +1,62 @@
+use pulldown_cmark::{CowStr, Event};
+use serde::{Deserialize, Serialize};
+
+/// How `$...$` and `$$...$$` math in Markdown is rendered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MathMode {
+    /// Dollar signs are plain text
+    #[default]
+    Off,
+    /// `<span class="math inline">\(...\)</span>` and
+    /// `<span class="math display">\[...\]</span>`, the delimiters KaTeX's
+    /// auto-render and MathJax pick up by default
+    Markup,
+}
+
+/// Replace the math events of an enabled [`MathMode`] with their markup
+///
+/// The TeX source is kept as written, apart from HTML escaping: smart
+/// punctuation and emphasis never reach it.
+pub(crate) fn render_math(events: &mut [Event<'_>], mode: MathMode) {
+    if mode == MathMode::Off {
+        return;
+    }
+    for event in events.iter_mut() {
+        let html = match event {
+            Event::InlineMath(tex) => format!(r#"<span class="math inline">\({}\)</span>"#, escape(tex)),
+            Event::DisplayMath(tex) => format!(r#"<span class="math display">\[{}\]</span>"#, escape(tex)),
+            _ => continue,
+        };
+        *event = Event::InlineHtml(CowStr::Boxed(html.into_boxed_str()));
+    }
+}
+
+fn escape(tex: &str) -> String {
+    tex.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MarkdownProcessor;
+
+    use super::*;
+
+    #[test]
+    fn test_math_markup() {
+        let processor = MarkdownProcessor::new().with_math(MathMode::Markup);
+        let html = processor
+            .render("Euler's \"identity\" $e^{i\\pi} + 1 = 0$ -- and\n\n$$\na < b -- c\n$$")
+            .unwrap();
+
+        assert!(html.contains(r#"Euler’s “identity” <span class="math inline">\(e^{i\pi} + 1 = 0\)</span> – and"#));
+        assert!(html.contains(r#"<span class="math display">\["#));
+        assert!(html.contains(r#"a &lt; b -- c"#));
+    }
+
+    #[test]
+    fn test_math_off() {
+        let html = MarkdownProcessor::new().render("Costs $5 -- or $x$").unwrap();
+        assert_eq!(html, "<p>Costs $5 – or $x$</p>\n");
+    }
+}
@@ -0,0 +1,212 @@
+use crate::highlight::{find_theme, line_highlight_css, DEFAULT_THEME, SYNTAX_SET, THEME_SET};
+use syntect::easy::ScopeRegionIterator;
+use syntect::highlighting::{Color, FontStyle, Highlighter};
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference};
+use syntect::util::LinesWithEndings;
+
+/// Scope prefixes and the Rouge/Pygments token class they map to
+///
+/// The first prefix a scope starts with wins, so more specific prefixes come
+/// first. The prefix also serves as the scope a theme is asked to color the
+/// class with in [`stylesheet`].
+const CLASSES: &[(&str, &str)] = &[
+    ("comment.line", "c1"),
+    ("comment.block", "cm"),
+    ("comment", "c"),
+    ("meta.preprocessor", "cp"),
+    ("meta.annotation", "nd"),
+    ("string.regexp", "sr"),
+    ("string.quoted.single", "s1"),
+    ("string.quoted.double", "s2"),
+    ("string", "s"),
+    ("constant.character.escape", "se"),
+    ("constant.character", "sc"),
+    ("constant.numeric.float", "mf"),
+    ("constant.numeric.integer.hexadecimal", "mh"),
+    ("constant.numeric.integer", "mi"),
+    ("constant.numeric", "m"),
+    ("constant.language", "kc"),
+    ("constant.other.symbol", "ss"),
+    ("constant", "no"),
+    ("keyword.operator.word", "ow"),
+    ("keyword.operator", "o"),
+    ("keyword.control.import", "kn"),
+    ("keyword.declaration", "kd"),
+    ("keyword", "k"),
+    ("storage.type.function", "kd"),
+    ("storage.modifier", "kd"),
+    ("storage.type", "kt"),
+    ("storage", "k"),
+    ("support.function.builtin", "nb"),
+    ("support.function", "nf"),
+    ("support.type", "kt"),
+    ("support.class", "nc"),
+    ("support.constant", "no"),
+    ("entity.name.function.decorator", "nd"),
+    ("entity.name.function", "nf"),
+    ("entity.name.class", "nc"),
+    ("entity.name.struct", "nc"),
+    ("entity.name.enum", "nc"),
+    ("entity.name.trait", "nc"),
+    ("entity.name.type", "nc"),
+    ("entity.name.namespace", "nn"),
+    ("entity.name.module", "nn"),
+    ("entity.name.tag", "nt"),
+    ("entity.name.label", "nl"),
+    ("entity.name.constant", "no"),
+    ("entity.name.section", "gh"),
+    ("entity.name", "n"),
+    ("entity.other.attribute-name", "na"),
+    ("entity.other.inherited-class", "nc"),
+    ("variable.parameter", "nv"),
+    ("variable.language", "bp"),
+    ("variable.function", "nf"),
+    ("variable.other.constant", "no"),
+    ("variable.other.member", "py"),
+    ("variable", "nv"),
+    ("markup.heading", "gh"),
+    ("markup.inserted", "gi"),
+    ("markup.deleted", "gd"),
+    ("markup.bold", "gs"),
+    ("markup.italic", "ge"),
+    ("invalid", "err"),
+    ("punctuation", "p"),
+];
+
+/// Token class of the innermost scope that has one
+///
+/// Delimiters of strings and comments take the class of what they delimit,
+/// as they do in Rouge.
+fn token_class(stack: &ScopeStack) -> Option<&'static str> {
+    stack.as_slice().iter().rev().find_map(|scope| {
+        let name = scope.build_string();
+        if name.starts_with("punctuation.definition.string") || name.starts_with("punctuation.definition.comment") {
+            return None;
+        }
+        CLASSES
+            .iter()
+            .find(|(prefix, _)| name == *prefix || name.starts_with(&format!("{}.", prefix)))
+            .map(|(_, class)| *class)
+    })
+}
+
+/// Highlight code with Rouge's `<span class="k">` token classes
+pub(crate) fn highlight(code: &str, syntax: &SyntaxReference) -> Option<String> {
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut html = String::new();
+    // Class of the open span; consecutive tokens of one class share it
+    let mut open: Option<&str> = None;
+
+    for line in LinesWithEndings::from(code) {
+        let ops = state.parse_line(line, &SYNTAX_SET).ok()?;
+        for (text, op) in ScopeRegionIterator::new(&ops, line) {
+            stack.apply(op).ok()?;
+            if text.is_empty() {
+                continue;
+            }
+            // Line breaks stay outside of spans
+            for (i, part) in text.split('\n').enumerate() {
+                if i > 0 {
+                    close_span(&mut html, &mut open);
+                    html.push('\n');
+                }
+                if part.is_empty() {
+                    continue;
+                }
+                let class = token_class(&stack);
+                if class != open {
+                    close_span(&mut html, &mut open);
+                    if let Some(class) = class {
+                        html.push_str(&format!("<span class=\"{}\">", class));
+                    }
+                    open = class;
+                }
+                html.push_str(&escape(part));
+            }
+        }
+    }
+    close_span(&mut html, &mut open);
+    Some(html)
+}
+
+fn close_span(html: &mut String, open: &mut Option<&str>) {
+    if open.take().is_some() {
+        html.push_str("</span>");
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Rouge-style stylesheet coloring the token classes with a bundled theme
+pub(crate) fn stylesheet(theme: &str) -> String {
+    let theme = find_theme(theme).unwrap_or_else(|| &THEME_SET.themes[DEFAULT_THEME]);
+    let highlighter = Highlighter::new(theme);
+    let base = highlighter.style_for_stack(&[]);
+
+    let mut css = format!(
+        ".highlight {{ color: {}; background-color: {}; }}\n",
+        hex(theme.settings.foreground.unwrap_or(base.foreground)),
+        hex(theme.settings.background.unwrap_or(base.background)),
+    );
+    let mut written: Vec<&str> = Vec::new();
+    for (prefix, class) in CLASSES {
+        if written.contains(class) {
+            continue;
+        }
+        written.push(class);
+
+        let Ok(scope) = Scope::new(prefix) else {
+            continue;
+        };
+        let style = highlighter.style_for_stack(&[scope]);
+        let mut rules = Vec::new();
+        if style.foreground != base.foreground {
+            rules.push(format!("color: {};", hex(style.foreground)));
+        }
+        if style.font_style.contains(FontStyle::BOLD) {
+            rules.push("font-weight: bold;".to_string());
+        }
+        if style.font_style.contains(FontStyle::ITALIC) {
+            rules.push("font-style: italic;".to_string());
+        }
+        if style.font_style.contains(FontStyle::UNDERLINE) {
+            rules.push("text-decoration: underline;".to_string());
+        }
+        if !rules.is_empty() {
+            css.push_str(&format!(".highlight .{} {{ {} }}\n", class, rules.join(" ")));
+        }
+    }
+    css.push_str(&line_highlight_css(theme, ".highlight"));
+    css
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rouge_classes() {
+        let syntax = SYNTAX_SET.find_syntax_by_token("rust").unwrap();
+        let html = highlight("// hi\nfn main() { let s = \"a<b\"; }\n", syntax).unwrap();
+
+        assert!(html.starts_with("<span class=\"c1\">// hi</span>\n"));
+        assert!(html.contains("<span class=\"nf\">main</span>"));
+        assert!(html.contains("<span class=\"s2\">\"a&lt;b\"</span>"));
+        assert!(!html.contains("style="));
+    }
+
+    #[test]
+    fn test_rouge_stylesheet() {
+        let css = stylesheet("InspiredGitHub");
+        assert!(css.starts_with(".highlight { color: #"));
+        assert!(css.contains(".highlight .c1 {"));
+        assert!(css.contains(".highlight .k {"));
+    }
+}
@@ -0,0 +1,72 @@
+use once_cell::sync::Lazy;
+use pulldown_cmark::{Event, Options, Parser};
+use regex::Regex;
+
+/// Ordered list marker at the start of a line (`1.`)
+static ORDERED_MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d{1,9})\.(\s|$)").unwrap());
+
+/// Apply the Markdown renderer's smart punctuation (curly quotes, en/em
+/// dashes, ellipses) to plain text, leaving everything else as written
+pub fn smartify(text: &str) -> String {
+    let mut output = text.lines().map(smartify_line).collect::<Vec<_>>().join("\n");
+    if text.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+fn smartify_line(line: &str) -> String {
+    let content = line.trim();
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let trailing = &line[indent.len() + content.len()..];
+
+    // A line of dashes would parse as a thematic break; leave it alone
+    if content.is_empty() || content.chars().all(|c| c == '-' || c == ' ') {
+        return line.to_string();
+    }
+
+    let escaped = escape_markdown(content);
+    let parser = Parser::new_ext(&escaped, Options::ENABLE_SMART_PUNCTUATION);
+    let mut output = String::from(indent);
+    for event in parser {
+        if let Event::Text(text) = event {
+            output.push_str(&text);
+        }
+    }
+    output.push_str(trailing);
+    output
+}
+
+/// Backslash-escape everything Markdown would interpret, except the
+/// characters smart punctuation works on
+fn escape_markdown(content: &str) -> String {
+    let mut escaped = String::with_capacity(content.len());
+    for (i, c) in content.char_indices() {
+        let list_marker = i == 0 && c == '-' && content[1..].starts_with(' ');
+        if list_marker || (c.is_ascii_punctuation() && !matches!(c, '\'' | '"' | '-' | '.')) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    ORDERED_MARKER.replace(&escaped, "$1\\.$2").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smartify_punctuation() {
+        assert_eq!(
+            smartify(r#"Use "Jekyll" --- the static generator... it's great -- mostly"#),
+            "Use \u{201c}Jekyll\u{201d} \u{2014} the static generator\u{2026} it\u{2019}s great \u{2013} mostly"
+        );
+    }
+
+    #[test]
+    fn test_smartify_leaves_markdown_syntax_alone() {
+        assert_eq!(smartify("*not* _emphasis_ <b>&amp;</b> [x](y)"), "*not* _emphasis_ <b>&amp;</b> [x](y)");
+        assert_eq!(smartify("- item\n# heading\n1. first\n---"), "- item\n# heading\n1. first\n---");
+        assert_eq!(smartify("    indented \"code\"  \n"), "    indented \u{201c}code\u{201d}  \n");
+    }
+}
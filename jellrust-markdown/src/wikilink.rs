@@ -0,0 +1,107 @@
+use crate::heading_id;
+use once_cell::sync::Lazy;
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use regex::Regex;
+
+/// `[[Target]]`, `[[Target|label]]` or `[[Target#Heading|label]]`
+static WIKILINK: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[\[([^\[\]|#]*)(?:#([^\[\]|]*))?(?:\|([^\[\]]*))?\]\]").unwrap());
+
+/// Class marking the links [`render_wikilinks`] emits
+pub const WIKILINK_CLASS: &str = "wikilink";
+
+/// Turn `[[...]]` in text into `<a class="wikilink" data-wikilink="Target">`
+///
+/// The target is resolved once every page and post is known, by replacing
+/// the link's `href`; until then it only holds the `#heading` fragment.
+pub(crate) fn render_wikilinks(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut text = String::new();
+    let mut in_code_block = false;
+    for event in events {
+        match event {
+            Event::Text(chunk) if !in_code_block => {
+                text.push_str(&chunk);
+                continue;
+            }
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            _ => {}
+        }
+        flush_text(&mut text, &mut output);
+        output.push(event);
+    }
+    flush_text(&mut text, &mut output);
+    output
+}
+
+/// Emit buffered text (which pulldown-cmark splits at brackets) with its
+/// wikilinks replaced
+fn flush_text(text: &mut String, output: &mut Vec<Event<'_>>) {
+    if text.is_empty() {
+        return;
+    }
+    let mut last = 0;
+    for caps in WIKILINK.captures_iter(text) {
+        let target = caps[1].trim();
+        let heading = caps.get(2).map(|m| m.as_str().trim()).filter(|h| !h.is_empty());
+        if target.is_empty() && heading.is_none() {
+            continue;
+        }
+
+        let whole = caps.get(0).expect("group 0 always matches");
+        if whole.start() > last {
+            output.push(Event::Text(CowStr::Boxed(text[last..whole.start()].into())));
+        }
+        let label = caps
+            .get(3)
+            .map(|m| m.as_str().trim())
+            .filter(|l| !l.is_empty())
+            .or(heading.filter(|_| target.is_empty()))
+            .unwrap_or(target);
+        let href = heading.map(|h| format!("#{}", heading_id(h))).unwrap_or_default();
+        let html = format!(
+            "<a href=\"{}\" class=\"{}\" data-wikilink=\"{}\">{}</a>",
+            href,
+            WIKILINK_CLASS,
+            escape(target),
+            escape(label)
+        );
+        output.push(Event::InlineHtml(CowStr::Boxed(html.into_boxed_str())));
+        last = whole.end();
+    }
+    if last < text.len() {
+        output.push(Event::Text(CowStr::Boxed(text[last..].into())));
+    }
+    text.clear();
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MarkdownProcessor;
+
+    #[test]
+    fn test_wikilinks() {
+        let processor = MarkdownProcessor::new().with_wikilinks(true);
+        let html = processor
+            .render("See [[Getting Started]], [[guide#Install it|the install]] and [[#Notes]].\n\n    [[code]]")
+            .unwrap();
+
+        assert!(html.contains(
+            "See <a href=\"\" class=\"wikilink\" data-wikilink=\"Getting Started\">Getting Started</a>, "
+        ));
+        assert!(html.contains("<a href=\"#install-it\" class=\"wikilink\" data-wikilink=\"guide\">the install</a>"));
+        assert!(html.contains("<a href=\"#notes\" class=\"wikilink\" data-wikilink=\"\">Notes</a>."));
+        assert!(html.contains("<pre><code>[[code]]"));
+    }
+
+    #[test]
+    fn test_wikilinks_off() {
+        let html = MarkdownProcessor::new().render("[[Page]]").unwrap();
+        assert_eq!(html, "<p>[[Page]]</p>\n");
+    }
+}
@@ -1,20 +1,30 @@
 use anyhow::Result;
 use axum::{
     body::Body,
-    extract::State,
-    http::{Response, StatusCode, Uri},
+    extract::{Request, State},
+    http::{header, Response, StatusCode, Uri},
     response::IntoResponse,
     routing::get,
     Router,
 };
-use jellrust_core::{config::Config, site::SiteBuilder};
-use jellrust_types::{FileChangeChannel, ReloadFlag};
+use jellrust_core::{
+    config::Config,
+    deps::CACHE_DIR,
+    notification::{notify_rebuild, RebuildOutcome},
+    site::{SiteBuilder, BUILD_ID_FILE},
+    summary::BuildSummary,
+};
+use jellrust_types::paths::is_within;
+use jellrust_types::{BuildCounter, FileChangeChannel};
+use hyper_util::client::legacy::{connect::HttpConnector, Client};
+use hyper_util::rt::TokioExecutor;
 use notify::{Event as NotifyEvent, EventKind, RecursiveMode, Watcher};
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
 use tokio::time::Duration;
+use tower::ServiceExt;
 use tower_http::services::ServeDir;
 
 // ============================================================================
@@ -30,9 +40,15 @@ const RELOAD_CHECK_INTERVAL_MS: u64 = 1000;
 /// Endpoint for live reload status checks
 const RELOAD_ENDPOINT: &str = "/__reload__";
 
+/// Endpoint exposing the id of the last successful build
+const STATUS_ENDPOINT: &str = "/__status__";
+
 /// HTML file extension
 const HTML_EXTENSION: &str = "html";
 
+/// How often the source is scanned for changes when native file watching fails
+const POLL_INTERVAL_MS: u64 = 1000;
+
 // ============================================================================
 // Server Structures
 // ============================================================================
@@ -46,13 +62,19 @@ pub struct DevServer {
     port: u16,
     host: String,
     include_drafts: bool,
+    incremental: bool,
+    livereload: bool,
 }
 
 /// Shared application state for HTTP handlers
 #[derive(Clone)]
 struct AppState {
     destination: PathBuf,
-    reload_flag: ReloadFlag,
+    build_counter: BuildCounter,
+    livereload: bool,
+    /// `serve.proxy` rules, longest path prefix first
+    proxy: Arc<Vec<(String, String)>>,
+    client: Client<HttpConnector, Body>,
 }
 
 // ============================================================================
@@ -75,25 +97,40 @@ impl DevServer {
             port,
             host,
             include_drafts,
+            incremental: false,
+            livereload: true,
         }
     }
+
+    /// Inject the script reloading open pages after each rebuild into served HTML
+    pub fn set_livereload(&mut self, livereload: bool) {
+        self.livereload = livereload;
+    }
+
+    /// Only re-render documents whose source, layouts or includes changed
+    ///
+    /// The dependency graph is kept in the source's build cache, so a
+    /// restarted server continues from the last build instead of a cold one.
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
     
     /// Start the development server with hot-reload capabilities
     pub async fn run(self) -> Result<()> {
-        let reload_flag = Arc::new(RwLock::new(false));
+        let build_counter = Arc::new(RwLock::new(0));
         let (file_change_tx, file_change_rx) = mpsc::unbounded_channel();
 
         // Spawn file change handler with debouncing
         self.spawn_file_change_handler(
             file_change_rx,
-            reload_flag.clone(),
+            build_counter.clone(),
         );
 
         // Set up file watcher
-        let _watcher = self.setup_watcher(file_change_tx)?;
+        self.spawn_watcher(file_change_tx)?;
 
         // Start HTTP server
-        self.start_http_server(reload_flag).await?;
+        self.start_http_server(build_counter).await?;
 
         Ok(())
     }
@@ -102,29 +139,39 @@ impl DevServer {
     fn spawn_file_change_handler(
         &self,
         rx: mpsc::UnboundedReceiver<()>,
-        reload_flag: ReloadFlag,
+        build_counter: BuildCounter,
     ) {
         let source = self.source.clone();
         let destination = self.destination.clone();
         let config = self.config.clone();
         let include_drafts = self.include_drafts;
+        let incremental = self.incremental;
 
         tokio::spawn(async move {
-            handle_file_changes(rx, reload_flag, source, destination, config, include_drafts).await;
+            handle_file_changes(rx, build_counter, source, destination, config, include_drafts, incremental).await;
         });
     }
 
     /// Start the HTTP server
-    async fn start_http_server(&self, reload_flag: ReloadFlag) -> Result<()> {
+    async fn start_http_server(&self, build_counter: BuildCounter) -> Result<()> {
+        let mut proxy: Vec<(String, String)> = self.config.serve.proxy.clone().into_iter().collect();
+        proxy.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        for (prefix, target) in &proxy {
+            tracing::info!("Proxying {} to {}", prefix, target);
+        }
+
         let state = AppState {
             destination: self.destination.clone(),
-            reload_flag,
+            build_counter,
+            livereload: self.livereload,
+            proxy: Arc::new(proxy),
+            client: Client::builder(TokioExecutor::new()).build_http(),
         };
 
         let app = Router::new()
             .route(RELOAD_ENDPOINT, get(reload_status))
+            .route(STATUS_ENDPOINT, get(build_status))
             .fallback(serve_static)
-            .nest_service("/", ServeDir::new(&self.destination))
             .with_state(state);
 
         let addr: SocketAddr = format!("{}:{}", self.host, self.port)
@@ -139,28 +186,101 @@ impl DevServer {
         Ok(())
     }
     
-    /// Set up file system watcher for source directory
-    fn setup_watcher(
-        &self,
-        tx: FileChangeChannel,
-    ) -> Result<notify::RecommendedWatcher> {
+    /// Watch the source directory, falling back to polling if native watching fails
+    ///
+    /// Native watching can fail right away or while serving, e.g. once the
+    /// inotify watch limit is reached. Either way the reason and its fix are
+    /// logged, and changes are still picked up by scanning the source.
+    fn spawn_watcher(&self, tx: FileChangeChannel) -> Result<()> {
+        let source = self.source.clone();
         let destination = canonicalize_path(&self.destination);
 
         tracing::info!("Watching source directory, ignoring: {:?}", destination);
 
-        let watcher = notify::recommended_watcher(move |res: notify::Result<NotifyEvent>| {
-            if let Ok(event) = res {
-                if should_trigger_rebuild(&event, &destination) {
-                    tracing::info!("Source file change detected: {:?}", event.paths);
-                    let _ = tx.send(());
+        let (error_tx, mut error_rx) = mpsc::unbounded_channel();
+        let watcher = match native_watcher(&source, &destination, tx.clone(), error_tx) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn_watcher_failed(&e);
+                polling_watcher(&source, &destination, tx.clone())?
+            }
+        };
+
+        tokio::spawn(async move {
+            // The watcher stops once dropped, so this task keeps it
+            let mut watcher = watcher;
+            if let Some(e) = error_rx.recv().await {
+                warn_watcher_failed(&e);
+                match polling_watcher(&source, &destination, tx) {
+                    Ok(polling) => watcher = polling,
+                    Err(e) => tracing::error!("Failed to poll for source changes: {}", e),
                 }
             }
-        })?;
+            let _watcher = watcher;
+            std::future::pending::<()>().await;
+        });
+
+        Ok(())
+    }
+}
+
+/// Watcher using the platform's file change notifications
+///
+/// Errors reported while watching go to `errors`, except for files that
+/// vanished before they could be watched.
+fn native_watcher(
+    source: &Path,
+    destination: &Path,
+    tx: FileChangeChannel,
+    errors: mpsc::UnboundedSender<notify::Error>,
+) -> notify::Result<Box<dyn Watcher + Send>> {
+    let destination = destination.to_path_buf();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<NotifyEvent>| match res {
+        Ok(event) => send_change(&event, &destination, &tx),
+        Err(e) if matches!(e.kind, notify::ErrorKind::PathNotFound | notify::ErrorKind::WatchNotFound) => {
+            tracing::debug!("File watcher: {}", e);
+        }
+        Err(e) => {
+            let _ = errors.send(e);
+        }
+    })?;
+    watcher.watch(source, RecursiveMode::Recursive)?;
+    Ok(Box::new(watcher))
+}
 
-        let mut w = watcher;
-        w.watch(&self.source, RecursiveMode::Recursive)?;
+/// Watcher scanning the source every [`POLL_INTERVAL_MS`]
+fn polling_watcher(source: &Path, destination: &Path, tx: FileChangeChannel) -> Result<Box<dyn Watcher + Send>> {
+    let destination = destination.to_path_buf();
+    let config = notify::Config::default().with_poll_interval(Duration::from_millis(POLL_INTERVAL_MS));
+    let mut watcher = notify::PollWatcher::new(
+        move |res: notify::Result<NotifyEvent>| match res {
+            Ok(event) => send_change(&event, &destination, &tx),
+            Err(e) => tracing::debug!("Polling for changes: {}", e),
+        },
+        config,
+    )?;
+    watcher.watch(source, RecursiveMode::Recursive)?;
+    tracing::info!("Polling the source directory for changes every {}ms", POLL_INTERVAL_MS);
+    Ok(Box::new(watcher))
+}
+
+/// Queue a rebuild for a change to the source
+fn send_change(event: &NotifyEvent, destination: &Path, tx: &FileChangeChannel) {
+    if should_trigger_rebuild(event, destination) {
+        tracing::info!("Source file change detected: {:?}", event.paths);
+        let _ = tx.send(());
+    }
+}
 
-        Ok(w)
+/// Explain why native file watching stopped and how to fix it
+fn warn_watcher_failed(error: &notify::Error) {
+    match error.kind {
+        notify::ErrorKind::MaxFilesWatch => tracing::warn!(
+            "File watcher hit the system's inotify watch limit; falling back to polling. \
+             Raise the limit with `sudo sysctl fs.inotify.max_user_watches=524288` \
+             (add it to /etc/sysctl.conf to keep it)."
+        ),
+        _ => tracing::warn!("File watcher failed ({}); falling back to polling", error),
     }
 }
 
@@ -171,11 +291,12 @@ impl DevServer {
 /// Handle file changes with debouncing to avoid rebuilding on every single change
 async fn handle_file_changes(
     mut rx: mpsc::UnboundedReceiver<()>,
-    reload_flag: ReloadFlag,
+    build_counter: BuildCounter,
     source: PathBuf,
     destination: PathBuf,
     config: Config,
     include_drafts: bool,
+    incremental: bool,
 ) {
     let debounce_duration = Duration::from_millis(DEBOUNCE_DURATION_MS);
 
@@ -190,9 +311,10 @@ async fn handle_file_changes(
         // Debounce: wait for a period of no events
         wait_for_quiet_period(&mut rx, debounce_duration).await;
 
-        // Trigger rebuild
-        trigger_reload(&reload_flag).await;
-        rebuild_site_with_logging(&source, &destination, &config, include_drafts).await;
+        // Rebuild, then let every open page know a new build is available
+        if rebuild_site_with_logging(&source, &destination, &config, include_drafts, incremental).await {
+            trigger_reload(&build_counter).await;
+        }
     }
 }
 
@@ -226,17 +348,30 @@ async fn wait_for_quiet_period(
 
 /// Determine if a file system event should trigger a rebuild
 fn should_trigger_rebuild(event: &NotifyEvent, destination: &Path) -> bool {
-    // Filter out events from the destination directory to prevent infinite rebuild loop
-    let is_destination_event = event.paths.iter().any(|path| {
-        let canonical_path = canonicalize_path(path);
-        canonical_path.starts_with(destination)
-    });
+    // Filter out events from the destination directory to prevent infinite rebuild loop.
+    // Paths of removed files can't be canonicalized, so compare in a form that
+    // ignores Windows' `\\?\` prefixes and drive letter case.
+    let is_destination_event = event
+        .paths
+        .iter()
+        .any(|path| is_within(&canonicalize_path(path), destination));
 
     if is_destination_event {
         tracing::debug!("Ignoring event from destination: {:?}", event);
         return false;
     }
 
+    // Build caches are written by the build itself
+    let is_cache_event = event
+        .paths
+        .iter()
+        .all(|path| path.components().any(|c| c.as_os_str() == CACHE_DIR));
+
+    if is_cache_event {
+        tracing::debug!("Ignoring event from build cache: {:?}", event);
+        return false;
+    }
+
     // Only trigger rebuild for relevant file changes
     let is_relevant_event = matches!(
         event.kind,
@@ -256,23 +391,37 @@ fn canonicalize_path(path: &Path) -> PathBuf {
     path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
 }
 
-/// Set the reload flag to notify clients to refresh
-async fn trigger_reload(reload_flag: &ReloadFlag) {
-    let mut flag = reload_flag.write().await;
-    *flag = true;
+/// Bump the build counter so clients served an older build refresh
+async fn trigger_reload(build_counter: &BuildCounter) {
+    let mut counter = build_counter.write().await;
+    *counter += 1;
 }
 
-/// Rebuild the site and log the result
+/// Rebuild the site, log and announce the result, returning whether it succeeded
 async fn rebuild_site_with_logging(
-    source: &PathBuf,
-    destination: &PathBuf,
+    source: &Path,
+    destination: &Path,
     config: &Config,
     include_drafts: bool,
-) {
-    match rebuild_site(source, destination, config, include_drafts).await {
-        Ok(_) => tracing::info!("✅ Site rebuilt successfully"),
-        Err(e) => tracing::error!("❌ Failed to rebuild site: {}", e),
-    }
+    incremental: bool,
+) -> bool {
+    let outcome = match rebuild_site(source, destination, config, include_drafts, incremental).await {
+        Ok(summary) => {
+            tracing::info!("✅ Site rebuilt: {}", summary);
+            RebuildOutcome::Success {
+                duration: summary.duration,
+            }
+        }
+        Err(e) => {
+            tracing::error!("❌ Failed to rebuild site: {}", e);
+            RebuildOutcome::Failure {
+                error: e.to_string(),
+            }
+        }
+    };
+
+    notify_rebuild(config, &outcome);
+    matches!(outcome, RebuildOutcome::Success { .. })
 }
 
 // ============================================================================
@@ -281,13 +430,15 @@ async fn rebuild_site_with_logging(
 
 /// Rebuild the site when files change
 async fn rebuild_site(
-    source: &PathBuf,
-    destination: &PathBuf,
+    source: &Path,
+    destination: &Path,
     config: &Config,
     include_drafts: bool,
-) -> Result<()> {
-    let mut builder = SiteBuilder::new(source.clone(), destination.clone(), config.clone());
+    incremental: bool,
+) -> Result<BuildSummary> {
+    let mut builder = SiteBuilder::new(source.to_path_buf(), destination.to_path_buf(), config.clone());
     builder.set_include_drafts(include_drafts);
+    builder.set_incremental(incremental);
     builder.build().await.map_err(anyhow::Error::from)
 }
 
@@ -297,31 +448,105 @@ async fn rebuild_site(
 
 /// Handler for reload status endpoint (for live reload client)
 async fn reload_status(State(state): State<AppState>) -> impl IntoResponse {
-    let mut flag = state.reload_flag.write().await;
-    let should_reload = *flag;
-    
-    // Reset flag and notify client
-    if should_reload {
-        *flag = false;
-        build_response(StatusCode::OK, "reload")
+    // Clients compare this against the build they were served with, so
+    // polling never consumes the reload for other open tabs
+    let build = *state.build_counter.read().await;
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Cache-Control", "no-store")
+        .body(Body::from(build.to_string()))
+        .unwrap()
+}
+
+/// Handler for build status endpoint (id of the last successful build)
+async fn build_status(State(state): State<AppState>) -> impl IntoResponse {
+    let build_id = read_build_id(&state.destination).await;
+    let status = if build_id.is_some() {
+        StatusCode::OK
     } else {
-        build_response(StatusCode::OK, "ok")
-    }
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let body = serde_json::json!({ "build_id": build_id }).to_string();
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .header("Cache-Control", "no-store")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// Read the id written by the last successful build
+async fn read_build_id(destination: &Path) -> Option<String> {
+    tokio::fs::read_to_string(destination.join(BUILD_ID_FILE))
+        .await
+        .ok()
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
 }
 
-/// Serve static files with live reload injection for HTML
+/// Serve static files with live reload injection for HTML, or forward
+/// requests under a `serve.proxy` prefix
 async fn serve_static(
     State(state): State<AppState>,
-    uri: Uri,
+    request: Request,
 ) -> impl IntoResponse {
-    let file_path = resolve_file_path(&state.destination, uri.path());
+    if let Some(target) = proxy_target(&state.proxy, request.uri()) {
+        return forward(&state.client, target, request).await;
+    }
+    
+    let file_path = resolve_file_path(&state.destination, request.uri().path());
+    if !is_html_file(&file_path) {
+        // Content types, ranges and directory redirects
+        return match ServeDir::new(&state.destination).oneshot(request).await {
+            Ok(response) => response.into_response(),
+            Err(error) => match error {},
+        };
+    }
+    let build = state.livereload.then_some(*state.build_counter.read().await);
     
-    match serve_file(&file_path).await {
+    match serve_file(&file_path, build).await {
         Ok(response) => response,
         Err(status) => build_response(status, status_message(status)),
     }
 }
 
+/// Backend URL for a request under one of the proxied path prefixes
+///
+/// The whole path is kept, so with `/api: http://localhost:8080` a request
+/// for `/api/users?page=2` goes to `http://localhost:8080/api/users?page=2`.
+fn proxy_target(rules: &[(String, String)], uri: &Uri) -> Option<String> {
+    let path = uri.path();
+    let (_, target) = rules.iter().find(|(prefix, _)| {
+        let prefix = prefix.trim_end_matches('/');
+        path.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })?;
+    let path_and_query = uri.path_and_query().map_or(path, |pq| pq.as_str());
+    Some(format!("{}{}", target.trim_end_matches('/'), path_and_query))
+}
+
+/// Send a request on to a proxy target, answering 502 when it can't be reached
+async fn forward(client: &Client<HttpConnector, Body>, target: String, request: Request) -> Response<Body> {
+    let (mut parts, body) = request.into_parts();
+    parts.uri = match target.parse() {
+        Ok(uri) => uri,
+        Err(e) => {
+            tracing::warn!("Invalid proxy URL {}: {}", target, e);
+            return build_response(StatusCode::BAD_GATEWAY, "502 Bad Gateway");
+        }
+    };
+    // Let the client set the backend's host
+    parts.headers.remove(header::HOST);
+    
+    match client.request(Request::from_parts(parts, body)).await {
+        Ok(response) => response.map(Body::new),
+        Err(e) => {
+            tracing::warn!("Proxy request to {} failed: {}", target, e);
+            build_response(StatusCode::BAD_GATEWAY, "502 Bad Gateway")
+        }
+    }
+}
+
 /// Resolve URI path to file system path
 fn resolve_file_path(destination: &Path, uri_path: &str) -> PathBuf {
     let path = uri_path.trim_start_matches('/');
@@ -333,8 +558,9 @@ fn resolve_file_path(destination: &Path, uri_path: &str) -> PathBuf {
     }
 }
 
-/// Serve a file from the file system
-async fn serve_file(file_path: &Path) -> Result<Response<Body>, StatusCode> {
+/// Serve a file from the file system, tagging HTML with the current build
+/// unless live reload is off
+async fn serve_file(file_path: &Path, build: Option<u64>) -> Result<Response<Body>, StatusCode> {
     if !file_path.exists() || !file_path.is_file() {
         return Err(StatusCode::NOT_FOUND);
     }
@@ -346,8 +572,11 @@ async fn serve_file(file_path: &Path) -> Result<Response<Body>, StatusCode> {
     // Inject live reload script for HTML files
     if is_html_file(file_path) {
         let html = String::from_utf8_lossy(&content);
-        let with_reload = inject_reload_script(&html);
-        Ok(build_html_response(with_reload))
+        let html = match build {
+            Some(build) => inject_reload_script(&html, build),
+            None => html.into_owned(),
+        };
+        Ok(build_html_response(html))
     } else {
         Ok(build_response(StatusCode::OK, content))
     }
@@ -391,9 +620,9 @@ fn status_message(status: StatusCode) -> &'static str {
 // Live Reload Script Injection
 // ============================================================================
 
-/// Inject live reload script into HTML
-fn inject_reload_script(html: &str) -> String {
-    let script = create_reload_script();
+/// Inject live reload script into HTML served as part of `build`
+fn inject_reload_script(html: &str, build: u64) -> String {
+    let script = create_reload_script(build);
     
     // Inject before </body> tag if present, otherwise append
     if let Some(pos) = html.rfind("</body>") {
@@ -405,19 +634,21 @@ fn inject_reload_script(html: &str) -> String {
     }
 }
 
-/// Create the live reload JavaScript
-fn create_reload_script() -> String {
+/// Create the live reload JavaScript for a page served as part of `build`
+fn create_reload_script(build: u64) -> String {
     format!(
         r#"
 <script>
 (function() {{
     'use strict';
     
+    var servedBuild = {build};
+    
     function checkReload() {{
-        fetch('{endpoint}')
+        fetch('{endpoint}', {{ cache: 'no-store' }})
             .then(res => res.text())
             .then(data => {{
-                if (data === 'reload') {{
+                if (Number(data) > servedBuild) {{
                     console.log('🔄 Reloading page...');
                     location.reload();
                 }}
@@ -430,6 +661,7 @@ fn create_reload_script() -> String {
 }})();
 </script>
 "#,
+        build = build,
         endpoint = RELOAD_ENDPOINT,
         interval = RELOAD_CHECK_INTERVAL_MS
     )
@@ -443,10 +675,18 @@ fn create_reload_script() -> String {
 mod tests {
     use super::*;
     
+    /// Create an empty scratch directory for a test
+    fn temp_site(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jellrust-server-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
     #[test]
     fn test_inject_reload_script_with_body_tag() {
         let html = "<html><body><h1>Test</h1></body></html>";
-        let result = inject_reload_script(html);
+        let result = inject_reload_script(html, 0);
         
         assert!(result.contains("<script>"));
         assert!(result.contains("checkReload"));
@@ -461,12 +701,39 @@ mod tests {
     #[test]
     fn test_inject_reload_script_without_body_tag() {
         let html = "<html><h1>Test</h1></html>";
-        let result = inject_reload_script(html);
+        let result = inject_reload_script(html, 0);
         
         assert!(result.contains("<script>"));
         assert!(result.contains("checkReload"));
     }
     
+    #[test]
+    fn test_reload_script_embeds_served_build() {
+        let result = inject_reload_script("<html><body></body></html>", 7);
+        
+        assert!(result.contains("var servedBuild = 7;"));
+        assert!(!result.contains("'reload'"));
+    }
+    
+    #[tokio::test]
+    async fn test_reload_status_does_not_consume_build() {
+        let state = AppState {
+            destination: PathBuf::from("/site"),
+            build_counter: Arc::new(RwLock::new(0)),
+            livereload: true,
+            proxy: Arc::new(Vec::new()),
+            client: Client::builder(TokioExecutor::new()).build_http(),
+        };
+        trigger_reload(&state.build_counter).await;
+        
+        // Every tab polling the endpoint sees the same build
+        for _ in 0..2 {
+            let response = reload_status(State(state.clone())).await.into_response();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(&body[..], b"1");
+        }
+    }
+    
     #[test]
     fn test_is_html_file() {
         assert!(is_html_file(Path::new("index.html")));
@@ -505,6 +772,60 @@ mod tests {
         );
     }
     
+    #[test]
+    fn test_proxy_target() {
+        let rules = vec![
+            ("/api/v2".to_string(), "http://localhost:9000/".to_string()),
+            ("/api".to_string(), "http://localhost:8080".to_string()),
+        ];
+        let target = |uri: &str| proxy_target(&rules, &uri.parse().unwrap());
+        
+        assert_eq!(target("/api/users?page=2"), Some("http://localhost:8080/api/users?page=2".to_string()));
+        assert_eq!(target("/api"), Some("http://localhost:8080/api".to_string()));
+        assert_eq!(target("/api/v2/users"), Some("http://localhost:9000/api/v2/users".to_string()));
+        assert_eq!(target("/apiary.html"), None);
+        assert_eq!(target("/index.html"), None);
+    }
+    
+    #[tokio::test]
+    async fn test_serve_file_without_livereload() {
+        let dir = temp_site("serve-file");
+        let file = dir.join("index.html");
+        std::fs::write(&file, "<html><body></body></html>").unwrap();
+        
+        let body = |response: Response<Body>| async {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        };
+        assert!(body(serve_file(&file, Some(1)).await.unwrap()).await.contains("checkReload"));
+        assert_eq!(body(serve_file(&file, None).await.unwrap()).await, "<html><body></body></html>");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_read_build_id() {
+        let dir = temp_site("status");
+        assert_eq!(read_build_id(&dir).await, None);
+
+        std::fs::write(dir.join(BUILD_ID_FILE), "1700000000000\n").unwrap();
+        assert_eq!(read_build_id(&dir).await, Some("1700000000000".to_string()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_polling_watcher() {
+        let dir = temp_site("poll");
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let _watcher = polling_watcher(&dir, &dir.join("_site"), tx).unwrap();
+
+        std::fs::write(dir.join("index.md"), "# Hi").unwrap();
+        let change = tokio::time::timeout(Duration::from_millis(POLL_INTERVAL_MS * 5), rx.recv()).await;
+        assert_eq!(change, Ok(Some(())));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
     #[test]
     fn test_canonicalize_path() {
         let path = Path::new(".");
@@ -0,0 +1,168 @@
+use crate::dependencies::DependencyRecorder;
+use crate::diagnostics::TemplateError;
+use crate::TemplateEngine;
+use anyhow::{Context, Result};
+use jellrust_markdown::strip_bom;
+use jellrust_types::paths::{relative_to, to_url_path};
+use liquid::model::Object;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+
+/// `{% include "name" %}` in a Tera template
+static TERA_INCLUDE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\{%-?\s*include\s+["']([^"']+)["']"#).unwrap());
+
+/// Language layouts are written in
+///
+/// Page content always goes through Liquid; the backend only renders the
+/// body of each layout, with the same `site`, `page`, `layout` and `content`
+/// variables.
+pub trait TemplateBackend: Send + Sync {
+    /// Render `template`, the body of the layout at `path`
+    ///
+    /// `first_line` is the line of the file the body starts on, after the
+    /// front matter, for pointing errors at the right place.
+    fn render(&self, path: &Path, template: &str, first_line: usize, globals: &Object) -> Result<String>;
+
+    /// Pick up changed includes before a rebuild
+    fn reload(&self) {}
+}
+
+/// The engine's own Liquid parser as a backend
+pub(crate) struct LiquidBackend<'a>(pub(crate) &'a TemplateEngine);
+
+impl TemplateBackend for LiquidBackend<'_> {
+    fn render(&self, path: &Path, template: &str, first_line: usize, globals: &Object) -> Result<String> {
+        let located = |action, error| TemplateError::new(action, path.to_path_buf(), template, first_line, error);
+        let template = self.0.parse(path, template).map_err(|e| located("parse", e))?;
+        Ok(template.render(globals).map_err(|e| located("render", e))?)
+    }
+}
+
+/// Layouts written for [Tera](https://keats.github.io/tera/)
+///
+/// Includes are read from `_includes/` (falling back to the theme's) and
+/// addressed by their path there, as in `{% include "nav/menu.html" %}`.
+/// Includes that aren't valid Tera, such as a theme's Liquid ones, are
+/// skipped with a warning. Output is not autoescaped, as in Liquid.
+pub struct TeraBackend {
+    roots: Vec<PathBuf>,
+    tera: Mutex<tera::Tera>,
+    /// Include name -> source, for recording dependencies
+    includes: Mutex<BTreeMap<String, String>>,
+    dependencies: Arc<DependencyRecorder>,
+}
+
+impl TeraBackend {
+    /// Backend with the includes of `roots`, in order of precedence
+    pub fn new(roots: Vec<PathBuf>, dependencies: Arc<DependencyRecorder>) -> Self {
+        let backend = Self {
+            roots,
+            tera: Mutex::default(),
+            includes: Mutex::default(),
+            dependencies,
+        };
+        backend.reload();
+        backend
+    }
+
+    /// Record the includes `template` uses, directly or through other includes
+    fn record_includes(&self, template: &str) {
+        let includes = self.includes.lock().expect("tera includes poisoned");
+        let mut seen = HashSet::new();
+        let mut pending: Vec<&str> = TERA_INCLUDE.captures_iter(template).map(|c| c.get(1).unwrap().as_str()).collect();
+        while let Some(name) = pending.pop() {
+            if !seen.insert(name) {
+                continue;
+            }
+            self.dependencies.record(Path::new("_includes").join(name));
+            if let Some(source) = includes.get(name) {
+                pending.extend(TERA_INCLUDE.captures_iter(source).map(|c| c.get(1).unwrap().as_str()));
+            }
+        }
+    }
+}
+
+impl TemplateBackend for TeraBackend {
+    fn render(&self, path: &Path, template: &str, _first_line: usize, globals: &Object) -> Result<String> {
+        self.record_includes(template);
+
+        let name = to_url_path(path);
+        let context = tera::Context::from_serialize(globals)
+            .with_context(|| format!("Failed to prepare variables for {}", name))?;
+
+        let mut tera = self.tera.lock().expect("tera poisoned");
+        tera.add_raw_template(&name, template)
+            .with_context(|| format!("Failed to parse {}", name))?;
+        tera.render(&name, &context)
+            .with_context(|| format!("Failed to render {}", name))
+    }
+
+    fn reload(&self) {
+        let mut includes = BTreeMap::new();
+        for root in self.roots.iter().rev() {
+            for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let Some(name) = relative_to(entry.path(), root) else {
+                    continue;
+                };
+                if let Ok(source) = fs::read_to_string(entry.path()) {
+                    includes.insert(name, strip_bom(&source).to_string());
+                }
+            }
+        }
+
+        let mut tera = tera::Tera::default();
+        tera.autoescape_on(Vec::new());
+        for (name, source) in &includes {
+            if let Err(e) = tera.add_raw_template(name, source) {
+                tracing::warn!("Skipping include {} that isn't valid Tera: {}", name, e);
+            }
+        }
+
+        *self.tera.lock().expect("tera poisoned") = tera;
+        *self.includes.lock().expect("tera includes poisoned") = includes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+
+    #[test]
+    fn test_tera_backend() {
+        let dir = temp_site("tera");
+        fs::create_dir_all(dir.join("site/_includes")).unwrap();
+        fs::create_dir_all(dir.join("theme/_includes")).unwrap();
+        fs::write(dir.join("site/_includes/nav.html"), "<nav>{{ site.title }}</nav>{% include \"footer.html\" %}").unwrap();
+        fs::write(dir.join("theme/_includes/footer.html"), "<footer>{{ page.title | upper }}</footer>").unwrap();
+        fs::write(dir.join("theme/_includes/liquid.html"), "{% assign x = 1 %}").unwrap();
+
+        let dependencies = Arc::new(DependencyRecorder::default());
+        let backend = TeraBackend::new(vec![dir.join("site/_includes"), dir.join("theme/_includes")], dependencies.clone());
+        let globals = liquid::object!({
+            "site": { "title": "Blog" },
+            "page": { "title": "Hello" },
+            "content": "<p>Hi & bye</p>",
+        });
+
+        let output = backend
+            .render(Path::new("_layouts/default.html"), "{% include \"nav.html\" %}{{ content }}", 1, &globals)
+            .unwrap();
+        assert_eq!(output, "<nav>Blog</nav><footer>HELLO</footer><p>Hi & bye</p>");
+        let recorded: HashSet<PathBuf> = dependencies.take().into_iter().collect();
+        assert!(recorded.contains(Path::new("_includes/footer.html")));
+
+        let error = backend.render(Path::new("_layouts/bad.html"), "{{ page.title | nope }}", 1, &globals);
+        assert!(format!("{:#}", error.unwrap_err()).contains("_layouts/bad.html"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,41 @@
+use jellrust_types::{Page, Post, Site};
+use liquid::model::Object;
+use std::path::Path;
+
+/// A document being rendered, for [`ContextProvider::document`]
+#[derive(Debug, Clone, Copy)]
+pub enum Document<'a> {
+    Post(&'a Post),
+    Page(&'a Page),
+}
+
+impl Document<'_> {
+    /// Path to the source file
+    pub fn path(&self) -> &Path {
+        match self {
+            Document::Post(post) => &post.path,
+            Document::Page(page) => &page.path,
+        }
+    }
+}
+
+/// Extra variables a plugin adds to the Liquid context, such as a `git`
+/// object with the commit the site is built from
+///
+/// Both methods run for every document rendered, possibly from several
+/// threads at once, so expensive values should be computed once and kept.
+pub trait ContextProvider: Send + Sync {
+    /// Global variables next to `site` and `page`
+    ///
+    /// Built-in globals win over variables with the same name.
+    fn globals(&self, _site: &Site) -> Object {
+        Object::new()
+    }
+
+    /// Variables added to `page` for one document
+    ///
+    /// Front matter and built-in page variables win over these.
+    fn document(&self, _document: Document<'_>, _site: &Site) -> Object {
+        Object::new()
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Records the layouts and includes used while rendering a document
+///
+/// Paths are relative to the site source (e.g. `_includes/header.html`).
+/// The engine renders one document at a time, so callers take the recorded
+/// set after each render.
+#[derive(Debug, Default)]
+pub struct DependencyRecorder {
+    used: Mutex<BTreeSet<PathBuf>>,
+}
+
+impl DependencyRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note that the current document depends on `path`
+    pub fn record(&self, path: PathBuf) {
+        self.used.lock().expect("dependency recorder poisoned").insert(path);
+    }
+
+    /// Return and clear everything recorded so far
+    pub fn take(&self) -> Vec<PathBuf> {
+        let mut used = self.used.lock().expect("dependency recorder poisoned");
+        std::mem::take(&mut *used).into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_clears_and_dedupes() {
+        let recorder = DependencyRecorder::new();
+        recorder.record(PathBuf::from("_includes/b.html"));
+        recorder.record(PathBuf::from("_includes/a.html"));
+        recorder.record(PathBuf::from("_includes/b.html"));
+
+        assert_eq!(
+            recorder.take(),
+            vec![PathBuf::from("_includes/a.html"), PathBuf::from("_includes/b.html")]
+        );
+        assert!(recorder.take().is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Lines shown around the offending one
+const CONTEXT_LINES: usize = 2;
+
+/// Position reported by the Liquid grammar, ` --> 3:10`
+static PARSE_POSITION: Lazy<Regex> = Lazy::new(|| Regex::new(r"-->\s*(\d+):(\d+)").unwrap());
+
+/// Trace details naming what failed, most specific first
+static CLUES: Lazy<[Regex; 4]> = Lazy::new(|| {
+    [
+        Regex::new(r"(?m)^\s*variable=(\S+)\n\s*requested index=(\S+)").unwrap(),
+        Regex::new(r"(?m)^\s*requested (?:variable|filter)=(\S+)").unwrap(),
+        Regex::new(r"(?m)^\s*filter=(\w+)").unwrap(),
+        Regex::new(r"(?m)^from: \{%-?\s*(\w+(?:\s+\S+){0,2})").unwrap(),
+    ]
+});
+
+/// A Liquid error in a layout, pointing at the file and line it came from
+///
+/// Liquid only reports positions for syntax errors; for render errors the
+/// line is found by looking for the variable, filter or tag named in the
+/// error's trace. The message then shows the line with some context.
+#[derive(Debug)]
+pub struct TemplateError {
+    /// `parse` or `render`
+    action: &'static str,
+    /// Path relative to the site source, e.g. `_layouts/default.html`
+    path: PathBuf,
+    /// 1-based line and column in the file
+    line: Option<(usize, Option<usize>)>,
+    /// Numbered source lines around `line`
+    snippet: Vec<(usize, String)>,
+    error: liquid::Error,
+}
+
+impl TemplateError {
+    /// Locate `error` in `text`, a template whose first line is line `first_line` of the file
+    pub fn new(
+        action: &'static str,
+        path: PathBuf,
+        text: &str,
+        first_line: usize,
+        error: liquid::Error,
+    ) -> Self {
+        let message = error.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        let position = parse_position(&message).or_else(|| find_clue(&message, &lines));
+
+        let snippet = match position {
+            Some((line, _)) => {
+                let start = line.saturating_sub(CONTEXT_LINES + 1);
+                let end = (line + CONTEXT_LINES).min(lines.len());
+                (start..end)
+                    .map(|i| (i + first_line, lines[i].to_string()))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        Self {
+            action,
+            path,
+            line: position.map(|(line, column)| (line + first_line - 1, column)),
+            snippet,
+            error,
+        }
+    }
+
+    /// Line of the file the error was found on
+    pub fn line(&self) -> Option<usize> {
+        self.line.map(|(line, _)| line)
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to {} {}", self.action, self.path.display())?;
+        let Some((line, column)) = self.line else {
+            return write!(f, ": {}", self.error.to_string().trim_end());
+        };
+        writeln!(f, ", line {}:", line)?;
+
+        let width = self.snippet.last().map_or(1, |(number, _)| number.to_string().len());
+        for (number, text) in &self.snippet {
+            let marker = if *number == line { '>' } else { ' ' };
+            writeln!(f, "{} {:>width$} | {}", marker, number, text)?;
+            if let (true, Some(column)) = (*number == line, column) {
+                writeln!(f, "  {:width$} | {:>column$}", "", "^")?;
+            }
+        }
+        write!(f, "{}", self.error.to_string().trim_end())
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// Line of `whole` on which `part`, a subslice of it, starts
+pub(crate) fn line_of(whole: &str, part: &str) -> usize {
+    let offset = (part.as_ptr() as usize)
+        .checked_sub(whole.as_ptr() as usize)
+        .filter(|&offset| offset <= whole.len())
+        .unwrap_or(0);
+    whole[..offset].matches('\n').count() + 1
+}
+
+/// Line and column from a syntax error (` --> 3:10`)
+fn parse_position(message: &str) -> Option<(usize, Option<usize>)> {
+    let caps = PARSE_POSITION.captures(message)?;
+    Some((caps[1].parse().ok()?, caps[2].parse().ok()))
+}
+
+/// First line mentioning what the error's trace names, with its column
+fn find_clue(message: &str, lines: &[&str]) -> Option<(usize, Option<usize>)> {
+    CLUES.iter().find_map(|clue| {
+        let caps = clue.captures(message)?;
+        let needle = match caps.get(2) {
+            Some(index) => format!("{}.{}", &caps[1], index.as_str()),
+            None => caps[1].split_whitespace().collect::<Vec<_>>().join(" "),
+        };
+
+        lines.iter().enumerate().find_map(|(i, line)| {
+            let column = line.find(&needle).or_else(|| {
+                let collapsed = line.split_whitespace().collect::<Vec<_>>().join(" ");
+                collapsed.contains(&needle).then(|| line.len() - line.trim_start().len())
+            })?;
+            Some((i + 1, Some(column + 1)))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> TemplateError {
+        let parser = liquid::ParserBuilder::with_stdlib().build().unwrap();
+        let globals = liquid::object!({ "page": { "title": "Hi" } });
+        let (action, error) = match parser.parse(text) {
+            Err(error) => ("parse", error),
+            Ok(template) => ("render", template.render(&globals).unwrap_err()),
+        };
+        TemplateError::new(action, PathBuf::from("_layouts/default.html"), text, 3, error)
+    }
+
+    #[test]
+    fn test_syntax_error_position() {
+        let error = error("<html>\n<body>\n  {{ page.title | }}\n</body>\n</html>");
+        assert_eq!(error.line(), Some(5));
+        let message = error.to_string();
+        assert!(message.starts_with("Failed to parse _layouts/default.html, line 5:\n"));
+        assert!(message.contains("  4 | <body>\n> 5 |   {{ page.title | }}\n    |"));
+        assert!(message.contains("expected Identifier"));
+    }
+
+    #[test]
+    fn test_render_error_line() {
+        let text = "<p>\n{{ page.title }}\n{{ page.title.nope.deeper }}\n</p>";
+        assert_eq!(error(text).line(), Some(5));
+        assert_eq!(error("a\nb\n{{ 1 | divided_by: 0 }}").line(), Some(5));
+        assert_eq!(error("{{ page.title | nope }}").line(), Some(3));
+
+        let unlocated = TemplateError::new(
+            "render",
+            PathBuf::from("_layouts/default.html"),
+            "",
+            1,
+            liquid::Error::with_msg("boom"),
+        );
+        assert_eq!(unlocated.to_string(), "Failed to render _layouts/default.html: liquid: boom");
+    }
+
+    #[test]
+    fn test_line_of() {
+        let layout = "---\ntitle: x\n---\n<html>";
+        assert_eq!(line_of(layout, &layout[layout.find("<html>").unwrap()..]), 4);
+    }
+}
@@ -0,0 +1,504 @@
+use liquid_core::model::KString;
+use liquid_core::runtime::{StackFrame, Template};
+use liquid_core::{
+    Display_filter, Expression, Filter, FilterParameters, FilterReflection, FromFilterParameters,
+    ParseFilter,
+};
+use liquid_core::model::Array;
+use liquid_core::{Error, Language, Object, Renderable, Result, Runtime, Value, ValueView};
+use once_cell::sync::Lazy;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// Language used to evaluate `where_exp` conditions (only the `if` block)
+static CONDITION_LANGUAGE: Lazy<Language> = Lazy::new(|| {
+    let mut language = Language::empty();
+    language
+        .blocks
+        .register("if".to_string(), Box::new(liquid_lib::stdlib::IfBlock));
+    language
+});
+
+/// Iterate over an array's items, a hash's values, or nothing
+fn collection_items(input: &dyn ValueView) -> Option<Vec<&dyn ValueView>> {
+    if let Some(array) = input.as_array() {
+        Some(array.values().collect())
+    } else {
+        input.as_object().map(|object| object.values().collect())
+    }
+}
+
+/// Jekyll's comparison: stringified equality, or membership for arrays
+fn property_matches(property: Option<&dyn ValueView>, target: &dyn ValueView) -> bool {
+    let Some(property) = property.filter(|p| !p.is_nil()) else {
+        return target.is_nil();
+    };
+
+    let target = target.to_kstr();
+    match property.as_array() {
+        Some(values) => values.values().any(|v| v.to_kstr() == target),
+        None => property.to_kstr() == target,
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct WhereArgs {
+    #[parameter(description = "The property to match on.", arg_type = "str")]
+    property: Expression,
+
+    #[parameter(description = "The value the property must equal (or contain).", arg_type = "any")]
+    target: Expression,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "where",
+    description = "Select the items of an array whose property equals a value.",
+    parameters(WhereArgs),
+    parsed(WhereFilter)
+)]
+pub struct Where;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "where"]
+struct WhereFilter {
+    #[parameters]
+    args: WhereArgs,
+}
+
+impl Filter for WhereFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let Some(items) = collection_items(input) else {
+            return Ok(input.to_value());
+        };
+
+        let selected = items
+            .into_iter()
+            .filter(|item| item_matches(*item, &args.property, args.target.as_view()))
+            .map(|item| item.to_value())
+            .collect();
+        Ok(Value::Array(selected))
+    }
+}
+
+/// Whether an item's property equals (or contains) a value, as for `where`
+fn item_matches(item: &dyn ValueView, property: &str, target: &dyn ValueView) -> bool {
+    let value = item.as_object().and_then(|object| object.get(property));
+    property_matches(value, target)
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "find",
+    description = "Return the first item of an array whose property equals a value.",
+    parameters(WhereArgs),
+    parsed(FindFilter)
+)]
+pub struct Find;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "find"]
+struct FindFilter {
+    #[parameters]
+    args: WhereArgs,
+}
+
+impl Filter for FindFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let Some(items) = collection_items(input) else {
+            return Ok(input.to_value());
+        };
+
+        let found = items
+            .into_iter()
+            .find(|item| item_matches(*item, &args.property, args.target.as_view()));
+        Ok(found.map_or(Value::Nil, |item| item.to_value()))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct WhereExpArgs {
+    #[parameter(description = "The name each item is bound to.", arg_type = "str")]
+    variable: Expression,
+
+    #[parameter(description = "The Liquid condition to evaluate for each item.", arg_type = "str")]
+    expression: Expression,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "where_exp",
+    description = "Select the items of an array for which a Liquid condition is true.",
+    parameters(WhereExpArgs),
+    parsed(WhereExpFilter)
+)]
+pub struct WhereExp;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "where_exp"]
+struct WhereExpFilter {
+    #[parameters]
+    args: WhereExpArgs,
+}
+
+impl Filter for WhereExpFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let Some(items) = collection_items(input) else {
+            return Ok(input.to_value());
+        };
+
+        let condition = Condition::parse("where_exp", &args.variable, &args.expression)?;
+        let selected = items
+            .into_iter()
+            .filter(|item| condition.holds(*item, runtime))
+            .map(|item| item.to_value())
+            .collect();
+        Ok(Value::Array(selected))
+    }
+}
+
+/// Condition of `where_exp` and `find_exp`, with the name items are bound to
+struct Condition {
+    variable: KString,
+    template: Template,
+}
+
+impl Condition {
+    fn parse(filter: &str, variable: &str, expression: &str) -> Result<Self> {
+        let condition = format!("{{% if {} %}}true{{% endif %}}", expression);
+        let template = liquid_core::parser::parse(&condition, &CONDITION_LANGUAGE)
+            .map(Template::new)
+            .map_err(|e| {
+                Error::with_msg(format!("Invalid {} condition", filter))
+                    .context("condition", expression.to_string())
+                    .context("cause", e.to_string())
+            })?;
+        Ok(Self {
+            variable: KString::from_ref(variable),
+            template,
+        })
+    }
+
+    fn holds(&self, item: &dyn ValueView, runtime: &dyn Runtime) -> bool {
+        let mut scope = Object::new();
+        scope.insert(self.variable.clone(), item.to_value());
+        let frame = StackFrame::new(runtime, &scope);
+        // Items missing a referenced property simply don't match
+        self.template.render(&frame).is_ok_and(|output| output == "true")
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "find_exp",
+    description = "Return the first item of an array for which a Liquid condition is true.",
+    parameters(WhereExpArgs),
+    parsed(FindExpFilter)
+)]
+pub struct FindExp;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "find_exp"]
+struct FindExpFilter {
+    #[parameters]
+    args: WhereExpArgs,
+}
+
+impl Filter for FindExpFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let Some(items) = collection_items(input) else {
+            return Ok(input.to_value());
+        };
+
+        let condition = Condition::parse("find_exp", &args.variable, &args.expression)?;
+        let found = items.into_iter().find(|item| condition.holds(*item, runtime));
+        Ok(found.map_or(Value::Nil, |item| item.to_value()))
+    }
+}
+
+/// Copy of the input array, or `None` when the input isn't an array
+fn input_array(input: &dyn ValueView) -> Option<Array> {
+    input.as_array().map(|array| array.values().map(|v| v.to_value()).collect())
+}
+
+/// Optional count argument (`pop: 2`), defaulting to one
+fn count_arg(count: Option<i64>) -> usize {
+    count.map(|n| n.max(0) as usize).unwrap_or(1)
+}
+
+/// Random number below `bound` (non-zero), seeded from the std hasher keys
+fn random_below(bound: usize) -> usize {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_usize(bound);
+    (hasher.finish() % bound as u64) as usize
+}
+
+#[derive(Debug, FilterParameters)]
+struct ElementArgs {
+    #[parameter(description = "The element to add.", arg_type = "any")]
+    element: Expression,
+}
+
+#[derive(Debug, FilterParameters)]
+struct CountArgs {
+    #[parameter(description = "How many elements (defaults to 1).", arg_type = "integer")]
+    count: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "push",
+    description = "Append an element to the end of an array.",
+    parameters(ElementArgs),
+    parsed(PushFilter)
+)]
+pub struct Push;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "push"]
+struct PushFilter {
+    #[parameters]
+    args: ElementArgs,
+}
+
+impl Filter for PushFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let Some(mut array) = input_array(input) else {
+            return Ok(input.to_value());
+        };
+        array.push(args.element.to_value());
+        Ok(Value::Array(array))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "unshift",
+    description = "Prepend an element to the start of an array.",
+    parameters(ElementArgs),
+    parsed(UnshiftFilter)
+)]
+pub struct Unshift;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "unshift"]
+struct UnshiftFilter {
+    #[parameters]
+    args: ElementArgs,
+}
+
+impl Filter for UnshiftFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let Some(mut array) = input_array(input) else {
+            return Ok(input.to_value());
+        };
+        array.insert(0, args.element.to_value());
+        Ok(Value::Array(array))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "pop",
+    description = "Remove elements from the end of an array.",
+    parameters(CountArgs),
+    parsed(PopFilter)
+)]
+pub struct Pop;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "pop"]
+struct PopFilter {
+    #[parameters]
+    args: CountArgs,
+}
+
+impl Filter for PopFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let Some(mut array) = input_array(input) else {
+            return Ok(input.to_value());
+        };
+        let keep = array.len().saturating_sub(count_arg(args.count));
+        array.truncate(keep);
+        Ok(Value::Array(array))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "shift",
+    description = "Remove elements from the start of an array.",
+    parameters(CountArgs),
+    parsed(ShiftFilter)
+)]
+pub struct Shift;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "shift"]
+struct ShiftFilter {
+    #[parameters]
+    args: CountArgs,
+}
+
+impl Filter for ShiftFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let Some(mut array) = input_array(input) else {
+            return Ok(input.to_value());
+        };
+        let count = count_arg(args.count).min(array.len());
+        array.drain(..count);
+        Ok(Value::Array(array))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "sample",
+    description = "Pick a random element (or, with a count, that many distinct elements) from an array.",
+    parameters(CountArgs),
+    parsed(SampleFilter)
+)]
+pub struct Sample;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "sample"]
+struct SampleFilter {
+    #[parameters]
+    args: CountArgs,
+}
+
+impl Filter for SampleFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let Some(mut array) = input_array(input) else {
+            return Ok(input.to_value());
+        };
+
+        // Partial Fisher-Yates shuffle of the first `count` slots
+        let count = count_arg(args.count).min(array.len());
+        for i in 0..count {
+            let j = i + random_below(array.len() - i);
+            array.swap(i, j);
+        }
+        array.truncate(count);
+
+        match args.count {
+            None => Ok(array.pop().unwrap_or(Value::Nil)),
+            Some(_) => Ok(Value::Array(array)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posts() -> Value {
+        liquid_core::value!([
+            { "title": "A", "category": "rust", "tags": ["cli", "web"], "draft": false },
+            { "title": "B", "category": "go", "tags": ["web"], "draft": true },
+            { "title": "C", "tags": [] }
+        ])
+    }
+
+    fn titles(value: Value) -> Vec<String> {
+        value
+            .as_array()
+            .unwrap()
+            .values()
+            .map(|v| v.as_object().unwrap().get("title").unwrap().to_kstr().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_where_matches_scalar_and_array_properties() {
+        let result = liquid_core::call_filter!(Where, posts(), "category", "rust").unwrap();
+        assert_eq!(titles(result), ["A"]);
+
+        let result = liquid_core::call_filter!(Where, posts(), "tags", "web").unwrap();
+        assert_eq!(titles(result), ["A", "B"]);
+
+        let result = liquid_core::call_filter!(Where, posts(), "draft", false).unwrap();
+        assert_eq!(titles(result), ["A"]);
+    }
+
+    #[test]
+    fn test_where_exp() {
+        let result =
+            liquid_core::call_filter!(WhereExp, posts(), "post", "post.tags contains 'web'").unwrap();
+        assert_eq!(titles(result), ["A", "B"]);
+
+        let result =
+            liquid_core::call_filter!(WhereExp, posts(), "post", "post.draft == false or post.category == 'go'")
+                .unwrap();
+        assert_eq!(titles(result), ["A", "B"]);
+    }
+
+    #[test]
+    fn test_find() {
+        let result = liquid_core::call_filter!(Find, posts(), "tags", "web").unwrap();
+        assert_eq!(result.as_object().unwrap().get("title").unwrap().to_kstr(), "A");
+        assert_eq!(liquid_core::call_filter!(Find, posts(), "category", "c").unwrap(), Value::Nil);
+
+        let result = liquid_core::call_filter!(FindExp, posts(), "post", "post.draft").unwrap();
+        assert_eq!(result.as_object().unwrap().get("title").unwrap().to_kstr(), "B");
+        assert_eq!(liquid_core::call_filter!(FindExp, posts(), "post", "post.title == 'D'").unwrap(), Value::Nil);
+        assert!(liquid_core::call_filter!(FindExp, posts(), "post", "post.title ==").is_err());
+    }
+
+    #[test]
+    fn test_push_unshift() {
+        let input = liquid_core::value!(["b"]);
+        assert_eq!(
+            liquid_core::call_filter!(Push, input.clone(), "c").unwrap(),
+            liquid_core::value!(["b", "c"])
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Unshift, input, "a").unwrap(),
+            liquid_core::value!(["a", "b"])
+        );
+        assert_eq!(liquid_core::call_filter!(Push, "text", "c").unwrap(), liquid_core::value!("text"));
+    }
+
+    #[test]
+    fn test_pop_shift() {
+        let input = liquid_core::value!([1, 2, 3]);
+        assert_eq!(liquid_core::call_filter!(Pop, input.clone()).unwrap(), liquid_core::value!([1, 2]));
+        assert_eq!(liquid_core::call_filter!(Pop, input.clone(), 5).unwrap(), liquid_core::value!([]));
+        assert_eq!(liquid_core::call_filter!(Shift, input.clone()).unwrap(), liquid_core::value!([2, 3]));
+        assert_eq!(liquid_core::call_filter!(Shift, input, 2).unwrap(), liquid_core::value!([3]));
+    }
+
+    #[test]
+    fn test_sample() {
+        let input = liquid_core::value!(["a", "b", "c"]);
+        let one = liquid_core::call_filter!(Sample, input.clone()).unwrap();
+        assert!(["a", "b", "c"].contains(&one.to_kstr().as_str()));
+
+        let mut two: Vec<String> = liquid_core::call_filter!(Sample, input.clone(), 2)
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .values()
+            .map(|v| v.to_kstr().to_string())
+            .collect();
+        two.sort();
+        two.dedup();
+        assert_eq!(two.len(), 2);
+
+        assert_eq!(liquid_core::call_filter!(Sample, liquid_core::value!([])).unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn test_where_exp_invalid_condition() {
+        assert!(liquid_core::call_filter!(WhereExp, posts(), "post", "post.draft ==").is_err());
+    }
+}
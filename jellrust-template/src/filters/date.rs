@@ -0,0 +1,264 @@
+use liquid_core::model::ScalarCow;
+use liquid_core::{
+    Display_filter, Error, Expression, Filter, FilterParameters, FilterReflection,
+    FromFilterParameters, ParseFilter,
+};
+use liquid_core::{Result, Runtime, Value, ValueView};
+
+/// Month and day names of a language
+struct DateNames {
+    language: &'static str,
+    months: [&'static str; 12],
+    short_months: [&'static str; 12],
+    /// Monday first
+    days: [&'static str; 7],
+    short_days: [&'static str; 7],
+}
+
+/// Languages the `date` filter knows besides English
+const LOCALES: &[DateNames] = &[
+    DateNames {
+        language: "de",
+        months: [
+            "Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September",
+            "Oktober", "November", "Dezember",
+        ],
+        short_months: ["Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez"],
+        days: ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag", "Sonntag"],
+        short_days: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    },
+    DateNames {
+        language: "es",
+        months: [
+            "enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto", "septiembre",
+            "octubre", "noviembre", "diciembre",
+        ],
+        short_months: ["ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sep", "oct", "nov", "dic"],
+        days: ["lunes", "martes", "miércoles", "jueves", "viernes", "sábado", "domingo"],
+        short_days: ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+    },
+    DateNames {
+        language: "fr",
+        months: [
+            "janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre",
+            "octobre", "novembre", "décembre",
+        ],
+        short_months: [
+            "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.", "déc.",
+        ],
+        days: ["lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche"],
+        short_days: ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."],
+    },
+    DateNames {
+        language: "id",
+        months: [
+            "Januari", "Februari", "Maret", "April", "Mei", "Juni", "Juli", "Agustus", "September",
+            "Oktober", "November", "Desember",
+        ],
+        short_months: ["Jan", "Feb", "Mar", "Apr", "Mei", "Jun", "Jul", "Agu", "Sep", "Okt", "Nov", "Des"],
+        days: ["Senin", "Selasa", "Rabu", "Kamis", "Jumat", "Sabtu", "Minggu"],
+        short_days: ["Sen", "Sel", "Rab", "Kam", "Jum", "Sab", "Min"],
+    },
+    DateNames {
+        language: "it",
+        months: [
+            "gennaio", "febbraio", "marzo", "aprile", "maggio", "giugno", "luglio", "agosto",
+            "settembre", "ottobre", "novembre", "dicembre",
+        ],
+        short_months: ["gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic"],
+        days: ["lunedì", "martedì", "mercoledì", "giovedì", "venerdì", "sabato", "domenica"],
+        short_days: ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+    },
+    DateNames {
+        language: "nl",
+        months: [
+            "januari", "februari", "maart", "april", "mei", "juni", "juli", "augustus", "september",
+            "oktober", "november", "december",
+        ],
+        short_months: ["jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec"],
+        days: ["maandag", "dinsdag", "woensdag", "donderdag", "vrijdag", "zaterdag", "zondag"],
+        short_days: ["ma", "di", "wo", "do", "vr", "za", "zo"],
+    },
+    DateNames {
+        language: "pt",
+        months: [
+            "janeiro", "fevereiro", "março", "abril", "maio", "junho", "julho", "agosto", "setembro",
+            "outubro", "novembro", "dezembro",
+        ],
+        short_months: ["jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez"],
+        days: [
+            "segunda-feira", "terça-feira", "quarta-feira", "quinta-feira", "sexta-feira", "sábado", "domingo",
+        ],
+        short_days: ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"],
+    },
+    DateNames {
+        language: "sv",
+        months: [
+            "januari", "februari", "mars", "april", "maj", "juni", "juli", "augusti", "september",
+            "oktober", "november", "december",
+        ],
+        short_months: ["jan", "feb", "mar", "apr", "maj", "jun", "jul", "aug", "sep", "okt", "nov", "dec"],
+        days: ["måndag", "tisdag", "onsdag", "torsdag", "fredag", "lördag", "söndag"],
+        short_days: ["mån", "tis", "ons", "tors", "fre", "lör", "sön"],
+    },
+];
+
+/// Names for a locale such as `fr`, `fr_FR` or `pt-BR`; `None` for English
+fn date_names(locale: &str) -> Result<Option<&'static DateNames>> {
+    let language = locale.split(['_', '-']).next().unwrap_or_default().to_ascii_lowercase();
+    if language.is_empty() || language == "en" {
+        return Ok(None);
+    }
+    LOCALES
+        .iter()
+        .find(|names| names.language == language)
+        .map(Some)
+        .ok_or_else(|| Error::with_msg(format!("Unknown date locale: {}", locale)))
+}
+
+/// Replace the name directives (`%B`, `%b`, `%h`, `%A`, `%a`) of a format
+/// with the localized names, honouring the `^`/`#` upcase flags and a width
+fn localize_format(format: &str, names: &DateNames, month: usize, weekday: usize) -> String {
+    let mut localized = String::with_capacity(format.len());
+    let mut rest = format;
+    while let Some(start) = rest.find('%') {
+        localized.push_str(&rest[..start]);
+        let directive = &rest[start + 1..];
+        let flags = directive.len() - directive.trim_start_matches(['-', '_', '0', '^', '#']).len();
+        let digits = directive[flags..].len() - directive[flags..].trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let spec = directive[flags + digits..].chars().next();
+        let name = match spec {
+            Some('B') => Some(names.months[month]),
+            Some('b' | 'h') => Some(names.short_months[month]),
+            Some('A') => Some(names.days[weekday]),
+            Some('a') => Some(names.short_days[weekday]),
+            _ => None,
+        };
+        match (name, spec) {
+            (Some(name), _) => {
+                let name = if directive[..flags].contains(['^', '#']) {
+                    name.to_uppercase()
+                } else {
+                    name.to_string()
+                };
+                let width: usize = directive[flags..flags + digits].parse().unwrap_or(0);
+                localized.push_str(&format!("{:>width$}", name, width = width));
+                rest = &directive[flags + digits + 1..];
+            }
+            // Keep other directives, and `%%` whole so its second `%` isn't read as one
+            (None, Some(spec)) => {
+                let end = flags + digits + spec.len_utf8();
+                localized.push('%');
+                localized.push_str(&directive[..end]);
+                rest = &directive[end..];
+            }
+            (None, None) => {
+                localized.push('%');
+                localized.push_str(directive);
+                rest = "";
+            }
+        }
+    }
+    localized.push_str(rest);
+    localized
+}
+
+#[derive(Debug, FilterParameters)]
+struct DateArgs {
+    #[parameter(description = "The format to return the date in.", arg_type = "str")]
+    format: Expression,
+
+    #[parameter(
+        description = "Language of month and day names, e.g. `fr_FR`. Defaults to `site.locale`.",
+        arg_type = "str"
+    )]
+    locale: Option<Expression>,
+}
+
+/// liquid's `date`, with month and day names in `site.locale` or the
+/// filter's `locale` argument
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "date",
+    description = "Converts a timestamp into another date format.",
+    parameters(DateArgs),
+    parsed(DateFilter)
+)]
+pub struct Date;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "date"]
+struct DateFilter {
+    #[parameters]
+    args: DateArgs,
+}
+
+impl Filter for DateFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let date = match input.as_scalar().and_then(|s| s.to_date_time()) {
+            Some(date) if !args.format.is_empty() => date,
+            _ => return Ok(input.to_value()),
+        };
+
+        let path = ["site", "locale"].map(|key| ScalarCow::new(key.to_string()));
+        let locale = match args.locale {
+            Some(locale) => Some(locale.into_owned()),
+            None => runtime.try_get(&path).map(|locale| locale.to_kstr().into_owned()),
+        };
+        let format = match locale.as_deref().map(date_names).transpose()?.flatten() {
+            Some(names) => localize_format(
+                &args.format,
+                names,
+                date.month() as usize - 1,
+                date.weekday().number_days_from_monday() as usize,
+            ),
+            None => args.format.to_string(),
+        };
+        let formatted = date
+            .format(&format)
+            .map_err(|_| Error::with_msg(format!("Invalid date-format string: {}", args.format)))?;
+        Ok(Value::scalar(formatted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATE: &str = "2024-03-05 10:00:00 +0000";
+
+    #[test]
+    fn test_date_english() {
+        assert_eq!(
+            liquid_core::call_filter!(Date, DATE, "%A %d %B %Y").unwrap(),
+            liquid_core::value!("Tuesday 05 March 2024")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Date, DATE, "%b %-d", "en_US").unwrap(),
+            liquid_core::value!("Mar 5")
+        );
+        assert_eq!(liquid_core::call_filter!(Date, "soon", "%Y").unwrap(), liquid_core::value!("soon"));
+    }
+
+    #[test]
+    fn test_date_localized() {
+        assert_eq!(
+            liquid_core::call_filter!(Date, DATE, "%A %-d %B %Y", "fr_FR").unwrap(),
+            liquid_core::value!("mardi 5 mars 2024")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Date, DATE, "%a, %d. %b", "de").unwrap(),
+            liquid_core::value!("Di, 05. Mär")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Date, DATE, "%^B %% %6a|", "pt-BR").unwrap(),
+            liquid_core::value!("MARÇO %    ter|")
+        );
+    }
+
+    #[test]
+    fn test_date_unknown_locale() {
+        assert!(liquid_core::call_filter!(Date, DATE, "%B", "xx_XX").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Result, Runtime, Value, ValueView};
+
+/// Escape the characters that are special in XML/HTML text and attributes
+pub fn xml_escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Percent-encode every byte for which `keep` is false
+fn percent_encode(input: &str, keep: impl Fn(u8) -> bool) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if keep(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Encode for use in a query string (`application/x-www-form-urlencoded`)
+pub(crate) fn cgi_escape(input: &str) -> String {
+    percent_encode(input, |b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'-' | b'~' | b' '))
+        .replace(' ', "+")
+}
+
+/// Encode characters not allowed in a URI, keeping reserved delimiters intact
+pub(crate) fn uri_escape(input: &str) -> String {
+    percent_encode(input, |b| {
+        b.is_ascii_alphanumeric()
+            || matches!(
+                b,
+                b'-' | b'.' | b'_' | b'~' | b'%'
+                    | b':' | b'/' | b'?' | b'#' | b'[' | b']' | b'@'
+                    | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'='
+            )
+    })
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "xml_escape",
+    description = "Escape text for use in XML (and HTML) documents.",
+    parsed(XmlEscapeFilter)
+)]
+pub struct XmlEscape;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "xml_escape"]
+struct XmlEscapeFilter;
+
+impl Filter for XmlEscapeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        if input.is_nil() {
+            return Ok(Value::Nil);
+        }
+        Ok(Value::scalar(xml_escape(&input.to_kstr())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "cgi_escape",
+    description = "Percent-encode a string for use in a URL query string.",
+    parsed(CgiEscapeFilter)
+)]
+pub struct CgiEscape;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "cgi_escape"]
+struct CgiEscapeFilter;
+
+impl Filter for CgiEscapeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(cgi_escape(&input.to_kstr())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "uri_escape",
+    description = "Percent-encode characters that are not valid in a URI.",
+    parsed(UriEscapeFilter)
+)]
+pub struct UriEscape;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "uri_escape"]
+struct UriEscapeFilter;
+
+impl Filter for UriEscapeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(uri_escape(&input.to_kstr())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xml_escape() {
+        assert_eq!(
+            xml_escape(r#"Tom & "Jerry's" <show>"#),
+            "Tom &amp; &quot;Jerry&#39;s&quot; &lt;show&gt;"
+        );
+        assert_eq!(liquid_core::call_filter!(XmlEscape, liquid_core::Value::Nil).unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn test_cgi_escape() {
+        assert_eq!(cgi_escape("foo, bar; baz?"), "foo%2C+bar%3B+baz%3F");
+        assert_eq!(cgi_escape("a~b_c.d-e"), "a~b_c.d-e");
+        assert_eq!(cgi_escape("café"), "caf%C3%A9");
+    }
+
+    #[test]
+    fn test_uri_escape() {
+        assert_eq!(uri_escape("foo, bar \\baz?"), "foo,%20bar%20%5Cbaz?");
+        assert_eq!(uri_escape("/search?q=a+b&x=%20"), "/search?q=a+b&x=%20");
+        assert_eq!(
+            liquid_core::call_filter!(UriEscape, "/tags/café/").unwrap(),
+            liquid_core::value!("/tags/caf%C3%A9/")
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use liquid_core::{
+    Display_filter, Expression, Filter, FilterParameters, FilterReflection, FromFilterParameters,
+    ParseFilter,
+};
+use liquid_core::{Result, Runtime, Value, ValueView};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Characters `truncate_html` keeps when not given a length, as Liquid's `truncate`
+const DEFAULT_TRUNCATE_LENGTH: i64 = 50;
+
+/// Elements without a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// Matches an HTML tag, capturing a closing slash and the name
+static HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<(/?)([A-Za-z][\w-]*)?[^>]*>").unwrap());
+
+/// A character reference at the start of text, which counts as one character
+static ENTITY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^&(?:[A-Za-z][A-Za-z0-9]*|#[0-9]+|#[xX][0-9A-Fa-f]+);").unwrap());
+
+/// Opening tag of a paragraph
+static PARAGRAPH: Lazy<Regex> = Lazy::new(|| Regex::new(r"<p\b[^>]*>").unwrap());
+
+/// Comments, and scripts and styles whose content isn't text
+static HIDDEN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<!--.*?-->|<script\b.*?</script\s*>|<style\b.*?</style\s*>").unwrap()
+});
+
+/// Text of an HTML fragment, with whitespace collapsed
+///
+/// Comments, scripts and styles are dropped with their content. Entities
+/// are kept, so the text can go back into HTML as is.
+pub fn strip_html(html: &str) -> String {
+    let visible = HIDDEN.replace_all(html, " ");
+    let text = HTML_TAG.replace_all(&visible, " ");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Cut text to at most `max_len` characters on a word boundary, marking the cut with an ellipsis
+pub fn truncate_text(text: &str, max_len: usize) -> String {
+    if text.chars().count() <= max_len {
+        return text.to_string();
+    }
+
+    let truncated: String = text.chars().take(max_len).collect();
+    let cut = truncated.rfind(' ').unwrap_or(truncated.len());
+    format!("{}...", truncated[..cut].trim_end())
+}
+
+/// Byte offsets at which each character of text ends, a character
+/// reference counting as a single character
+fn char_ends(text: &str) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut pos = 0;
+    while let Some(c) = text[pos..].chars().next() {
+        pos += match c {
+            '&' => ENTITY.find(&text[pos..]).map_or(1, |m| m.end()),
+            c => c.len_utf8(),
+        };
+        ends.push(pos);
+    }
+    ends
+}
+
+/// Cut HTML to at most `max_len` characters of text, closing the tags left open
+pub fn truncate_html(html: &str, max_len: usize) -> String {
+    let mut output = String::new();
+    let mut open: Vec<String> = Vec::new();
+    let mut remaining = max_len;
+    let mut last = 0;
+
+    let mut truncated = false;
+    for tag in HTML_TAG.captures_iter(html).map(Some).chain([None]) {
+        let whole = tag.as_ref().and_then(|caps| caps.get(0));
+        let text = &html[last..whole.map_or(html.len(), |m| m.start())];
+
+        let ends = char_ends(text);
+        let length = ends.len();
+        if length > remaining {
+            let head = &text[..remaining.checked_sub(1).map_or(0, |i| ends[i])];
+            let cut = match head.rfind(char::is_whitespace) {
+                Some(cut) => cut,
+                None if remaining == max_len => head.len(),
+                None => 0,
+            };
+            output.push_str(&head[..cut]);
+            truncated = true;
+            break;
+        }
+        output.push_str(text);
+        remaining -= length;
+        if remaining == 0 && whole.is_some_and(|m| !strip_html(&html[m.start()..]).is_empty()) {
+            truncated = true;
+            break;
+        }
+
+        let (Some(caps), Some(whole)) = (tag, whole) else {
+            break;
+        };
+        last = whole.end();
+        output.push_str(whole.as_str());
+
+        let Some(name) = caps.get(2).map(|m| m.as_str().to_ascii_lowercase()) else {
+            continue;
+        };
+        if !caps[1].is_empty() {
+            if let Some(pos) = open.iter().rposition(|tag| *tag == name) {
+                open.truncate(pos);
+            }
+        } else if !whole.as_str().ends_with("/>") && !VOID_ELEMENTS.contains(&name.as_str()) {
+            open.push(name);
+        }
+    }
+
+    if !truncated {
+        return html.to_string();
+    }
+    output.truncate(output.trim_end().len());
+    output.push_str("...");
+    for tag in open.iter().rev() {
+        output.push_str(&format!("</{}>", tag));
+    }
+    output
+}
+
+/// HTML with the elements still open at its end closed, e.g. content cut
+/// in the middle of a paragraph
+pub fn close_html(html: &str) -> String {
+    let mut open: Vec<String> = Vec::new();
+    for caps in HTML_TAG.captures_iter(html) {
+        let Some(name) = caps.get(2).map(|m| m.as_str().to_ascii_lowercase()) else {
+            continue;
+        };
+        if !caps[1].is_empty() {
+            if let Some(pos) = open.iter().rposition(|tag| *tag == name) {
+                open.truncate(pos);
+            }
+        } else if !caps[0].ends_with("/>") && !VOID_ELEMENTS.contains(&name.as_str()) {
+            open.push(name);
+        }
+    }
+
+    let mut output = html.to_string();
+    for tag in open.iter().rev() {
+        output.push_str(&format!("</{}>", tag));
+    }
+    output
+}
+
+/// Inner HTML of the first `<p>`, with or without attributes
+pub fn first_paragraph(html: &str) -> Option<&str> {
+    let start = PARAGRAPH.find(html)?.end();
+    let end = html[start..].find("</p>")?;
+    Some(&html[start..start + end])
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "strip_html",
+    description = "Remove HTML tags, comments, scripts and styles, collapsing whitespace.",
+    parsed(StripHtmlFilter)
+)]
+pub struct StripHtml;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "strip_html"]
+struct StripHtmlFilter;
+
+impl Filter for StripHtmlFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(strip_html(&input.to_kstr())))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct TruncateHtmlArgs {
+    #[parameter(description = "The maximum number of text characters to keep.", arg_type = "integer")]
+    length: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "truncate_html",
+    description = "Shorten HTML to a number of text characters, closing the tags left open.",
+    parameters(TruncateHtmlArgs),
+    parsed(TruncateHtmlFilter)
+)]
+pub struct TruncateHtml;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "truncate_html"]
+struct TruncateHtmlFilter {
+    #[parameters]
+    args: TruncateHtmlArgs,
+}
+
+impl Filter for TruncateHtmlFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let length = args.length.unwrap_or(DEFAULT_TRUNCATE_LENGTH).max(0) as usize;
+        Ok(Value::scalar(truncate_html(&input.to_kstr(), length)))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct ExcerptArgs {
+    #[parameter(
+        description = "The maximum number of text characters to keep, the whole paragraph by default.",
+        arg_type = "integer"
+    )]
+    length: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "excerpt",
+    description = "Take the first paragraph of HTML, optionally shortened to a number of text characters.",
+    parameters(ExcerptArgs),
+    parsed(ExcerptFilter)
+)]
+pub struct Excerpt;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "excerpt"]
+struct ExcerptFilter {
+    #[parameters]
+    args: ExcerptArgs,
+}
+
+impl Filter for ExcerptFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let html = input.to_kstr();
+        let paragraph = first_paragraph(&html).unwrap_or(&html);
+        let excerpt = match args.length {
+            Some(length) => truncate_html(paragraph, length.max(0) as usize),
+            None => paragraph.to_string(),
+        };
+        Ok(Value::scalar(excerpt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_html() {
+        assert_eq!(
+            strip_html("<p>Fish &amp; <em>chips</em></p><!-- <b>no</b> --><script>var a = '<p>';</script>\n<p>now</p>"),
+            "Fish &amp; chips now"
+        );
+        assert_eq!(
+            liquid_core::call_filter!(StripHtml, "<STYLE>p { color: red }</STYLE><b>bold</b>").unwrap(),
+            liquid_core::value!("bold")
+        );
+    }
+
+    #[test]
+    fn test_truncate_html() {
+        assert_eq!(truncate_html("a<br>b <img src=x/> c", 3), "a<br>b...");
+        assert_eq!(truncate_html("<b>short</b>", 10), "<b>short</b>");
+        assert_eq!(truncate_html("<i>Unbreakable</i>", 5), "<i>Unbre...</i>");
+        assert_eq!(truncate_html("&amp;&amp;", 3), "&amp;&amp;");
+        assert_eq!(truncate_html("&amp;&amp;&amp;", 1), "&amp;...");
+        assert_eq!(truncate_html("&amp;&#169;&#x2014;&", 3), "&amp;&#169;&#x2014;...");
+        assert_eq!(truncate_html("Fish &amp; chips", 10), "Fish &amp;...");
+        assert_eq!(close_html("<p>Intro <em>cut<br> <!-- x -->"), "<p>Intro <em>cut<br> <!-- x --></em></p>");
+        assert_eq!(
+            liquid_core::call_filter!(TruncateHtml, "<p>Read <a href=\"/x\">the whole story</a></p>", 12).unwrap(),
+            liquid_core::value!("<p>Read <a href=\"/x\">the...</a></p>")
+        );
+    }
+
+    #[test]
+    fn test_excerpt_filter() {
+        let html = "<h1>T</h1><p>First <em>part</em> of it</p><p>More</p>";
+        assert_eq!(
+            liquid_core::call_filter!(Excerpt, html).unwrap(),
+            liquid_core::value!("First <em>part</em> of it")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Excerpt, html, 10).unwrap(),
+            liquid_core::value!("First <em>part...</em>")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Excerpt, "<pre>code</pre><p class=\"lead\">Lead</p>").unwrap(),
+            liquid_core::value!("Lead")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Excerpt, "no paragraph").unwrap(),
+            liquid_core::value!("no paragraph")
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use super::xml_escape;
+use liquid_core::{
+    Display_filter, Expression, Filter, FilterParameters, FilterReflection, FromFilterParameters,
+    ParseFilter,
+};
+use liquid_core::{Error, Result, Runtime, Value, ValueView};
+
+/// Levels of objects and arrays `inspect` opens when not given a depth
+const DEFAULT_INSPECT_DEPTH: i64 = 3;
+
+/// Characters of a string `inspect` shows before cutting it short
+const INSPECT_STRING_LENGTH: usize = 80;
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "jsonify",
+    description = "Serialize a value (including objects and arrays) to JSON.",
+    parsed(JsonifyFilter)
+)]
+pub struct Jsonify;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "jsonify"]
+struct JsonifyFilter;
+
+impl Filter for JsonifyFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        let json = serde_json::to_string(&input.to_value())
+            .map_err(|e| Error::with_msg("Failed to serialize value to JSON").context("cause", e.to_string()))?;
+        Ok(Value::scalar(json))
+    }
+}
+
+/// Indented outline of a value: the type of everything in it, the size of
+/// objects and arrays, and scalar values
+///
+/// Object keys are sorted. Objects and arrays more than `depth` levels down
+/// only show their size, and long strings are cut short.
+fn inspect(value: &dyn ValueView, depth: usize) -> String {
+    let mut output = String::new();
+    write_outline(&mut output, value, depth, 0);
+    output.truncate(output.trim_end().len());
+    output
+}
+
+fn write_outline(output: &mut String, value: &dyn ValueView, depth: usize, level: usize) {
+    let indent = "  ".repeat(level + 1);
+    if let Some(object) = value.as_object() {
+        output.push_str(&format!("object ({})\n", count(object.size(), "key")));
+        if level < depth {
+            let mut entries: Vec<_> = object.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (key, value) in entries {
+                output.push_str(&format!("{}{}: ", indent, key));
+                write_outline(output, value, depth, level + 1);
+            }
+        }
+    } else if let Some(array) = value.as_array() {
+        output.push_str(&format!("array ({})\n", count(array.size(), "item")));
+        if level < depth {
+            for value in array.values() {
+                output.push_str(&format!("{}- ", indent));
+                write_outline(output, value, depth, level + 1);
+            }
+        }
+    } else if value.is_nil() {
+        output.push_str("nil\n");
+    } else {
+        let text = value.to_kstr();
+        let length = text.chars().count();
+        let shown = if value.type_name() == "string" && length > INSPECT_STRING_LENGTH {
+            let head: String = text.chars().take(INSPECT_STRING_LENGTH).collect();
+            format!("{:?}... ({} chars)", head, length)
+        } else if value.type_name() == "string" {
+            format!("{:?}", text.as_str())
+        } else {
+            text.to_string()
+        };
+        output.push_str(&format!("{} {}\n", value.type_name(), shown));
+    }
+}
+
+/// `1 key`, `2 keys`
+fn count(n: i64, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+#[derive(Debug, FilterParameters)]
+struct InspectArgs {
+    #[parameter(description = "Levels of objects and arrays to show, 3 by default.", arg_type = "integer")]
+    depth: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "inspect",
+    description = "Show the structure of a value, with types, for debugging templates.",
+    parameters(InspectArgs),
+    parsed(InspectFilter)
+)]
+pub struct Inspect;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "inspect"]
+struct InspectFilter {
+    #[parameters]
+    args: InspectArgs,
+}
+
+impl Filter for InspectFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let depth = args.depth.unwrap_or(DEFAULT_INSPECT_DEPTH).max(0) as usize;
+        // Escaped like Jekyll's, so the output can go straight into a <pre>
+        Ok(Value::scalar(xml_escape(&inspect(input, depth))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonify_scalars() {
+        assert_eq!(
+            liquid_core::call_filter!(Jsonify, "say \"hi\"").unwrap(),
+            liquid_core::value!(r#""say \"hi\"""#)
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Jsonify, 42).unwrap(),
+            liquid_core::value!("42")
+        );
+    }
+
+    #[test]
+    fn test_jsonify_object() {
+        let input = liquid_core::value!({ "title": "Hello", "tags": ["a", "b"], "draft": false });
+        let json = liquid_core::call_filter!(Jsonify, input).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json.to_kstr()).unwrap();
+
+        assert_eq!(parsed["title"], "Hello");
+        assert_eq!(parsed["tags"][1], "b");
+        assert_eq!(parsed["draft"], false);
+    }
+
+    #[test]
+    fn test_inspect() {
+        let input = liquid_core::value!({
+            "title": "<Hello>",
+            "tags": ["a", 2],
+            "draft": false,
+            "author": { "name": "Jane", "links": { "web": "https://example.com" } },
+            "image": nil,
+        });
+        assert_eq!(
+            inspect(&input, 2),
+            concat!(
+                "object (5 keys)\n",
+                "  author: object (2 keys)\n",
+                "    links: object (1 key)\n",
+                "    name: string \"Jane\"\n",
+                "  draft: boolean false\n",
+                "  image: nil\n",
+                "  tags: array (2 items)\n",
+                "    - string \"a\"\n",
+                "    - whole number 2\n",
+                "  title: string \"<Hello>\"",
+            )
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Inspect, input, 0).unwrap(),
+            liquid_core::value!("object (5 keys)")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Inspect, "<b>").unwrap(),
+            liquid_core::value!("string &quot;&lt;b&gt;&quot;")
+        );
+
+        let long = "x".repeat(100);
+        assert_eq!(inspect(&liquid_core::value!(long), 3), format!("string {:?}... (100 chars)", "x".repeat(80)));
+    }
+}
@@ -0,0 +1,22 @@
+mod array;
+mod date;
+mod escape;
+mod html;
+mod json;
+mod slugify;
+mod text;
+mod toc;
+mod translate;
+mod url;
+
+pub use array::{Find, FindExp, Pop, Push, Sample, Shift, Unshift, Where, WhereExp};
+pub use date::Date;
+pub use escape::{xml_escape, CgiEscape, UriEscape, XmlEscape};
+pub use html::{close_html, first_paragraph, strip_html, truncate_html, truncate_text, Excerpt, StripHtml, TruncateHtml};
+pub use json::{Inspect, Jsonify};
+pub use slugify::{slugify, Slugify, SlugifyMode};
+pub use text::{NormalizeWhitespace, NumberOfWords, ReadingTime, Smartify};
+pub use toc::{toc_entries, Toc};
+pub use translate::Translate;
+pub(crate) use translate::translate;
+pub use url::{absolute_url, relative_url, AbsoluteUrl, AssetUrl, RelativeUrl};
@@ -0,0 +1,135 @@
+use liquid_core::{
+    Display_filter, Expression, Filter, FilterParameters, FilterReflection, FromFilterParameters,
+    ParseFilter,
+};
+use liquid_core::{Result, Runtime, Value, ValueView};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static INVALID_DEFAULT: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^\p{Alphabetic}\p{N}]+").unwrap());
+static INVALID_RAW: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
+static INVALID_PRETTY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[^\p{Alphabetic}\p{N}._~!$&'()+,;=@]+").unwrap());
+static INVALID_ASCII: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^a-zA-Z0-9]+").unwrap());
+
+/// Jekyll slugify modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlugifyMode {
+    /// No transformation besides lowercasing
+    None,
+    /// Replace whitespace only
+    Raw,
+    /// Replace everything but letters and numbers
+    Default,
+    /// Keep URL-safe punctuation such as `._~!$&'()+,;=@`
+    Pretty,
+    /// Replace everything but ASCII letters and numbers
+    Ascii,
+    /// Transliterate to ASCII first, then behave like `Ascii`
+    Latin,
+}
+
+impl SlugifyMode {
+    pub fn parse(mode: &str) -> Self {
+        match mode {
+            "none" => Self::None,
+            "raw" => Self::Raw,
+            "pretty" => Self::Pretty,
+            "ascii" => Self::Ascii,
+            "latin" => Self::Latin,
+            _ => Self::Default,
+        }
+    }
+}
+
+/// Turn a string into a URL slug, following Jekyll's `slugify`
+pub fn slugify(input: &str, mode: SlugifyMode, cased: bool) -> String {
+    let input = input.trim();
+    let slug = match mode {
+        SlugifyMode::None => input.to_string(),
+        SlugifyMode::Raw => INVALID_RAW.replace_all(input, "-").into_owned(),
+        SlugifyMode::Default => INVALID_DEFAULT.replace_all(input, "-").into_owned(),
+        SlugifyMode::Pretty => INVALID_PRETTY.replace_all(input, "-").into_owned(),
+        SlugifyMode::Ascii => INVALID_ASCII.replace_all(input, "-").into_owned(),
+        SlugifyMode::Latin => INVALID_ASCII
+            .replace_all(&deunicode::deunicode(input), "-")
+            .into_owned(),
+    };
+
+    let slug = if mode == SlugifyMode::None {
+        slug
+    } else {
+        slug.trim_matches('-').to_string()
+    };
+
+    if cased { slug } else { slug.to_lowercase() }
+}
+
+#[derive(Debug, FilterParameters)]
+struct SlugifyArgs {
+    #[parameter(
+        description = "The slugify mode: \"none\", \"raw\", \"default\", \"pretty\", \"ascii\" or \"latin\".",
+        arg_type = "str"
+    )]
+    mode: Option<Expression>,
+
+    #[parameter(description = "Keep the original letter case.", arg_type = "bool")]
+    cased: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "slugify",
+    description = "Convert a string into a lowercase URL slug.",
+    parameters(SlugifyArgs),
+    parsed(SlugifyFilter)
+)]
+pub struct Slugify;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "slugify"]
+struct SlugifyFilter {
+    #[parameters]
+    args: SlugifyArgs,
+}
+
+impl Filter for SlugifyFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let mode = args
+            .mode
+            .map(|mode| SlugifyMode::parse(mode.as_str()))
+            .unwrap_or(SlugifyMode::Default);
+
+        Ok(Value::scalar(slugify(
+            &input.to_kstr(),
+            mode,
+            args.cased.unwrap_or(false),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify_modes() {
+        let title = "The _config.yml file?!";
+        assert_eq!(slugify(title, SlugifyMode::Default, false), "the-config-yml-file");
+        assert_eq!(slugify(title, SlugifyMode::Pretty, false), "the-_config.yml-file-!");
+        assert_eq!(slugify(title, SlugifyMode::Raw, false), "the-_config.yml-file?!");
+        assert_eq!(slugify(title, SlugifyMode::None, false), "the _config.yml file?!");
+        assert_eq!(slugify("Café Ünïcode", SlugifyMode::Ascii, false), "caf-n-code");
+        assert_eq!(slugify("Café Ünïcode", SlugifyMode::Latin, false), "cafe-unicode");
+        assert_eq!(slugify("Hello World", SlugifyMode::Default, true), "Hello-World");
+    }
+
+    #[test]
+    fn test_slugify_filter() {
+        assert_eq!(
+            liquid_core::call_filter!(Slugify, "Hello, World", "pretty").unwrap(),
+            liquid_core::value!("hello,-world")
+        );
+    }
+}
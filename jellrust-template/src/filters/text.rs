@@ -0,0 +1,204 @@
+use liquid_core::{
+    Display_filter, Expression, Filter, FilterParameters, FilterReflection, FromFilterParameters,
+    ParseFilter,
+};
+use super::strip_html;
+use liquid_core::model::ScalarCow;
+use liquid_core::{Result, Runtime, Value, ValueView};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static CJK_CHAR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[\p{Han}\p{Katakana}\p{Hiragana}\p{Hangul}]").unwrap());
+
+/// Words per minute when neither the filter nor `site.words_per_minute` sets it
+const DEFAULT_WORDS_PER_MINUTE: i64 = 200;
+
+/// Count words; in `cjk` mode every CJK character counts as a word, and
+/// `auto` switches to `cjk` only when the text contains CJK characters
+pub(crate) fn number_of_words(input: &str, mode: Option<&str>) -> usize {
+    let count_cjk = match mode {
+        Some("cjk") => true,
+        Some("auto") => CJK_CHAR.is_match(input),
+        _ => false,
+    };
+
+    if count_cjk {
+        let cjk = CJK_CHAR.find_iter(input).count();
+        cjk + CJK_CHAR.replace_all(input, " ").split_whitespace().count()
+    } else {
+        input.split_whitespace().count()
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct NumberOfWordsArgs {
+    #[parameter(
+        description = "Counting mode: \"cjk\" counts each CJK character as a word, \"auto\" does so only when CJK text is present.",
+        arg_type = "str"
+    )]
+    mode: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "number_of_words",
+    description = "Count the number of words in a string.",
+    parameters(NumberOfWordsArgs),
+    parsed(NumberOfWordsFilter)
+)]
+pub struct NumberOfWords;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "number_of_words"]
+struct NumberOfWordsFilter {
+    #[parameters]
+    args: NumberOfWordsArgs,
+}
+
+impl Filter for NumberOfWordsFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let count = number_of_words(&input.to_kstr(), args.mode.as_deref());
+        Ok(Value::scalar(count as i64))
+    }
+}
+
+/// Collapse every run of whitespace into a single space and trim the ends
+pub(crate) fn normalize_whitespace(input: &str) -> String {
+    input.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "normalize_whitespace",
+    description = "Replace any run of whitespace with a single space.",
+    parsed(NormalizeWhitespaceFilter)
+)]
+pub struct NormalizeWhitespace;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "normalize_whitespace"]
+struct NormalizeWhitespaceFilter;
+
+impl Filter for NormalizeWhitespaceFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(normalize_whitespace(&input.to_kstr())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "smartify",
+    description = "Convert quotes, dashes and ellipses into their typographic forms.",
+    parsed(SmartifyFilter)
+)]
+pub struct Smartify;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "smartify"]
+struct SmartifyFilter;
+
+impl Filter for SmartifyFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(jellrust_markdown::smartify(&input.to_kstr())))
+    }
+}
+
+/// Whole minutes it takes to read `html` at `words_per_minute`, at least 1
+pub(crate) fn reading_time(html: &str, words_per_minute: usize) -> usize {
+    let words = number_of_words(&strip_html(html), None);
+    words.div_ceil(words_per_minute.max(1)).max(1)
+}
+
+#[derive(Debug, FilterParameters)]
+struct ReadingTimeArgs {
+    #[parameter(
+        description = "Words read per minute, `site.words_per_minute` by default.",
+        arg_type = "integer"
+    )]
+    words_per_minute: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "reading_time",
+    description = "Estimate the minutes it takes to read some content.",
+    parameters(ReadingTimeArgs),
+    parsed(ReadingTimeFilter)
+)]
+pub struct ReadingTime;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "reading_time"]
+struct ReadingTimeFilter {
+    #[parameters]
+    args: ReadingTimeArgs,
+}
+
+impl Filter for ReadingTimeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let path = ["site", "words_per_minute"].map(|key| ScalarCow::new(key.to_string()));
+        let words_per_minute = args
+            .words_per_minute
+            .or_else(|| runtime.try_get(&path)?.as_scalar()?.to_integer())
+            .unwrap_or(DEFAULT_WORDS_PER_MINUTE);
+        let minutes = reading_time(&input.to_kstr(), words_per_minute.max(1) as usize);
+        Ok(Value::scalar(minutes as i64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_of_words() {
+        assert_eq!(number_of_words("  Hello   wide\nworld ", None), 3);
+        assert_eq!(number_of_words("", None), 0);
+    }
+
+    #[test]
+    fn test_number_of_words_cjk() {
+        let text = "Rust 是一门 language";
+        assert_eq!(number_of_words(text, None), 3);
+        assert_eq!(number_of_words(text, Some("cjk")), 5);
+        assert_eq!(number_of_words(text, Some("auto")), 5);
+        assert_eq!(number_of_words("plain text", Some("auto")), 2);
+        assert_eq!(
+            liquid_core::call_filter!(NumberOfWords, "こんにちは world", "cjk").unwrap(),
+            liquid_core::value!(6)
+        );
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        assert_eq!(normalize_whitespace("  a\tb \n\n c  "), "a b c");
+        assert_eq!(normalize_whitespace(""), "");
+    }
+
+    #[test]
+    fn test_smartify_filter() {
+        assert_eq!(
+            liquid_core::call_filter!(Smartify, "\"Hi\" -- it's me...").unwrap(),
+            liquid_core::value!("\u{201c}Hi\u{201d} \u{2013} it\u{2019}s me\u{2026}")
+        );
+    }
+
+    #[test]
+    fn test_reading_time() {
+        let html = format!("<p>{}</p>", "word ".repeat(450));
+        assert_eq!(reading_time(&html, 200), 3);
+        assert_eq!(reading_time(&html, 450), 1);
+        assert_eq!(reading_time("", 200), 1);
+        assert_eq!(
+            liquid_core::call_filter!(ReadingTime, html.as_str(), 100).unwrap(),
+            liquid_core::value!(5)
+        );
+        assert_eq!(
+            liquid_core::call_filter!(ReadingTime, html.as_str()).unwrap(),
+            liquid_core::value!(3)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use super::strip_html;
+use jellrust_types::TocEntry;
+use liquid_core::{
+    Display_filter, Expression, Filter, FilterParameters, FilterReflection, FromFilterParameters,
+    ParseFilter,
+};
+use liquid_core::{Result, Runtime, Value, ValueView};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// An `<h1>` to `<h6>` element, capturing the level, attributes and inner HTML
+static HEADING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<h([1-6])(\s[^>]*)?>(.*?)</h[1-6]\s*>").unwrap());
+static ID: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?i)\bid\s*=\s*["']([^"']*)["']"#).unwrap());
+
+/// Headings from `min` to `max` level that have an `id`, as `(level, id, text)`
+///
+/// Only those can be linked to; Markdown headings always get an `id`.
+fn headings(html: &str, min: u8, max: u8) -> impl Iterator<Item = (u8, String, String)> + '_ {
+    HEADING.captures_iter(html).filter_map(move |caps| {
+        let level: u8 = caps[1].parse().ok()?;
+        let id = ID.captures(caps.get(2)?.as_str())?.get(1)?.as_str();
+        let text = strip_html(&caps[3]);
+        (min..=max).contains(&level).then(|| (level, id.to_string(), text))
+    })
+}
+
+/// Heading tree of rendered HTML, for `page.toc`
+///
+/// Nests headings the way the `toc` filter does: under the closest preceding
+/// heading of a lower level.
+pub fn toc_entries(html: &str) -> Vec<TocEntry> {
+    let mut entries: Vec<TocEntry> = Vec::new();
+    for (level, id, text) in headings(html, 1, 6) {
+        let entry = TocEntry {
+            level,
+            text,
+            id,
+            children: Vec::new(),
+        };
+        // Descend while the last entry is a lower level than this one
+        let mut siblings = &mut entries;
+        while siblings.last().is_some_and(|last| last.level < level) {
+            siblings = &mut siblings.last_mut().expect("checked above").children;
+        }
+        siblings.push(entry);
+    }
+    entries
+}
+
+/// Nested `<ul class="toc">` linking to the headings of rendered HTML
+///
+/// Only headings from `min` to `max` level that have an `id` are listed. A
+/// heading nests under the closest preceding one of a lower level. Returns an
+/// empty string when there is nothing to list.
+pub(crate) fn toc(html: &str, min: u8, max: u8) -> String {
+    let mut output = String::new();
+    // Levels of the list items still open, outermost first
+    let mut open: Vec<u8> = Vec::new();
+    for (level, id, text) in headings(html, min, max) {
+        while open.last().is_some_and(|&top| top > level) {
+            close_item(&mut output, &mut open);
+        }
+        match open.last() {
+            Some(&top) if top == level => {
+                output.push_str("</li>\n");
+                open.pop();
+            }
+            Some(_) => output.push_str("\n<ul>\n"),
+            None if output.is_empty() => output.push_str("<ul class=\"toc\">\n"),
+            None => {}
+        }
+        output.push_str(&format!("<li><a href=\"#{}\">{}</a>", id, text));
+        open.push(level);
+    }
+
+    if output.is_empty() {
+        return output;
+    }
+    while !open.is_empty() {
+        close_item(&mut output, &mut open);
+    }
+    output.push_str("</ul>");
+    output
+}
+
+/// Close the innermost open list item, and the nested list holding it
+fn close_item(output: &mut String, open: &mut Vec<u8>) {
+    open.pop();
+    output.push_str("</li>\n");
+    if !open.is_empty() {
+        output.push_str("</ul>\n");
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct TocArgs {
+    #[parameter(description = "The lowest heading level to list, 1 by default.", arg_type = "integer")]
+    min: Option<Expression>,
+
+    #[parameter(description = "The highest heading level to list, 6 by default.", arg_type = "integer")]
+    max: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "toc",
+    description = "Build a table of contents from the headings of rendered HTML.",
+    parameters(TocArgs),
+    parsed(TocFilter)
+)]
+pub struct Toc;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "toc"]
+struct TocFilter {
+    #[parameters]
+    args: TocArgs,
+}
+
+impl Filter for TocFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let level = |value: Option<i64>, default| value.map_or(default, |level| level.clamp(1, 6) as u8);
+        let html = toc(&input.to_kstr(), level(args.min, 1), level(args.max, 6));
+        Ok(Value::scalar(html))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = concat!(
+        "<h1 id=\"guide\">Guide</h1><p>Intro</p>",
+        "<h2 id=\"install\">Install <code>cargo</code></h2>",
+        "<h3 id=\"linux\">Linux</h3><h3 id=\"mac\">Mac</h3>",
+        "<h2 id=\"usage\">Usage</h2><h2>No id</h2>",
+    );
+
+    #[test]
+    fn test_toc() {
+        assert_eq!(
+            toc(CONTENT, 1, 6),
+            concat!(
+                "<ul class=\"toc\">\n<li><a href=\"#guide\">Guide</a>\n",
+                "<ul>\n<li><a href=\"#install\">Install cargo</a>\n",
+                "<ul>\n<li><a href=\"#linux\">Linux</a></li>\n<li><a href=\"#mac\">Mac</a></li>\n</ul>\n",
+                "</li>\n<li><a href=\"#usage\">Usage</a></li>\n</ul>\n",
+                "</li>\n</ul>",
+            )
+        );
+        assert_eq!(toc("<p>No headings</p>", 1, 6), "");
+    }
+
+    #[test]
+    fn test_toc_entries() {
+        let entry = |level, id: &str, text: &str, children| TocEntry {
+            level,
+            text: text.to_string(),
+            id: id.to_string(),
+            children,
+        };
+        assert_eq!(
+            toc_entries(CONTENT),
+            vec![entry(
+                1,
+                "guide",
+                "Guide",
+                vec![
+                    entry(
+                        2,
+                        "install",
+                        "Install cargo",
+                        vec![entry(3, "linux", "Linux", vec![]), entry(3, "mac", "Mac", vec![])]
+                    ),
+                    entry(2, "usage", "Usage", vec![]),
+                ]
+            )]
+        );
+        // A deeper heading first stays at the top
+        assert_eq!(
+            toc_entries("<h3 id=\"a\">A</h3><h2 id=\"b\">B</h2>"),
+            vec![entry(3, "a", "A", vec![]), entry(2, "b", "B", vec![])]
+        );
+    }
+
+    #[test]
+    fn test_toc_levels() {
+        assert_eq!(
+            liquid_core::call_filter!(Toc, CONTENT, 2, 2).unwrap(),
+            liquid_core::value!(
+                "<ul class=\"toc\">\n<li><a href=\"#install\">Install cargo</a></li>\n<li><a href=\"#usage\">Usage</a></li>\n</ul>"
+            )
+        );
+        // A deeper heading first still closes properly
+        assert_eq!(
+            toc("<h3 id=\"a\">A</h3><h2 id=\"b\">B</h2>", 1, 6),
+            "<ul class=\"toc\">\n<li><a href=\"#a\">A</a></li>\n<li><a href=\"#b\">B</a></li>\n</ul>"
+        );
+    }
+}
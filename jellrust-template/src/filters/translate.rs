@@ -0,0 +1,84 @@
+use liquid_core::model::ScalarCow;
+use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Result, Runtime, Value, ValueView};
+
+/// Locale used when neither `page.locale` nor `site.locale` is set
+const DEFAULT_LOCALE: &str = "en";
+
+/// Locale of the document being rendered: `page.locale`, else `site.locale`
+fn current_locale(runtime: &dyn Runtime) -> String {
+    [["page", "locale"], ["site", "locale"]]
+        .iter()
+        .find_map(|path| {
+            let path = path.map(|key| ScalarCow::new(key.to_string()));
+            let locale = runtime.try_get(&path)?.to_kstr().into_owned();
+            (!locale.is_empty()).then(|| locale.to_string())
+        })
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+/// Look up a dotted key such as `nav.home` in `_data/locales/<locale>.yml`
+///
+/// A locale like `fr_FR` falls back to `fr.yml`. Missing translations are
+/// logged and render as the key itself, so gaps stay visible.
+pub(crate) fn translate(runtime: &dyn Runtime, key: &str) -> String {
+    let locale = current_locale(runtime);
+    let language = locale.split(['_', '-']).next().unwrap_or_default();
+    let found = [locale.as_str(), language].into_iter().find_map(|file| {
+        let path: Vec<ScalarCow<'_>> = ["site", "data", "locales", file]
+            .into_iter()
+            .chain(key.split('.'))
+            .map(|segment| ScalarCow::new(segment.to_string()))
+            .collect();
+        let value = runtime.try_get(&path)?;
+        value.as_scalar().map(|text| text.to_kstr().to_string())
+    });
+    found.unwrap_or_else(|| {
+        tracing::warn!("No translation for {} in locale {}", key, locale);
+        key.to_string()
+    })
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "t",
+    description = "Translate a key from _data/locales for the page's locale.",
+    parsed(TranslateFilter)
+)]
+pub struct Translate;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "t"]
+struct TranslateFilter;
+
+impl Filter for TranslateFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(translate(runtime, &input.to_kstr())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, globals: &liquid::Object) -> String {
+        let parser = liquid::ParserBuilder::with_stdlib().filter(Translate).build().unwrap();
+        parser.parse(template).unwrap().render(globals).unwrap()
+    }
+
+    #[test]
+    fn test_translate_filter() {
+        let site = liquid::object!({
+            "locale": "fr_FR",
+            "data": { "locales": {
+                "en": { "nav": { "home": "Home" } },
+                "fr": { "nav": { "home": "Accueil" } }
+            } }
+        });
+        let template = "{{ 'nav.home' | t }}|{{ 'nav.missing' | t }}";
+        assert_eq!(render(template, &liquid::object!({ "site": site.clone() })), "Accueil|nav.missing");
+
+        let globals = liquid::object!({ "site": site, "page": { "locale": "en" } });
+        assert_eq!(render(template, &globals), "Home|nav.missing");
+    }
+}
@@ -0,0 +1,222 @@
+use crate::dependencies::DependencyRecorder;
+use crate::urls::UrlIndex;
+use liquid_core::model::ScalarCow;
+use liquid_core::parser::{FilterArguments, ParameterReflection};
+use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Error, Result, Runtime, Value, ValueView};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Read a string variable from the `site` object
+fn site_var(runtime: &dyn Runtime, key: &str) -> String {
+    let path = [ScalarCow::new("site"), ScalarCow::new(key.to_string())];
+    runtime
+        .try_get(&path)
+        .map(|v| v.to_kstr().to_string())
+        .unwrap_or_default()
+}
+
+/// Whether the input already carries a scheme (`https:`, `mailto:`, ...)
+fn has_scheme(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    }) || url.starts_with("//")
+}
+
+/// Prefix a site-relative path with the base URL
+pub fn relative_url(baseurl: &str, input: &str) -> String {
+    if has_scheme(input) {
+        return input.to_string();
+    }
+
+    let baseurl = baseurl.trim_end_matches('/');
+    let baseurl = if baseurl.is_empty() || baseurl.starts_with('/') {
+        baseurl.to_string()
+    } else {
+        format!("/{}", baseurl)
+    };
+
+    format!("{}/{}", baseurl, input.trim_start_matches('/'))
+}
+
+/// Prefix a site-relative path with the site URL and base URL
+pub fn absolute_url(url: &str, baseurl: &str, input: &str) -> String {
+    if has_scheme(input) {
+        return input.to_string();
+    }
+
+    format!("{}{}", url.trim_end_matches('/'), relative_url(baseurl, input))
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "relative_url",
+    description = "Prefix a path with the site's baseurl.",
+    parsed(RelativeUrlFilter)
+)]
+pub struct RelativeUrl;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "relative_url"]
+struct RelativeUrlFilter;
+
+impl Filter for RelativeUrlFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let baseurl = site_var(runtime, "baseurl");
+        Ok(Value::scalar(relative_url(&baseurl, &input.to_kstr())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "absolute_url",
+    description = "Prefix a path with the site's url and baseurl.",
+    parsed(AbsoluteUrlFilter)
+)]
+pub struct AbsoluteUrl;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "absolute_url"]
+struct AbsoluteUrlFilter;
+
+impl Filter for AbsoluteUrlFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let url = site_var(runtime, "url");
+        let baseurl = site_var(runtime, "baseurl");
+        Ok(Value::scalar(absolute_url(&url, &baseurl, &input.to_kstr())))
+    }
+}
+
+/// Source path of an asset: `css/main.css` and `/assets/css/main.css`
+/// both name `assets/css/main.css`
+fn asset_path(input: &str) -> String {
+    let path = input.trim_start_matches('/');
+    if path.starts_with("assets/") {
+        path.to_string()
+    } else {
+        format!("assets/{}", path)
+    }
+}
+
+/// `{{ "css/main.css" | asset_url }}`: the URL of a file under `assets/`,
+/// with the content hash configured by `fingerprint:`
+///
+/// Bundle manifests resolve to their fingerprinted output. Pages using an
+/// asset are rebuilt when it changes, so the hash they link stays current.
+#[derive(Clone, Debug)]
+pub struct AssetUrl {
+    assets: Arc<UrlIndex>,
+    dependencies: Arc<DependencyRecorder>,
+}
+
+impl AssetUrl {
+    pub fn new(assets: Arc<UrlIndex>, dependencies: Arc<DependencyRecorder>) -> Self {
+        Self { assets, dependencies }
+    }
+}
+
+impl FilterReflection for AssetUrl {
+    fn name(&self) -> &str {
+        "asset_url"
+    }
+
+    fn description(&self) -> &str {
+        "Resolve an asset path to its URL, fingerprinted if configured."
+    }
+
+    fn positional_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+
+    fn keyword_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+}
+
+impl ParseFilter for AssetUrl {
+    fn parse(&self, mut arguments: FilterArguments<'_>) -> Result<Box<dyn Filter>> {
+        if arguments.positional.next().is_some() || arguments.keyword.next().is_some() {
+            return Err(Error::with_msg("Invalid number of arguments").context("filter", "asset_url"));
+        }
+        Ok(Box::new(self.clone()))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+impl Filter for AssetUrl {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let input = input.to_kstr();
+        if has_scheme(&input) {
+            return Ok(Value::scalar(input.to_string()));
+        }
+
+        let path = asset_path(&input);
+        self.dependencies.record(PathBuf::from(&path));
+        let url = self.assets.get(&path).unwrap_or_else(|| {
+            tracing::warn!("asset_url: no asset at {}", path);
+            format!("/{}", path)
+        });
+        let baseurl = site_var(runtime, "baseurl");
+        Ok(Value::scalar(relative_url(&baseurl, &url)))
+    }
+}
+
+impl fmt::Display for AssetUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "asset_url")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_url() {
+        assert_eq!(relative_url("", "/assets/style.css"), "/assets/style.css");
+        assert_eq!(relative_url("/blog", "/assets/style.css"), "/blog/assets/style.css");
+        assert_eq!(relative_url("blog/", "about/"), "/blog/about/");
+        assert_eq!(relative_url("/blog", "https://example.org/x"), "https://example.org/x");
+    }
+
+    #[test]
+    fn test_absolute_url() {
+        assert_eq!(
+            absolute_url("https://example.com/", "/blog", "feed.xml"),
+            "https://example.com/blog/feed.xml"
+        );
+        assert_eq!(absolute_url("", "", "/about/"), "/about/");
+    }
+
+    #[test]
+    fn test_asset_url() {
+        let assets = Arc::new(UrlIndex::new());
+        assets.replace(std::collections::HashMap::from([(
+            "assets/css/main.css".to_string(),
+            "/assets/css/main.css?v=0123abcd".to_string(),
+        )]));
+        let dependencies = Arc::new(DependencyRecorder::new());
+        let parser = liquid::ParserBuilder::with_stdlib()
+            .filter(AssetUrl::new(assets, dependencies.clone()))
+            .build()
+            .unwrap();
+        let template = parser
+            .parse("{{ 'css/main.css' | asset_url }} {{ '/assets/js/app.js' | asset_url }}")
+            .unwrap();
+
+        let globals = liquid::object!({ "site": { "baseurl": "/blog" } });
+        assert_eq!(
+            template.render(&globals).unwrap(),
+            "/blog/assets/css/main.css?v=0123abcd /blog/assets/js/app.js"
+        );
+        assert_eq!(
+            dependencies.take(),
+            [PathBuf::from("assets/css/main.css"), PathBuf::from("assets/js/app.js")]
+        );
+    }
+}
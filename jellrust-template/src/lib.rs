@@ -1,24 +1,354 @@
 use anyhow::{Context, Result};
-use jellrust_types::{Config, Page, Post, Site};
-use liquid::model::{Object, Value};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use jellrust_markdown::strip_bom;
+use jellrust_types::paths;
+use jellrust_types::{CommentsConfig, Config, FrontMatter, LiquidConfig, Page, Paginator, Post, SeoConfig, Site, TemplateLanguage, TocEntry};
+use liquid::model::{Object, Value, ValueView};
+use liquid::partials::LazyCompiler;
 use liquid::ParserBuilder;
+use liquid_core::{ParseBlock, ParseFilter, ParseTag};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub mod backend;
+pub mod context;
+pub mod dependencies;
+pub mod diagnostics;
+pub mod filters;
+pub mod partials;
+pub mod profile;
+pub mod tags;
+pub mod unsupported;
+pub mod urls;
+
+#[cfg(test)]
+mod testing;
+
+use backend::{LiquidBackend, TeraBackend, TemplateBackend};
+use context::{ContextProvider, Document};
+use dependencies::DependencyRecorder;
+use diagnostics::line_of;
+use partials::IncludesSource;
+use profile::{ProfileReport, Profiler};
+use tags::IncludeCache;
+use unsupported::UnsupportedCheck;
+use urls::UrlIndex;
+
+/// Liquid crate used for filters and tags, re-exported for implementing custom ones
+pub use liquid_core;
+
+/// Number of posts in `site.related_posts`
+const RELATED_POSTS_LIMIT: usize = 10;
 
 pub struct TemplateEngine {
     source_dir: PathBuf,
+    /// Theme whose layouts and includes fill in for missing ones
+    theme_dir: Option<PathBuf>,
+    options: TagOptions,
     parser: liquid::Parser,
+    shared: Shared,
+    custom: CustomLiquid,
+    /// Variables registered by plugins
+    context: Vec<Box<dyn ContextProvider>>,
+    unsupported: Arc<UnsupportedCheck>,
+    /// Renders layouts when they aren't written in Liquid
+    layout_backend: Option<Box<dyn TemplateBackend>>,
+    /// `site.data` of the current build, see [`set_site`](Self::set_site)
+    data: Mutex<Option<Value>>,
+    /// Site variables of the current build, see [`set_site`](Self::set_site)
+    site: Mutex<CachedSite>,
+}
+
+/// Site variables built once per build and lent to one document at a time
+#[derive(Debug, Default)]
+enum CachedSite {
+    /// Not built, so every document builds its own
+    #[default]
+    None,
+    Ready(Object),
+    /// In the globals of the document being rendered
+    Lent,
+}
+
+/// Settings from _config.yml the parser's tags depend on
+#[derive(Debug, Clone, Default)]
+struct TagOptions {
+    liquid: LiquidConfig,
+    seo: SeoConfig,
+    comments: CommentsConfig,
+    /// Whether `{% comments %}` outputs the widget
+    production: bool,
+}
+
+/// State shared between the engine and the tags of its parser
+#[derive(Debug, Default)]
+struct Shared {
+    dependencies: Arc<DependencyRecorder>,
+    profiler: Arc<Profiler>,
+    posts: Arc<UrlIndex>,
+    documents: Arc<UrlIndex>,
+    assets: Arc<UrlIndex>,
+    include_cache: Arc<IncludeCache>,
+}
+
+/// Filters, tags and blocks registered by library users
+#[derive(Clone, Default)]
+struct CustomLiquid {
+    filters: Vec<Box<dyn ParseFilter>>,
+    tags: Vec<Box<dyn ParseTag>>,
+    blocks: Vec<Box<dyn ParseBlock>>,
+}
+
+impl CustomLiquid {
+    /// Names of all registered filters, tags and blocks
+    fn names(&self) -> HashSet<String> {
+        let filters = self.filters.iter().map(|f| f.reflection().name().to_string());
+        let tags = self.tags.iter().map(|t| t.reflection().tag().to_string());
+        let blocks = self.blocks.iter().map(|b| b.reflection().start_tag().to_string());
+        filters.chain(tags).chain(blocks).collect()
+    }
 }
 
 impl TemplateEngine {
     pub fn new(source_dir: PathBuf) -> Self {
-        let parser = ParserBuilder::with_stdlib()
-            .build()
-            .unwrap();
+        let shared = Shared::default();
+        let unsupported = Arc::new(UnsupportedCheck::default());
+        let options = TagOptions::default();
+        let parser = build_parser(&source_dir, None, &options, &shared, &CustomLiquid::default(), &unsupported);
         
         Self {
             source_dir,
+            theme_dir: None,
+            options,
             parser,
+            shared,
+            custom: CustomLiquid::default(),
+            context: Vec::new(),
+            unsupported,
+            layout_backend: None,
+            data: Mutex::new(None),
+            site: Mutex::new(CachedSite::None),
+        }
+    }
+    
+    /// Add a Liquid filter, replacing any built-in filter with the same name
+    ///
+    /// Filters are implemented with [`liquid_core`]'s `ParseFilter` derive
+    /// macros, the same way as the ones in [`filters`].
+    pub fn register_filter<F: ParseFilter + 'static>(&mut self, filter: F) {
+        self.custom.filters.push(Box::new(filter));
+        self.rebuild_parser();
+    }
+    
+    /// Add a Liquid tag such as a `{% youtube id %}` shortcode
+    ///
+    /// Like filters, custom tags replace built-in ones with the same name.
+    pub fn register_tag<T: ParseTag + 'static>(&mut self, tag: T) {
+        self.custom.tags.push(Box::new(tag));
+        self.rebuild_parser();
+    }
+    
+    /// Add a Liquid block tag (`{% name %}...{% endname %}`)
+    pub fn register_block<B: ParseBlock + 'static>(&mut self, block: B) {
+        self.custom.blocks.push(Box::new(block));
+        self.rebuild_parser();
+    }
+    
+    /// Add global and per-document variables computed by a plugin
+    pub fn register_context<P: ContextProvider + 'static>(&mut self, provider: P) {
+        self.context.push(Box::new(provider));
+    }
+    
+    /// Builder-style [`register_filter`](Self::register_filter)
+    pub fn with_filter<F: ParseFilter + 'static>(mut self, filter: F) -> Self {
+        self.register_filter(filter);
+        self
+    }
+    
+    /// Builder-style [`register_tag`](Self::register_tag)
+    pub fn with_tag<T: ParseTag + 'static>(mut self, tag: T) -> Self {
+        self.register_tag(tag);
+        self
+    }
+    
+    /// Builder-style [`register_block`](Self::register_block)
+    pub fn with_block<B: ParseBlock + 'static>(mut self, block: B) -> Self {
+        self.register_block(block);
+        self
+    }
+    
+    /// Builder-style [`register_context`](Self::register_context)
+    pub fn with_context<P: ContextProvider + 'static>(mut self, provider: P) -> Self {
+        self.register_context(provider);
+        self
+    }
+    
+    /// Fall back to the `_layouts` and `_includes` of a theme directory
+    pub fn set_theme(&mut self, theme_dir: Option<PathBuf>) {
+        self.theme_dir = theme_dir;
+        self.rebuild_parser();
+    }
+    
+    /// Builder-style [`set_theme`](Self::set_theme)
+    pub fn with_theme(mut self, theme_dir: PathBuf) -> Self {
+        self.set_theme(Some(theme_dir));
+        self
+    }
+    
+    /// Write layouts in another language than Liquid, per `template_engine:` in _config.yml
+    ///
+    /// Includes are looked up in the site and the theme set with
+    /// [`set_theme`](Self::set_theme), which must be called first.
+    pub fn set_template_language(&mut self, language: TemplateLanguage) {
+        let backend: Option<Box<dyn TemplateBackend>> = match language {
+            TemplateLanguage::Liquid => None,
+            TemplateLanguage::Tera => {
+                let roots = [Some(&self.source_dir), self.theme_dir.as_ref()]
+                    .into_iter()
+                    .flatten()
+                    .map(|dir| dir.join("_includes"))
+                    .collect();
+                Some(Box::new(TeraBackend::new(roots, self.shared.dependencies.clone())))
+            }
+        };
+        self.layout_backend = backend;
+    }
+    
+    /// Render layouts with a custom [`TemplateBackend`]
+    pub fn set_layout_backend(&mut self, backend: Box<dyn TemplateBackend>) {
+        self.layout_backend = Some(backend);
+    }
+    
+    /// Make undefined variables or unknown filters errors, per `liquid:` in _config.yml
+    pub fn set_liquid_options(&mut self, options: LiquidConfig) {
+        self.options.liquid = options;
+        self.rebuild_parser();
+    }
+    
+    /// Metadata `{% seo %}` falls back on, per `seo:` in _config.yml
+    pub fn set_seo_options(&mut self, options: SeoConfig) {
+        self.options.seo = options;
+        self.rebuild_parser();
+    }
+    
+    /// Widget `{% comments %}` embeds, per `comments:` in _config.yml
+    ///
+    /// Outside `production` the tag outputs nothing, so previews don't
+    /// create threads.
+    pub fn set_comments_options(&mut self, options: CommentsConfig, production: bool) {
+        self.options.comments = options;
+        self.options.production = production;
+        self.rebuild_parser();
+    }
+    
+    /// File of a layout, from the site or else from the theme
+    fn layout_path(&self, name: &str) -> Option<PathBuf> {
+        let file = Path::new("_layouts").join(format!("{}.html", name));
+        std::iter::once(&self.source_dir)
+            .chain(&self.theme_dir)
+            .map(|dir| dir.join(&file))
+            .find(|path| path.exists())
+    }
+    
+    /// Whether the site or its theme has a layout
+    pub fn has_layout(&self, name: &str) -> bool {
+        self.layout_path(name).is_some()
+    }
+    
+    /// Recreate the parser so it picks up newly registered extensions
+    fn rebuild_parser(&mut self) {
+        self.unsupported = Arc::new(UnsupportedCheck::new(self.custom.names()));
+        self.parser = build_parser(
+            &self.source_dir,
+            self.theme_dir.as_deref(),
+            &self.options,
+            &self.shared,
+            &self.custom,
+            &self.unsupported,
+        );
+    }
+    
+    /// Set the post URLs `{% post_url %}` resolves, keyed by post name
+    pub fn set_post_urls(&self, urls: HashMap<String, String>) {
+        self.shared.posts.replace(urls);
+    }
+    
+    /// Set the document URLs `{% link %}` resolves, keyed by source path
+    pub fn set_document_urls(&self, urls: HashMap<String, String>) {
+        self.shared.documents.replace(urls);
+    }
+    
+    /// Set the asset URLs `asset_url` resolves, keyed by source path
+    pub fn set_asset_urls(&self, urls: HashMap<String, String>) {
+        self.shared.assets.replace(urls);
+    }
+    
+    /// Build the site variables shared by a build's documents, so they
+    /// aren't built again for each one
+    ///
+    /// Call it again when the site changes, e.g. once post bodies are
+    /// rendered. With `liquid_in_data`, the Liquid in data files is rendered
+    /// on the first call after [`clear_site`](Self::clear_site), once the
+    /// rest of the site variables are known, and not again per document.
+    pub fn set_site(&self, site: &Site, config: &Config) {
+        *self.site.lock().expect("site cache poisoned") = CachedSite::None;
+        if self.data.lock().expect("site data poisoned").is_none() {
+            let data = liquid::model::to_value(&site.data).unwrap_or(Value::Nil);
+            let data = if config.liquid_in_data {
+                let mut globals = Object::new();
+                globals.insert("site".into(), self.site_to_value(site, config, None));
+                self.render_data(data, &globals)
+            } else {
+                data
+            };
+            *self.data.lock().expect("site data poisoned") = Some(data);
+        }
+        *self.site.lock().expect("site cache poisoned") = CachedSite::Ready(self.site_object(site, config));
+    }
+    
+    /// Forget the site variables of the previous build
+    pub fn clear_site(&self) {
+        *self.data.lock().expect("site data poisoned") = None;
+        *self.site.lock().expect("site cache poisoned") = CachedSite::None;
+    }
+    
+    /// Forget the output of `include_cached` partials and reload layout backend
+    /// includes, so a rebuild sees changes
+    pub fn clear_include_cache(&self) {
+        self.shared.include_cache.clear();
+        if let Some(backend) = &self.layout_backend {
+            backend.reload();
+        }
+    }
+    
+    /// Layouts and includes used since the last call, relative to the source
+    pub fn take_dependencies(&self) -> Vec<PathBuf> {
+        self.shared.dependencies.take()
+    }
+    
+    /// Record how long each layout and include takes to render
+    pub fn set_profiling(&self, enabled: bool) {
+        self.shared.profiler.set_enabled(enabled);
+    }
+    
+    /// Render times recorded while profiling
+    pub fn profile_report(&self) -> ProfileReport {
+        self.shared.profiler.report()
+    }
+    
+    /// Parse a Liquid template, accepting Jekyll-style bare include paths
+    ///
+    /// `path` names the template in warnings about unsupported Jekyll tags and filters.
+    fn parse(&self, path: &Path, text: &str) -> Result<liquid::Template, liquid::Error> {
+        self.unsupported.check(path, text);
+        let text = tags::quote_include_paths(text);
+        if is_lenient(&self.options.liquid) {
+            self.parser.parse(&tags::outputs_to_echo(&text))
+        } else {
+            self.parser.parse(&text)
         }
     }
     
@@ -31,11 +361,14 @@ impl TemplateEngine {
     ) -> Result<String> {
         let mut globals = Object::new();
         
-        // Add site variables
-        globals.insert("site".into(), self.site_to_value(site, config));
-        
         // Add page variables (post data)
-        globals.insert("page".into(), self.post_to_value(post));
+        let mut page = self.post_to_value(post, site);
+        self.insert_related(&mut page, &post.front_matter, &post.path, site)?;
+        globals.insert("page".into(), page);
+        
+        // Add site variables
+        globals.insert("site".into(), self.lend_site(site, config, Some(post)));
+        self.insert_plugin_context(&mut globals, Document::Post(post), site);
         
         // Add content
         globals.insert("content".into(), Value::scalar(post.html.clone()));
@@ -44,12 +377,43 @@ impl TemplateEngine {
         let layout_name = post
             .front_matter
             .layout
-            .as_ref()
-            .map(|s| s.as_str())
+            .as_deref()
             .unwrap_or("default");
         
         // Render with layout
-        self.render_with_layout(&post.html, layout_name, &globals)
+        let output = self.shared.profiler
+            .time_document(|| self.render_with_layout(&post.html, layout_name, &mut globals));
+        self.return_site(&mut globals);
+        output
+    }
+    
+    /// Render Liquid templates in post content, with the post as `page`
+    pub fn render_post_content(
+        &self,
+        content: &str,
+        post: &Post,
+        site: &Site,
+        config: &Config,
+    ) -> Result<String> {
+        let mut globals = Object::new();
+        
+        // Add page variables (post data)
+        let mut page = self.post_to_value(post, site);
+        self.insert_related(&mut page, &post.front_matter, &post.path, site)?;
+        globals.insert("page".into(), page);
+        
+        // Process Liquid templates in the content
+        let path = post.path.strip_prefix(&self.source_dir).unwrap_or(&post.path);
+        let template = self.parse(path, content)
+            .context("Failed to parse Liquid templates in post content")?;
+        
+        // Add site variables
+        globals.insert("site".into(), self.lend_site(site, config, Some(post)));
+        self.insert_plugin_context(&mut globals, Document::Post(post), site);
+        
+        let output = self.shared.profiler.time_document(|| template.render(&globals));
+        self.return_site(&mut globals);
+        output.context("Failed to render Liquid templates in post content")
     }
     
     /// Render Liquid templates in page content (before Markdown processing)
@@ -62,18 +426,26 @@ impl TemplateEngine {
     ) -> Result<String> {
         let mut globals = Object::new();
 
-        // Add site variables
-        globals.insert("site".into(), self.site_to_value(site, config));
-
         // Add page variables
-        globals.insert("page".into(), self.page_to_value(page));
+        let mut page_value = self.page_to_value(page);
+        self.insert_related(&mut page_value, &page.front_matter, &page.path, site)?;
+        globals.insert("page".into(), page_value);
+        if let Some(paginator) = &page.paginator {
+            globals.insert("paginator".into(), self.paginator_to_value(paginator, site));
+        }
 
         // Process Liquid templates in the content
-        let template = self.parser.parse(content)
-            .with_context(|| format!("Failed to parse Liquid templates in page content"))?;
+        let path = page.path.strip_prefix(&self.source_dir).unwrap_or(&page.path);
+        let template = self.parse(path, content)
+            .context("Failed to parse Liquid templates in page content")?;
+
+        // Add site variables
+        globals.insert("site".into(), self.lend_site(site, config, None));
+        self.insert_plugin_context(&mut globals, Document::Page(page), site);
 
-        template.render(&globals)
-            .with_context(|| format!("Failed to render Liquid templates in page content"))
+        let output = self.shared.profiler.time_document(|| template.render(&globals));
+        self.return_site(&mut globals);
+        output.context("Failed to render Liquid templates in page content")
     }
 
     /// Render a page with its layout
@@ -85,11 +457,17 @@ impl TemplateEngine {
     ) -> Result<String> {
         let mut globals = Object::new();
         
-        // Add site variables
-        globals.insert("site".into(), self.site_to_value(site, config));
-        
         // Add page variables
-        globals.insert("page".into(), self.page_to_value(page));
+        let mut page_value = self.page_to_value(page);
+        self.insert_related(&mut page_value, &page.front_matter, &page.path, site)?;
+        globals.insert("page".into(), page_value);
+        if let Some(paginator) = &page.paginator {
+            globals.insert("paginator".into(), self.paginator_to_value(paginator, site));
+        }
+        
+        // Add site variables
+        globals.insert("site".into(), self.lend_site(site, config, None));
+        self.insert_plugin_context(&mut globals, Document::Page(page), site);
         
         // Add content
         globals.insert("content".into(), Value::scalar(page.html.clone()));
@@ -98,75 +476,122 @@ impl TemplateEngine {
         let layout_name = page
             .front_matter
             .layout
-            .as_ref()
-            .map(|s| s.as_str())
+            .as_deref()
             .unwrap_or("default");
         
         // Render with layout
-        self.render_with_layout(&page.html, layout_name, &globals)
+        let output = self.shared.profiler
+            .time_document(|| self.render_with_layout(&page.html, layout_name, &mut globals));
+        self.return_site(&mut globals);
+        output
+    }
+    
+    /// Add the variables of registered [`ContextProvider`]s, without
+    /// replacing any already set
+    fn insert_plugin_context(&self, globals: &mut Object, document: Document<'_>, site: &Site) {
+        for provider in &self.context {
+            for (key, value) in provider.globals(site) {
+                globals.entry(key).or_insert(value);
+            }
+            if let Some(Value::Object(page)) = globals.get_mut("page") {
+                for (key, value) in provider.document(document, site) {
+                    page.entry(key).or_insert(value);
+                }
+            }
+        }
     }
     
     /// Render content with a layout
+    ///
+    /// `layout` and `content` are updated in `globals` in place as the chain
+    /// is rendered, so the site variables aren't copied for every layout.
     fn render_with_layout(
         &self,
         content: &str,
         layout_name: &str,
-        globals: &Object,
+        globals: &mut Object,
     ) -> Result<String> {
-        let layout_path = self
-            .source_dir
-            .join("_layouts")
-            .join(format!("{}.html", layout_name));
+        self.render_layout_chain(content, layout_name, globals, &mut Vec::new())
+    }
+    
+    /// Render content with a layout and its parents, `chain` holding the
+    /// layouts already rendered around it
+    fn render_layout_chain(
+        &self,
+        content: &str,
+        layout_name: &str,
+        globals: &mut Object,
+        chain: &mut Vec<String>,
+    ) -> Result<String> {
+        if let Some(start) = chain.iter().position(|name| name == layout_name) {
+            let cycle: Vec<&str> = chain[start..].iter().map(String::as_str).chain([layout_name]).collect();
+            anyhow::bail!("layout cycle: {}", cycle.join(" → "));
+        }
+        chain.push(layout_name.to_string());
         
-        if !layout_path.exists() {
+        let Some(layout_path) = self.layout_path(layout_name) else {
             // No layout, return content as-is
             tracing::warn!("Layout not found: {}", layout_name);
             return Ok(content.to_string());
-        }
+        };
+        
+        let relative_layout = Path::new("_layouts").join(format!("{}.html", layout_name));
+        self.shared.dependencies.record(relative_layout.clone());
         
         let layout_content = fs::read_to_string(&layout_path)
             .with_context(|| format!("Failed to read layout: {}", layout_path.display()))?;
+        let layout_content = strip_bom(&layout_content);
 
-        // Extract parent layout from front matter
-        let parent_layout = self.extract_parent_layout(&layout_content);
+        // Expose the layout's front matter as `layout`; values set by a child
+        // layout win over its parent's, as in Jekyll
+        let mut layout = self.extract_layout_front_matter(layout_content);
+        let parent_layout = layout
+            .get("layout")
+            .and_then(|v| v.as_scalar())
+            .map(|v| v.to_kstr().to_string());
+        if let Some(Value::Object(child)) = globals.remove("layout") {
+            layout.extend(child);
+        }
+        globals.insert("layout".into(), Value::Object(layout));
 
         // Extract template content (strip front matter)
-        let template_content = self.extract_template_content(&layout_content);
-
-        // Parse and render the layout
-        let template = self.parser.parse(template_content)
-            .with_context(|| format!("Failed to parse layout: {}", layout_name))?;
+        let template_content = self.extract_template_content(layout_content);
 
-        let output = template.render(globals)
-            .with_context(|| format!("Failed to render layout: {}", layout_name))?;
+        // Render the layout, pointing errors at the offending line
+        let first_line = line_of(layout_content, template_content);
+        let liquid = LiquidBackend(self);
+        let backend = self.layout_backend.as_deref().unwrap_or(&liquid);
+        let output = self.shared.profiler.time(
+            || relative_layout.clone(),
+            || backend.render(&relative_layout, template_content, first_line, globals),
+        )?;
 
         // Check if this layout has a parent layout
         if let Some(parent_layout) = parent_layout {
-            let mut new_globals = globals.clone();
-            new_globals.insert("content".into(), Value::scalar(output.clone()));
-            return self.render_with_layout(&output, &parent_layout, &new_globals);
+            globals.insert("content".into(), Value::scalar(output.clone()));
+            return self.render_layout_chain(&output, &parent_layout, globals, chain);
         }
         
         Ok(output)
     }
     
-    /// Extract parent layout from front matter in layout file
-    fn extract_parent_layout(&self, layout_content: &str) -> Option<String> {
+    /// Front matter of a layout file as a Liquid object
+    fn extract_layout_front_matter(&self, layout_content: &str) -> Object {
         if !layout_content.trim().starts_with("---") {
-            return None;
+            return Object::new();
         }
 
         let rest = &layout_content.trim()[3..];
         if let Some(end_pos) = rest.find("\n---") {
             let yaml_content = &rest[..end_pos];
-            if let Ok(data) = serde_yaml::from_str::<serde_yaml::Value>(yaml_content) {
-                if let Some(layout) = data.get("layout") {
-                    return layout.as_str().map(|s| s.to_string());
-                }
+            if let Ok(data) = serde_yaml::from_str::<serde_yaml::Value>(yaml_content)
+                && let Ok(Value::Object(front_matter)) = liquid::model::to_value(&data)
+            {
+                return front_matter;
             }
         }
 
-        None
+        Object::new()
     }
 
     /// Extract template content from layout by stripping front matter
@@ -182,15 +607,60 @@ impl TemplateEngine {
         let rest = &trimmed[3..];
         if let Some(end_pos) = rest.find("\n---") {
             // Return everything after the front matter
-            &rest[end_pos + 4..].trim_start()
+            rest[end_pos + 4..].trim_start()
         } else {
             // No closing --- found, return original content
             layout_content
         }
     }
     
+    /// Site variables for a document, lent from those [`set_site`](Self::set_site)
+    /// built for the build when there are any
+    ///
+    /// Hand them back with [`return_site`](Self::return_site) once the
+    /// document is rendered.
+    fn lend_site(&self, site: &Site, config: &Config, post: Option<&Post>) -> Value {
+        let cached = {
+            let mut cache = self.site.lock().expect("site cache poisoned");
+            match std::mem::take(&mut *cache) {
+                CachedSite::Ready(obj) => {
+                    *cache = CachedSite::Lent;
+                    Some(obj)
+                }
+                other => {
+                    *cache = other;
+                    None
+                }
+            }
+        };
+        // Not built, or lent to a document rendered at the same time
+        let Some(mut obj) = cached else {
+            return self.site_to_value(site, config, post);
+        };
+        
+        obj.insert("related_posts".into(), self.related_posts(site, post));
+        Value::Object(obj)
+    }
+    
+    /// Put the site variables lent to a document back for the next one
+    fn return_site(&self, globals: &mut Object) {
+        let mut cache = self.site.lock().expect("site cache poisoned");
+        if let (CachedSite::Lent, Some(Value::Object(obj))) = (&*cache, globals.remove("site")) {
+            *cache = CachedSite::Ready(obj);
+        }
+    }
+    
     /// Convert Site to Liquid Value
-    fn site_to_value(&self, site: &Site, config: &Config) -> Value {
+    ///
+    /// `post` is the post being rendered, left out of `site.related_posts`.
+    fn site_to_value(&self, site: &Site, config: &Config, post: Option<&Post>) -> Value {
+        let mut obj = self.site_object(site, config);
+        obj.insert("related_posts".into(), self.related_posts(site, post));
+        Value::Object(obj)
+    }
+    
+    /// Site variables that are the same for every document
+    fn site_object(&self, site: &Site, config: &Config) -> Object {
         let mut obj = Object::new();
         
         // Add config values
@@ -198,14 +668,56 @@ impl TemplateEngine {
         obj.insert("description".into(), Value::scalar(config.description.clone()));
         obj.insert("url".into(), Value::scalar(config.url.clone()));
         obj.insert("baseurl".into(), Value::scalar(config.baseurl.clone()));
+        obj.insert("words_per_minute".into(), Value::scalar(config.words_per_minute as i64));
+        if let Some(locale) = &config.locale {
+            obj.insert("locale".into(), Value::scalar(locale.clone()));
+        }
+        obj.insert("time".into(), Value::scalar(local_time(site.time, config)));
         
-        // Add posts
-        let posts: Vec<Value> = site
-            .posts
-            .iter()
-            .map(|p| self.post_to_value(p))
+        // Add posts; expired ones are built but not listed
+        let posts: Vec<Value> = listed_posts(site)
+            .map(|p| self.post_to_value(p, site))
             .collect();
-        obj.insert("posts".into(), Value::Array(posts));
+        obj.insert("posts".into(), Value::Array(posts.clone()));
+        
+        // Posts are the only collection, so they are all the documents
+        obj.insert("documents".into(), Value::Array(posts.clone()));
+        
+        // Collections as in Jekyll, each also reachable as `site.<label>`
+        let mut posts_collection = Object::new();
+        posts_collection.insert("label".into(), Value::scalar("posts"));
+        posts_collection.insert("docs".into(), Value::Array(posts));
+        posts_collection.insert("files".into(), Value::Array(Vec::new()));
+        posts_collection.insert("relative_directory".into(), Value::scalar("_posts"));
+        posts_collection.insert("output".into(), Value::scalar(true));
+        posts_collection.insert("permalink".into(), Value::scalar(config.permalink.clone()));
+        obj.insert("collections".into(), Value::Array(vec![Value::Object(posts_collection)]));
+        
+        // Tag and category name -> posts, in the same order as `site.posts`
+        obj.insert("tags".into(), self.posts_by(site, |post| &post.front_matter.tags));
+        obj.insert("categories".into(), self.posts_by(site, |post| &post.front_matter.categories));
+        
+        // Tags with post counts and weights from 1 to 5, sorted by slug
+        let tag_cloud = liquid::model::to_value(&site.tag_cloud).unwrap_or(Value::Nil);
+        obj.insert("tag_cloud".into(), tag_cloud);
+        
+        // Featured posts, in the same order as `site.posts`
+        let featured_posts: Vec<Value> = listed_posts(site)
+            .filter(|p| p.featured)
+            .map(|p| self.post_to_value(p, site))
+            .collect();
+        obj.insert("featured_posts".into(), Value::Array(featured_posts));
+        
+        // Entries of the generated feed, for a custom `feed.xml` template
+        let feed_posts: Vec<&Post> = site.feed_posts.iter().filter_map(|&i| site.posts.get(i)).collect();
+        let updated = feed_posts.iter().map(|p| p.date).max().unwrap_or(site.time);
+        let mut feed = Object::new();
+        feed.insert(
+            "posts".into(),
+            Value::Array(feed_posts.iter().map(|p| self.post_to_value(p, site)).collect()),
+        );
+        feed.insert("updated".into(), Value::scalar(local_time(updated, config)));
+        obj.insert("feed".into(), Value::Object(feed));
         
         // Add pages
         let pages: Vec<Value> = site
@@ -215,16 +727,160 @@ impl TemplateEngine {
             .collect();
         obj.insert("pages".into(), Value::Array(pages));
         
-        Value::Object(obj)
+        // Pages rendered to HTML, e.g. for navigation menus
+        let html_pages: Vec<Value> = site
+            .pages
+            .iter()
+            .filter(|p| p.url.ends_with(".html") || p.url.ends_with('/'))
+            .map(|p| self.page_to_value(p))
+            .collect();
+        obj.insert("html_pages".into(), Value::Array(html_pages));
+        
+        // Files copied as is, e.g. for image galleries and download lists
+        let static_files: Vec<Value> = site
+            .static_files
+            .iter()
+            .map(|file| {
+                let mut file_obj = Object::new();
+                file_obj.insert("path".into(), Value::scalar(file.path.clone()));
+                file_obj.insert("name".into(), Value::scalar(file.name.clone()));
+                file_obj.insert("basename".into(), Value::scalar(file.basename.clone()));
+                file_obj.insert("extname".into(), Value::scalar(file.extname.clone()));
+                file_obj.insert("modified_time".into(), Value::scalar(local_time(file.modified_time, config)));
+                Value::Object(file_obj)
+            })
+            .collect();
+        obj.insert("static_files".into(), Value::Array(static_files));
+        
+        // Downloaded `vendor:` assets, e.g. `site.vendor["katex.min.js"].integrity`
+        let vendor = liquid::model::to_value(&site.vendor).unwrap_or(Value::Nil);
+        obj.insert("vendor".into(), vendor);
+        
+        // Fingerprinted asset bundles, e.g. `site.bundles["assets/js/main.bundle"]`
+        let bundles: Object = site
+            .bundles
+            .iter()
+            .map(|(manifest, url)| (manifest.clone().into(), Value::scalar(url.clone())))
+            .collect();
+        obj.insert("bundles".into(), Value::Object(bundles));
+        
+        // Add data files, with their Liquid rendered once `set_site` ran
+        let data = self.data.lock().expect("site data poisoned").clone();
+        let data = data.unwrap_or_else(|| liquid::model::to_value(&site.data).unwrap_or(Value::Nil));
+        obj.insert("data".into(), data);
+        
+        obj
+    }
+    
+    /// Like Jekyll without LSI: the most recent posts other than `post`
+    fn related_posts(&self, site: &Site, post: Option<&Post>) -> Value {
+        let related_posts: Vec<Value> = listed_posts(site)
+            .filter(|p| post.is_none_or(|post| post.path != p.path))
+            .take(RELATED_POSTS_LIMIT)
+            .map(|p| self.post_to_value(p, site))
+            .collect();
+        Value::Array(related_posts)
+    }
+    
+    /// Map from each term a post lists (tag or category) to those posts
+    fn posts_by(&self, site: &Site, terms: impl Fn(&Post) -> &Vec<String>) -> Value {
+        let mut map = Object::new();
+        for post in listed_posts(site) {
+            for term in terms(post) {
+                let posts = map
+                    .entry(term.clone())
+                    .or_insert_with(|| Value::Array(Vec::new()));
+                if let Value::Array(posts) = posts {
+                    posts.push(self.post_to_value(post, site));
+                }
+            }
+        }
+        Value::Object(map)
+    }
+    
+    /// Render Liquid markup in every string of a data value
+    ///
+    /// Strings that fail to render are kept as written.
+    fn render_data(&self, value: Value, globals: &Object) -> Value {
+        match value {
+            Value::Scalar(scalar) => {
+                let text = scalar.to_kstr();
+                if !text.contains("{{") && !text.contains("{%") {
+                    return Value::Scalar(scalar);
+                }
+                match self.parse(Path::new("_data"), &text).and_then(|template| template.render(globals)) {
+                    Ok(rendered) => Value::scalar(rendered),
+                    Err(e) => {
+                        tracing::warn!("Failed to render Liquid in data value {:?}: {}", text.as_str(), e);
+                        Value::Scalar(scalar)
+                    }
+                }
+            }
+            Value::Array(items) => Value::Array(
+                items.into_iter().map(|item| self.render_data(item, globals)).collect(),
+            ),
+            Value::Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, field)| (key, self.render_data(field, globals)))
+                    .collect(),
+            ),
+            other => other,
+        }
     }
     
     /// Convert Post to Liquid Value
-    fn post_to_value(&self, post: &Post) -> Value {
-        let mut obj = Object::new();
+    fn post_to_value(&self, post: &Post, site: &Site) -> Value {
+        let mut obj = self.post_fields(post);
+        
+        // Older and newer posts, in date order even when pinned posts come first;
+        // their own neighbours are left out so the objects stay finite
+        for (key, index) in [("previous", post.previous), ("next", post.next)] {
+            let neighbour = index.and_then(|i| site.posts.get(i));
+            obj.insert(key.into(), neighbour.map_or(Value::Nil, |p| Value::Object(self.post_fields(p))));
+        }
+        
+        Value::Object(obj)
+    }
+    
+    /// Variables of a post object other than its neighbours
+    fn post_fields(&self, post: &Post) -> Object {
+        let mut obj = custom_front_matter(&post.front_matter);
         
         obj.insert("url".into(), Value::scalar(post.url.clone()));
+        obj.insert("id".into(), Value::scalar(post.id.clone()));
+        obj.insert("slug".into(), Value::scalar(post.slug.clone()));
+        obj.insert("path".into(), Value::scalar(self.relative_path(&post.path)));
+        obj.insert("collection".into(), Value::scalar("posts"));
         obj.insert("date".into(), Value::scalar(post.date.to_rfc3339()));
+        obj.insert("content".into(), Value::scalar(post.html.clone()));
         obj.insert("excerpt".into(), Value::scalar(post.excerpt.clone()));
+        obj.insert("draft".into(), Value::scalar(post.draft));
+        obj.insert("pinned".into(), Value::scalar(post.pinned));
+        obj.insert("featured".into(), Value::scalar(post.featured));
+        
+        // Front matter wins over values extracted from the content
+        let image = post
+            .front_matter
+            .custom
+            .get("image")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .or_else(|| post.image.clone());
+        if let Some(image) = image {
+            obj.insert("image".into(), Value::scalar(image));
+        }
+        
+        let description = post
+            .front_matter
+            .custom
+            .get("description")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .or_else(|| post.description.clone());
+        if let Some(description) = description {
+            obj.insert("description".into(), Value::scalar(description));
+        }
         
         if let Some(title) = &post.front_matter.title {
             obj.insert("title".into(), Value::scalar(title.clone()));
@@ -252,31 +908,1127 @@ impl TemplateEngine {
             .collect();
         obj.insert("tags".into(), Value::Array(tags));
         
+        insert_toc(&mut obj, &post.front_matter, &post.toc);
+        
+        obj
+    }
+    
+    /// Check that every `related:` reference in the site resolves
+    pub fn check_related(&self, site: &Site) -> Result<()> {
+        let documents = site
+            .posts
+            .iter()
+            .map(|post| (&post.front_matter, &post.path))
+            .chain(site.pages.iter().map(|page| (&page.front_matter, &page.path)));
+        for (front_matter, path) in documents {
+            self.related_documents(front_matter, path, site)?;
+        }
+        Ok(())
+    }
+    
+    /// Replace the `related:` references of a page object with the documents they name
+    fn insert_related(&self, page: &mut Value, front_matter: &FrontMatter, path: &Path, site: &Site) -> Result<()> {
+        if let (Some(related), Value::Object(obj)) = (self.related_documents(front_matter, path, site)?, page) {
+            obj.insert("related".into(), Value::Array(related));
+        }
+        Ok(())
+    }
+    
+    /// Documents listed under `related:` in front matter
+    ///
+    /// Each entry is a source path (`docs/intro.md`) or a post name
+    /// (`2024-01-15-hello`), so posts and pages can link to each other.
+    fn related_documents(&self, front_matter: &FrontMatter, path: &Path, site: &Site) -> Result<Option<Vec<Value>>> {
+        let Some(related) = front_matter.custom.get("related") else {
+            return Ok(None);
+        };
+        let references: Vec<&str> = match related {
+            serde_yaml::Value::String(reference) => vec![reference.as_str()],
+            serde_yaml::Value::Sequence(items) => items.iter().filter_map(|item| item.as_str()).collect(),
+            _ => anyhow::bail!("`related` in {} must be a list of document paths", self.relative_path(path)),
+        };
+        
+        references
+            .into_iter()
+            .map(|reference| {
+                let key = reference.trim().trim_start_matches('/');
+                let post = site.posts.iter().find(|post| {
+                    self.relative_path(&post.path) == key
+                        || post.path.file_stem().is_some_and(|stem| stem == key)
+                });
+                if let Some(post) = post {
+                    return Ok(self.post_to_value(post, site));
+                }
+                let page = site.pages.iter().find(|page| self.relative_path(&page.path) == key);
+                match page {
+                    Some(page) => Ok(self.page_to_value(page)),
+                    None => anyhow::bail!(
+                        "Related document {:?} referenced by {} does not exist",
+                        reference,
+                        self.relative_path(path)
+                    ),
+                }
+            })
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+    
+    /// Convert Paginator to Liquid Value
+    fn paginator_to_value(&self, paginator: &Paginator, site: &Site) -> Value {
+        let mut obj = Object::new();
+        let number = |n: usize| Value::scalar(n as i64);
+        let optional = |value: Option<Value>| value.unwrap_or(Value::Nil);
+        
+        let posts: Vec<Value> = paginator
+            .posts
+            .iter()
+            .filter_map(|&i| site.posts.get(i))
+            .map(|p| self.post_to_value(p, site))
+            .collect();
+        obj.insert("posts".into(), Value::Array(posts));
+        obj.insert("page".into(), number(paginator.page));
+        obj.insert("per_page".into(), number(paginator.per_page));
+        obj.insert("total_posts".into(), number(paginator.total_posts));
+        obj.insert("total_pages".into(), number(paginator.total_pages));
+        obj.insert("previous_page".into(), optional(paginator.previous_page.map(number)));
+        obj.insert(
+            "previous_page_path".into(),
+            optional(paginator.previous_page_path.clone().map(Value::scalar)),
+        );
+        obj.insert("next_page".into(), optional(paginator.next_page.map(number)));
+        obj.insert(
+            "next_page_path".into(),
+            optional(paginator.next_page_path.clone().map(Value::scalar)),
+        );
+        
         Value::Object(obj)
     }
     
+    /// Path of a source file relative to the site root, with `/` separators
+    fn relative_path(&self, path: &Path) -> String {
+        paths::relative_to(path, &self.source_dir).unwrap_or_else(|| paths::to_url_path(path))
+    }
+    
     /// Convert Page to Liquid Value
     fn page_to_value(&self, page: &Page) -> Value {
-        let mut obj = Object::new();
+        let mut obj = custom_front_matter(&page.front_matter);
+        
+        let path = self.relative_path(&page.path);
+        let name = path.rsplit('/').next().unwrap_or_default().to_string();
         
         obj.insert("url".into(), Value::scalar(page.url.clone()));
+        obj.insert("dir".into(), Value::scalar(url_dir(&page.url)));
+        obj.insert("name".into(), Value::scalar(name));
+        obj.insert("path".into(), Value::scalar(path));
+        obj.insert("content".into(), Value::scalar(page.html.clone()));
+        
+        // An `excerpt` written in front matter wins
+        if !page.front_matter.custom.contains_key("excerpt") {
+            obj.insert("excerpt".into(), Value::scalar(page.excerpt.clone()));
+        }
+        
+        if let Some(date) = page.date {
+            obj.insert("date".into(), Value::scalar(date.format("%Y-%m-%d %H:%M:%S %z").to_string()));
+        }
         
         if let Some(title) = &page.front_matter.title {
             obj.insert("title".into(), Value::scalar(title.clone()));
         }
         
+        if let Some(layout) = &page.front_matter.layout {
+            obj.insert("layout".into(), Value::scalar(layout.clone()));
+        }
+        
+        let categories: Vec<Value> = page
+            .front_matter
+            .categories
+            .iter()
+            .map(|c| Value::scalar(c.clone()))
+            .collect();
+        obj.insert("categories".into(), Value::Array(categories));
+        
+        let tags: Vec<Value> = page
+            .front_matter
+            .tags
+            .iter()
+            .map(|t| Value::scalar(t.clone()))
+            .collect();
+        obj.insert("tags".into(), Value::Array(tags));
+        
+        insert_toc(&mut obj, &page.front_matter, &page.toc);
+        
         Value::Object(obj)
     }
 }
 
+/// Add the heading tree as `toc`
+///
+/// Themes commonly use a `toc:` front matter flag to switch their sidebar
+/// on, so one set in front matter is kept.
+fn insert_toc(obj: &mut Object, front_matter: &FrontMatter, toc: &[TocEntry]) {
+    if front_matter.custom.contains_key("toc") {
+        return;
+    }
+    if let Ok(toc) = liquid::model::to_value(&toc) {
+        obj.insert("toc".into(), toc);
+    }
+}
+
+/// Posts shown in listings: all but those whose `expires:` has passed by build time
+fn listed_posts(site: &Site) -> impl Iterator<Item = &Post> {
+    site.posts.iter().filter(|post| !post.is_expired(site.time))
+}
+
+/// Directory part of a URL, like Jekyll's `page.dir` (`/docs/` for `docs/intro.html`)
+fn url_dir(url: &str) -> String {
+    let url = format!("/{}", url.trim_start_matches('/'));
+    match url.rfind('/') {
+        Some(end) => url[..=end].to_string(),
+        None => "/".to_string(),
+    }
+}
+
+/// A timestamp in the configured `timezone`, formatted like Jekyll's
+/// (`2024-07-01 08:00:00 -0400`) so the `date` filter understands it
+fn local_time(time: DateTime<Utc>, config: &Config) -> String {
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+    let Some(timezone) = &config.timezone else {
+        return time.format(FORMAT).to_string();
+    };
+    match timezone.parse::<Tz>() {
+        Ok(tz) => time.with_timezone(&tz).format(FORMAT).to_string(),
+        Err(_) => {
+            tracing::warn!("Unknown timezone {:?}, using UTC", timezone);
+            time.format(FORMAT).to_string()
+        }
+    }
+}
+
+/// User-defined front matter keys, the base of `page`/`post` objects
+///
+/// Built-in variables are inserted afterwards and take precedence.
+fn custom_front_matter(front_matter: &FrontMatter) -> Object {
+    front_matter
+        .custom
+        .iter()
+        .filter_map(|(key, value)| match liquid::model::to_value(value) {
+            Ok(value) => Some((key.clone().into(), value)),
+            Err(e) => {
+                tracing::warn!("Skipping front matter key {:?}: {}", key, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Whether templates render `{{ }}` through [`tags::EchoTag`] to tolerate undefined values
+fn is_lenient(liquid: &LiquidConfig) -> bool {
+    !liquid.strict_variables || !liquid.strict_filters
+}
+
+/// Liquid parser with the Jekyll tags and filters, followed by the custom ones
+fn build_parser(
+    source_dir: &Path,
+    theme_dir: Option<&Path>,
+    options: &TagOptions,
+    shared: &Shared,
+    custom: &CustomLiquid,
+    unsupported: &Arc<UnsupportedCheck>,
+) -> liquid::Parser {
+    let mut includes = IncludesSource::new(source_dir.join("_includes"))
+        .with_unsupported_check(unsupported.clone())
+        .with_echo_outputs(is_lenient(&options.liquid));
+    if let Some(theme_dir) = theme_dir {
+        includes = includes.with_fallback(theme_dir.join("_includes"));
+    }
+    let mut builder = ParserBuilder::with_stdlib()
+        .tag(tags::EchoTag::new(options.liquid.clone()))
+        .tag(tags::IncludeTag::new(shared.dependencies.clone(), shared.profiler.clone()))
+        .tag(tags::RenderTag::new(shared.dependencies.clone(), shared.profiler.clone()))
+        .tag(tags::IncludeCachedTag::new(
+            shared.include_cache.clone(),
+            shared.dependencies.clone(),
+            shared.profiler.clone(),
+        ))
+        .tag(tags::IncludeRelativeTag::new(
+            source_dir.to_path_buf(),
+            shared.dependencies.clone(),
+            shared.profiler.clone(),
+        ))
+        .tag(tags::PostUrlTag::new(shared.posts.clone()))
+        .tag(tags::LinkTag::new(shared.documents.clone()))
+        .tag(tags::SeoTag::new(options.seo.clone()))
+        .tag(tags::TranslateTag)
+        .tag(tags::CommentsTag::new(options.comments.clone(), options.production))
+        .filter(filters::RelativeUrl)
+        .filter(filters::AbsoluteUrl)
+        .filter(filters::AssetUrl::new(shared.assets.clone(), shared.dependencies.clone()))
+        .filter(filters::Slugify)
+        .filter(filters::Jsonify)
+        .filter(filters::Inspect)
+        .filter(filters::Where)
+        .filter(filters::WhereExp)
+        .filter(filters::Find)
+        .filter(filters::FindExp)
+        .filter(filters::Push)
+        .filter(filters::Pop)
+        .filter(filters::Shift)
+        .filter(filters::Unshift)
+        .filter(filters::Sample)
+        .filter(filters::XmlEscape)
+        .filter(filters::CgiEscape)
+        .filter(filters::UriEscape)
+        .filter(filters::NumberOfWords)
+        .filter(filters::NormalizeWhitespace)
+        .filter(filters::Smartify)
+        .filter(filters::ReadingTime)
+        .filter(filters::Date)
+        .filter(filters::Toc)
+        .filter(filters::Translate)
+        .filter(filters::StripHtml)
+        .filter(filters::TruncateHtml)
+        .filter(filters::Excerpt);
+    builder = unsupported::register_stand_ins(builder);
+    
+    // Registered last so they win over built-ins of the same name
+    for filter in &custom.filters {
+        builder = builder.filter(filter.clone());
+    }
+    for tag in &custom.tags {
+        builder = builder.tag(tag.clone());
+    }
+    for block in &custom.blocks {
+        builder = builder.block(block.clone());
+    }
+    
+    builder
+        .partials(LazyCompiler::new(includes))
+        .build()
+        .expect("built-in Liquid tags and filters are valid")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::temp_site;
     
     #[test]
     fn test_template_engine_creation() {
         let engine = TemplateEngine::new(PathBuf::from("."));
         assert!(engine.source_dir.ends_with("."));
     }
+    
+    #[test]
+    fn test_include_from_includes_dir() {
+        let dir = temp_site("include");
+        fs::create_dir_all(dir.join("_includes/nav")).unwrap();
+        fs::write(dir.join("_includes/header.html"), "<header>{{ site.title }}</header>").unwrap();
+        fs::write(dir.join("_includes/nav/menu.html"), "<nav></nav>").unwrap();
+        
+        let engine = TemplateEngine::new(dir.clone());
+        let page = Page::new(dir.join("index.html"));
+        let output = engine
+            .render_page_content(
+                "{% include header.html %}{% include nav/menu.html %}",
+                &page,
+                &Site::new(),
+                &Config::default(),
+            )
+            .unwrap();
+        
+        assert_eq!(output, "<header>My Site</header><nav></nav>");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_page_toc() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let mut page = Page::new(PathBuf::from("guide.md"));
+        page.toc = filters::toc_entries("<h2 id=\"install\">Install</h2><h3 id=\"linux\">Linux</h3>");
+        let template = "{% for h in page.toc %}{{ h.level }} {{ h.text }} #{{ h.id }}\
+                        {% for c in h.children %} > {{ c.text }}{% endfor %}{% endfor %}";
+        let output = engine
+            .render_page_content(template, &page, &Site::new(), &Config::default())
+            .unwrap();
+        assert_eq!(output, "2 Install #install > Linux");
+        
+        // A `toc:` front matter flag is left alone
+        page.front_matter.custom.insert("toc".to_string(), serde_yaml::Value::Bool(true));
+        let output = engine
+            .render_page_content("{{ page.toc }}", &page, &Site::new(), &Config::default())
+            .unwrap();
+        assert_eq!(output, "true");
+    }
+    
+    #[test]
+    fn test_theme_fallback() {
+        let dir = temp_site("theme");
+        let theme = dir.join("theme");
+        fs::create_dir_all(dir.join("_includes")).unwrap();
+        fs::create_dir_all(theme.join("_includes")).unwrap();
+        fs::create_dir_all(theme.join("_layouts")).unwrap();
+        fs::write(dir.join("_includes/footer.html"), "site footer").unwrap();
+        fs::write(theme.join("_includes/footer.html"), "theme footer").unwrap();
+        fs::write(theme.join("_includes/nav.html"), "theme nav").unwrap();
+        fs::write(theme.join("_layouts/default.html"), "{% include nav.html %}|{{ content }}|{% include footer.html %}").unwrap();
+        
+        let engine = TemplateEngine::new(dir.clone());
+        assert!(!engine.has_layout("default"));
+        
+        let engine = engine.with_theme(theme);
+        assert!(engine.has_layout("default"));
+        let mut page = Page::new(dir.join("index.html"));
+        page.html = "body".to_string();
+        let output = engine.render_page(&page, &Site::new(), &Config::default()).unwrap();
+        assert_eq!(output, "theme nav|body|site footer");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_include_cached() {
+        let dir = temp_site("include-cached");
+        fs::create_dir_all(dir.join("_includes")).unwrap();
+        fs::write(dir.join("_includes/nav.html"), "{{ include.active }}:{{ page.title }}").unwrap();
+        
+        let engine = TemplateEngine::new(dir.clone());
+        let render = |content: &str, title: &str| {
+            let mut page = Page::new(dir.join("index.html"));
+            page.front_matter.title = Some(title.to_string());
+            engine.render_page_content(content, &page, &Site::new(), &Config::default()).unwrap()
+        };
+        assert_eq!(render("{% include_cached nav.html active='home' %}", "One"), "home:One");
+        
+        // Reused until cleared, while other parameters render anew
+        assert_eq!(render("{% include_cached nav.html active='home' %}", "Two"), "home:One");
+        assert_eq!(render("{% include_cached nav.html active='blog' %}", "Two"), "blog:Two");
+        engine.clear_include_cache();
+        assert_eq!(render("{% include_cached nav.html active='home' %}", "Two"), "home:Two");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_include_with_parameters() {
+        let dir = temp_site("include-params");
+        fs::create_dir_all(dir.join("_includes")).unwrap();
+        fs::write(
+            dir.join("_includes/card.html"),
+            "<div>{{ include.title }}-{{ include.count }}</div>",
+        )
+        .unwrap();
+        
+        let engine = TemplateEngine::new(dir.clone());
+        let page = Page::new(dir.join("index.html"));
+        let output = engine
+            .render_page_content(
+                "{% assign n = 3 %}{% include card.html title=\"Foo\" count=n %}",
+                &page,
+                &Site::new(),
+                &Config::default(),
+            )
+            .unwrap();
+        
+        assert_eq!(output, "<div>Foo-3</div>");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_include_relative() {
+        let dir = temp_site("include-relative");
+        fs::create_dir_all(dir.join("docs/snippets")).unwrap();
+        fs::write(dir.join("docs/snippets/note.md"), "Note for {{ include.who }}").unwrap();
+        
+        let engine = TemplateEngine::new(dir.clone());
+        let page = Page::new(dir.join("docs/guide.md"));
+        let output = engine
+            .render_page_content(
+                "{% include_relative snippets/note.md who=\"you\" %}",
+                &page,
+                &Site::new(),
+                &Config::default(),
+            )
+            .unwrap();
+        
+        assert_eq!(output, "Note for you");
+        
+        let escaping = engine.render_page_content(
+            "{% include_relative \"../secret.txt\" %}",
+            &page,
+            &Site::new(),
+            &Config::default(),
+        );
+        assert!(escaping.is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_url_filters_use_config() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let page = Page::new(PathBuf::from("index.html"));
+        let config = Config {
+            url: "https://example.com".to_string(),
+            baseurl: "/blog".to_string(),
+            ..Config::default()
+        };
+        let output = engine
+            .render_page_content(
+                "{{ '/assets/style.css' | relative_url }} {{ 'feed.xml' | absolute_url }}",
+                &page,
+                &Site::new(),
+                &config,
+            )
+            .unwrap();
+        
+        assert_eq!(output, "/blog/assets/style.css https://example.com/blog/feed.xml");
+    }
+    
+    #[test]
+    fn test_array_filters_build_navigation() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let page = Page::new(PathBuf::from("index.html"));
+        let template = "{% assign nav = '' | split: ',' | push: 'Home' | push: 'Blog' %}\
+            {% assign extra = 'About,Contact' | split: ',' %}\
+            {% assign nav = nav | concat: extra | unshift: 'Start' | pop | shift %}\
+            {{ nav | join: ',' }}";
+        
+        let output = engine
+            .render_page_content(template, &page, &Site::new(), &Config::default())
+            .unwrap();
+        
+        assert_eq!(output, "Home,Blog,About");
+    }
+    
+    #[test]
+    fn test_convenience_collections() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let mut site = Site::new();
+        for name in ["a.html", "feed.xml", "docs/"] {
+            let mut page = Page::new(PathBuf::from(name));
+            page.url = name.to_string();
+            site.pages.push(page);
+        }
+        for i in 0..12 {
+            let mut post = Post::new(PathBuf::from(format!("_posts/2024-01-{:02}-p.md", i)));
+            post.url = format!("/p{}.html", i);
+            site.posts.push(post);
+        }
+        let template = "{{ site.html_pages | map: 'url' | join: ',' }}|{{ site.documents.size }}|\
+            {{ site.related_posts.size }}:{{ site.related_posts.first.url }}|\
+            {% for c in site.collections %}{{ c.label }}={{ c.docs.size }},{{ c.docs.first.collection }}{% endfor %}";
+        
+        let page = Page::new(PathBuf::from("index.html"));
+        let output = engine
+            .render_page_content(template, &page, &site, &Config::default())
+            .unwrap();
+        assert_eq!(output, "a.html,docs/|12|10:/p0.html|posts=12,posts");
+        
+        let post = site.posts[0].clone();
+        let mut globals = Object::new();
+        globals.insert("site".into(), engine.site_to_value(&site, &Config::default(), Some(&post)));
+        let output = engine.parse(Path::new("test.html"), template).unwrap().render(&globals).unwrap();
+        assert_eq!(output, "a.html,docs/|12|10:/p1.html|posts=12,posts");
+        
+        site.feed_posts = vec![3, 1];
+        let output = engine
+            .render_page_content("{{ site.feed.posts | map: 'url' | join: ',' }}", &page, &site, &Config::default())
+            .unwrap();
+        assert_eq!(output, "/p3.html,/p1.html");
+    }
+    
+    #[test]
+    fn test_post_content() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let mut site = Site::new();
+        for (day, html) in [(2, "<p>Second <em>post</em> body</p>"), (1, "<p>First &amp; foremost</p><p>More</p>")] {
+            let mut post = Post::new(PathBuf::from(format!("_posts/2024-01-0{}-p.md", day)));
+            post.html = html.to_string();
+            site.posts.push(post);
+        }
+        
+        let template = "{% for post in site.posts %}{{ post.content | strip_html }}|\
+            {{ post.content | truncate_html: 6 }}|{{ post.content | excerpt }};{% endfor %}";
+        let page = Page::new(PathBuf::from("index.html"));
+        let output = engine
+            .render_page_content(template, &page, &site, &Config::default())
+            .unwrap();
+        assert_eq!(
+            output,
+            "Second post body|<p>Second...</p>|Second <em>post</em> body;\
+            First &amp; foremost More|<p>First...</p>|First &amp; foremost;"
+        );
+    }
+    
+    #[test]
+    fn test_post_neighbours() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let mut site = Site::new();
+        for (i, slug) in ["new", "mid", "old"].iter().enumerate() {
+            let mut post = Post::new(PathBuf::from(format!("_posts/2024-01-0{}-{}.md", 3 - i, slug)));
+            post.url = format!("/{}.html", slug);
+            post.next = i.checked_sub(1);
+            post.previous = (i < 2).then_some(i + 1);
+            site.posts.push(post);
+        }
+        
+        let template = "{% for post in site.posts %}{{ post.previous.url }}<{{ post.url }}>{{ post.next.url }} {% endfor %}";
+        let page = Page::new(PathBuf::from("index.html"));
+        let output = engine
+            .render_page_content(template, &page, &site, &Config::default())
+            .unwrap();
+        assert_eq!(output, "/mid.html</new.html> /old.html</mid.html>/new.html </old.html>/mid.html ");
+        
+        let mut globals = Object::new();
+        globals.insert("page".into(), engine.post_to_value(&site.posts[1], &site));
+        let output = engine
+            .parse(Path::new("test.html"), "{{ page.next.url }} {{ page.previous.url }} {{ page.next.next }}")
+            .unwrap()
+            .render(&globals)
+            .unwrap();
+        assert_eq!(output, "/new.html /old.html ");
+    }
+    
+    #[test]
+    fn test_context_provider() {
+        struct Git;
+        impl ContextProvider for Git {
+            fn globals(&self, _site: &Site) -> Object {
+                liquid::object!({ "git": { "commit": "abc123" }, "site": "ignored" })
+            }
+            
+            fn document(&self, document: Document<'_>, _site: &Site) -> Object {
+                let name = document.path().file_name().unwrap().to_string_lossy().to_string();
+                liquid::object!({ "last_author": name, "title": "ignored" })
+            }
+        }
+        
+        let engine = TemplateEngine::new(PathBuf::from(".")).with_context(Git);
+        let mut page = Page::new(PathBuf::from("about.md"));
+        page.front_matter.title = Some("About".to_string());
+        let output = engine
+            .render_page_content(
+                "{{ git.commit }} {{ page.last_author }} {{ page.title }} {{ site.title }}",
+                &page,
+                &Site::new(),
+                &Config::default(),
+            )
+            .unwrap();
+        assert_eq!(output, "abc123 about.md About My Site");
+    }
+    
+    #[test]
+    fn test_custom_front_matter() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let mut page = Page::new(PathBuf::from("index.html"));
+        page.front_matter = serde_yaml::from_str(
+            "title: Home\nsubtitle: Welcome\nhero:\n  image: /a.png\n  links: [one, two]\nurl: /spoofed",
+        )
+        .unwrap();
+        page.url = "index.html".to_string();
+        
+        let output = engine
+            .render_page_content(
+                "{{ page.subtitle }} {{ page.hero.image }} {{ page.hero.links | join: '+' }} {{ page.url }}",
+                &page,
+                &Site::new(),
+                &Config::default(),
+            )
+            .unwrap();
+        assert_eq!(output, "Welcome /a.png one+two index.html");
+    }
+    
+    #[test]
+    fn test_related_documents() {
+        let engine = TemplateEngine::new(PathBuf::from("/site"));
+        let mut site = Site::new();
+        let mut post = Post::new(PathBuf::from("/site/_posts/2024-01-01-post.md"));
+        post.front_matter = serde_yaml::from_str("title: Post\nrelated: [guide/intro.md]").unwrap();
+        site.posts.push(post);
+        let mut page = Page::new(PathBuf::from("/site/guide/intro.md"));
+        page.front_matter = serde_yaml::from_str("title: Intro\nrelated: [2024-01-01-post]").unwrap();
+        site.pages.push(page);
+        
+        engine.check_related(&site).unwrap();
+        let output = engine
+            .render_page_content("{{ page.related[0].title }}", &site.pages[0], &site, &Config::default())
+            .unwrap();
+        assert_eq!(output, "Post");
+        
+        site.pages[0].front_matter = serde_yaml::from_str("related: [_posts/2024-01-01-post.md, nope.md]").unwrap();
+        let err = engine.check_related(&site).unwrap_err();
+        assert!(err.to_string().contains("\"nope.md\" referenced by guide/intro.md"));
+    }
+    
+    #[test]
+    fn test_site_tags_and_categories() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let mut site = Site::new();
+        for (url, front_matter) in [
+            ("/a.html", "tags: [rust, web]\ncategories: [blog]"),
+            ("/b.html", "tags: [rust]"),
+        ] {
+            let mut post = Post::new(PathBuf::from(url));
+            post.url = url.to_string();
+            post.front_matter = serde_yaml::from_str(front_matter).unwrap();
+            site.posts.push(post);
+        }
+        
+        let page = Page::new(PathBuf::from("index.html"));
+        let output = engine
+            .render_page_content(
+                "{{ site.tags.rust | map: 'url' | join: ',' }}|{{ site.tags.web.size }}|\
+                 {{ site.categories.blog[0].url }}|{{ site.tags.size }}",
+                &page,
+                &site,
+                &Config::default(),
+            )
+            .unwrap();
+        assert_eq!(output, "/a.html,/b.html|1|/a.html|2");
+    }
+    
+    #[test]
+    fn test_site_time_in_timezone() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let page = Page::new(PathBuf::from("index.html"));
+        let mut site = Site::new();
+        site.time = "2024-07-01T12:00:00Z".parse().unwrap();
+        let template = "{{ site.time }}|{{ site.time | date: '%H:%M' }}";
+        
+        let output = engine
+            .render_page_content(template, &page, &site, &Config::default())
+            .unwrap();
+        assert_eq!(output, "2024-07-01 12:00:00 +0000|12:00");
+        
+        let config = Config {
+            timezone: Some("America/New_York".to_string()),
+            ..Config::default()
+        };
+        let output = engine.render_page_content(template, &page, &site, &config).unwrap();
+        assert_eq!(output, "2024-07-01 08:00:00 -0400|08:00");
+        
+        let config = Config {
+            locale: Some("fr_FR".to_string()),
+            ..Config::default()
+        };
+        let template = "{{ site.time | date: '%A %-d %B' }}|{{ site.time | date: '%B', 'en' }}";
+        let output = engine.render_page_content(template, &page, &site, &config).unwrap();
+        assert_eq!(output, "lundi 1 juillet|July");
+    }
+    
+    #[test]
+    fn test_paginator() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let mut site = Site::new();
+        for i in 0..3 {
+            let mut post = Post::new(PathBuf::from(format!("{}.md", i)));
+            post.url = format!("/p{}.html", i);
+            site.posts.push(post);
+        }
+        let mut page = Page::new(PathBuf::from("index.html"));
+        page.paginator = Some(Paginator {
+            page: 2,
+            per_page: 2,
+            posts: vec![2],
+            total_posts: 3,
+            total_pages: 2,
+            previous_page: Some(1),
+            previous_page_path: Some("/".to_string()),
+            next_page: None,
+            next_page_path: None,
+        });
+        
+        let output = engine
+            .render_page_content(
+                "{% for post in paginator.posts %}{{ post.url }}{% endfor %} \
+                 {{ paginator.page }}/{{ paginator.total_pages }} \
+                 {{ paginator.previous_page_path }}{% if paginator.next_page_path %}next{% endif %}",
+                &page,
+                &site,
+                &Config::default(),
+            )
+            .unwrap();
+        assert_eq!(output, "/p2.html 2/2 /");
+    }
+    
+    #[test]
+    fn test_page_variables() {
+        let engine = TemplateEngine::new(PathBuf::from("/site"));
+        let mut page = Page::new(PathBuf::from("/site/docs/intro.md"));
+        page.url = "docs/intro.html".to_string();
+        page.html = "<p>Intro</p>".to_string();
+        page.excerpt = "Intro".to_string();
+        page.date = Some("2024-01-15T10:30:00Z".parse().unwrap());
+        page.front_matter = serde_yaml::from_str("title: Intro\ntags: [a]").unwrap();
+        
+        let output = engine
+            .render_page(&page, &Site::new(), &Config::default())
+            .unwrap();
+        assert_eq!(output, "<p>Intro</p>");
+        
+        let output = engine
+            .render_page_content(
+                "{{ page.dir }}|{{ page.name }}|{{ page.path }}|{{ page.excerpt }}|\
+                 {{ page.date | date: '%Y/%m/%d' }}|{{ page.tags[0] }}|{{ page.content }}",
+                &page,
+                &Site::new(),
+                &Config::default(),
+            )
+            .unwrap();
+        assert_eq!(output, "/docs/|intro.md|docs/intro.md|Intro|2024/01/15|a|<p>Intro</p>");
+        assert_eq!(url_dir("index.html"), "/");
+        assert_eq!(url_dir("/blog/page2/index.html"), "/blog/page2/");
+    }
+    
+    #[test]
+    fn test_layout_front_matter() {
+        let dir = temp_site("layout-vars");
+        fs::create_dir_all(dir.join("_layouts")).unwrap();
+        fs::write(
+            dir.join("_layouts/base.html"),
+            "---\nsidebar: true\ntheme: dark\n---\n<body class=\"{{ layout.theme }}\">{% if layout.sidebar %}[side]{% endif %}{{ content }}</body>",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("_layouts/post.html"),
+            "---\nlayout: base\ntheme: light\n---\n<article>{{ layout.theme }}:{{ content }}</article>",
+        )
+        .unwrap();
+        
+        let engine = TemplateEngine::new(dir.clone());
+        let mut page = Page::new(dir.join("index.html"));
+        page.html = "Hi".to_string();
+        page.front_matter.layout = Some("post".to_string());
+        
+        let output = engine.render_page(&page, &Site::new(), &Config::default()).unwrap();
+        assert_eq!(output, "<body class=\"light\">[side]<article>light:Hi</article></body>");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_layout_cycle() {
+        let dir = temp_site("layout-cycle");
+        fs::create_dir_all(dir.join("_layouts")).unwrap();
+        fs::write(dir.join("_layouts/post.html"), "---\nlayout: a\n---\n{{ content }}").unwrap();
+        fs::write(dir.join("_layouts/a.html"), "---\nlayout: b\n---\n{{ content }}").unwrap();
+        fs::write(dir.join("_layouts/b.html"), "---\nlayout: a\n---\n{{ content }}").unwrap();
+        
+        let engine = TemplateEngine::new(dir.clone());
+        let mut page = Page::new(dir.join("index.html"));
+        page.front_matter.layout = Some("post".to_string());
+        
+        let error = engine.render_page(&page, &Site::new(), &Config::default()).unwrap_err();
+        assert_eq!(error.to_string(), "layout cycle: a → b → a");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_liquid_in_data() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let page = Page::new(PathBuf::from("index.html"));
+        let mut site = Site::new();
+        site.data.insert(
+            "nav".to_string(),
+            serde_yaml::from_str("- title: \"{{ site.title | upcase }} home\"\n  weight: 1").unwrap(),
+        );
+        let template = "{{ site.data.nav[0].title }}/{{ site.data.nav[0].weight }}";
+        
+        let output = engine
+            .render_page_content(template, &page, &site, &Config::default())
+            .unwrap();
+        assert_eq!(output, "{{ site.title | upcase }} home/1");
+        
+        let config = Config {
+            liquid_in_data: true,
+            ..Config::default()
+        };
+        engine.set_site(&site, &config);
+        let output = engine.render_page_content(template, &page, &site, &config).unwrap();
+        assert_eq!(output, "MY SITE home/1");
+        
+        engine.clear_site();
+        let output = engine.render_page_content(template, &page, &site, &config).unwrap();
+        assert_eq!(output, "{{ site.title | upcase }} home/1");
+    }
+    
+    #[test]
+    fn test_site_variables_built_once() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let page = Page::new(PathBuf::from("index.html"));
+        let mut site = Site::new();
+        for name in ["a", "b"] {
+            let mut post = Post::new(PathBuf::from(format!("_posts/2024-01-01-{}.md", name)));
+            post.url = format!("/{}.html", name);
+            site.posts.push(post);
+        }
+        let template = "{{ site.posts.size }}:{{ site.related_posts | map: 'url' | join: ',' }}";
+        
+        engine.set_site(&site, &Config::default());
+        site.posts.push(Post::new(PathBuf::from("_posts/2024-01-01-c.md")));
+        for (post, related) in [(0, "/b.html,"), (1, "/a.html,")] {
+            let output = engine
+                .render_post_content(template, &site.posts[post], &site, &Config::default())
+                .unwrap();
+            assert_eq!(output, format!("2:{}", related));
+        }
+        
+        engine.set_site(&site, &Config::default());
+        let output = engine.render_page_content(template, &page, &site, &Config::default()).unwrap();
+        assert_eq!(output, "3:/a.html,/b.html,");
+    }
+    
+    #[test]
+    fn test_post_url_tag() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let page = Page::new(PathBuf::from("index.html"));
+        engine.set_post_urls(HashMap::from([(
+            "2024-01-15-hello".to_string(),
+            "/2024/01/15/hello/".to_string(),
+        )]));
+        
+        let output = engine
+            .render_page_content(
+                "<a href=\"{{ site.baseurl }}{% post_url 2024-01-15-hello %}\">Hello</a>",
+                &page,
+                &Site::new(),
+                &Config::default(),
+            )
+            .unwrap();
+        assert_eq!(output, "<a href=\"/2024/01/15/hello/\">Hello</a>");
+        
+        let missing = engine
+            .render_page_content("{% post_url 2024-01-15-nope %}", &page, &Site::new(), &Config::default())
+            .unwrap_err();
+        assert!(format!("{:?}", missing).contains("2024-01-15-nope"));
+    }
+    
+    #[test]
+    fn test_link_tag() {
+        let engine = TemplateEngine::new(PathBuf::from("."));
+        let page = Page::new(PathBuf::from("index.html"));
+        engine.set_document_urls(HashMap::from([(
+            "docs/intro.md".to_string(),
+            "/guide/".to_string(),
+        )]));
+        
+        let output = engine
+            .render_page_content("{% link docs/intro.md %}", &page, &Site::new(), &Config::default())
+            .unwrap();
+        assert_eq!(output, "/guide/");
+        
+        let missing = engine
+            .render_page_content("{% link docs/nope.md %}", &page, &Site::new(), &Config::default())
+            .unwrap_err();
+        assert!(format!("{:?}", missing).contains("docs/nope.md"));
+    }
+    
+    #[test]
+    fn test_strict_liquid_options() {
+        let dir = temp_site("strict");
+        fs::create_dir_all(dir.join("_includes")).unwrap();
+        fs::write(dir.join("_includes/card.html"), "[{{ include.nope }}{{ page.nope.deeper }}]").unwrap();
+        
+        let mut page = Page::new(dir.join("index.html"));
+        page.front_matter.title = Some("Hi".to_string());
+        let render = |engine: &TemplateEngine, content: &str| {
+            engine.render_page_content(content, &page, &Site::new(), &Config::default())
+        };
+        let lenient = "{{ page.nope }}|{{ nope | default: 'd' }}|{{ page.title | nope: 1 | upcase }}|\
+                       {% assign items = 'a,b' | split: ',' %}{% for i in items %}{{ i.nope }}{{ i }}{% endfor %}|\
+                       {% include card.html %}";
+        
+        let mut engine = TemplateEngine::new(dir.clone());
+        assert_eq!(render(&engine, lenient).unwrap(), "|d|HI|ab|[]");
+        
+        engine.set_liquid_options(LiquidConfig { strict_variables: true, ..Default::default() });
+        let error = render(&engine, "{{ page.nope }}").unwrap_err();
+        assert!(format!("{:#}", error).contains("Unknown index"), "{:#}", error);
+        assert_eq!(render(&engine, "{{ page.title | nope }}").unwrap(), "Hi");
+        
+        engine.set_liquid_options(LiquidConfig { strict_variables: true, strict_filters: true });
+        assert!(render(&engine, "{{ page.title | nope }}").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_register_filter() {
+        use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+        use liquid_core::{Runtime, ValueView};
+        
+        #[derive(Clone, ParseFilter, FilterReflection)]
+        #[filter(name = "shout", description = "Upper-case with emphasis.", parsed(ShoutFilter))]
+        struct Shout;
+        
+        #[derive(Debug, Default, Display_filter)]
+        #[name = "shout"]
+        struct ShoutFilter;
+        
+        impl Filter for ShoutFilter {
+            fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> liquid_core::Result<Value> {
+                Ok(Value::scalar(format!("{}!", input.to_kstr().to_uppercase())))
+            }
+        }
+        
+        let page = Page::new(PathBuf::from("index.html"));
+        let render = |engine: &TemplateEngine| {
+            engine.render_page_content("{{ 'hi' | shout }}", &page, &Site::new(), &Config::default())
+        };
+        
+        let mut engine = TemplateEngine::new(PathBuf::from("."));
+        assert_eq!(render(&engine).unwrap(), "hi");
+        engine.set_liquid_options(LiquidConfig { strict_filters: true, ..Default::default() });
+        assert!(render(&engine).is_err());
+        
+        engine.register_filter(Shout);
+        assert_eq!(render(&engine).unwrap(), "HI!");
+        
+        let engine = TemplateEngine::new(PathBuf::from(".")).with_filter(Shout);
+        assert_eq!(render(&engine).unwrap(), "HI!");
+    }
+    
+    #[test]
+    fn test_register_tag_and_block() {
+        use liquid_core::error::ResultLiquidReplaceExt;
+        use liquid_core::parser::{BlockReflection, TagBlock, TagReflection, TagTokenIter};
+        use liquid_core::{Language, Renderable, Runtime};
+        use std::io::Write;
+        
+        /// `{% youtube id %}`
+        #[derive(Clone)]
+        struct YoutubeTag;
+        
+        impl TagReflection for YoutubeTag {
+            fn tag(&self) -> &'static str {
+                "youtube"
+            }
+            
+            fn description(&self) -> &'static str {
+                "Embed a YouTube video"
+            }
+        }
+        
+        impl ParseTag for YoutubeTag {
+            fn parse(&self, mut arguments: TagTokenIter<'_>, _options: &Language) -> liquid_core::Result<Box<dyn Renderable>> {
+                let id = arguments.expect_next("Video id expected.")?.as_str().to_string();
+                Ok(Box::new(Youtube(id)))
+            }
+            
+            fn reflection(&self) -> &dyn TagReflection {
+                self
+            }
+        }
+        
+        #[derive(Debug)]
+        struct Youtube(String);
+        
+        impl Renderable for Youtube {
+            fn render_to(&self, writer: &mut dyn Write, _runtime: &dyn Runtime) -> liquid_core::Result<()> {
+                write!(writer, "<iframe src=\"https://www.youtube.com/embed/{}\"></iframe>", self.0)
+                    .replace("Failed to render")
+            }
+        }
+        
+        /// `{% note %}...{% endnote %}`
+        #[derive(Clone)]
+        struct NoteBlock;
+        
+        impl BlockReflection for NoteBlock {
+            fn start_tag(&self) -> &'static str {
+                "note"
+            }
+            
+            fn end_tag(&self) -> &'static str {
+                "endnote"
+            }
+            
+            fn description(&self) -> &'static str {
+                "Wrap content in a note box"
+            }
+        }
+        
+        impl ParseBlock for NoteBlock {
+            fn parse(
+                &self,
+                _arguments: TagTokenIter<'_>,
+                mut block: TagBlock<'_, '_>,
+                options: &Language,
+            ) -> liquid_core::Result<Box<dyn Renderable>> {
+                let body = block.parse_all(options)?;
+                block.assert_empty();
+                Ok(Box::new(Note(liquid_core::runtime::Template::new(body))))
+            }
+            
+            fn reflection(&self) -> &dyn BlockReflection {
+                self
+            }
+        }
+        
+        #[derive(Debug)]
+        struct Note(liquid_core::runtime::Template);
+        
+        impl Renderable for Note {
+            fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> liquid_core::Result<()> {
+                write!(writer, "<aside>").replace("Failed to render")?;
+                self.0.render_to(writer, runtime)?;
+                write!(writer, "</aside>").replace("Failed to render")
+            }
+        }
+        
+        let engine = TemplateEngine::new(PathBuf::from("."))
+            .with_tag(YoutubeTag)
+            .with_block(NoteBlock);
+        let page = Page::new(PathBuf::from("index.html"));
+        let output = engine
+            .render_page_content(
+                "{% note %}{{ site.title }}{% endnote %}{% youtube abc123 %}",
+                &page,
+                &Site::new(),
+                &Config::default(),
+            )
+            .unwrap();
+        
+        assert_eq!(
+            output,
+            "<aside>My Site</aside><iframe src=\"https://www.youtube.com/embed/abc123\"></iframe>"
+        );
+    }
+    
+    #[test]
+    fn test_dependencies_are_recorded() {
+        let dir = temp_site("dependencies");
+        fs::create_dir_all(dir.join("_includes")).unwrap();
+        fs::create_dir_all(dir.join("_layouts")).unwrap();
+        fs::write(dir.join("_includes/header.html"), "{% include nav.html %}").unwrap();
+        fs::write(dir.join("_includes/nav.html"), "<nav></nav>").unwrap();
+        fs::write(dir.join("_layouts/default.html"), "{% include header.html %}{{ content }}").unwrap();
+        
+        let engine = TemplateEngine::new(dir.clone());
+        let page = Page::new(dir.join("index.html"));
+        engine.render_page(&page, &Site::new(), &Config::default()).unwrap();
+        
+        assert_eq!(
+            engine.take_dependencies(),
+            vec![
+                PathBuf::from("_includes/header.html"),
+                PathBuf::from("_includes/nav.html"),
+                PathBuf::from("_layouts/default.html"),
+            ]
+        );
+        assert!(engine.take_dependencies().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_missing_include_is_error() {
+        let dir = temp_site("missing-include");
+        let engine = TemplateEngine::new(dir.clone());
+        let page = Page::new(dir.join("index.html"));
+        let result = engine.render_page_content(
+            "{% include nope.html %}",
+            &page,
+            &Site::new(),
+            &Config::default(),
+        );
+        
+        assert!(result.is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
 
@@ -0,0 +1,120 @@
+use crate::tags::{outputs_to_echo, quote_include_paths};
+use crate::unsupported::UnsupportedCheck;
+use jellrust_markdown::strip_bom;
+use jellrust_types::paths::relative_to;
+use liquid::partials::PartialSource;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use walkdir::WalkDir;
+
+/// Partial source backed by the site's `_includes/` directory
+///
+/// Partials are addressed by their path relative to `_includes/`, using `/`
+/// as separator (e.g. `header.html` or `nav/menu.html`). Includes missing
+/// from the site are looked up in the fallback directories, such as a theme's.
+#[derive(Debug, Default)]
+pub struct IncludesSource {
+    /// Name -> file, from the first directory that has it
+    files: BTreeMap<String, PathBuf>,
+    unsupported: Arc<UnsupportedCheck>,
+    /// Render `{{ }}` through the `echo` tag, see [`crate::tags::EchoTag`]
+    echo_outputs: bool,
+}
+
+impl IncludesSource {
+    pub fn new(root: PathBuf) -> Self {
+        Self::default().with_fallback(root)
+    }
+
+    /// Also serve the includes of `root` that aren't found in earlier directories
+    pub fn with_fallback(mut self, root: PathBuf) -> Self {
+        for name in scan_includes(&root) {
+            let path = root.join(&name);
+            self.files.entry(name).or_insert(path);
+        }
+        self
+    }
+
+    /// Warn about unsupported Jekyll constructs in includes with `check`
+    pub fn with_unsupported_check(mut self, check: Arc<UnsupportedCheck>) -> Self {
+        self.unsupported = check;
+        self
+    }
+
+    /// Rewrite `{{ }}` output markup to `{% echo %}` tags
+    pub fn with_echo_outputs(mut self, enabled: bool) -> Self {
+        self.echo_outputs = enabled;
+        self
+    }
+}
+
+impl PartialSource for IncludesSource {
+    fn contains(&self, name: &str) -> bool {
+        self.files.contains_key(name)
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.files.keys().map(|n| n.as_str()).collect()
+    }
+
+    fn try_get<'a>(&'a self, name: &str) -> Option<Cow<'a, str>> {
+        let path = self.files.get(name)?;
+
+        let content = fs::read_to_string(path).ok()?;
+        let content = strip_bom(&content);
+        self.unsupported.check(&Path::new("_includes").join(name), content);
+        let content = quote_include_paths(content);
+        if self.echo_outputs {
+            Some(Cow::Owned(outputs_to_echo(&content).into_owned()))
+        } else {
+            Some(Cow::Owned(content.into_owned()))
+        }
+    }
+}
+
+/// Collect the names of all files below the includes directory
+fn scan_includes(root: &Path) -> Vec<String> {
+    if !root.exists() {
+        return Vec::new();
+    }
+
+    WalkDir::new(root)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| relative_to(e.path(), root))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_site;
+
+    #[test]
+    fn test_missing_includes_dir() {
+        let source = IncludesSource::new(PathBuf::from("/nonexistent/_includes"));
+        assert!(source.names().is_empty());
+        assert!(!source.contains("header.html"));
+    }
+
+    #[test]
+    fn test_fallback_includes() {
+        let dir = temp_site("partials");
+        fs::create_dir_all(dir.join("site")).unwrap();
+        fs::create_dir_all(dir.join("theme")).unwrap();
+        fs::write(dir.join("site/header.html"), "site header").unwrap();
+        fs::write(dir.join("theme/header.html"), "theme header").unwrap();
+        fs::write(dir.join("theme/footer.html"), "theme footer").unwrap();
+
+        let source = IncludesSource::new(dir.join("site")).with_fallback(dir.join("theme"));
+        assert_eq!(source.names(), ["footer.html", "header.html"]);
+        assert_eq!(source.try_get("header.html").as_deref(), Some("site header"));
+        assert_eq!(source.try_get("footer.html").as_deref(), Some("theme footer"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
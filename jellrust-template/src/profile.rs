@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Time spent rendering each layout and include, for `jellrust build --profile`
+///
+/// Include timings contain the includes nested in them; layout timings do
+/// not contain their parent layouts. Nothing is recorded unless enabled.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    enabled: AtomicBool,
+    timings: Mutex<Timings>,
+}
+
+#[derive(Debug, Default)]
+struct Timings {
+    documents: Duration,
+    templates: HashMap<PathBuf, (usize, Duration)>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Run `render`, charging its time to the template at `path`
+    pub fn time<T>(&self, path: impl FnOnce() -> PathBuf, render: impl FnOnce() -> T) -> T {
+        if !self.is_enabled() {
+            return render();
+        }
+
+        let started = Instant::now();
+        let result = render();
+        let elapsed = started.elapsed();
+
+        let mut timings = self.timings.lock().expect("profiler poisoned");
+        let entry = timings.templates.entry(path()).or_default();
+        entry.0 += 1;
+        entry.1 += elapsed;
+        result
+    }
+
+    /// Run `render`, counting it towards the total render time
+    pub fn time_document<T>(&self, render: impl FnOnce() -> T) -> T {
+        if !self.is_enabled() {
+            return render();
+        }
+
+        let started = Instant::now();
+        let result = render();
+        self.timings.lock().expect("profiler poisoned").documents += started.elapsed();
+        result
+    }
+
+    /// Everything recorded so far, slowest templates first
+    pub fn report(&self) -> ProfileReport {
+        let timings = self.timings.lock().expect("profiler poisoned");
+        let mut templates: Vec<TemplateTiming> = timings
+            .templates
+            .iter()
+            .map(|(path, &(calls, time))| TemplateTiming {
+                path: path.clone(),
+                calls,
+                time,
+            })
+            .collect();
+        templates.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.path.cmp(&b.path)));
+
+        ProfileReport {
+            total: timings.documents,
+            templates,
+        }
+    }
+}
+
+/// Render time of one layout or include across the build
+#[derive(Debug, Clone)]
+pub struct TemplateTiming {
+    /// Path relative to the site source, e.g. `_includes/analytics.html`
+    pub path: PathBuf,
+    pub calls: usize,
+    pub time: Duration,
+}
+
+/// Per-template render times, printed as a table
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    /// Time spent rendering posts and pages, templates included
+    pub total: Duration,
+    pub templates: Vec<TemplateTiming>,
+}
+
+impl ProfileReport {
+    /// Share of the total render time spent in a template, in percent
+    pub fn share(&self, timing: &TemplateTiming) -> f64 {
+        if self.total.is_zero() {
+            return 0.0;
+        }
+        (timing.time.as_secs_f64() / self.total.as_secs_f64() * 100.0).min(100.0)
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .templates
+            .iter()
+            .map(|t| t.path.to_string_lossy().len())
+            .chain(["Template".len()])
+            .max()
+            .unwrap_or_default();
+
+        writeln!(f, "{:<width$}  {:>6}  {:>10}  {:>6}", "Template", "Calls", "Time", "Share")?;
+        for timing in &self.templates {
+            writeln!(
+                f,
+                "{:<width$}  {:>6}  {:>8.2}ms  {:>5.1}%",
+                timing.path.display(),
+                timing.calls,
+                timing.time.as_secs_f64() * 1000.0,
+                self.share(timing)
+            )?;
+        }
+        write!(f, "Total render time: {:.2}ms", self.total.as_secs_f64() * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_only_when_enabled() {
+        let profiler = Profiler::new();
+        profiler.time(|| PathBuf::from("_includes/a.html"), || ());
+        assert!(profiler.report().templates.is_empty());
+
+        profiler.set_enabled(true);
+        profiler.time_document(|| {
+            profiler.time(|| PathBuf::from("_includes/a.html"), || ());
+            profiler.time(|| PathBuf::from("_includes/a.html"), || ());
+        });
+
+        let report = profiler.report();
+        assert_eq!(report.templates.len(), 1);
+        assert_eq!(report.templates[0].calls, 2);
+        assert!(report.total >= report.templates[0].time);
+        assert!(report.to_string().contains("_includes/a.html"));
+    }
+}
@@ -0,0 +1,197 @@
+use super::seo::variable;
+use crate::filters::xml_escape;
+use jellrust_types::{CommentProvider, CommentsConfig};
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::model::ScalarCow;
+use liquid_core::{
+    Error, Language, ParseTag, Renderable, Result, Runtime, TagReflection, TagTokenIter, ValueView,
+};
+use std::io::Write;
+
+/// `{% comments %}` in a post layout: the comment widget of `comments.provider`
+///
+/// Outputs nothing outside production builds, without a provider, or on
+/// pages with `comments: false` in their front matter. The thread is picked
+/// by the page's `comment_id`, or else its `id` or URL, so threads survive
+/// changes to the site's domain.
+#[derive(Clone, Debug)]
+pub struct CommentsTag {
+    options: CommentsConfig,
+    production: bool,
+}
+
+impl CommentsTag {
+    pub fn new(options: CommentsConfig, production: bool) -> Self {
+        Self { options, production }
+    }
+}
+
+impl TagReflection for CommentsTag {
+    fn tag(&self) -> &'static str {
+        "comments"
+    }
+
+    fn description(&self) -> &'static str {
+        "Output the comment widget configured under `comments:`"
+    }
+}
+
+impl ParseTag for CommentsTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        arguments.expect_nothing()?;
+
+        let required: &[(&str, &Option<String>)] = match self.options.provider {
+            None => &[],
+            Some(CommentProvider::Giscus) => &[
+                ("repo", &self.options.repo),
+                ("repo_id", &self.options.repo_id),
+                ("category", &self.options.category),
+                ("category_id", &self.options.category_id),
+            ],
+            Some(CommentProvider::Utterances) => &[("repo", &self.options.repo)],
+            Some(CommentProvider::Isso) => &[("server", &self.options.server)],
+        };
+        if let Some((name, _)) = required.iter().find(|(_, value)| value.is_none()) {
+            return Error::with_msg("Missing comments setting")
+                .context("setting", format!("comments.{}", name))
+                .into_err();
+        }
+
+        Ok(Box::new(Comments {
+            options: self.options.clone(),
+            production: self.production,
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Comments {
+    options: CommentsConfig,
+    production: bool,
+}
+
+impl Renderable for Comments {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let Some(provider) = self.options.provider else {
+            return Ok(());
+        };
+        if !self.production || comments_disabled(runtime) {
+            return Ok(());
+        }
+
+        let thread = ["comment_id", "id", "url"].into_iter().find_map(|key| variable(runtime, "page", key));
+        let Some(thread) = thread else {
+            return Ok(());
+        };
+        let title = variable(runtime, "page", "title").unwrap_or_default();
+        let setting = |value: &Option<String>| xml_escape(value.as_deref().unwrap_or_default());
+        let theme = |default: &str| xml_escape(self.options.theme.as_deref().unwrap_or(default));
+
+        let widget = match provider {
+            CommentProvider::Giscus => format!(
+                concat!(
+                    "<script src=\"https://giscus.app/client.js\" data-repo=\"{}\" data-repo-id=\"{}\" ",
+                    "data-category=\"{}\" data-category-id=\"{}\" data-mapping=\"specific\" data-term=\"{}\" ",
+                    "data-reactions-enabled=\"1\" data-theme=\"{}\" crossorigin=\"anonymous\" async></script>",
+                ),
+                setting(&self.options.repo),
+                setting(&self.options.repo_id),
+                setting(&self.options.category),
+                setting(&self.options.category_id),
+                xml_escape(&thread),
+                theme("preferred_color_scheme"),
+            ),
+            CommentProvider::Utterances => {
+                let label = match &self.options.label {
+                    Some(label) => format!(" label=\"{}\"", xml_escape(label)),
+                    None => String::new(),
+                };
+                format!(
+                    "<script src=\"https://utteranc.es/client.js\" repo=\"{}\" issue-term=\"{}\"{} theme=\"{}\" crossorigin=\"anonymous\" async></script>",
+                    setting(&self.options.repo),
+                    xml_escape(&thread),
+                    label,
+                    theme("github-light"),
+                )
+            }
+            CommentProvider::Isso => {
+                let server = xml_escape(self.options.server.as_deref().unwrap_or_default().trim_end_matches('/'));
+                format!(
+                    "<script data-isso=\"{0}/\" src=\"{0}/js/embed.min.js\"></script>\n<section id=\"isso-thread\" data-isso-id=\"{1}\" data-title=\"{2}\"></section>",
+                    server,
+                    xml_escape(&thread),
+                    xml_escape(&title),
+                )
+            }
+        };
+
+        writeln!(writer, "<div class=\"comments\">\n{}\n</div>", widget).replace("Failed to render")?;
+        Ok(())
+    }
+}
+
+/// Whether the page turns comments off with `comments: false`
+fn comments_disabled(runtime: &dyn Runtime) -> bool {
+    let path = ["page", "comments"].map(|key| ScalarCow::new(key.to_string()));
+    runtime
+        .try_get(&path)
+        .and_then(|value| value.as_scalar().and_then(|scalar| scalar.to_bool()))
+        == Some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(globals: &liquid::Object, yaml: &str, production: bool) -> Result<String> {
+        let options: CommentsConfig = serde_yaml::from_str(yaml).unwrap();
+        let parser = liquid::ParserBuilder::with_stdlib()
+            .tag(CommentsTag::new(options, production))
+            .build()
+            .unwrap();
+        parser.parse("{% comments %}")?.render(globals)
+    }
+
+    #[test]
+    fn test_comments_tag() {
+        let globals = liquid::object!({
+            "page": { "title": "Hello \"world\"", "url": "/2024/hello.html" },
+        });
+
+        let giscus = "provider: giscus\nrepo: me/blog\nrepo_id: R_1\ncategory: Comments\ncategory_id: C_1";
+        let output = render(&globals, giscus, true).unwrap();
+        assert!(output.contains("data-repo=\"me/blog\""));
+        assert!(output.contains("data-term=\"/2024/hello.html\""));
+        assert!(output.contains("data-theme=\"preferred_color_scheme\""));
+
+        let output = render(&globals, "provider: utterances\nrepo: me/blog\nlabel: comments", true).unwrap();
+        assert!(output.contains("issue-term=\"/2024/hello.html\" label=\"comments\" theme=\"github-light\""));
+        let post = liquid::object!({ "page": { "url": "/2024/hello/", "id": "/2024/hello" } });
+        let output = render(&post, "provider: utterances\nrepo: me/blog", true).unwrap();
+        assert!(output.contains("issue-term=\"/2024/hello\""));
+
+        let output = render(&globals, "provider: isso\nserver: https://comments.example.com/", true).unwrap();
+        assert!(output.contains("src=\"https://comments.example.com/js/embed.min.js\""));
+        assert!(output.contains("data-title=\"Hello &quot;world&quot;\""));
+
+        // Nothing while previewing, without a provider, or when the page opts out
+        assert_eq!(render(&globals, giscus, false).unwrap(), "");
+        assert_eq!(render(&globals, "{}", true).unwrap(), "");
+        let opted_out = liquid::object!({ "page": { "url": "/a.html", "comments": false, "comment_id": "a" } });
+        assert_eq!(render(&opted_out, "provider: utterances\nrepo: me/blog", true).unwrap(), "");
+    }
+
+    #[test]
+    fn test_comments_tag_missing_setting() {
+        let error = render(&liquid::Object::new(), "provider: giscus\nrepo: me/blog", true).unwrap_err();
+        assert!(error.to_string().contains("comments.repo_id"));
+    }
+}
@@ -0,0 +1,226 @@
+use jellrust_types::LiquidConfig;
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::model::{KString, KStringCow, KStringRef, ScalarCow, ValueCow};
+use liquid_core::parser::{FilterChain, Tag};
+use liquid_core::runtime::{PartialStore, Registers};
+use liquid_core::{
+    Error, Language, ParseTag, Renderable, Result, Runtime, TagReflection, TagTokenIter, Value,
+    ValueView,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::Write;
+
+/// `{{ }}` output markup, or a `raw` or `highlight` section whose body is kept as written
+static OUTPUT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?s)\{%-?\s*raw\s*-?%\}.*?\{%-?\s*endraw\s*-?%\}",
+        r"|\{%-?\s*highlight\b.*?\{%-?\s*endhighlight\s*-?%\}",
+        r"|\{\{(-?)(.*?)(-?)\}\}",
+    ))
+    .unwrap()
+});
+
+/// Turn `{{ expression }}` into `{% echo expression %}`
+///
+/// The Liquid parser handles output markup itself, always strictly, so
+/// lenient rendering goes through [`EchoTag`] instead.
+pub(crate) fn outputs_to_echo(text: &str) -> Cow<'_, str> {
+    OUTPUT.replace_all(text, |caps: &regex::Captures<'_>| match caps.get(2) {
+        Some(expression) => format!("{{%{} echo {} {}%}}", &caps[1], expression.as_str().trim(), &caps[3]),
+        None => caps[0].to_string(),
+    })
+}
+
+/// Liquid's `{% echo expression %}`, outputting like `{{ expression }}`
+///
+/// Unless `liquid.strict_variables` is set, undefined variables and indexes
+/// evaluate to nil. Unless `liquid.strict_filters` is set, unknown filters are
+/// skipped.
+#[derive(Clone, Debug)]
+pub struct EchoTag {
+    options: LiquidConfig,
+}
+
+impl EchoTag {
+    pub fn new(options: LiquidConfig) -> Self {
+        Self { options }
+    }
+}
+
+impl TagReflection for EchoTag {
+    fn tag(&self) -> &'static str {
+        "echo"
+    }
+
+    fn description(&self) -> &'static str {
+        "Output the value of an expression"
+    }
+}
+
+impl ParseTag for EchoTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let Some(token) = arguments.next() else {
+            return Ok(Box::new(Echo { chain: None, strict: self.options.strict_variables }));
+        };
+
+        let source = token.as_str().to_string();
+        let segments = split_filters(&source);
+        let (known, unknown): (Vec<&str>, Vec<&str>) = segments[1..]
+            .iter()
+            .partition(|segment| options.filters.get(filter_name(segment)).is_some());
+
+        let chain = match unknown.first() {
+            None => token.expect_filter_chain(options).into_result()?,
+            Some(segment) if self.options.strict_filters => {
+                return Error::with_msg("Unknown filter")
+                    .context("requested filter", filter_name(segment).to_string())
+                    .into_err();
+            }
+            Some(_) => {
+                tracing::debug!("Skipping unknown filters in {{{{ {} }}}}", source);
+                let text = format!("{{% echo {} %}}", [&[segments[0]], &known[..]].concat().join("|"));
+                let mut tag = Tag::new(&text)?;
+                tag.tokens()
+                    .expect_next("Expression expected.")?
+                    .expect_filter_chain(options)
+                    .into_result()?
+            }
+        };
+        arguments.expect_nothing()?;
+
+        Ok(Box::new(Echo {
+            chain: Some(chain),
+            strict: self.options.strict_variables,
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+/// Split a filter chain on the `|` outside of string literals
+fn split_filters(chain: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let (mut start, mut quote) = (0, None);
+    for (i, c) in chain.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '|') => {
+                segments.push(&chain[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    segments.push(&chain[start..]);
+    segments
+}
+
+/// Name of the filter in a chain segment such as ` truncate: 20`
+fn filter_name(segment: &str) -> &str {
+    segment
+        .trim()
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .next()
+        .unwrap_or_default()
+}
+
+#[derive(Debug)]
+struct Echo {
+    chain: Option<FilterChain>,
+    strict: bool,
+}
+
+impl fmt::Display for Echo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.chain {
+            Some(chain) => write!(f, "{{{{ {} }}}}", chain),
+            None => write!(f, "{{{{ }}}}"),
+        }
+    }
+}
+
+impl Renderable for Echo {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let Some(chain) = &self.chain else {
+            return Ok(());
+        };
+        let lenient = Lenient(runtime);
+        let runtime: &dyn Runtime = if self.strict { runtime } else { &lenient };
+        let value = chain.evaluate(runtime)?;
+        write!(writer, "{}", value.render()).replace("Failed to render")
+    }
+}
+
+/// Runtime resolving undefined variables to nil instead of failing
+struct Lenient<'r>(&'r dyn Runtime);
+
+impl Runtime for Lenient<'_> {
+    fn partials(&self) -> &dyn PartialStore {
+        self.0.partials()
+    }
+
+    fn name(&self) -> Option<KStringRef<'_>> {
+        self.0.name()
+    }
+
+    fn roots(&self) -> BTreeSet<KStringCow<'_>> {
+        self.0.roots()
+    }
+
+    fn try_get(&self, path: &[ScalarCow<'_>]) -> Option<ValueCow<'_>> {
+        self.0.try_get(path)
+    }
+
+    fn get(&self, path: &[ScalarCow<'_>]) -> Result<ValueCow<'_>> {
+        Ok(self.0.try_get(path).unwrap_or(ValueCow::Owned(Value::Nil)))
+    }
+
+    fn set_global(&self, name: KString, val: Value) -> Option<Value> {
+        self.0.set_global(name, val)
+    }
+
+    fn set_index(&self, name: KString, val: Value) -> Option<Value> {
+        self.0.set_index(name, val)
+    }
+
+    fn get_index<'a>(&'a self, name: &str) -> Option<ValueCow<'a>> {
+        self.0.get_index(name)
+    }
+
+    fn registers(&self) -> &Registers {
+        self.0.registers()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outputs_to_echo() {
+        assert_eq!(
+            outputs_to_echo("<p>{{ page.title | upcase }}</p>{{- x -}}{% raw %}{{ y }}{% endraw %}{% highlight %}{{ z }}{% endhighlight %}"),
+            "<p>{% echo page.title | upcase %}</p>{%- echo x -%}{% raw %}{{ y }}{% endraw %}{% highlight %}{{ z }}{% endhighlight %}"
+        );
+    }
+
+    #[test]
+    fn test_split_filters() {
+        assert_eq!(
+            split_filters(r#"a | split: "|" | join: '|'"#),
+            ["a ", r#" split: "|" "#, " join: '|'"]
+        );
+        assert_eq!(filter_name(" truncate: 20"), "truncate");
+    }
+}
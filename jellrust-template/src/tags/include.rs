@@ -0,0 +1,188 @@
+use liquid_core::error::ResultLiquidExt;
+use liquid_core::model::KString;
+use liquid_core::parser::TryMatchToken;
+use liquid_core::runtime::StackFrame;
+use liquid_core::{
+    Error, Expression, Language, Object, ParseTag, Renderable, Result, Runtime, TagReflection,
+    TagTokenIter, Value,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use crate::dependencies::DependencyRecorder;
+use crate::profile::Profiler;
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Matches include, post_url and link tags whose argument is written bare (unquoted)
+static BARE_INCLUDE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(\{%-?\s*(?:include(?:_relative|_cached)?|post_url|link)\s+)([^\s"'%{}]+)"#).unwrap()
+});
+
+/// Quote bare include paths, post names and link paths so the Liquid grammar accepts them
+///
+/// Jekyll allows `{% include nav/menu.html %}`, `{% link docs/intro.md %}`
+/// and `{% post_url 2024-01-15-hello %}`, but the Liquid tokenizer rejects
+/// such words outside of string literals.
+pub(crate) fn quote_include_paths(text: &str) -> Cow<'_, str> {
+    BARE_INCLUDE.replace_all(text, "$1\"$2\"")
+}
+
+/// Parse the `key=value` pairs following an include's file name
+pub(crate) fn parse_include_params(
+    arguments: &mut TagTokenIter<'_>,
+) -> Result<Vec<(KString, Expression)>> {
+    let mut params = Vec::new();
+
+    while let Ok(next) = arguments.expect_next("") {
+        let key = next.expect_identifier().into_result()?.to_owned();
+
+        arguments
+            .expect_next("\"=\" expected.")?
+            .expect_str("=")
+            .into_result_custom_msg("expected \"=\" to be used for the assignment")?;
+
+        let value = arguments
+            .expect_next("expected value")?
+            .expect_value()
+            .into_result()?;
+
+        params.push((key.into(), value));
+    }
+
+    Ok(params)
+}
+
+/// Evaluate include parameters into the `include` scope object
+pub(crate) fn include_scope(
+    params: &[(KString, Expression)],
+    runtime: &dyn Runtime,
+) -> Result<Object> {
+    let mut include = Object::new();
+
+    for (key, expression) in params {
+        let value = expression
+            .try_evaluate(runtime)
+            .ok_or_else(|| Error::with_msg("failed to evaluate include parameter"))?
+            .into_owned();
+        include.insert(key.clone(), value);
+    }
+
+    let mut scope = Object::new();
+    scope.insert("include".into(), Value::Object(include));
+    Ok(scope)
+}
+
+/// Jekyll-style `{% include file.html key=value %}` resolving partials from `_includes/`
+///
+/// Parameters are exposed to the partial as `include.key`.
+#[derive(Clone, Debug)]
+pub struct IncludeTag {
+    dependencies: Arc<DependencyRecorder>,
+    profiler: Arc<Profiler>,
+}
+
+impl IncludeTag {
+    pub fn new(dependencies: Arc<DependencyRecorder>, profiler: Arc<Profiler>) -> Self {
+        Self {
+            dependencies,
+            profiler,
+        }
+    }
+}
+
+impl TagReflection for IncludeTag {
+    fn tag(&self) -> &'static str {
+        "include"
+    }
+
+    fn description(&self) -> &'static str {
+        "Render a partial from the _includes directory"
+    }
+}
+
+impl ParseTag for IncludeTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let name = arguments.expect_next("Include file name expected.")?;
+
+        // Accept both bare (`header.html`) and quoted (`"header.html"`) names
+        let name = match name.expect_identifier() {
+            TryMatchToken::Matches(name) => name.to_string(),
+            TryMatchToken::Fails(name) => name.as_str().trim_matches(['"', '\'']).to_string(),
+        };
+
+        let params = parse_include_params(&mut arguments)?;
+
+        Ok(Box::new(Include {
+            name,
+            params,
+            dependencies: self.dependencies.clone(),
+            profiler: self.profiler.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Include {
+    name: String,
+    params: Vec<(KString, Expression)>,
+    dependencies: Arc<DependencyRecorder>,
+    profiler: Arc<Profiler>,
+}
+
+impl Renderable for Include {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let path = Path::new("_includes").join(&self.name);
+        self.dependencies.record(path.clone());
+
+        let scope = include_scope(&self.params, runtime)?;
+        let frame = StackFrame::new(runtime, &scope);
+
+        let partial = frame
+            .partials()
+            .get(&self.name)
+            .trace_with(|| format!("{{% include {} %}}", self.name).into())?;
+
+        self.profiler
+            .time(|| path, || partial.render_to(writer, &frame))
+            .trace_with(|| format!("{{% include {} %}}", self.name).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_include_paths() {
+        assert_eq!(
+            quote_include_paths("{% include nav/menu.html %}"),
+            "{% include \"nav/menu.html\" %}"
+        );
+        assert_eq!(
+            quote_include_paths("{% include_relative snippets/a.md %}"),
+            "{% include_relative \"snippets/a.md\" %}"
+        );
+        assert_eq!(
+            quote_include_paths("{% post_url 2024-01-15-hello %}"),
+            "{% post_url \"2024-01-15-hello\" %}"
+        );
+        assert_eq!(
+            quote_include_paths("{%- link docs/intro.md -%}"),
+            "{%- link \"docs/intro.md\" -%}"
+        );
+        assert_eq!(
+            quote_include_paths("{%- include 'footer.html' -%}"),
+            "{%- include 'footer.html' -%}"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use super::include::{include_scope, parse_include_params};
+use crate::dependencies::DependencyRecorder;
+use crate::profile::Profiler;
+use liquid_core::error::{ResultLiquidExt, ResultLiquidReplaceExt};
+use liquid_core::model::KString;
+use liquid_core::parser::TryMatchToken;
+use liquid_core::runtime::StackFrame;
+use liquid_core::{
+    Expression, Language, ParseTag, Renderable, Result, Runtime, TagReflection, TagTokenIter, Value,
+};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Output of `include_cached` partials, keyed by name and parameters
+#[derive(Debug, Default)]
+pub struct IncludeCache {
+    outputs: Mutex<HashMap<String, String>>,
+}
+
+impl IncludeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget all outputs, e.g. before a rebuild
+    pub fn clear(&self) {
+        self.outputs.lock().expect("include cache poisoned").clear();
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.outputs.lock().expect("include cache poisoned").get(key).cloned()
+    }
+
+    fn insert(&self, key: String, output: String) {
+        self.outputs.lock().expect("include cache poisoned").insert(key, output);
+    }
+}
+
+/// `{% include_cached file.html key=value %}`, like jekyll-include-cache
+///
+/// Renders a partial once per distinct set of parameters and reuses the
+/// output. The partial must only depend on its parameters and site-wide
+/// variables, since `page` may differ on the pages reusing the output.
+#[derive(Clone, Debug)]
+pub struct IncludeCachedTag {
+    cache: Arc<IncludeCache>,
+    dependencies: Arc<DependencyRecorder>,
+    profiler: Arc<Profiler>,
+}
+
+impl IncludeCachedTag {
+    pub fn new(
+        cache: Arc<IncludeCache>,
+        dependencies: Arc<DependencyRecorder>,
+        profiler: Arc<Profiler>,
+    ) -> Self {
+        Self {
+            cache,
+            dependencies,
+            profiler,
+        }
+    }
+}
+
+impl TagReflection for IncludeCachedTag {
+    fn tag(&self) -> &'static str {
+        "include_cached"
+    }
+
+    fn description(&self) -> &'static str {
+        "Render a partial from the _includes directory once per set of parameters"
+    }
+}
+
+impl ParseTag for IncludeCachedTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let name = arguments.expect_next("Include file name expected.")?;
+        let name = match name.expect_identifier() {
+            TryMatchToken::Matches(name) => name.to_string(),
+            TryMatchToken::Fails(name) => name.as_str().trim_matches(['"', '\'']).to_string(),
+        };
+
+        let params = parse_include_params(&mut arguments)?;
+
+        Ok(Box::new(IncludeCached {
+            name,
+            params,
+            cache: self.cache.clone(),
+            dependencies: self.dependencies.clone(),
+            profiler: self.profiler.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct IncludeCached {
+    name: String,
+    params: Vec<(KString, Expression)>,
+    cache: Arc<IncludeCache>,
+    dependencies: Arc<DependencyRecorder>,
+    profiler: Arc<Profiler>,
+}
+
+impl Renderable for IncludeCached {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let path = Path::new("_includes").join(&self.name);
+        self.dependencies.record(path.clone());
+
+        let trace = || format!("{{% include_cached {} %}}", self.name).into();
+        let scope = include_scope(&self.params, runtime)?;
+        let params = scope.get("include").cloned().unwrap_or(Value::Nil);
+        let key = format!(
+            "{}\u{0}{}",
+            self.name,
+            serde_json::to_string(&params).unwrap_or_default()
+        );
+
+        let output = match self.cache.get(&key) {
+            Some(output) => output,
+            None => {
+                let frame = StackFrame::new(runtime, &scope);
+                let partial = frame.partials().get(&self.name).trace_with(trace)?;
+                let output = self
+                    .profiler
+                    .time(|| path, || partial.render(&frame))
+                    .trace_with(trace)?;
+                self.cache.insert(key, output.clone());
+                output
+            }
+        };
+
+        writer
+            .write_all(output.as_bytes())
+            .replace("Failed to render")
+    }
+}
@@ -0,0 +1,143 @@
+use super::include::{include_scope, parse_include_params, quote_include_paths};
+use crate::dependencies::DependencyRecorder;
+use crate::profile::Profiler;
+use jellrust_markdown::strip_bom;
+use liquid_core::error::ResultLiquidExt;
+use liquid_core::model::{KString, ScalarCow};
+use liquid_core::parser::TryMatchToken;
+use liquid_core::runtime::{StackFrame, Template};
+use liquid_core::{
+    Error, Expression, Language, ParseTag, Renderable, Result, Runtime, TagReflection,
+    TagTokenIter, ValueView,
+};
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Jekyll-style `{% include_relative file.md %}` resolving partials next to the current page
+#[derive(Clone, Debug)]
+pub struct IncludeRelativeTag {
+    source_dir: PathBuf,
+    dependencies: Arc<DependencyRecorder>,
+    profiler: Arc<Profiler>,
+}
+
+impl IncludeRelativeTag {
+    pub fn new(source_dir: PathBuf, dependencies: Arc<DependencyRecorder>, profiler: Arc<Profiler>) -> Self {
+        Self {
+            source_dir,
+            dependencies,
+            profiler,
+        }
+    }
+}
+
+impl TagReflection for IncludeRelativeTag {
+    fn tag(&self) -> &'static str {
+        "include_relative"
+    }
+
+    fn description(&self) -> &'static str {
+        "Render a partial relative to the current page's directory"
+    }
+}
+
+impl ParseTag for IncludeRelativeTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let name = arguments.expect_next("Include file name expected.")?;
+        let name = match name.expect_identifier() {
+            TryMatchToken::Matches(name) => name.to_string(),
+            TryMatchToken::Fails(name) => name.as_str().trim_matches(['"', '\'']).to_string(),
+        };
+
+        // Like Jekyll, refuse to climb out of the page's directory
+        if Path::new(&name)
+            .components()
+            .any(|c| matches!(c, Component::ParentDir | Component::RootDir))
+        {
+            return Err(Error::with_msg("include_relative paths may not contain '..' or be absolute")
+                .context("path", name));
+        }
+
+        let params = parse_include_params(&mut arguments)?;
+
+        Ok(Box::new(IncludeRelative {
+            name,
+            params,
+            source_dir: self.source_dir.clone(),
+            language: Arc::new(options.clone()),
+            dependencies: self.dependencies.clone(),
+            profiler: self.profiler.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+struct IncludeRelative {
+    name: String,
+    params: Vec<(KString, Expression)>,
+    source_dir: PathBuf,
+    language: Arc<Language>,
+    dependencies: Arc<DependencyRecorder>,
+    profiler: Arc<Profiler>,
+}
+
+impl std::fmt::Debug for IncludeRelative {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IncludeRelative")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+impl IncludeRelative {
+    /// Directory of the page being rendered, from `page.path`
+    fn page_dir(&self, runtime: &dyn Runtime) -> PathBuf {
+        let path = [ScalarCow::new("page"), ScalarCow::new("path")];
+        let page_path = runtime
+            .try_get(&path)
+            .map(|v| v.to_kstr().to_string())
+            .unwrap_or_default();
+
+        Path::new(&page_path)
+            .parent()
+            .map(|dir| self.source_dir.join(dir))
+            .unwrap_or_else(|| self.source_dir.clone())
+    }
+}
+
+impl Renderable for IncludeRelative {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let file = self.page_dir(runtime).join(&self.name);
+        let relative = file.strip_prefix(&self.source_dir).map(Path::to_path_buf);
+        if let Ok(relative) = &relative {
+            self.dependencies.record(relative.clone());
+        }
+
+        let content = fs::read_to_string(&file).map_err(|e| {
+            Error::with_msg("Unable to read relative include")
+                .context("path", file.display().to_string())
+                .context("cause", e.to_string())
+        })?;
+
+        let template = liquid_core::parser::parse(&quote_include_paths(strip_bom(&content)), &self.language)
+            .map(Template::new)
+            .trace_with(|| format!("{{% include_relative {} %}}", self.name).into())?;
+
+        let scope = include_scope(&self.params, runtime)?;
+        let frame = StackFrame::new(runtime, &scope);
+
+        self.profiler
+            .time(|| relative.unwrap_or(file), || template.render_to(writer, &frame))
+            .trace_with(|| format!("{{% include_relative {} %}}", self.name).into())
+    }
+}
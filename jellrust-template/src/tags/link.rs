@@ -0,0 +1,75 @@
+use crate::urls::UrlIndex;
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::parser::TryMatchToken;
+use liquid_core::{
+    Error, Language, ParseTag, Renderable, Result, Runtime, TagReflection, TagTokenIter,
+};
+use std::io::Write;
+use std::sync::Arc;
+
+/// Jekyll-style `{% link docs/intro.md %}` emitting a document's generated URL
+///
+/// Links name the source file, so they keep working when permalinks change.
+#[derive(Clone, Debug)]
+pub struct LinkTag {
+    documents: Arc<UrlIndex>,
+}
+
+impl LinkTag {
+    pub fn new(documents: Arc<UrlIndex>) -> Self {
+        Self { documents }
+    }
+}
+
+impl TagReflection for LinkTag {
+    fn tag(&self) -> &'static str {
+        "link"
+    }
+
+    fn description(&self) -> &'static str {
+        "Output the URL of a page, post or asset given its source path"
+    }
+}
+
+impl ParseTag for LinkTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let name = arguments.expect_next("Source path expected.")?;
+        let name = match name.expect_identifier() {
+            TryMatchToken::Matches(name) => name.to_string(),
+            TryMatchToken::Fails(name) => name.as_str().trim_matches(['"', '\'']).to_string(),
+        };
+        arguments.expect_nothing()?;
+
+        Ok(Box::new(Link {
+            name,
+            documents: self.documents.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Link {
+    name: String,
+    documents: Arc<UrlIndex>,
+}
+
+impl Renderable for Link {
+    fn render_to(&self, writer: &mut dyn Write, _runtime: &dyn Runtime) -> Result<()> {
+        let url = self.documents.get(&self.name).ok_or_else(|| {
+            Error::with_msg("Could not find document for link")
+                .context("path", self.name.clone())
+                .context("hint", "use the path relative to the site source, e.g. docs/intro.md")
+        })?;
+
+        write!(writer, "{}", url).replace("Failed to render")?;
+        Ok(())
+    }
+}
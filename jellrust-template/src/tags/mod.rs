@@ -0,0 +1,23 @@
+mod comments;
+mod echo;
+mod include;
+mod include_cached;
+mod include_relative;
+mod link;
+mod post_url;
+mod render;
+mod seo;
+mod translate;
+
+pub use comments::CommentsTag;
+pub use echo::EchoTag;
+pub use include::IncludeTag;
+pub use include_cached::{IncludeCache, IncludeCachedTag};
+pub use include_relative::IncludeRelativeTag;
+pub use link::LinkTag;
+pub use post_url::PostUrlTag;
+pub use render::RenderTag;
+pub use seo::SeoTag;
+pub use translate::TranslateTag;
+pub(crate) use echo::outputs_to_echo;
+pub(crate) use include::quote_include_paths;
@@ -0,0 +1,78 @@
+use crate::urls::UrlIndex;
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::parser::TryMatchToken;
+use liquid_core::{
+    Error, Language, ParseTag, Renderable, Result, Runtime, TagReflection, TagTokenIter,
+};
+use std::io::Write;
+use std::sync::Arc;
+
+/// Jekyll-style `{% post_url 2024-01-15-hello-world %}` emitting a post's generated URL
+#[derive(Clone, Debug)]
+pub struct PostUrlTag {
+    posts: Arc<UrlIndex>,
+}
+
+impl PostUrlTag {
+    pub fn new(posts: Arc<UrlIndex>) -> Self {
+        Self { posts }
+    }
+}
+
+impl TagReflection for PostUrlTag {
+    fn tag(&self) -> &'static str {
+        "post_url"
+    }
+
+    fn description(&self) -> &'static str {
+        "Output the URL of a post given its _posts file name"
+    }
+}
+
+impl ParseTag for PostUrlTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let name = arguments.expect_next("Post name expected.")?;
+        let name = match name.expect_identifier() {
+            TryMatchToken::Matches(name) => name.to_string(),
+            TryMatchToken::Fails(name) => name.as_str().trim_matches(['"', '\'']).to_string(),
+        };
+        arguments.expect_nothing()?;
+
+        Ok(Box::new(PostUrl {
+            name,
+            posts: self.posts.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct PostUrl {
+    name: String,
+    posts: Arc<UrlIndex>,
+}
+
+impl Renderable for PostUrl {
+    fn render_to(&self, writer: &mut dyn Write, _runtime: &dyn Runtime) -> Result<()> {
+        // Tolerate a file extension, as older Jekyll versions did
+        let url = self
+            .posts
+            .get(&self.name)
+            .or_else(|| self.posts.get(self.name.rsplit_once('.')?.0));
+        let url = url.ok_or_else(|| {
+            Error::with_msg("Could not find post for post_url")
+                .context("post", self.name.clone())
+                .context("hint", "use the _posts file name without extension, e.g. 2024-01-15-hello-world")
+        })?;
+
+        write!(writer, "{}", url).replace("Failed to render")?;
+        Ok(())
+    }
+}
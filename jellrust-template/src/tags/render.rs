@@ -0,0 +1,245 @@
+use crate::dependencies::DependencyRecorder;
+use crate::profile::Profiler;
+use liquid_core::error::ResultLiquidExt;
+use liquid_core::model::{KString, ScalarCow};
+use liquid_core::parser::TryMatchToken;
+use liquid_core::runtime::{GlobalFrame, Interrupt, InterruptRegister, SandboxedStackFrame};
+use liquid_core::{
+    Error, Expression, Language, Object, ParseTag, Renderable, Result, Runtime, TagReflection,
+    TagTokenIter, Value, ValueView,
+};
+use liquid_lib::stdlib::{ForloopObject, RangeExpression};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Variables of the document being rendered, which partials see in spite of
+/// their isolated scope
+const DOCUMENT_GLOBALS: &[&str] = &["site", "page", "layout", "paginator"];
+
+/// `{% render "card.html", title: post.title %}`: a partial from `_includes/`
+/// with its own variable scope
+///
+/// Unlike `{% include %}`, the partial doesn't see variables assigned by the
+/// template rendering it, only `site`, `page`, `layout`, `paginator` and the
+/// variables passed to it, and its own assignments stay inside. `with value
+/// as name` passes a single value, and `for items as name` renders the
+/// partial once per item with a `forloop` object.
+#[derive(Clone, Debug)]
+pub struct RenderTag {
+    dependencies: Arc<DependencyRecorder>,
+    profiler: Arc<Profiler>,
+}
+
+impl RenderTag {
+    pub fn new(dependencies: Arc<DependencyRecorder>, profiler: Arc<Profiler>) -> Self {
+        Self {
+            dependencies,
+            profiler,
+        }
+    }
+}
+
+impl TagReflection for RenderTag {
+    fn tag(&self) -> &'static str {
+        "render"
+    }
+
+    fn description(&self) -> &'static str {
+        "Render a partial from the _includes directory in an isolated scope"
+    }
+}
+
+impl ParseTag for RenderTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let name = arguments.expect_next("Partial name expected.")?;
+        let name = name.as_str().trim_matches(['"', '\'']).to_string();
+
+        let mut token = arguments.next();
+        let mut vars: Vec<(KString, Expression)> = Vec::new();
+        let mut for_ = None;
+        match token.as_ref().map(|t| t.as_str()) {
+            Some("with") => {
+                let value = arguments.expect_next("Value expected.")?.expect_value().into_result()?;
+                let alias = expect_alias(&mut arguments)?;
+                vars.push((alias, value));
+                token = arguments.next();
+            }
+            Some("for") => {
+                let range = arguments.expect_next("Array or range expected.")?;
+                let range = match range.expect_value() {
+                    TryMatchToken::Matches(array) => RangeExpression::Array(array),
+                    TryMatchToken::Fails(range) => match range.expect_range() {
+                        TryMatchToken::Matches((start, stop)) => RangeExpression::Counted(start, stop),
+                        TryMatchToken::Fails(range) => return range.raise_error().into_err(),
+                    },
+                };
+                for_ = Some((range, expect_alias(&mut arguments)?));
+                token = arguments.next();
+            }
+            _ => {}
+        }
+
+        // `, key: value` pairs
+        while let Some(separator) = token {
+            separator
+                .expect_str(",")
+                .into_result_custom_msg("`,` is needed to separate variables")?;
+            let Some(key) = arguments.next() else {
+                break;
+            };
+            let key = key.expect_identifier().into_result()?.to_owned();
+            arguments
+                .expect_next("\":\" expected.")?
+                .expect_str(":")
+                .into_result_custom_msg("expected \":\" to be used for the assignment")?;
+            let value = arguments.expect_next("Value expected.")?.expect_value().into_result()?;
+            vars.push((key.into(), value));
+            token = arguments.next();
+        }
+        arguments.expect_nothing()?;
+
+        Ok(Box::new(Render {
+            name,
+            vars,
+            for_,
+            dependencies: self.dependencies.clone(),
+            profiler: self.profiler.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+/// `as name` after `with` or `for`
+fn expect_alias(arguments: &mut TagTokenIter<'_>) -> Result<KString> {
+    arguments
+        .expect_next("\"as\" expected.")?
+        .expect_str("as")
+        .into_result_custom_msg("expected \"as\" to name the value")?;
+    let alias = arguments.expect_next("Identifier expected.")?.expect_identifier().into_result()?;
+    Ok(alias.to_owned().into())
+}
+
+#[derive(Debug)]
+struct Render {
+    name: String,
+    vars: Vec<(KString, Expression)>,
+    for_: Option<(RangeExpression, KString)>,
+    dependencies: Arc<DependencyRecorder>,
+    profiler: Arc<Profiler>,
+}
+
+impl Render {
+    /// The document globals and the passed variables
+    fn scope(&self, runtime: &dyn Runtime) -> Result<Object> {
+        let mut scope = Object::new();
+        for name in DOCUMENT_GLOBALS {
+            if let Some(value) = runtime.try_get(&[ScalarCow::new(*name)]) {
+                scope.insert((*name).into(), value.into_owned());
+            }
+        }
+        for (key, expression) in &self.vars {
+            let value = expression
+                .try_evaluate(runtime)
+                .ok_or_else(|| Error::with_msg("failed to evaluate render parameter"))?;
+            scope.insert(key.clone(), value.into_owned());
+        }
+        Ok(scope)
+    }
+
+    /// Render the partial once, returning whether it hit a `break`
+    fn render_partial(&self, writer: &mut dyn Write, runtime: &dyn Runtime, scope: &Object) -> Result<bool> {
+        let path = Path::new("_includes").join(&self.name);
+        self.dependencies.record(path.clone());
+
+        let frame = GlobalFrame::new(SandboxedStackFrame::new(runtime, scope));
+        let partial = frame
+            .partials()
+            .get(&self.name)
+            .trace_with(|| format!("{{% render {} %}}", self.name).into())?;
+        self.profiler
+            .time(|| path, || partial.render_to(writer, &frame))
+            .trace_with(|| format!("{{% render {} %}}", self.name).into())?;
+
+        let interrupt = frame.registers().get_mut::<InterruptRegister>().reset();
+        Ok(matches!(interrupt, Some(Interrupt::Break)))
+    }
+}
+
+impl Renderable for Render {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let mut scope = self.scope(runtime)?;
+        let Some((range, alias)) = &self.for_ else {
+            self.render_partial(writer, runtime, &scope)?;
+            return Ok(());
+        };
+
+        let range = range.evaluate(runtime)?;
+        let items = range.evaluate()?;
+        let len = items.len();
+        for (index, item) in items.into_iter().enumerate() {
+            scope.insert(alias.clone(), item.into_owned());
+            scope.insert("forloop".into(), forloop(index, len));
+            // A `break` in the partial ends a `for` rendering
+            if self.render_partial(writer, runtime, &scope)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `forloop` object of a `for` rendering
+fn forloop(index: usize, len: usize) -> Value {
+    ForloopObject::new(index, len).to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::partials::{EagerCompiler, InMemorySource};
+
+    fn render(template: &str, globals: &liquid::Object) -> Result<String> {
+        let mut partials = InMemorySource::new();
+        partials.add(
+            "card.html",
+            "[{{ label }}|{% if item %}{{ item }}{% endif %}|{{ page.title }}|{% if secret %}S{% endif %}]{% assign leaked = 1 %}",
+        );
+        partials.add("loop.html", "{{ forloop.index }}/{{ forloop.length }}:{{ n }} ");
+        let dependencies = Arc::new(DependencyRecorder::default());
+        let parser = liquid::ParserBuilder::with_stdlib()
+            .tag(RenderTag::new(dependencies.clone(), Arc::new(Profiler::new())))
+            .partials(EagerCompiler::new(partials))
+            .build()
+            .unwrap();
+        let output = parser.parse(template)?.render(globals)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn test_render_tag_scope() {
+        let globals = liquid::object!({ "page": { "title": "Home" } });
+        let template = concat!(
+            "{% assign secret = 's' %}{% render \"card.html\", label: 'L' %}",
+            "{% render 'card.html' with 'W' as item, label: page.title %}{% if leaked %}leaked{% endif %}",
+        );
+        assert_eq!(render(template, &globals).unwrap(), "[L||Home|][Home|W|Home|]");
+    }
+
+    #[test]
+    fn test_render_tag_for() {
+        let globals = liquid::object!({ "numbers": [1, 2, 3] });
+        assert_eq!(
+            render("{% render 'loop.html' for numbers as n %}", &globals).unwrap(),
+            "1/3:1 2/3:2 3/3:3 "
+        );
+        assert!(render("{% render 'card.html' with %}", &globals).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use crate::filters::{absolute_url, xml_escape};
+use chrono::{DateTime, FixedOffset};
+use jellrust_types::SeoConfig;
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::model::ScalarCow;
+use liquid_core::{
+    Error, Language, ParseTag, Renderable, Result, Runtime, TagReflection, TagTokenIter, ValueView,
+};
+use std::io::Write;
+
+/// Jekyll SEO tag equivalent: `{% seo %}` in a layout's `<head>`
+///
+/// Emits the `<title>`, description, canonical URL, OpenGraph and Twitter
+/// meta tags from `site` and `page` variables. `{% seo title=false %}` leaves
+/// out the `<title>` for layouts that write their own.
+#[derive(Clone, Debug)]
+pub struct SeoTag {
+    options: SeoConfig,
+}
+
+impl SeoTag {
+    pub fn new(options: SeoConfig) -> Self {
+        Self { options }
+    }
+}
+
+impl TagReflection for SeoTag {
+    fn tag(&self) -> &'static str {
+        "seo"
+    }
+
+    fn description(&self) -> &'static str {
+        "Output title, description, canonical URL and social meta tags"
+    }
+}
+
+impl ParseTag for SeoTag {
+    fn parse(
+        &self,
+        arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let arguments: String = arguments.map(|token| token.as_str().to_string()).collect();
+        let title = match arguments.as_str() {
+            "" | "title=true" => true,
+            "title=false" => false,
+            _ => {
+                return Error::with_msg("Unknown seo option")
+                    .context("option", arguments)
+                    .context("hint", "the only option is title=false")
+                    .into_err();
+            }
+        };
+
+        Ok(Box::new(Seo {
+            options: self.options.clone(),
+            title,
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Seo {
+    options: SeoConfig,
+    /// Whether to output the `<title>`
+    title: bool,
+}
+
+impl Renderable for Seo {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let site = |key| variable(runtime, "site", key);
+        let page = |key| variable(runtime, "page", key);
+        let url = |path: &str| {
+            absolute_url(
+                &site("url").unwrap_or_default(),
+                &site("baseurl").unwrap_or_default(),
+                path,
+            )
+        };
+
+        let site_title = site("title");
+        let page_title = page("title").or_else(|| site_title.clone());
+        let description = page("description")
+            .or_else(|| site("description"))
+            .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "));
+        let canonical = page("canonical_url").or_else(|| page("url")).map(|path| url(&path));
+        let image = page_image(runtime).or_else(|| self.options.image.clone()).map(|path| url(&path));
+        let published = page("date").and_then(|date| iso_date(&date));
+
+        let mut tags = vec!["<!-- Begin JellRust SEO tag -->".to_string()];
+        if self.title {
+            let title = match (&page_title, &site_title) {
+                (Some(page), Some(site)) if page != site => Some(format!("{} | {}", page, site)),
+                (title, _) => title.clone(),
+            };
+            if let Some(title) = title {
+                tags.push(format!("<title>{}</title>", xml_escape(&title)));
+            }
+        }
+        tags.push(meta("name", "generator", &format!("JellRust v{}", env!("CARGO_PKG_VERSION"))));
+        if let Some(title) = &page_title {
+            tags.push(meta("property", "og:title", title));
+        }
+        if let Some(author) = page("author") {
+            tags.push(meta("name", "author", &author));
+        }
+        tags.push(meta("property", "og:locale", &self.options.locale));
+        if let Some(description) = &description {
+            tags.push(meta("name", "description", description));
+            tags.push(meta("property", "og:description", description));
+        }
+        if let Some(canonical) = &canonical {
+            tags.push(format!("<link rel=\"canonical\" href=\"{}\" />", xml_escape(canonical)));
+            tags.push(meta("property", "og:url", canonical));
+        }
+        if let Some(site_title) = &site_title {
+            tags.push(meta("property", "og:site_name", site_title));
+        }
+        if let Some(image) = &image {
+            tags.push(meta("property", "og:image", image));
+        }
+        match &published {
+            Some(published) => {
+                tags.push(meta("property", "og:type", "article"));
+                tags.push(meta("property", "article:published_time", published));
+            }
+            None => tags.push(meta("property", "og:type", "website")),
+        }
+        let card = if image.is_some() { "summary_large_image" } else { "summary" };
+        tags.push(meta("name", "twitter:card", card));
+        if let Some(title) = &page_title {
+            tags.push(meta("property", "twitter:title", title));
+        }
+        if let Some(handle) = &self.options.twitter {
+            tags.push(meta("name", "twitter:site", &format!("@{}", handle.trim_start_matches('@'))));
+        }
+        tags.push("<!-- End JellRust SEO tag -->".to_string());
+
+        writeln!(writer, "{}", tags.join("\n")).replace("Failed to render")?;
+        Ok(())
+    }
+}
+
+/// A non-empty string variable such as `page.title`
+pub(super) fn variable(runtime: &dyn Runtime, object: &str, key: &str) -> Option<String> {
+    let path = [ScalarCow::new(object.to_string()), ScalarCow::new(key.to_string())];
+    let value = runtime.try_get(&path)?;
+    let text = value.as_scalar()?.to_kstr().trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// `page.image`, either a path or an object with a `path`
+fn page_image(runtime: &dyn Runtime) -> Option<String> {
+    variable(runtime, "page", "image").or_else(|| {
+        let path = ["page", "image", "path"].map(|key| ScalarCow::new(key.to_string()));
+        let value = runtime.try_get(&path)?;
+        Some(value.as_scalar()?.to_kstr().to_string()).filter(|path| !path.is_empty())
+    })
+}
+
+/// A post or page date in ISO 8601, as OpenGraph expects
+fn iso_date(date: &str) -> Option<String> {
+    DateTime::parse_from_rfc3339(date)
+        .or_else(|_| DateTime::<FixedOffset>::parse_from_str(date, "%Y-%m-%d %H:%M:%S %z"))
+        .ok()
+        .map(|date| date.to_rfc3339())
+}
+
+fn meta(attribute: &str, name: &str, content: &str) -> String {
+    format!("<meta {}=\"{}\" content=\"{}\" />", attribute, name, xml_escape(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(text: &str, globals: &liquid::Object, options: SeoConfig) -> String {
+        let parser = liquid::ParserBuilder::with_stdlib()
+            .tag(SeoTag::new(options))
+            .build()
+            .unwrap();
+        parser.parse(text).unwrap().render(globals).unwrap()
+    }
+
+    #[test]
+    fn test_seo_tag() {
+        let globals = liquid::object!({
+            "site": { "title": "Blog", "description": "Notes", "url": "https://example.com", "baseurl": "/blog" },
+            "page": {
+                "title": "Hello & welcome",
+                "url": "/2024/hello.html",
+                "date": "2024-01-15T09:30:00+00:00",
+                "image": { "path": "/img/hello.png" },
+                "author": "Ann",
+            },
+        });
+        let options = SeoConfig {
+            twitter: Some("@blog".to_string()),
+            ..SeoConfig::default()
+        };
+        let output = render("{% seo %}", &globals, options);
+
+        assert!(output.contains("<title>Hello &amp; welcome | Blog</title>"));
+        assert!(output.contains("<meta name=\"description\" content=\"Notes\" />"));
+        assert!(output.contains("<link rel=\"canonical\" href=\"https://example.com/blog/2024/hello.html\" />"));
+        assert!(output.contains("<meta property=\"og:image\" content=\"https://example.com/blog/img/hello.png\" />"));
+        assert!(output.contains("<meta property=\"article:published_time\" content=\"2024-01-15T09:30:00+00:00\" />"));
+        assert!(output.contains("<meta name=\"twitter:card\" content=\"summary_large_image\" />"));
+        assert!(output.contains("<meta name=\"twitter:site\" content=\"@blog\" />"));
+        assert!(output.contains("<meta name=\"author\" content=\"Ann\" />"));
+    }
+
+    #[test]
+    fn test_seo_tag_without_title() {
+        let globals = liquid::object!({
+            "site": { "title": "Blog" },
+            "page": { "url": "/about/" },
+        });
+        let output = render("{% seo title=false %}", &globals, SeoConfig::default());
+
+        assert!(!output.contains("<title>"));
+        assert!(output.contains("<meta property=\"og:title\" content=\"Blog\" />"));
+        assert!(output.contains("<meta property=\"og:type\" content=\"website\" />"));
+        assert!(output.contains("<link rel=\"canonical\" href=\"/about/\" />"));
+        assert!(!output.contains("og:description"));
+    }
+}
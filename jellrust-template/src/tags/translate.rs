@@ -0,0 +1,68 @@
+use crate::filters::translate;
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::parser::TryMatchToken;
+use liquid_core::{Language, ParseTag, Renderable, Result, Runtime, TagReflection, TagTokenIter};
+use std::io::Write;
+
+/// `{% t nav.home %}`: a UI string from `_data/locales/<locale>.yml`, in
+/// `page.locale` or else `site.locale`
+#[derive(Clone, Debug, Default)]
+pub struct TranslateTag;
+
+impl TagReflection for TranslateTag {
+    fn tag(&self) -> &'static str {
+        "t"
+    }
+
+    fn description(&self) -> &'static str {
+        "Output the translation of a key from _data/locales"
+    }
+}
+
+impl ParseTag for TranslateTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let key = arguments.expect_next("Translation key expected.")?;
+        let key = match key.expect_identifier() {
+            TryMatchToken::Matches(key) => key.to_string(),
+            TryMatchToken::Fails(key) => key.as_str().trim_matches(['"', '\'']).to_string(),
+        };
+        arguments.expect_nothing()?;
+
+        Ok(Box::new(Translation { key }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Translation {
+    key: String,
+}
+
+impl Renderable for Translation {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        write!(writer, "{}", translate(runtime, &self.key)).replace("Failed to render")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_tag() {
+        let parser = liquid::ParserBuilder::with_stdlib().tag(TranslateTag).build().unwrap();
+        let globals = liquid::object!({
+            "site": { "data": { "locales": { "en": { "footer": { "copyright": "All rights reserved" } } } } }
+        });
+        let template = parser.parse("{% t footer.copyright %} {% t 'footer.copyright' %}").unwrap();
+        assert_eq!(template.render(&globals).unwrap(), "All rights reserved All rights reserved");
+    }
+}
@@ -0,0 +1,12 @@
+//! Helpers shared by the crate's tests
+
+use std::fs;
+use std::path::PathBuf;
+
+/// Create an empty scratch directory for a test
+pub(crate) fn temp_site(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("jellrust-template-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
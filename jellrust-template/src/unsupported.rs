@@ -0,0 +1,358 @@
+use crate::filters::xml_escape;
+use liquid::partials::PartialCompiler;
+use liquid::ParserBuilder;
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::parser::{FilterArguments, ParameterReflection};
+use liquid_core::{
+    BlockReflection, Filter, FilterReflection, Language, ParseBlock, ParseFilter, ParseTag,
+    Renderable, Result, Runtime, TagBlock, TagReflection, TagTokenIter, Value, ValueView,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Liquid markup outside of `{% raw %}` sections
+static MARKUP: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)\{%-?\s*raw\s*-?%\}.*?\{%-?\s*endraw\s*-?%\}|\{\{.*?\}\}|\{%.*?%\}").unwrap()
+});
+
+static TAG_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\{%-?\s*(\w+)").unwrap());
+
+static FILTER_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"\|\s*([A-Za-z_]\w*)").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Renders nothing
+    Tag,
+    /// Shows its body as a plain code block
+    Block,
+    /// Passes its input through unchanged
+    Filter,
+}
+
+/// A Jekyll tag or filter jellrust doesn't implement yet
+#[derive(Debug)]
+pub struct Construct {
+    pub name: &'static str,
+    pub kind: Kind,
+    /// What to use instead
+    pub hint: &'static str,
+}
+
+impl fmt::Display for Construct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::Tag | Kind::Block => write!(f, "the {{% {} %}} tag", self.name),
+            Kind::Filter => write!(f, "the `{}` filter", self.name),
+        }
+    }
+}
+
+/// Constructs reported by [`UnsupportedCheck`], dropped from here once implemented
+pub static UNSUPPORTED: &[Construct] = &[
+    Construct {
+        name: "highlight",
+        kind: Kind::Block,
+        hint: "use a fenced code block (```lang) instead",
+    },
+    Construct {
+        name: "feed_meta",
+        kind: Kind::Tag,
+        hint: "link {{ \"/feed.xml\" | relative_url }} with <link rel=\"alternate\">",
+    },
+    Construct {
+        name: "gist",
+        kind: Kind::Tag,
+        hint: "paste the gist's <script> embed",
+    },
+    Construct {
+        name: "markdownify",
+        kind: Kind::Filter,
+        hint: "move the text into Markdown content",
+    },
+    Construct {
+        name: "date_to_xmlschema",
+        kind: Kind::Filter,
+        hint: "use date: \"%Y-%m-%dT%H:%M:%S%:z\"",
+    },
+    Construct {
+        name: "date_to_rfc822",
+        kind: Kind::Filter,
+        hint: "use date: \"%a, %d %b %Y %H:%M:%S %z\"",
+    },
+    Construct {
+        name: "date_to_string",
+        kind: Kind::Filter,
+        hint: "use date: \"%d %b %Y\"",
+    },
+    Construct {
+        name: "date_to_long_string",
+        kind: Kind::Filter,
+        hint: "use date: \"%d %B %Y\"",
+    },
+    Construct {
+        name: "array_to_sentence_string",
+        kind: Kind::Filter,
+        hint: "use join: \", \"",
+    },
+    Construct {
+        name: "group_by",
+        kind: Kind::Filter,
+        hint: "loop over the items and compare them with the previous one",
+    },
+    Construct {
+        name: "group_by_exp",
+        kind: Kind::Filter,
+        hint: "loop over the items and compare them with the previous one",
+    },
+    Construct {
+        name: "to_integer",
+        kind: Kind::Filter,
+        hint: "use plus: 0",
+    },
+    Construct {
+        name: "sassify",
+        kind: Kind::Filter,
+        hint: "compile the stylesheet ahead of time",
+    },
+    Construct {
+        name: "scssify",
+        kind: Kind::Filter,
+        hint: "compile the stylesheet ahead of time",
+    },
+];
+
+/// Unsupported constructs used in a template, in order of first use
+pub fn find_unsupported(text: &str) -> Vec<&'static Construct> {
+    let mut found: Vec<&'static Construct> = Vec::new();
+    let mut add = |name: &str, kinds: &[Kind]| {
+        let construct = UNSUPPORTED
+            .iter()
+            .find(|c| c.name == name && kinds.contains(&c.kind));
+        if let Some(construct) = construct.filter(|c| !found.iter().any(|f| f.name == c.name)) {
+            found.push(construct);
+        }
+    };
+
+    for markup in MARKUP.find_iter(text).map(|m| m.as_str()) {
+        if let Some(caps) = TAG_NAME.captures(markup) {
+            if &caps[1] == "raw" {
+                continue;
+            }
+            add(&caps[1], &[Kind::Tag, Kind::Block]);
+        }
+        for caps in FILTER_NAME.captures_iter(markup) {
+            add(&caps[1], &[Kind::Filter]);
+        }
+    }
+    found
+}
+
+/// Warns about unsupported Jekyll constructs, once per file
+///
+/// Templates using them still parse: the stand-ins registered by
+/// [`register_stand_ins`] take their place. Names registered as custom tags or
+/// filters are not reported.
+#[derive(Debug, Default)]
+pub struct UnsupportedCheck {
+    overridden: HashSet<String>,
+    warned: Mutex<HashSet<(PathBuf, &'static str)>>,
+}
+
+impl UnsupportedCheck {
+    pub fn new(overridden: HashSet<String>) -> Self {
+        Self {
+            overridden,
+            warned: Mutex::default(),
+        }
+    }
+
+    /// Log a warning for each unsupported construct `text` uses
+    ///
+    /// `path` names the template, relative to the site source.
+    pub fn check(&self, path: &Path, text: &str) {
+        for construct in find_unsupported(text) {
+            if self.overridden.contains(construct.name) {
+                continue;
+            }
+            let first = self
+                .warned
+                .lock()
+                .expect("unsupported warnings poisoned")
+                .insert((path.to_path_buf(), construct.name));
+            if first {
+                tracing::warn!(
+                    "{}: {} is not supported yet; {}",
+                    path.display(),
+                    construct,
+                    construct.hint
+                );
+            }
+        }
+    }
+}
+
+/// Register stand-ins for the unsupported constructs
+///
+/// Registered before custom tags and filters, which replace them.
+pub fn register_stand_ins<P: PartialCompiler>(mut builder: ParserBuilder<P>) -> ParserBuilder<P> {
+    for construct in UNSUPPORTED {
+        builder = match construct.kind {
+            Kind::Tag => builder.tag(StandIn(construct)),
+            Kind::Block => builder.block(StandIn(construct)),
+            Kind::Filter => builder.filter(StandIn(construct)),
+        };
+    }
+    builder
+}
+
+#[derive(Clone, Debug)]
+struct StandIn(&'static Construct);
+
+impl TagReflection for StandIn {
+    fn tag(&self) -> &str {
+        self.0.name
+    }
+
+    fn description(&self) -> &str {
+        "Unsupported Jekyll tag, renders nothing"
+    }
+}
+
+impl ParseTag for StandIn {
+    fn parse(&self, _arguments: TagTokenIter<'_>, _options: &Language) -> Result<Box<dyn Renderable>> {
+        Ok(Box::new(StandInOutput(None)))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+impl BlockReflection for StandIn {
+    fn start_tag(&self) -> &str {
+        self.0.name
+    }
+
+    fn end_tag(&self) -> &str {
+        match self.0.name {
+            "highlight" => "endhighlight",
+            _ => unreachable!("only highlight is an unsupported block"),
+        }
+    }
+
+    fn description(&self) -> &str {
+        "Unsupported Jekyll block, shows its body as plain code"
+    }
+}
+
+impl ParseBlock for StandIn {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        mut block: TagBlock<'_, '_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let language = arguments.next().map(|token| token.as_str().to_string());
+        let code = block.escape_liquid(false)?.trim_matches('\n');
+        let class = language
+            .map(|language| format!(" class=\"language-{}\"", xml_escape(&language)))
+            .unwrap_or_default();
+        let html = format!("<pre><code{}>{}</code></pre>", class, xml_escape(code));
+
+        block.assert_empty();
+        Ok(Box::new(StandInOutput(Some(html))))
+    }
+
+    fn reflection(&self) -> &dyn BlockReflection {
+        self
+    }
+}
+
+impl FilterReflection for StandIn {
+    fn name(&self) -> &str {
+        self.0.name
+    }
+
+    fn description(&self) -> &str {
+        "Unsupported Jekyll filter, returns its input"
+    }
+
+    fn positional_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+
+    fn keyword_parameters(&self) -> &'static [ParameterReflection] {
+        &[]
+    }
+}
+
+impl ParseFilter for StandIn {
+    fn parse(&self, _arguments: FilterArguments<'_>) -> Result<Box<dyn Filter>> {
+        Ok(Box::new(self.clone()))
+    }
+
+    fn reflection(&self) -> &dyn FilterReflection {
+        self
+    }
+}
+
+impl Filter for StandIn {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(input.to_value())
+    }
+}
+
+impl fmt::Display for StandIn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.name)
+    }
+}
+
+/// Output of a stand-in tag or block
+#[derive(Debug)]
+struct StandInOutput(Option<String>);
+
+impl Renderable for StandInOutput {
+    fn render_to(&self, writer: &mut dyn Write, _runtime: &dyn Runtime) -> Result<()> {
+        if let Some(html) = &self.0 {
+            writer.write_all(html.as_bytes()).replace("Failed to render")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_unsupported() {
+        let text = "{% highlight ruby %}x{% endhighlight %}\n\
+                    {{ page.date | date_to_xmlschema }} {{ page.date | date: \"%Y\" }}\n\
+                    {% assign n = page.count | to_integer | plus: 1 %}\n\
+                    {% raw %}{{ x | inspect }}{% endraw %} a | markdownify";
+        let names: Vec<&str> = find_unsupported(text).iter().map(|c| c.name).collect();
+        assert_eq!(names, ["highlight", "date_to_xmlschema", "to_integer"]);
+    }
+
+    #[test]
+    fn test_stand_ins() {
+        let parser = register_stand_ins(ParserBuilder::with_stdlib()).build().unwrap();
+        let render = |text: &str| {
+            let globals = liquid::object!({ "title": "Hi" });
+            parser.parse(text).unwrap().render(&globals).unwrap()
+        };
+
+        assert_eq!(render("{{ title | markdownify }}"), "Hi");
+        assert_eq!(render("a{% feed_meta %}b"), "ab");
+        assert_eq!(
+            render("{% highlight rust %}\nlet a = {{ 1 }} < 2;\n{% endhighlight %}"),
+            "<pre><code class=\"language-rust\">let a = {{ 1 }} &lt; 2;</code></pre>"
+        );
+    }
+}
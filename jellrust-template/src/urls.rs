@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Generated URLs keyed by a name the templates refer to them by
+///
+/// Backs `{% post_url %}` (keyed by post name, e.g. `2024-01-15-hello`),
+/// `{% link %}` (keyed by source path, e.g. `docs/intro.md`) and the
+/// `asset_url` filter (keyed by asset path, e.g. `assets/css/main.css`). The
+/// site builder fills the indexes once all content is known, before anything
+/// is rendered.
+#[derive(Debug, Default)]
+pub struct UrlIndex {
+    urls: RwLock<HashMap<String, String>>,
+}
+
+impl UrlIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the whole index
+    pub fn replace(&self, urls: HashMap<String, String>) {
+        *self.urls.write().expect("url index poisoned") = urls;
+    }
+
+    /// URL registered under `name`, ignoring leading slashes
+    pub fn get(&self, name: &str) -> Option<String> {
+        let name = name.trim_start_matches('/');
+        self.urls.read().expect("url index poisoned").get(name).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        let index = UrlIndex::new();
+        index.replace(HashMap::from([(
+            "docs/intro.md".to_string(),
+            "/docs/intro.html".to_string(),
+        )]));
+
+        assert_eq!(index.get("docs/intro.md").as_deref(), Some("/docs/intro.html"));
+        assert_eq!(index.get("/docs/intro.md").as_deref(), Some("/docs/intro.html"));
+        assert_eq!(index.get("docs/missing.md"), None);
+    }
+}
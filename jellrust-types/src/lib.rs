@@ -7,13 +7,19 @@ use tokio::sync::{mpsc, RwLock};
 
 // Re-export FrontMatter from jellrust-markdown
 pub use jellrust_markdown::FrontMatter;
+use jellrust_markdown::{AdmonitionSyntax, ExternalLinks, HighlightOptions, MarkdownExtensions, MathMode};
+
+pub mod paths;
 
 // ============================================================================
 // Server Types
 // ============================================================================
 
-/// Shared flag for triggering browser reload in development server
-pub type ReloadFlag = Arc<RwLock<bool>>;
+/// Shared counter of successful rebuilds in the development server.
+///
+/// Every open page remembers the value it was served with and reloads once
+/// the counter moves past it, so any number of tabs can follow the same build.
+pub type BuildCounter = Arc<RwLock<u64>>;
 
 /// Channel for communicating file change events
 pub type FileChangeChannel = mpsc::UnboundedSender<()>;
@@ -38,6 +44,53 @@ pub struct Page {
     
     /// Rendered HTML content
     pub html: String,
+    
+    /// Date from front matter
+    pub date: Option<DateTime<Utc>>,
+    
+    /// First paragraph of the rendered content
+    pub excerpt: String,
+    
+    /// Set on the pages of a paginated post listing
+    pub paginator: Option<Paginator>,
+    
+    /// Headings of the rendered content, exposed as `page.toc`
+    #[serde(default)]
+    pub toc: Vec<TocEntry>,
+    
+    /// Slug of the author whose posts this page lists, set on the pages
+    /// generated for `_data/authors.yml`
+    #[serde(default)]
+    pub author_page: Option<String>,
+}
+
+/// A heading of rendered content, with the headings nested under it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TocEntry {
+    /// Heading level, 1 for `<h1>` to 6 for `<h6>`
+    pub level: u8,
+    /// Heading text without markup
+    pub text: String,
+    /// `id` attribute the heading can be linked to with `#id`
+    pub id: String,
+    pub children: Vec<TocEntry>,
+}
+
+/// One page of a paginated post listing, exposed as `paginator`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Paginator {
+    /// Number of this page, starting at 1
+    pub page: usize,
+    pub per_page: usize,
+    
+    /// Indices into `Site::posts` of the posts on this page
+    pub posts: Vec<usize>,
+    pub total_posts: usize,
+    pub total_pages: usize,
+    pub previous_page: Option<usize>,
+    pub previous_page_path: Option<String>,
+    pub next_page: Option<usize>,
+    pub next_page_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,7 +101,16 @@ pub struct Post {
     /// URL path for the generated post
     pub url: String,
     
-    /// Post date (from filename or front matter)
+    /// URL slug: `slug:` front matter, or else the file name without its date
+    #[serde(default)]
+    pub slug: String,
+    
+    /// Identifier from the URL's directory and the slug, e.g. `/2024/01/15/hello`,
+    /// as Jekyll's `post.id`
+    #[serde(default)]
+    pub id: String,
+    
+    /// Post date and time (from front matter, or else the filename)
     pub date: DateTime<Utc>,
     
     /// Front matter data
@@ -62,6 +124,46 @@ pub struct Post {
     
     /// Excerpt (first paragraph or explicit)
     pub excerpt: String,
+    
+    /// Whether this post comes from `_drafts`
+    #[serde(default)]
+    pub draft: bool,
+    
+    /// First image in the content (fallback for `image` front matter)
+    #[serde(default)]
+    pub image: Option<String>,
+    
+    /// Plain-text description (fallback for `description` front matter)
+    #[serde(default)]
+    pub description: Option<String>,
+    
+    /// Whether the post is pinned ahead of others (`pinned: true`)
+    #[serde(default)]
+    pub pinned: bool,
+    
+    /// Whether the post is featured (`featured: true`)
+    #[serde(default)]
+    pub featured: bool,
+    
+    /// When the post drops out of listings and feeds (`expires:`)
+    #[serde(default)]
+    pub expires: Option<DateTime<Utc>>,
+    
+    /// When the post drops out of the sitemap (`sitemap_expires:`)
+    #[serde(default)]
+    pub sitemap_expires: Option<DateTime<Utc>>,
+    
+    /// Index in `site.posts` of the next older listed post (`page.previous`)
+    #[serde(default)]
+    pub previous: Option<usize>,
+    
+    /// Index in `site.posts` of the next newer listed post (`page.next`)
+    #[serde(default)]
+    pub next: Option<usize>,
+    
+    /// Headings of the rendered content, exposed as `page.toc`
+    #[serde(default)]
+    pub toc: Vec<TocEntry>,
 }
 
 impl Page {
@@ -72,23 +174,71 @@ impl Page {
             front_matter: FrontMatter::default(),
             content: String::new(),
             html: String::new(),
+            date: None,
+            excerpt: String::new(),
+            paginator: None,
+            toc: Vec::new(),
+            author_page: None,
         }
     }
 }
 
 impl Post {
     pub fn new(path: PathBuf) -> Self {
-        Self {
+        let mut post = Self {
             path,
             url: String::new(),
+            slug: String::new(),
+            id: String::new(),
             date: Utc::now(),
             front_matter: FrontMatter::default(),
             content: String::new(),
             html: String::new(),
             excerpt: String::new(),
+            draft: false,
+            image: None,
+            description: None,
+            pinned: false,
+            featured: false,
+            expires: None,
+            sitemap_expires: None,
+            previous: None,
+            next: None,
+            toc: Vec::new(),
+        };
+        post.slug = post.filename_slug();
+        post
+    }
+    
+    /// File name without the extension and `YYYY-MM-DD-` date prefix
+    pub fn filename_slug(&self) -> String {
+        let stem = self.path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+        match self.parse_date_from_filename() {
+            Some(_) => stem.splitn(4, '-').nth(3).unwrap_or_default().to_string(),
+            None => stem.to_string(),
         }
     }
     
+    /// Order of `site.posts`: newest first by date and time of day
+    ///
+    /// Posts published at the same moment are ordered by [`slug`](Self::slug),
+    /// alphabetically, so the order doesn't depend on the file system.
+    pub fn newest_first(&self, other: &Post) -> std::cmp::Ordering {
+        other.date.cmp(&self.date).then_with(|| self.filename_slug().cmp(&other.filename_slug()))
+    }
+    
+    /// Whether `expires:` has passed at `now`
+    ///
+    /// Expired posts are still built but left out of listings and feeds.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+    
+    /// Whether the post should be left out of the sitemap at `now`
+    pub fn is_sitemap_expired(&self, now: DateTime<Utc>) -> bool {
+        self.is_expired(now) || self.sitemap_expires.is_some_and(|expires| expires <= now)
+    }
+    
     /// Parse date from filename (YYYY-MM-DD-title.md)
     pub fn parse_date_from_filename(&self) -> Option<DateTime<Utc>> {
         let filename = self.path.file_name()?.to_str()?;
@@ -115,8 +265,26 @@ pub struct Site {
     /// All posts (sorted by date, newest first)
     pub posts: Vec<Post>,
     
-    /// Static files (images, CSS, JS, etc.)
-    pub static_files: Vec<PathBuf>,
+    /// Static files (images, CSS, JS, etc.) copied to the destination, by path
+    pub static_files: Vec<StaticFile>,
+    
+    /// Contents of `_data` files, exposed as `site.data`
+    pub data: HashMap<String, serde_yaml::Value>,
+    
+    /// When the build started, exposed as `site.time`
+    pub time: DateTime<Utc>,
+    
+    /// Bundle manifest path -> URL of its fingerprinted output, exposed as `site.bundles`
+    pub bundles: HashMap<String, String>,
+    
+    /// Tags with their post counts, exposed as `site.tag_cloud`
+    pub tag_cloud: Vec<CloudTag>,
+    
+    /// Downloaded `vendor:` assets by path under `assets/vendor/`, exposed as `site.vendor`
+    pub vendor: HashMap<String, VendoredAsset>,
+    
+    /// Indices into `posts` of the entries of feed.xml, exposed as `site.feed.posts`
+    pub feed_posts: Vec<usize>,
 }
 
 impl Site {
@@ -125,6 +293,12 @@ impl Site {
             pages: Vec::new(),
             posts: Vec::new(),
             static_files: Vec::new(),
+            data: HashMap::new(),
+            time: Utc::now(),
+            bundles: HashMap::new(),
+            tag_cloud: Vec::new(),
+            vendor: HashMap::new(),
+            feed_posts: Vec::new(),
         }
     }
 }
@@ -135,6 +309,56 @@ impl Default for Site {
     }
 }
 
+/// A file copied as is, in `site.static_files`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaticFile {
+    /// URL path from the site root, such as `/assets/img/logo.png`
+    pub path: String,
+    
+    /// File name with extension
+    pub name: String,
+    
+    /// File name without extension
+    pub basename: String,
+    
+    /// Extension including the dot, or empty
+    pub extname: String,
+    
+    /// When the source file was last modified
+    pub modified_time: DateTime<Utc>,
+}
+
+/// A `vendor:` asset served from the site, in `site.vendor`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VendoredAsset {
+    /// URL path from the site root, such as `/assets/vendor/katex.min.js`
+    pub url: String,
+    
+    /// Subresource Integrity hash of the file, for `integrity` attributes
+    pub integrity: String,
+}
+
+/// A tag in `site.tag_cloud`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudTag {
+    /// Display name
+    pub tag: String,
+    
+    /// URL slug, as used for `/tags/<slug>.json`
+    pub slug: String,
+    
+    /// Number of listed posts with the tag
+    pub count: usize,
+    
+    /// Bucket from 1 (least used) to [`CloudTag::WEIGHTS`] (most used)
+    pub weight: usize,
+}
+
+impl CloudTag {
+    /// Number of weight buckets
+    pub const WEIGHTS: usize = 5;
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     /// Site title
@@ -153,6 +377,15 @@ pub struct Config {
     #[serde(default)]
     pub baseurl: String,
     
+    /// IANA time zone for `site.time` (e.g. `Europe/Berlin`); UTC when unset
+    #[serde(default)]
+    pub timezone: Option<String>,
+    
+    /// Theme providing layouts, includes and assets the site doesn't override:
+    /// a directory, or the name of a theme cached in `.jellrust-cache/themes`
+    #[serde(default)]
+    pub theme: Option<String>,
+    
     /// Markdown engine
     #[serde(default = "default_markdown")]
     pub markdown: String,
@@ -169,6 +402,14 @@ pub struct Config {
     #[serde(default = "default_paginate_path")]
     pub paginate_path: String,
     
+    /// Site content directory, relative to the directory holding _config.yml
+    #[serde(default = "default_source")]
+    pub source: String,
+    
+    /// Output directory, relative to the directory holding _config.yml
+    #[serde(default = "default_destination")]
+    pub destination: String,
+    
     /// Files/folders to exclude
     #[serde(default = "default_exclude")]
     pub exclude: Vec<String>,
@@ -181,11 +422,547 @@ pub struct Config {
     #[serde(default)]
     pub plugins: Vec<String>,
     
+    /// Show a "draft preview" banner on draft outputs
+    #[serde(default)]
+    pub draft_banner: bool,
+    
+    /// Extract the first content image as `page.image` fallback
+    #[serde(default = "default_true")]
+    pub extract_image: bool,
+    
+    /// Length of the extracted `page.description` fallback (0 disables)
+    #[serde(default = "default_description_length")]
+    pub description_length: usize,
+    
+    /// Reading speed the `reading_time` filter assumes, exposed as `site.words_per_minute`
+    #[serde(default = "default_words_per_minute")]
+    pub words_per_minute: usize,
+    
+    /// Language of `date` month and day names and of `{% t %}` translations,
+    /// e.g. `fr_FR`, exposed as `site.locale`
+    #[serde(default)]
+    pub locale: Option<String>,
+    
+    /// Sort pinned posts ahead of the others in `site.posts`
+    #[serde(default = "default_true")]
+    pub pinned_first: bool,
+    
+    /// Render Liquid markup found in `_data` strings (e.g. `{{ site.title }}`),
+    /// once per build before any document
+    #[serde(default)]
+    pub liquid_in_data: bool,
+    
+    /// Don't keep the source and HTML of every document in memory: convert
+    /// each one again when writing it. For very large sites; the trade-off
+    /// is that `content` is then empty in `site.pages` and `site.posts`,
+    /// post excerpts are taken before the post's Liquid is rendered, and
+    /// feed entries carry their `<summary>` but no `<content>`.
+    #[serde(default)]
+    pub low_memory: bool,
+    
+    /// Strip UTF-8 byte order marks from pages and posts before parsing
+    #[serde(default = "default_true")]
+    pub strip_bom: bool,
+    
+    /// Add `<meta charset="utf-8">` to HTML outputs that declare no charset
+    #[serde(default = "default_true")]
+    pub charset_meta: bool,
+    
+    /// Prefix root-relative links in HTML outputs with `baseurl`, for themes
+    /// that don't use `relative_url`
+    #[serde(default)]
+    pub rewrite_baseurl: bool,
+    
+    /// Attributes added to `<img>` tags in HTML outputs
+    #[serde(default)]
+    pub images: ImagesConfig,
+    
+    /// Reformatting of generated HTML: `off`, `pretty_html` or `minified`
+    #[serde(default)]
+    pub output: OutputStyle,
+    
+    /// Cache-busting hash the `asset_url` filter adds to asset URLs
+    #[serde(default)]
+    pub fingerprint: Fingerprint,
+    
+    /// Host-specific files collecting the `headers:` of pages and posts
+    #[serde(default)]
+    pub header_files: Vec<HeaderFile>,
+    
+    /// Seconds to wait for another build of the same site before giving up
+    #[serde(default = "default_build_lock_timeout")]
+    pub build_lock_timeout: u64,
+    
+    /// Notifications sent when a watch/serve rebuild finishes
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    
+    /// Development server defaults for `jellrust serve`
+    #[serde(default)]
+    pub serve: ServeConfig,
+    
+    /// `sitemap.xml` settings (generated with the `sitemap` plugin)
+    #[serde(default)]
+    pub sitemap: SitemapConfig,
+    
+    /// `feed.xml` settings (generated with the `feed` plugin)
+    #[serde(default)]
+    pub feed: FeedConfig,
+    
+    /// Tag and category normalization
+    #[serde(default)]
+    pub taxonomy: TaxonomyConfig,
+    
+    /// Syntax highlighting of fenced code blocks
+    #[serde(default)]
+    pub highlight: HighlightConfig,
+    
+    /// Markdown syntax extensions, e.g. `markdown_ext: {tables: false}`
+    #[serde(default)]
+    pub markdown_ext: MarkdownExtensions,
+    
+    /// Rendering of `$...$` and `$$...$$` math in Markdown (`math: markup`)
+    #[serde(default)]
+    pub math: MathMode,
+    
+    /// `target`/`rel` added to Markdown links to other sites
+    #[serde(default)]
+    pub external_links: ExternalLinks,
+    
+    /// Render images with a title on their own line as `<figure>` with the
+    /// title as `<figcaption>`
+    #[serde(default)]
+    pub figures: bool,
+    
+    /// Turn `[[Page Title]]` and `[[slug|label]]` in Markdown into links to
+    /// the post or page with that title, slug or file name
+    #[serde(default)]
+    pub wikilinks: bool,
+    
+    /// Which callout syntax renders as `<div class="admonition note">`:
+    /// `blockquote` (`> [!note]`), `fenced` (`:::note`) or `both`
+    #[serde(default)]
+    pub admonitions: AdmonitionSyntax,
+    
+    /// Author pages generated from `_data/authors.yml`
+    #[serde(default)]
+    pub authors: AuthorsConfig,
+    
+    /// How strictly Liquid templates are rendered
+    #[serde(default)]
+    pub liquid: LiquidConfig,
+    
+    /// Language layouts are written in (page content is always Liquid)
+    #[serde(default)]
+    pub template_engine: TemplateLanguage,
+    
+    /// Length and markup of `excerpt`, also used by feeds and descriptions
+    #[serde(default)]
+    pub excerpt: ExcerptConfig,
+    
+    /// Marker ending the excerpt, e.g. `<!--more-->`; a post's own
+    /// `excerpt_separator` wins
+    #[serde(default)]
+    pub excerpt_separator: Option<String>,
+    
+    /// Extra metadata for the `{% seo %}` tag
+    #[serde(default)]
+    pub seo: SeoConfig,
+    
+    /// Comment widget `{% comments %}` outputs
+    #[serde(default)]
+    pub comments: CommentsConfig,
+    
+    /// Remote assets downloaded into `assets/vendor/` at build time
+    #[serde(default)]
+    pub vendor: Vec<VendorAsset>,
+    
+    /// How `jellrust check --external-links` checks outbound links
+    #[serde(default)]
+    pub link_check: LinkCheckConfig,
+    
     /// Custom variables
     #[serde(flatten)]
     pub custom: HashMap<String, serde_yaml::Value>,
 }
 
+/// How generated HTML is reformatted before it is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStyle {
+    /// Written as rendered
+    #[default]
+    Off,
+    /// Block elements on their own, indented lines (for development)
+    PrettyHtml,
+    /// Comments and insignificant whitespace removed (for production)
+    Minified,
+}
+
+/// How `asset_url` makes browsers fetch an asset again once it changes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fingerprint {
+    /// Plain asset URLs
+    #[default]
+    Off,
+    /// The content hash as a query string: `style.css?v=<hash>`
+    Query,
+    /// A copy of the asset with the content hash in its name: `style-<hash>.css`,
+    /// replacing the copy made for its previous content
+    Filename,
+}
+
+/// Host configuration file written from `headers:` front matter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeaderFile {
+    /// Netlify `_headers`
+    Netlify,
+    /// Vercel `vercel.json`
+    Vercel,
+}
+
+/// A `vendor:` entry in _config.yml
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VendorAsset {
+    /// Where to download the asset from
+    pub url: String,
+    
+    /// File under `assets/vendor/`, the last segment of the URL by default
+    #[serde(default)]
+    pub path: Option<String>,
+    
+    /// Expected Subresource Integrity hash (`sha256-...`, `sha384-...` or
+    /// `sha512-...`), checked after download
+    #[serde(default)]
+    pub integrity: Option<String>,
+}
+
+/// `images:` settings in _config.yml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImagesConfig {
+    /// Add `loading="lazy"`
+    #[serde(default)]
+    pub lazy: bool,
+    
+    /// Add `width` and `height` read from the image, when it is a local file,
+    /// so the page doesn't shift as images load
+    #[serde(default)]
+    pub dimensions: bool,
+}
+
+/// `link_check:` settings in _config.yml
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkCheckConfig {
+    /// URLs never checked, as glob patterns such as `https://twitter.com/*`
+    #[serde(default)]
+    pub allow: Vec<String>,
+    
+    /// Links checked at the same time
+    #[serde(default = "default_link_concurrency")]
+    pub concurrency: usize,
+    
+    /// Further attempts for a link that timed out, was rate limited or hit a server error
+    #[serde(default = "default_link_retries")]
+    pub retries: u32,
+    
+    /// Seconds to wait for a response
+    #[serde(default = "default_link_timeout")]
+    pub timeout: u64,
+    
+    /// Hours a working link isn't checked again (0 always checks)
+    #[serde(default = "default_link_cache_hours")]
+    pub cache_hours: u64,
+}
+
+impl Default for LinkCheckConfig {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            concurrency: default_link_concurrency(),
+            retries: default_link_retries(),
+            timeout: default_link_timeout(),
+            cache_hours: default_link_cache_hours(),
+        }
+    }
+}
+
+/// `serve:` settings in _config.yml, overridden by `jellrust serve` flags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServeConfig {
+    /// Port to serve on
+    #[serde(default = "default_serve_port")]
+    pub port: u16,
+    
+    /// Host to bind to
+    #[serde(default = "default_serve_host")]
+    pub host: String,
+    
+    /// Reload open pages after each rebuild
+    #[serde(default = "default_true")]
+    pub livereload: bool,
+    
+    /// Open the site in a browser once it is served
+    #[serde(default)]
+    pub open: bool,
+    
+    /// Path prefix -> backend URL requests under it are forwarded to,
+    /// e.g. `/api: http://localhost:8080`
+    #[serde(default)]
+    pub proxy: HashMap<String, String>,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        Self {
+            port: default_serve_port(),
+            host: default_serve_host(),
+            livereload: true,
+            open: false,
+            proxy: HashMap::new(),
+        }
+    }
+}
+
+/// `notifications:` settings in _config.yml (all off by default)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// Show a desktop notification after each rebuild
+    #[serde(default)]
+    pub desktop: bool,
+    
+    /// URL receiving a JSON POST after each rebuild
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+/// `sitemap:` settings in _config.yml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SitemapConfig {
+    /// URL globs (e.g. `/thanks.html`, `/page*/`) left out of the sitemap
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// `feed:` settings in _config.yml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeedConfig {
+    /// URL globs of posts left out of the feed
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// `authors:` settings in _config.yml
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorsConfig {
+    /// Layout of the author pages; nothing is generated until it exists
+    #[serde(default = "default_author_layout")]
+    pub layout: String,
+    
+    /// URL of each author page, `:author` being the key in _data/authors.yml
+    #[serde(default = "default_author_permalink")]
+    pub permalink: String,
+    
+    /// Posts per author page (0 lists them all on one page)
+    #[serde(default)]
+    pub paginate: usize,
+    
+    /// Write a `feed.xml` of each author's posts next to their page
+    #[serde(default)]
+    pub feed: bool,
+}
+
+impl Default for AuthorsConfig {
+    fn default() -> Self {
+        Self {
+            layout: default_author_layout(),
+            permalink: default_author_permalink(),
+            paginate: 0,
+            feed: false,
+        }
+    }
+}
+
+fn default_author_layout() -> String {
+    "author".to_string()
+}
+
+fn default_author_permalink() -> String {
+    "/authors/:author/".to_string()
+}
+
+/// `highlight:` settings in _config.yml
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighlightConfig {
+    /// Theme and `inline`/`classes`/`rouge` mode
+    #[serde(flatten)]
+    pub options: HighlightOptions,
+    
+    /// Stylesheet written in `classes` mode, relative to the destination
+    #[serde(default = "default_highlight_stylesheet")]
+    pub stylesheet: String,
+}
+
+impl Default for HighlightConfig {
+    fn default() -> Self {
+        Self {
+            options: HighlightOptions::default(),
+            stylesheet: default_highlight_stylesheet(),
+        }
+    }
+}
+
+/// `liquid:` settings in _config.yml
+///
+/// As in Jekyll, templates are lenient by default: undefined variables
+/// render as empty and unknown filters in `{{ }}` output are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiquidConfig {
+    /// Fail on variables and indexes that don't exist
+    #[serde(default)]
+    pub strict_variables: bool,
+    
+    /// Fail on filters that don't exist
+    #[serde(default)]
+    pub strict_filters: bool,
+}
+
+/// Template language of layouts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateLanguage {
+    #[default]
+    Liquid,
+    /// [Tera](https://keats.github.io/tera/), with Jinja2-like syntax
+    Tera,
+}
+
+/// `excerpt:` settings in _config.yml
+///
+/// The excerpt is the first paragraph of the content, or everything before
+/// the `excerpt_separator`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExcerptConfig {
+    /// Maximum characters of text, cut at a word boundary (0 keeps the whole paragraph)
+    #[serde(default)]
+    pub length: usize,
+    
+    /// Keep the paragraph's markup or reduce it to plain text
+    #[serde(default)]
+    pub format: ExcerptFormat,
+}
+
+/// Markup of excerpts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExcerptFormat {
+    /// Inline HTML such as links and emphasis
+    #[default]
+    Html,
+    /// Text without tags
+    Plain,
+}
+
+/// `seo:` settings in _config.yml
+///
+/// Titles, descriptions and URLs come from `site` and `page`; these fill in
+/// what they don't say.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeoConfig {
+    /// Twitter handle of the site for `twitter:site`, with or without the `@`
+    #[serde(default)]
+    pub twitter: Option<String>,
+    
+    /// `og:locale` of the pages
+    #[serde(default = "default_seo_locale")]
+    pub locale: String,
+    
+    /// Image for pages without an `image`, e.g. a logo
+    #[serde(default)]
+    pub image: Option<String>,
+}
+
+impl Default for SeoConfig {
+    fn default() -> Self {
+        Self {
+            twitter: None,
+            locale: default_seo_locale(),
+            image: None,
+        }
+    }
+}
+
+/// `comments:` settings in _config.yml
+///
+/// Each page is its own thread, identified by its `comment_id` front matter
+/// or else its `id` (posts) or URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommentsConfig {
+    /// Comment system; `{% comments %}` outputs nothing without one
+    #[serde(default)]
+    pub provider: Option<CommentProvider>,
+    
+    /// GitHub repository holding the threads, as `owner/name` (giscus, utterances)
+    #[serde(default)]
+    pub repo: Option<String>,
+    
+    /// Repository id from giscus.app
+    #[serde(default)]
+    pub repo_id: Option<String>,
+    
+    /// Discussion category name (giscus)
+    #[serde(default)]
+    pub category: Option<String>,
+    
+    /// Discussion category id from giscus.app
+    #[serde(default)]
+    pub category_id: Option<String>,
+    
+    /// Label added to new issues (utterances)
+    #[serde(default)]
+    pub label: Option<String>,
+    
+    /// Color theme of the widget, e.g. `preferred_color_scheme` or `github-light`
+    #[serde(default)]
+    pub theme: Option<String>,
+    
+    /// Address of the Isso server
+    #[serde(default)]
+    pub server: Option<String>,
+}
+
+/// Comment systems `{% comments %}` can embed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentProvider {
+    /// [giscus](https://giscus.app), on GitHub Discussions
+    Giscus,
+    /// [utterances](https://utteranc.es), on GitHub issues
+    Utterances,
+    /// [Isso](https://isso-comments.de), self-hosted
+    Isso,
+}
+
+/// `taxonomy:` settings in _config.yml
+///
+/// Tags and categories are always matched case-insensitively; these add
+/// spellings that match on top of that.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaxonomyConfig {
+    /// Alternative name -> canonical name (e.g. `js: javascript`)
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    
+    /// Name -> URL slug, overriding the slugified name (e.g. `c++: cpp`)
+    #[serde(default)]
+    pub slugs: HashMap<String, String>,
+    
+    /// Write `/tags/<slug>.json` and `/categories/<slug>.json` listing the posts of each term
+    #[serde(default)]
+    pub json: bool,
+}
+
 fn default_title() -> String {
     "My Site".to_string()
 }
@@ -202,10 +979,66 @@ fn default_paginate() -> usize {
     10
 }
 
+fn default_serve_port() -> u16 {
+    4000
+}
+
+fn default_serve_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_link_concurrency() -> usize {
+    8
+}
+
+fn default_link_retries() -> u32 {
+    2
+}
+
+fn default_link_timeout() -> u64 {
+    10
+}
+
+fn default_link_cache_hours() -> u64 {
+    24
+}
+
+fn default_words_per_minute() -> usize {
+    200
+}
+
 fn default_paginate_path() -> String {
     "/page:num/".to_string()
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_seo_locale() -> String {
+    "en_US".to_string()
+}
+
+fn default_description_length() -> usize {
+    160
+}
+
+fn default_build_lock_timeout() -> u64 {
+    60
+}
+
+fn default_highlight_stylesheet() -> String {
+    "assets/css/syntax.css".to_string()
+}
+
+fn default_source() -> String {
+    ".".to_string()
+}
+
+fn default_destination() -> String {
+    "_site".to_string()
+}
+
 fn default_exclude() -> Vec<String> {
     vec![
         "Gemfile".to_string(),
@@ -215,6 +1048,7 @@ fn default_exclude() -> Vec<String> {
         ".git".to_string(),
         ".gitignore".to_string(),
         "_site".to_string(),
+        ".jellrust-cache".to_string(),
     ]
 }
 
@@ -225,19 +1059,73 @@ impl Default for Config {
             description: String::new(),
             url: String::new(),
             baseurl: String::new(),
+            timezone: None,
+            theme: None,
             markdown: default_markdown(),
             permalink: default_permalink(),
             paginate: default_paginate(),
             paginate_path: default_paginate_path(),
+            source: default_source(),
+            destination: default_destination(),
             exclude: default_exclude(),
             include: Vec::new(),
             plugins: Vec::new(),
+            draft_banner: false,
+            extract_image: true,
+            description_length: default_description_length(),
+            words_per_minute: default_words_per_minute(),
+            locale: None,
+            pinned_first: true,
+            liquid_in_data: false,
+            low_memory: false,
+            strip_bom: true,
+            charset_meta: true,
+            rewrite_baseurl: false,
+            images: ImagesConfig::default(),
+            output: OutputStyle::default(),
+            fingerprint: Fingerprint::default(),
+            header_files: Vec::new(),
+            build_lock_timeout: default_build_lock_timeout(),
+            notifications: NotificationConfig::default(),
+            serve: ServeConfig::default(),
+            sitemap: SitemapConfig::default(),
+            feed: FeedConfig::default(),
+            taxonomy: TaxonomyConfig::default(),
+            highlight: HighlightConfig::default(),
+            markdown_ext: MarkdownExtensions::default(),
+            math: MathMode::default(),
+            external_links: ExternalLinks::default(),
+            figures: false,
+            wikilinks: false,
+            admonitions: AdmonitionSyntax::default(),
+            authors: AuthorsConfig::default(),
+            liquid: LiquidConfig::default(),
+            template_engine: TemplateLanguage::default(),
+            excerpt: ExcerptConfig::default(),
+            excerpt_separator: None,
+            seo: SeoConfig::default(),
+            comments: CommentsConfig::default(),
+            vendor: Vec::new(),
+            link_check: LinkCheckConfig::default(),
             custom: HashMap::new(),
         }
     }
 }
 
 impl Config {
+    /// Resolve the content directory for a site whose _config.yml is in `root`
+    pub fn source_dir(&self, root: &std::path::Path) -> PathBuf {
+        match self.source.trim() {
+            "" | "." | "./" => root.to_path_buf(),
+            source => root.join(source),
+        }
+    }
+    
+    /// Resolve the output directory for a site whose _config.yml is in `root`
+    pub fn destination_dir(&self, root: &std::path::Path) -> PathBuf {
+        root.join(&self.destination)
+    }
+    
     /// Check if a path should be excluded from processing
     pub fn is_excluded(&self, path: &std::path::Path) -> bool {
         let path_str = path.to_string_lossy();
@@ -269,6 +1157,8 @@ mod tests {
         let post = Post::new(PathBuf::from("_posts/2024-01-15-test-post.md"));
         let date = post.parse_date_from_filename().unwrap();
         assert_eq!(date.format("%Y-%m-%d").to_string(), "2024-01-15");
+        assert_eq!(post.slug, "test-post");
+        assert_eq!(Post::new(PathBuf::from("_drafts/my-draft-idea.md")).filename_slug(), "my-draft-idea");
     }
     
     #[test]
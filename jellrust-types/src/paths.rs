@@ -0,0 +1,205 @@
+//! Mapping file system paths to URL paths and comparing them
+//!
+//! Windows paths come in several spellings of the same location: `\` or `/`
+//! separators, `c:` or `C:`, and the `\\?\C:\` and `\\?\UNC\server\share`
+//! forms `canonicalize` returns. Everything here works on the text of a path
+//! rather than `Path` components, so it treats Windows paths the same on every
+//! platform (and can be tested anywhere). A `\` is always a separator, as
+//! site sources are expected to be portable.
+
+use std::path::Path;
+
+/// Device names Windows reserves in every directory, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// A path split into its root and segments
+#[derive(Debug, PartialEq, Eq)]
+struct Parts {
+    /// `C:/`, `C:` (drive-relative), `//server/share/`, `/` or empty
+    root: String,
+    segments: Vec<String>,
+}
+
+impl Parts {
+    fn parse(path: &str) -> Self {
+        let path = strip_verbatim(&path.replace('\\', "/"));
+        let path = path.as_str();
+
+        let (root, rest) = if let Some(unc) = path.strip_prefix("//") {
+            let mut parts = unc.splitn(3, '/');
+            let server = parts.next().unwrap_or_default();
+            let share = parts.next().unwrap_or_default();
+            (format!("//{}/{}/", server, share), parts.next().unwrap_or_default())
+        } else if has_drive(path) {
+            let drive = path[..1].to_ascii_uppercase();
+            match path[2..].strip_prefix('/') {
+                Some(rest) => (format!("{}:/", drive), rest),
+                None => (format!("{}:", drive), &path[2..]),
+            }
+        } else if let Some(rest) = path.strip_prefix('/') {
+            ("/".to_string(), rest)
+        } else {
+            (String::new(), path)
+        };
+
+        let mut segments: Vec<String> = Vec::new();
+        for segment in rest.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." if segments.last().is_some_and(|last| last != "..") => {
+                    segments.pop();
+                }
+                // `..` can't climb above a root
+                ".." if !root.is_empty() => {}
+                segment => segments.push(segment.to_string()),
+            }
+        }
+
+        Self { root, segments }
+    }
+
+    /// Windows paths compare case-insensitively, like the file systems they live on
+    fn is_windows(&self) -> bool {
+        self.root.starts_with("//") || has_drive(&self.root)
+    }
+
+    fn to_path_string(&self) -> String {
+        format!("{}{}", self.root, self.segments.join("/"))
+    }
+}
+
+/// Drop the `//?/` (or `//./`) prefix of a verbatim or device path
+fn strip_verbatim(path: &str) -> String {
+    let Some(rest) = path.strip_prefix("//?/").or_else(|| path.strip_prefix("//./")) else {
+        return path.to_string();
+    };
+    match rest.get(..4) {
+        Some(unc) if unc.eq_ignore_ascii_case("UNC/") => format!("//{}", &rest[4..]),
+        _ => rest.to_string(),
+    }
+}
+
+/// Whether a path starts with a drive letter such as `C:`
+fn has_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// A path with `/` separators, for URLs and cache keys
+///
+/// Verbatim prefixes are dropped, drive letters upper-cased, and `.`, `..`
+/// and repeated separators resolved, so equal locations give equal strings.
+pub fn to_url_path(path: &Path) -> String {
+    Parts::parse(&path.to_string_lossy()).to_path_string()
+}
+
+/// `path` relative to `base`, with `/` separators
+///
+/// Unlike [`Path::strip_prefix`], this matches a canonicalized base against
+/// a path that isn't (or the other way round), and compares Windows paths
+/// case-insensitively. Returns `None` when `path` is not inside `base`.
+pub fn relative_to(path: &Path, base: &Path) -> Option<String> {
+    let path = Parts::parse(&path.to_string_lossy());
+    let base = Parts::parse(&base.to_string_lossy());
+
+    let windows = path.is_windows() || base.is_windows();
+    let same = |a: &str, b: &str| if windows { a.eq_ignore_ascii_case(b) } else { a == b };
+
+    if !same(&path.root, &base.root) || path.segments.len() < base.segments.len() {
+        return None;
+    }
+    let inside = base
+        .segments
+        .iter()
+        .zip(&path.segments)
+        .all(|(base, path)| same(base, path));
+    inside.then(|| path.segments[base.segments.len()..].join("/"))
+}
+
+/// Whether `path` is `dir` or lies inside it, see [`relative_to`]
+pub fn is_within(path: &Path, dir: &Path) -> bool {
+    relative_to(path, dir).is_some()
+}
+
+/// First segment of a URL or path that Windows can't create as a file
+///
+/// Names like `con` or `aux.html` refer to devices there, so a page named
+/// after one builds everywhere else but fails on Windows.
+pub fn reserved_name(url: &str) -> Option<&str> {
+    url.split(['/', '\\']).find(|segment| {
+        let stem = segment.split('.').next().unwrap_or_default().trim_end();
+        RESERVED_NAMES.iter().any(|name| name.eq_ignore_ascii_case(stem))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(path: &str) -> String {
+        to_url_path(Path::new(path))
+    }
+
+    fn relative(path: &str, base: &str) -> Option<String> {
+        relative_to(Path::new(path), Path::new(base))
+    }
+
+    #[test]
+    fn test_to_url_path() {
+        assert_eq!(url("docs\\intro.html"), "docs/intro.html");
+        assert_eq!(url("./docs//guide/../intro.html"), "docs/intro.html");
+        assert_eq!(url("/srv/site/index.md"), "/srv/site/index.md");
+        assert_eq!(url("../shared/x.md"), "../shared/x.md");
+    }
+
+    #[test]
+    fn test_drive_letters() {
+        assert_eq!(url("c:\\Users\\me\\site"), "C:/Users/me/site");
+        assert_eq!(url("C:/Users/me/site/"), "C:/Users/me/site");
+        assert_eq!(url("c:site\\index.md"), "C:site/index.md");
+        assert_eq!(url("C:\\..\\site"), "C:/site");
+    }
+
+    #[test]
+    fn test_verbatim_and_unc_paths() {
+        assert_eq!(url("\\\\?\\C:\\site\\_site"), "C:/site/_site");
+        assert_eq!(url("\\\\.\\C:\\site"), "C:/site");
+        assert_eq!(url("\\\\server\\share\\site\\a.md"), "//server/share/site/a.md");
+        assert_eq!(url("\\\\?\\UNC\\server\\share\\site"), "//server/share/site");
+        assert_eq!(url("\\\\?\\unc\\server\\share"), "//server/share/");
+    }
+
+    #[test]
+    fn test_relative_to() {
+        assert_eq!(relative("C:\\site\\docs\\a.md", "C:\\site"), Some("docs/a.md".to_string()));
+        assert_eq!(relative("c:\\Site\\docs\\a.md", "\\\\?\\C:\\site\\"), Some("docs/a.md".to_string()));
+        assert_eq!(
+            relative("\\\\?\\UNC\\server\\share\\site\\a.md", "\\\\SERVER\\share\\site"),
+            Some("a.md".to_string())
+        );
+        assert_eq!(relative("C:\\site", "C:\\site"), Some(String::new()));
+        assert_eq!(relative("C:\\sites\\a.md", "C:\\site"), None);
+        assert_eq!(relative("D:\\site\\a.md", "C:\\site"), None);
+        assert_eq!(relative("\\\\other\\share\\site\\a.md", "\\\\server\\share\\site"), None);
+
+        // Unix paths stay case-sensitive
+        assert_eq!(relative("/srv/site/a.md", "/srv/site"), Some("a.md".to_string()));
+        assert_eq!(relative("/srv/Site/a.md", "/srv/site"), None);
+        assert!(is_within(Path::new("/srv/site/_site/x.html"), Path::new("/srv/site/./_site")));
+        assert!(!is_within(Path::new("/srv/site/_site2/x.html"), Path::new("/srv/site/_site")));
+    }
+
+    #[test]
+    fn test_reserved_name() {
+        assert_eq!(reserved_name("/docs/con.html"), Some("con.html"));
+        assert_eq!(reserved_name("/Aux/index.html"), Some("Aux"));
+        assert_eq!(reserved_name("nul"), Some("nul"));
+        assert_eq!(reserved_name("/lpt9.tar.gz"), Some("lpt9.tar.gz"));
+        assert_eq!(reserved_name("/console.html"), None);
+        assert_eq!(reserved_name("/com10.html"), None);
+        assert_eq!(reserved_name("/blog/2024/hello.html"), None);
+    }
+}